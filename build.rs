@@ -0,0 +1,20 @@
+fn main() {
+	println!("cargo::rustc-check-cfg=cfg(mpris)");
+	println!("cargo::rustc-check-cfg=cfg(global_keys)");
+
+	// mpris needs a d-bus session bus, which only exists on linux and the bsds
+	let os = std::env::var("CARGO_CFG_TARGET_OS").unwrap_or_default();
+	let dbus = matches!(
+		os.as_str(),
+		"linux" | "freebsd" | "openbsd" | "netbsd" | "dragonfly"
+	);
+
+	if dbus && std::env::var_os("CARGO_FEATURE_MPRIS").is_some() {
+		println!("cargo::rustc-cfg=mpris");
+	}
+
+	// the global media keys are read from evdev, which is linux only
+	if os == "linux" && std::env::var_os("CARGO_FEATURE_GLOBAL_KEYS").is_some() {
+		println!("cargo::rustc-cfg=global_keys");
+	}
+}
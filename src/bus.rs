@@ -0,0 +1,51 @@
+//! bus for the [`Event`]s found by [`State::tick`], published to every integration that [`Subscribe`]s
+//!
+//! a new integration implements [`Subscribe`] and is added to the subscribers in the main loop
+
+use crate::{
+	queue::{Queue, Repeat},
+	state::State,
+};
+
+/// change of the playback or the queue
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+	/// another track is playing, or none at all
+	TrackChanged,
+	/// the tags of the current track were edited
+	TagsChanged,
+	/// playback was paused or resumed
+	Paused(bool),
+	VolumeChanged(u8),
+	Muted(bool),
+	/// the position of the current track jumped
+	Seeked,
+	/// another directory was queued
+	QueueChanged,
+	/// the track list of the queue was edited
+	TracksEdited,
+	ShuffleChanged(bool),
+	RepeatChanged(Repeat),
+	/// [`State::can_next`] or [`State::can_last`] changed
+	ControlsChanged,
+}
+
+/// integration that is notified of the [`Event`]s
+pub trait Subscribe {
+	/// handle `event`, the `state` and the `queue` are already updated
+	fn notify(&mut self, event: Event, state: &State, queue: &Queue);
+}
+
+/// notify the `subscribers` of the `events` in order
+pub fn publish(
+	events: &[Event],
+	state: &State,
+	queue: &Queue,
+	subscribers: &mut [&mut dyn Subscribe],
+) {
+	for &event in events {
+		for subscriber in subscribers.iter_mut() {
+			subscriber.notify(event, state, queue);
+		}
+	}
+}
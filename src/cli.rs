@@ -0,0 +1,277 @@
+//! command line interface
+
+use crate::history::Export;
+use camino::Utf8PathBuf;
+use thiserror::Error;
+
+/// cli error
+#[derive(Debug, Error)]
+pub enum CliError {
+	/// a required argument is missing
+	#[error("missing argument <{0}>")]
+	MissingArgument(&'static str),
+	/// argument is not known
+	#[error("unknown argument {0:?}")]
+	UnknownArgument(String),
+	/// profile name isn't alphanumeric
+	#[error("invalid profile {0:?}, only letters, digits, '-' and '_' are allowed")]
+	InvalidProfile(String),
+}
+
+/// parsed command line
+#[derive(Debug, PartialEq, Eq)]
+pub struct Cli {
+	/// profile set with `--profile <name>`, which namespaces the config and state
+	pub profile: Option<String>,
+	/// start even if another instance is running, set with `--new-instance`
+	pub new_instance: bool,
+	/// disable changing the queue, the tags and the config, set with `--kiosk`
+	pub kiosk: bool,
+	pub command: Command,
+}
+
+/// command to run
+#[derive(Debug, PartialEq, Eq)]
+pub enum Command {
+	/// start the tui
+	Run,
+	/// start the tui and play the url, resolved with yt-dlp
+	Open(String),
+	/// start the tui and queue the newline separated paths on stdin
+	Stdin,
+	/// start the tui and queue the directory
+	Queue(Utf8PathBuf),
+	/// analyze loudness and write replaygain tags
+	ScanGain(Utf8PathBuf),
+	/// print the status of the running instance
+	Status(Format),
+	/// import the playlists of another player
+	Import(Utf8PathBuf),
+	/// print the listening history
+	History(Export),
+	/// update the library index of the root lists
+	Rescan,
+}
+
+/// output format of [`Command::Status`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+	/// one field per line
+	#[default]
+	Plain,
+	/// the status as json
+	Json,
+	/// json for a waybar custom module
+	Waybar,
+	/// single line for a polybar script module, empty when stopped
+	Polybar,
+}
+
+impl Cli {
+	/// parse [`Cli`] from [`std::env::args`]
+	pub fn parse() -> Result<Self, CliError> {
+		Cli::parse_from(std::env::args().skip(1))
+	}
+
+	/// parse `--profile <name>`, `--new-instance` and `--kiosk`, which can appear anywhere,
+	/// and the [`Command`]
+	fn parse_from<I: Iterator<Item = String>>(args: I) -> Result<Self, CliError> {
+		let mut args = args.collect::<Vec<_>>();
+
+		let len = args.len();
+		args.retain(|arg| arg != "--new-instance");
+		let new_instance = args.len() != len;
+
+		let len = args.len();
+		args.retain(|arg| arg != "--kiosk");
+		let kiosk = args.len() != len;
+
+		let profile = match args.iter().position(|arg| arg == "--profile") {
+			Some(idx) => {
+				args.remove(idx);
+				if idx >= args.len() {
+					return Err(CliError::MissingArgument("name"));
+				}
+
+				let name = args.remove(idx);
+				let valid = name
+					.chars()
+					.all(|ch| ch.is_ascii_alphanumeric() || matches!(ch, '-' | '_'));
+				if name.is_empty() || !valid {
+					return Err(CliError::InvalidProfile(name));
+				}
+
+				Some(name)
+			}
+			None => None,
+		};
+
+		let command = Command::parse_from(args.into_iter())?;
+		Ok(Cli {
+			profile,
+			new_instance,
+			kiosk,
+			command,
+		})
+	}
+}
+
+impl Command {
+	fn parse_from<I: Iterator<Item = String>>(mut args: I) -> Result<Self, CliError> {
+		let command = match args.next().as_deref() {
+			None => Command::Run,
+			Some("--stdin") => Command::Stdin,
+			Some("scan-gain") => {
+				let dir = args.next().ok_or(CliError::MissingArgument("dir"))?;
+				Command::ScanGain(dir.into())
+			}
+			Some("status") => {
+				let format = match args.next().as_deref() {
+					None | Some("plain") => Format::Plain,
+					Some("json") => Format::Json,
+					Some("waybar") => Format::Waybar,
+					Some("polybar") => Format::Polybar,
+					Some(arg) => return Err(CliError::UnknownArgument(arg.to_owned())),
+				};
+				Command::Status(format)
+			}
+			Some("queue") => {
+				let dir = args.next().ok_or(CliError::MissingArgument("dir"))?;
+				Command::Queue(dir.into())
+			}
+			Some("open") => {
+				let url = args.next().ok_or(CliError::MissingArgument("url"))?;
+				Command::Open(url)
+			}
+			Some("history") => {
+				let format = match args.next().as_deref() {
+					None | Some("csv") => Export::Csv,
+					Some("json") => Export::Json,
+					Some(arg) => return Err(CliError::UnknownArgument(arg.to_owned())),
+				};
+				Command::History(format)
+			}
+			Some("rescan") => Command::Rescan,
+			Some("import") => {
+				let file = args.next().ok_or(CliError::MissingArgument("file"))?;
+				Command::Import(file.into())
+			}
+			Some(arg) => return Err(CliError::UnknownArgument(arg.to_owned())),
+		};
+
+		if let Some(arg) = args.next() {
+			return Err(CliError::UnknownArgument(arg));
+		}
+
+		Ok(command)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{Cli, CliError, Command, Format};
+	use crate::history::Export;
+
+	fn parse(args: &[&str]) -> Result<Command, CliError> {
+		Command::parse_from(args.iter().map(|&arg| arg.to_owned()))
+	}
+
+	#[test]
+	fn commands() {
+		assert_eq!(parse(&[]).unwrap(), Command::Run);
+		assert_eq!(parse(&["--stdin"]).unwrap(), Command::Stdin);
+		assert_eq!(
+			parse(&["scan-gain", "mock/list 01"]).unwrap(),
+			Command::ScanGain("mock/list 01".into())
+		);
+
+		assert_eq!(
+			parse(&["open", "https://example.com/track"]).unwrap(),
+			Command::Open("https://example.com/track".to_owned())
+		);
+
+		assert_eq!(
+			parse(&["import", "road trip.xspf"]).unwrap(),
+			Command::Import("road trip.xspf".into())
+		);
+
+		assert_eq!(parse(&["rescan"]).unwrap(), Command::Rescan);
+		assert_eq!(parse(&["history"]).unwrap(), Command::History(Export::Csv));
+		assert_eq!(
+			parse(&["history", "json"]).unwrap(),
+			Command::History(Export::Json)
+		);
+
+		assert_eq!(parse(&["status"]).unwrap(), Command::Status(Format::Plain));
+		assert_eq!(
+			parse(&["status", "waybar"]).unwrap(),
+			Command::Status(Format::Waybar)
+		);
+
+		assert!(matches!(
+			parse(&["status", "xml"]),
+			Err(CliError::UnknownArgument(_))
+		));
+		assert!(matches!(
+			parse(&["open"]),
+			Err(CliError::MissingArgument("url"))
+		));
+		assert!(matches!(
+			parse(&["scan-gain"]),
+			Err(CliError::MissingArgument("dir"))
+		));
+		assert!(matches!(
+			parse(&["scan"]),
+			Err(CliError::UnknownArgument(_))
+		));
+		assert!(matches!(
+			parse(&["scan-gain", "a", "b"]),
+			Err(CliError::UnknownArgument(_))
+		));
+	}
+
+	#[test]
+	fn profile() {
+		let cli = |args: &[&str]| Cli::parse_from(args.iter().map(|&arg| arg.to_owned()));
+
+		assert_eq!(
+			cli(&["--profile", "audiobooks"]).unwrap(),
+			Cli {
+				profile: Some("audiobooks".to_owned()),
+				new_instance: false,
+				kiosk: false,
+				command: Command::Run
+			}
+		);
+		assert_eq!(
+			cli(&["status", "--profile", "music", "json"]).unwrap(),
+			Cli {
+				profile: Some("music".to_owned()),
+				new_instance: false,
+				kiosk: false,
+				command: Command::Status(Format::Json)
+			}
+		);
+		assert_eq!(cli(&["status"]).unwrap().profile, None);
+		assert!(cli(&["--kiosk", "queue", "mock/list 01"]).unwrap().kiosk);
+		assert!(!cli(&["queue", "mock/list 01"]).unwrap().kiosk);
+		assert_eq!(
+			cli(&["queue", "--new-instance", "mock/list 01"]).unwrap(),
+			Cli {
+				profile: None,
+				new_instance: true,
+				kiosk: false,
+				command: Command::Queue("mock/list 01".into())
+			}
+		);
+
+		assert!(matches!(
+			cli(&["--profile"]),
+			Err(CliError::MissingArgument("name"))
+		));
+		assert!(matches!(
+			cli(&["--profile", "../music"]),
+			Err(CliError::InvalidProfile(_))
+		));
+	}
+}
@@ -4,7 +4,9 @@
 //! and all [`List`] management
 
 use crate::{
+	playlist,
 	queue::{Queue, Track},
+	remote,
 	ui::utils as ui,
 };
 use camino::{Utf8Path, Utf8PathBuf};
@@ -13,23 +15,49 @@ use ratatui::{
 	text::Line,
 };
 use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
 use std::{
 	borrow::Cow,
+	collections::BTreeMap,
 	fmt::Display,
 	fs,
+	net::SocketAddr,
 	ops::{Deref, DerefMut},
 	path::PathBuf,
 	str::FromStr,
-	sync::LazyLock,
+	sync::{LazyLock, OnceLock},
 	time::Duration,
 };
 use thiserror::Error;
 use unicase::UniCase;
 
 /// path for config file
-static CONFIG_PATH: LazyLock<PathBuf> = LazyLock::new(|| CONFIG_DIR.join("config.json"));
+static CONFIG_PATH: LazyLock<PathBuf> =
+	LazyLock::new(|| CONFIG_DIR.join(profile_file("config", "json")));
 /// path to config directory
 pub static CONFIG_DIR: LazyLock<PathBuf> = LazyLock::new(config_dir);
+/// profile set with `--profile`
+static PROFILE: OnceLock<String> = OnceLock::new();
+
+/// set the profile, which namespaces the files of an instance
+///
+/// has to be called before any of their paths is accessed
+pub fn set_profile(profile: String) {
+	let _ = PROFILE.set(profile);
+}
+
+/// name of the profile, if one is set
+pub fn profile() -> Option<&'static str> {
+	PROFILE.get().map(String::as_str)
+}
+
+/// file name `{stem}.{extension}`, or `{stem}.{profile}.{extension}` if a profile is set
+pub fn profile_file(stem: &str, extension: &str) -> String {
+	match profile() {
+		Some(profile) => format!("{stem}.{profile}.{extension}"),
+		None => format!("{stem}.{extension}"),
+	}
+}
 
 /// path to config directory
 ///
@@ -61,6 +89,16 @@ pub enum ConfigError {
 	/// list doesn't exist
 	#[error("list {0:?} doesn't exist")]
 	ListDoesntExist(Utf8PathBuf),
+	/// list isn't a directory
+	#[error("list {0:?} isn't a directory")]
+	NotADirectory(Utf8PathBuf),
+	/// list couldn't be read
+	#[error("couldn't read list {0:?}")]
+	Unreadable(Utf8PathBuf, #[source] std::io::Error),
+	/// the direct backend has no sinc resampler
+	#[cfg(feature = "direct")]
+	#[error("the sinc resampler isn't available with the direct backend")]
+	SincUnavailable,
 }
 
 impl From<std::io::Error> for ConfigError {
@@ -73,6 +111,35 @@ impl From<std::io::Error> for ConfigError {
 	}
 }
 
+/// edit the `lists` in [`CONFIG_PATH`], keeping the rest of the file as it is
+fn patch_lists<F: FnOnce(&mut Vec<Value>)>(f: F) -> Result<(), ConfigError> {
+	let file = fs::read_to_string(&*CONFIG_PATH)?;
+	let mut json = serde_json::from_str::<Value>(&file)?;
+	let Some(config) = json.as_object_mut() else {
+		return Ok(());
+	};
+
+	let lists = config
+		.entry("lists")
+		.or_insert_with(|| Value::Array(Vec::new()));
+	if !lists.is_array() {
+		*lists = Value::Array(Vec::new());
+	}
+
+	if let Value::Array(lists) = lists {
+		f(lists);
+	}
+
+	let mut file = Vec::new();
+	let formatter = serde_json::ser::PrettyFormatter::with_indent(b"\t");
+	let mut json_serializer = serde_json::Serializer::with_formatter(&mut file, formatter);
+	json.serialize(&mut json_serializer)?;
+	file.push(b'\n');
+
+	fs::write(&*CONFIG_PATH, file)?;
+	Ok(())
+}
+
 /// [`Child`] of [`List`]
 ///
 /// created via [`List::children`]
@@ -82,6 +149,10 @@ pub enum Child {
 	List(List),
 	/// audio file
 	Mp3(Utf8PathBuf),
+	/// playlist file of another player, imported when selected
+	Playlist(Utf8PathBuf),
+	/// directory that couldn't be read, with the reason
+	Unreadable(Utf8PathBuf, String),
 }
 
 impl Child {
@@ -96,10 +167,31 @@ impl Child {
 				let path = format!("{path}/");
 				Cow::Owned(path)
 			}
-			Child::Mp3(path) => {
+			Child::Mp3(path) | Child::Playlist(path) => {
 				let path = path.file_name().unwrap_or_else(|| path.as_str());
 				Cow::Borrowed(path)
 			}
+			Child::Unreadable(path, reason) => {
+				let path = path.file_name().unwrap_or_else(|| path.as_str());
+				Cow::Owned(format!("{path}/ ({reason})"))
+			}
+		}
+	}
+
+	/// path of child
+	fn path(&self) -> &Utf8Path {
+		match self {
+			Child::List(list) => &list.path,
+			Child::Mp3(path) | Child::Playlist(path) | Child::Unreadable(path, _) => path,
+		}
+	}
+
+	/// lists are sorted first, then playlists, then tracks
+	fn rank(&self) -> u8 {
+		match self {
+			Child::List(_) | Child::Unreadable(..) => 0,
+			Child::Playlist(_) => 1,
+			Child::Mp3(_) => 2,
 		}
 	}
 
@@ -107,17 +199,25 @@ impl Child {
 	pub fn list(&self) -> Option<&List> {
 		match self {
 			Child::List(list) => Some(list),
-			Child::Mp3(_) => None,
+			Child::Mp3(_) | Child::Playlist(_) | Child::Unreadable(..) => None,
 		}
 	}
 
+	/// returns if child is [`Child::Unreadable`], which can't be selected
+	pub fn is_unreadable(&self) -> bool {
+		matches!(self, Child::Unreadable(..))
+	}
+
 	/// formats [`Child`] into a [`ratatui::text::Line`].
 	///
 	/// - lists are underlined
+	/// - unreadable lists are dimmed
 	/// - currently playing track / list is accented and bold
 	/// - containing lists are only accented
-	pub fn line(&self, queue: &Queue) -> Line<'_> {
-		let name = self.name();
+	///
+	/// the name is truncated to `width` terminal columns
+	pub fn line(&self, queue: &Queue, width: usize) -> Line<'_> {
+		let name = ui::widgets::truncate(self.name(), width);
 		match self {
 			Child::List(list) => {
 				let underline = Style::default().underlined();
@@ -137,7 +237,7 @@ impl Child {
 			Child::Mp3(path) => {
 				if let Some(track) = queue.track() {
 					if track == path {
-						ui::widgets::line(name, ui::style::accent().bold())
+						ui::widgets::line(name, ui::style::playing())
 					} else {
 						Line::raw(name)
 					}
@@ -145,6 +245,8 @@ impl Child {
 					Line::raw(name)
 				}
 			}
+			Child::Playlist(_) => ui::widgets::line(name, ui::style::italic()),
+			Child::Unreadable(..) => ui::widgets::line(name, ui::style::placeholder()),
 		}
 	}
 }
@@ -153,7 +255,7 @@ impl PartialEq<List> for Child {
 	fn eq(&self, other: &List) -> bool {
 		match self {
 			Child::List(list) => list.eq(other),
-			Child::Mp3(_) => false,
+			Child::Mp3(_) | Child::Playlist(_) | Child::Unreadable(..) => false,
 		}
 	}
 }
@@ -161,7 +263,7 @@ impl PartialEq<List> for Child {
 impl PartialEq<Track> for Child {
 	fn eq(&self, other: &Track) -> bool {
 		match self {
-			Child::List(_) => false,
+			Child::List(_) | Child::Playlist(_) | Child::Unreadable(..) => false,
 			Child::Mp3(path) => path == other,
 		}
 	}
@@ -169,14 +271,9 @@ impl PartialEq<Track> for Child {
 
 impl Ord for Child {
 	fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-		match (self, other) {
-			(Child::List(l1), Child::List(l2)) => {
-				UniCase::new(&l1.path).cmp(&UniCase::new(&l2.path))
-			}
-			(Child::Mp3(p1), Child::Mp3(p2)) => UniCase::new(&p1).cmp(&UniCase::new(&p2)),
-			(Child::List(_), Child::Mp3(_)) => std::cmp::Ordering::Less,
-			(Child::Mp3(_), Child::List(_)) => std::cmp::Ordering::Greater,
-		}
+		self.rank().cmp(&other.rank()).then_with(|| {
+			UniCase::new(self.path().as_str()).cmp(&UniCase::new(other.path().as_str()))
+		})
 	}
 }
 
@@ -197,7 +294,7 @@ pub struct List {
 
 impl List {
 	/// create [`List`] without parent.
-	fn new(path: Utf8PathBuf) -> Result<Self, ConfigError> {
+	pub fn new(path: Utf8PathBuf) -> Result<Self, ConfigError> {
 		if path.exists() {
 			let list = List { path, parent: None };
 			Ok(list)
@@ -226,15 +323,18 @@ impl List {
 
 	/// extract parent from [`List`], if list has parent
 	pub fn into_parent(mut self) -> Option<(Option<usize>, List)> {
-		self.parent
-			.take()
-			.map(|p| (p.children().iter().position(|l| l == &self), *p))
+		self.parent.take().map(|p| {
+			let children = p.children().unwrap_or_default();
+			(children.iter().position(|l| l == &self), *p)
+		})
 	}
 
-	// todo error handling
 	/// reads files in [`List`] and returns a vec of [`Child`]
-	pub fn children(&self) -> Vec<Child> {
-		let read = fs::read_dir(&self.path).unwrap();
+	///
+	/// subdirectories that can't be read are returned as [`Child::Unreadable`]
+	pub fn children(&self) -> Result<Vec<Child>, ConfigError> {
+		let read = fs::read_dir(&self.path)
+			.map_err(|err| ConfigError::Unreadable(self.path.clone(), err))?;
 		let mut children = read
 			.flatten()
 			// todo display non utf8
@@ -242,19 +342,29 @@ impl List {
 			.flat_map(Utf8PathBuf::try_from)
 			.filter_map(|path| {
 				if path.is_dir() {
-					let list = List::with_parent(path, self.clone()).unwrap();
-					let child = Child::List(list);
+					if let Err(err) = fs::read_dir(&path) {
+						let reason = err.kind().to_string();
+						return Some(Child::Unreadable(path, reason));
+					}
+
+					let child = match List::with_parent(path.clone(), self.clone()) {
+						Ok(list) => Child::List(list),
+						Err(err) => Child::Unreadable(path, err.to_string()),
+					};
 					Some(child)
 				} else if path.extension() == Some("mp3") {
 					let child = Child::Mp3(path);
 					Some(child)
+				} else if playlist::is_importable(&path) {
+					let child = Child::Playlist(path);
+					Some(child)
 				} else {
 					None
 				}
 			})
 			.collect::<Vec<_>>();
 		children.sort();
-		children
+		Ok(children)
 	}
 
 	/// check if [`List`] contains path
@@ -263,8 +373,10 @@ impl List {
 	}
 
 	/// format [`List`] into [`ratatui::text::Line`] struct for ratatui
-	pub fn line(&self, queue: &Queue) -> Line<'_> {
-		let name = self.path.as_str();
+	///
+	/// the path is truncated to `width` terminal columns
+	pub fn line(&self, queue: &Queue, width: usize) -> Line<'_> {
+		let name = ui::widgets::truncate(self.path.as_str(), width);
 
 		let underline = Style::default().underlined();
 		let accent = ui::style::accent().underlined();
@@ -284,10 +396,11 @@ impl List {
 	pub fn position(&self, queue: &Queue) -> Option<usize> {
 		let (q, t) = queue.path().zip(queue.track())?;
 
-		let children = self.children();
+		let children = self.children().ok()?;
 		children.iter().position(|child| match child {
 			Child::List(list) => list.contains_path(q),
 			Child::Mp3(path) => t == path,
+			Child::Playlist(_) | Child::Unreadable(..) => false,
 		})
 	}
 
@@ -296,9 +409,10 @@ impl List {
 		if self == &other {
 			Some(self.clone())
 		} else if self.contains_path(other) {
-			self.children().into_iter().find_map(|child| match child {
+			let children = self.children().ok()?;
+			children.into_iter().find_map(|child| match child {
 				Child::List(list) => list.find_list(other),
-				Child::Mp3(_) => None,
+				Child::Mp3(_) | Child::Playlist(_) | Child::Unreadable(..) => None,
 			})
 		} else {
 			None
@@ -332,7 +446,7 @@ impl Serialize for List {
 impl List {
 	/// deserialize Vec of [`List`]
 	///
-	/// ignores non-existant [`List`] items
+	/// ignores non-existant [`List`] items, webdav and sftp urls are replaced by their mirror
 	/// and unwraps an `Option` to an empty vec
 	pub fn maybe_deserialize<'de, D>(data: D) -> Result<Vec<List>, D::Error>
 	where
@@ -340,7 +454,11 @@ impl List {
 	{
 		let paths: Option<Vec<Utf8PathBuf>> = Deserialize::deserialize(data)?;
 		let paths = paths.unwrap_or_default();
-		let lists = paths.into_iter().flat_map(List::new).collect();
+		let lists = paths
+			.into_iter()
+			.map(|path| remote::mirror(path.as_str()).unwrap_or(path))
+			.flat_map(List::new)
+			.collect();
 		Ok(lists)
 	}
 }
@@ -424,6 +542,183 @@ impl serde::de::Visitor<'_> for ColorVis {
 	}
 }
 
+/// colors of individual ui elements, overriding the accent color
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Styles {
+	/// filled part of the seek bar
+	#[serde(skip_serializing_if = "Option::is_none")]
+	gauge: Option<ColorWrap>,
+	/// selected entry of the tracks and lists popups
+	#[serde(skip_serializing_if = "Option::is_none")]
+	selection: Option<ColorWrap>,
+	/// currently playing track
+	#[serde(skip_serializing_if = "Option::is_none")]
+	playing: Option<ColorWrap>,
+	/// borders of the windows and popups
+	#[serde(skip_serializing_if = "Option::is_none")]
+	border: Option<ColorWrap>,
+}
+
+impl Styles {
+	/// get and deref [`Styles::gauge`] to [`ratatui::style::Color`]
+	#[inline]
+	pub fn gauge(&self) -> Option<Color> {
+		self.gauge.as_deref().copied()
+	}
+
+	/// get and deref [`Styles::selection`] to [`ratatui::style::Color`]
+	#[inline]
+	pub fn selection(&self) -> Option<Color> {
+		self.selection.as_deref().copied()
+	}
+
+	/// get and deref [`Styles::playing`] to [`ratatui::style::Color`]
+	#[inline]
+	pub fn playing(&self) -> Option<Color> {
+		self.playing.as_deref().copied()
+	}
+
+	/// get and deref [`Styles::border`] to [`ratatui::style::Color`]
+	#[inline]
+	pub fn border(&self) -> Option<Color> {
+		self.border.as_deref().copied()
+	}
+}
+
+/// what time to display in the seek bar
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeDisplay {
+	/// time elapsed
+	#[default]
+	Elapsed,
+	/// time remaining
+	Remaining,
+	/// time elapsed and time remaining
+	Both,
+}
+
+impl TimeDisplay {
+	/// cycle to the next [`TimeDisplay`]
+	pub fn next(self) -> Self {
+		match self {
+			TimeDisplay::Elapsed => TimeDisplay::Remaining,
+			TimeDisplay::Remaining => TimeDisplay::Both,
+			TimeDisplay::Both => TimeDisplay::Elapsed,
+		}
+	}
+}
+
+/// where the tracks and lists browser is shown
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Layout {
+	/// as a popup over the main window
+	#[default]
+	Popup,
+	/// as a permanent pane on the left of the main window
+	Split,
+}
+
+/// what happens after the last track with [`Repeat::None`](crate::queue::Repeat::None)
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum End {
+	/// start over at the first track, paused
+	#[default]
+	Stop,
+	/// clear the queue
+	Clear,
+	/// quit maym
+	Quit,
+	/// run the command with `sh -c`, then stop
+	Hook(String),
+}
+
+/// time skipped at the start of tracks, like the intro of a podcast
+#[derive(Debug, Default, Clone)]
+pub struct Intro {
+	/// skipped for every track
+	skip: Duration,
+	/// skipped for the tracks in a directory, or a single track
+	paths: Vec<(Utf8PathBuf, Duration)>,
+}
+
+impl Intro {
+	/// time to skip at the start of `track`
+	///
+	/// tracks shorter than the intro are played from the start
+	pub fn get(&self, track: &Track) -> Duration {
+		let skip = self.skip(track.path());
+		if track.duration().is_some_and(|duration| duration <= skip) {
+			Duration::ZERO
+		} else {
+			skip
+		}
+	}
+
+	/// the most specific path containing `path` wins over the global setting
+	fn skip(&self, path: &Utf8Path) -> Duration {
+		(self.paths.iter())
+			.filter(|(prefix, _)| path.starts_with(prefix))
+			.max_by_key(|(prefix, _)| prefix.components().count())
+			.map_or(self.skip, |&(_, skip)| skip)
+	}
+}
+
+/// how tracks are buffered when streamed from disk
+///
+/// unset fields use the defaults of creek
+#[derive(Debug, Default, Clone, Copy, Deserialize, Serialize)]
+pub struct Buffering {
+	/// number of frames in a prefetch block
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub block_size: Option<usize>,
+	/// number of prefetch blocks in a cache
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub cache_blocks: Option<usize>,
+	/// maximum number of caches
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub caches: Option<usize>,
+	/// number of prefetch blocks read ahead of the playhead
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub look_ahead_blocks: Option<usize>,
+}
+
+/// quality of the resampler used when the track and output sample rates differ
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Resampling {
+	/// linear interpolation, cheapest
+	#[default]
+	Linear,
+	/// cubic polynomial interpolation
+	Cubic,
+	/// windowed sinc interpolation, best quality but most expensive
+	///
+	/// not available with the `direct` backend, a config asking for it is rejected
+	Sinc,
+}
+
+/// http remote
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Http {
+	/// address to serve on, like `0.0.0.0:8080`
+	pub addr: SocketAddr,
+	/// secret every request but the page has to send, the page is opened as `/?token=<token>`
+	pub token: String,
+}
+
+/// subsonic compatible server, like navidrome
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Subsonic {
+	/// base url of the server, without `/rest`
+	pub url: String,
+	pub user: String,
+	/// sent hex encoded, as the token authentication needs md5
+	pub password: String,
+}
+
 /// config file
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Config {
@@ -433,9 +728,144 @@ pub struct Config {
 	/// amount to seek by in tracks in seconds
 	#[serde(skip_serializing_if = "Option::is_none")]
 	seek: Option<u8>,
+	/// amount to jump back by with instant replay in seconds
+	#[serde(skip_serializing_if = "Option::is_none")]
+	replay: Option<u8>,
 	/// ui accent color
 	#[serde(skip_serializing_if = "Option::is_none")]
 	accent: Option<ColorWrap>,
+	/// colors of individual ui elements
+	#[serde(skip_serializing_if = "Option::is_none")]
+	styles: Option<Styles>,
+	/// colors the filled part of the seek bar fades through, from the start to the end of the track
+	#[serde(skip_serializing_if = "Option::is_none")]
+	gauge_gradient: Option<Vec<ColorWrap>>,
+	/// replace dim and italic styling with explicit high-contrast colors
+	#[serde(skip_serializing_if = "Option::is_none")]
+	high_contrast: Option<bool>,
+	/// popup width in percent of the main window
+	#[serde(skip_serializing_if = "Option::is_none")]
+	popup_width: Option<u8>,
+	/// popup height in percent of the main window
+	#[serde(skip_serializing_if = "Option::is_none")]
+	popup_height: Option<u8>,
+	/// show the tracks and lists browser as a popup or a permanent pane
+	#[serde(skip_serializing_if = "Option::is_none")]
+	layout: Option<Layout>,
+	/// time to display in the seek bar
+	#[serde(skip_serializing_if = "Option::is_none")]
+	time: Option<TimeDisplay>,
+	/// interval between ticks in milliseconds
+	#[serde(skip_serializing_if = "Option::is_none")]
+	tick: Option<u16>,
+	/// show track and state changes on a dedicated line for screen readers
+	#[serde(skip_serializing_if = "Option::is_none")]
+	announce: Option<bool>,
+	/// also speak announcements with `spd-say`
+	#[serde(skip_serializing_if = "Option::is_none")]
+	speak: Option<bool>,
+	/// directory recordings of the output are saved to
+	#[serde(skip_serializing_if = "Option::is_none")]
+	record_dir: Option<Utf8PathBuf>,
+	/// handle media keys reported by the terminal
+	#[serde(skip_serializing_if = "Option::is_none")]
+	media_keys: Option<bool>,
+	/// listen for media keys on the input devices, even when the terminal isn't focused
+	#[serde(skip_serializing_if = "Option::is_none")]
+	global_media_keys: Option<bool>,
+	/// stream buffering
+	#[serde(skip_serializing_if = "Option::is_none")]
+	buffering: Option<Buffering>,
+	/// resampler quality
+	#[serde(skip_serializing_if = "Option::is_none")]
+	resampler: Option<Resampling>,
+	/// soft limit samples after the volume stage
+	#[serde(skip_serializing_if = "Option::is_none")]
+	limiter: Option<bool>,
+	/// amplitude above which the limiter starts compressing, between 0 and 1
+	#[serde(skip_serializing_if = "Option::is_none")]
+	limiter_threshold: Option<f32>,
+	/// skip silence at the start and end of tracks
+	#[serde(skip_serializing_if = "Option::is_none")]
+	trim_silence: Option<bool>,
+	/// level in dBFS below which samples count as silence
+	#[serde(skip_serializing_if = "Option::is_none")]
+	silence_threshold: Option<f32>,
+	/// maximum amount of silence to skip at either end of a track in seconds
+	#[serde(skip_serializing_if = "Option::is_none")]
+	max_trim: Option<u8>,
+	/// end a track after this many seconds of silence, for rips with long trailing silence
+	#[serde(skip_serializing_if = "Option::is_none")]
+	end_on_silence: Option<u8>,
+	/// seconds at the end of a track in which silence counts for [`Config::end_on_silence`]
+	#[serde(skip_serializing_if = "Option::is_none")]
+	runout_window: Option<u16>,
+	/// http remote
+	#[serde(skip_serializing_if = "Option::is_none")]
+	http: Option<Http>,
+	/// party mode, remote clients can only request tracks and vote to skip
+	#[serde(skip_serializing_if = "Option::is_none")]
+	party: Option<bool>,
+	/// votes of remote clients needed to skip a track in party mode
+	#[serde(skip_serializing_if = "Option::is_none")]
+	skip_votes: Option<u8>,
+	/// remote library shown in the lists
+	#[serde(skip_serializing_if = "Option::is_none")]
+	subsonic: Option<Subsonic>,
+	/// record played tracks for `maym history`
+	#[serde(skip_serializing_if = "Option::is_none")]
+	history: Option<bool>,
+	/// index the root lists in a sqlite database, needs the `library` feature
+	#[serde(skip_serializing_if = "Option::is_none")]
+	library: Option<bool>,
+	/// what happens at the end of the queue without repeat
+	#[serde(skip_serializing_if = "Option::is_none")]
+	end: Option<End>,
+	/// command to edit the tags of a track, run with `sh -c` and the path as the last argument
+	#[serde(skip_serializing_if = "Option::is_none")]
+	tag_editor: Option<String>,
+	/// seconds to skip at the start of every track
+	#[serde(skip_serializing_if = "Option::is_none")]
+	skip_intro: Option<u16>,
+	/// show the bpm of the tracks in the tracks popup
+	#[serde(skip_serializing_if = "Option::is_none")]
+	bpm_column: Option<bool>,
+	/// separators between multiple artists in one artist tag
+	#[serde(skip_serializing_if = "Option::is_none")]
+	artist_separators: Option<Vec<String>>,
+	/// show the composer and work of classical tracks in the title
+	#[serde(skip_serializing_if = "Option::is_none")]
+	classical: Option<bool>,
+	/// file name patterns like `{track} - {artist} - {title}` to read the tags of untagged tracks from
+	#[serde(skip_serializing_if = "Option::is_none")]
+	filename_patterns: Option<Vec<String>>,
+	/// template like `{track} — {title|filename} [{album}]` tracks are listed with
+	#[serde(skip_serializing_if = "Option::is_none")]
+	list_format: Option<String>,
+	/// template the title of the playing track is displayed with, also sent to mpris
+	#[serde(skip_serializing_if = "Option::is_none")]
+	title_format: Option<String>,
+	/// milliseconds to fade out the old track when skipping, 0 to cut right away
+	#[serde(skip_serializing_if = "Option::is_none")]
+	skip_fade: Option<u16>,
+	/// frames per callback of the audio output, lower values make seeking and pausing
+	/// more responsive but risk dropouts
+	#[serde(skip_serializing_if = "Option::is_none")]
+	buffer_frames: Option<u32>,
+	/// name of a second output device, like headphones, that previews are played on
+	#[serde(skip_serializing_if = "Option::is_none")]
+	cue_device: Option<String>,
+	/// volume of the [`Config::cue_device`] in percent
+	#[serde(skip_serializing_if = "Option::is_none")]
+	cue_volume: Option<u8>,
+	/// play the playback on the [`Config::cue_device`] as well
+	#[serde(skip_serializing_if = "Option::is_none")]
+	mirror: Option<bool>,
+	/// seconds to skip at the start of the tracks in a directory or of a single track,
+	/// overriding [`Config::skip_intro`]
+	#[serde(skip_serializing_if = "BTreeMap::is_empty")]
+	#[serde(default)]
+	intros: BTreeMap<Utf8PathBuf, u16>,
 	/// list of playlists
 	#[serde(skip_serializing_if = "Vec::is_empty")]
 	#[serde(deserialize_with = "List::maybe_deserialize")]
@@ -449,7 +879,18 @@ impl Config {
 	/// todo gracefully handle malformed json
 	pub fn init() -> Result<Self, ConfigError> {
 		let file = fs::read_to_string(&*CONFIG_PATH)?;
-		let config = serde_json::from_str(&file)?;
+		Config::parse(&file)
+	}
+
+	/// parse [`Config`] from json, rejecting options the enabled backend can't honour
+	fn parse(file: &str) -> Result<Self, ConfigError> {
+		let config = serde_json::from_str::<Config>(file)?;
+
+		#[cfg(feature = "direct")]
+		if config.resampler == Some(Resampling::Sinc) {
+			return Err(ConfigError::SincUnavailable);
+		}
+
 		Ok(config)
 	}
 
@@ -459,6 +900,48 @@ impl Config {
 		&self.lists
 	}
 
+	/// paths of the [`Config::lists`]
+	pub fn roots(&self) -> Vec<Utf8PathBuf> {
+		self.lists.iter().map(|list| list.path.clone()).collect()
+	}
+
+	/// add the directory at `path` to [`Config::lists`] and write it to [`CONFIG_PATH`]
+	///
+	/// # Errors
+	///
+	/// returns [`ConfigError`] if `path` isn't an existing directory or the config can't be written
+	pub fn add_list(&mut self, path: Utf8PathBuf) -> Result<(), ConfigError> {
+		if !path.exists() {
+			return Err(ConfigError::ListDoesntExist(path));
+		} else if !path.is_dir() {
+			return Err(ConfigError::NotADirectory(path));
+		} else if self.lists.iter().any(|list| list == &path.as_path()) {
+			return Ok(());
+		}
+
+		patch_lists(|lists| lists.push(Value::from(path.as_str())))?;
+		self.lists.push(List::new(path)?);
+		Ok(())
+	}
+
+	/// remove the list at `path` from [`Config::lists`] and write it to [`CONFIG_PATH`]
+	///
+	/// # Errors
+	///
+	/// returns [`ConfigError`] if the config can't be written
+	pub fn remove_list(&mut self, path: &Utf8Path) -> Result<(), ConfigError> {
+		patch_lists(|lists| {
+			lists.retain(|list| {
+				// remote lists are written as their url, but listed as their mirror
+				let list = list.as_str().unwrap_or_default();
+				remote::mirror(list).map_or_else(|| list != path, |mirror| mirror != path)
+			});
+		})?;
+
+		self.lists.retain(|list| list != &path);
+		Ok(())
+	}
+
 	/// get [`Config::seek`] or unwrap to default value of 5
 	#[inline]
 	pub fn seek(&self) -> Duration {
@@ -466,25 +949,298 @@ impl Config {
 		Duration::from_secs(u64::from(seek))
 	}
 
+	/// get [`Config::replay`] or unwrap to default value of 10
+	#[inline]
+	pub fn replay(&self) -> Duration {
+		let replay = self.replay.unwrap_or(10);
+		Duration::from_secs(u64::from(replay))
+	}
+
 	/// get and deref [`Config::color`] to [`ratatui::style::Color`]
 	#[inline]
 	pub fn accent(&self) -> Option<Color> {
 		self.accent.as_deref().copied()
 	}
 
+	/// get [`Config::styles`] or unwrap to no colors
+	#[inline]
+	pub fn styles(&self) -> Styles {
+		self.styles.unwrap_or_default()
+	}
+
+	/// get and deref [`Config::gauge_gradient`] to [`ratatui::style::Color`]s or unwrap to none
+	#[inline]
+	pub fn gauge_gradient(&self) -> Vec<Color> {
+		self.gauge_gradient
+			.iter()
+			.flatten()
+			.map(|color| **color)
+			.collect()
+	}
+
+	/// get [`Config::high_contrast`] or unwrap to false
+	#[inline]
+	pub fn high_contrast(&self) -> bool {
+		self.high_contrast.unwrap_or(false)
+	}
+
 	/// get [`Config::vol`] or unwrap to default value of 5
 	#[inline]
 	pub fn vol(&self) -> u8 {
 		self.vol.unwrap_or(5)
 	}
+
+	/// get [`Config::layout`] or unwrap to [`Layout::Popup`]
+	#[inline]
+	pub fn layout(&self) -> Layout {
+		self.layout.unwrap_or_default()
+	}
+
+	/// get [`Config::time`] or unwrap to [`TimeDisplay::Elapsed`]
+	#[inline]
+	pub fn time(&self) -> TimeDisplay {
+		self.time.unwrap_or_default()
+	}
+
+	/// get [`Config::tick`] or unwrap to default value of 100ms
+	///
+	/// clamped between 10ms and 1s
+	#[inline]
+	pub fn tick(&self) -> Duration {
+		let tick = self.tick.unwrap_or(100).clamp(10, 1000);
+		Duration::from_millis(u64::from(tick))
+	}
+
+	/// get [`Config::buffering`] or unwrap to the defaults
+	#[inline]
+	#[cfg(not(feature = "direct"))]
+	pub fn buffering(&self) -> Buffering {
+		self.buffering.unwrap_or_default()
+	}
+
+	/// get [`Config::resampler`] or unwrap to [`Resampling::Linear`]
+	#[inline]
+	pub fn resampler(&self) -> Resampling {
+		self.resampler.unwrap_or_default()
+	}
+
+	/// get [`Config::limiter_threshold`] if [`Config::limiter`] is enabled
+	///
+	/// the limiter is disabled by default, the threshold defaults to 0.9 and is clamped between 0.1 and 1
+	#[inline]
+	pub fn limiter(&self) -> Option<f32> {
+		let threshold = self.limiter_threshold.unwrap_or(0.9).clamp(0.1, 1.0);
+		self.limiter.unwrap_or(false).then_some(threshold)
+	}
+
+	/// get [`Config::silence_threshold`] and [`Config::max_trim`] if [`Config::trim_silence`] is enabled
+	///
+	/// trimming is disabled by default, the threshold defaults to -60dBFS and the maximum to 10 seconds
+	#[inline]
+	pub fn trim_silence(&self) -> Option<(f32, Duration)> {
+		let threshold = self.silence_threshold.unwrap_or(-60.).clamp(-120., 0.);
+		let max = Duration::from_secs(u64::from(self.max_trim.unwrap_or(10)));
+		self.trim_silence
+			.unwrap_or(false)
+			.then_some((threshold, max))
+	}
+
+	/// get [`Config::silence_threshold`], [`Config::end_on_silence`] and [`Config::runout_window`]
+	/// if the end on silence is set
+	///
+	/// the threshold is shared with [`Config::trim_silence`], the window defaults to 5 minutes
+	/// and is at least as long as the silence
+	#[inline]
+	pub fn end_on_silence(&self) -> Option<(f32, Duration, Duration)> {
+		let threshold = self.silence_threshold.unwrap_or(-60.).clamp(-120., 0.);
+		let after = self.end_on_silence.filter(|&secs| secs > 0)?;
+		let window = self.runout_window.unwrap_or(300).max(u16::from(after));
+		Some((
+			threshold,
+			Duration::from_secs(u64::from(after)),
+			Duration::from_secs(u64::from(window)),
+		))
+	}
+
+	/// get [`Config::announce`] or unwrap to false
+	#[inline]
+	pub fn announce(&self) -> bool {
+		self.announce.unwrap_or(false)
+	}
+
+	/// get [`Config::speak`] or unwrap to false
+	#[inline]
+	pub fn speak(&self) -> bool {
+		self.speak.unwrap_or(false)
+	}
+
+	/// get [`Config::record_dir`]
+	///
+	/// unwraps to the audio directory of the user, or `recordings` in [`CONFIG_DIR`]
+	pub fn record_dir(&self) -> PathBuf {
+		self.record_dir.as_ref().map_or_else(
+			|| dirs::audio_dir().unwrap_or_else(|| CONFIG_DIR.join("recordings")),
+			|dir| dir.clone().into_std_path_buf(),
+		)
+	}
+
+	/// get [`Config::media_keys`]
+	///
+	/// unwraps to true if maym was built without mpris, as mpris already handles them
+	#[inline]
+	pub fn media_keys(&self) -> bool {
+		self.media_keys.unwrap_or(!cfg!(mpris))
+	}
+
+	/// get [`Config::global_media_keys`], the listener is disabled by default
+	#[inline]
+	#[cfg(global_keys)]
+	pub fn global_media_keys(&self) -> bool {
+		self.global_media_keys.unwrap_or(false)
+	}
+
+	/// get reference to [`Config::http`], the http remote is disabled by default
+	#[inline]
+	pub fn http(&self) -> Option<&Http> {
+		self.http.as_ref()
+	}
+
+	/// get [`Config::party`] or unwrap to false
+	#[inline]
+	pub fn party(&self) -> bool {
+		self.party.unwrap_or(false)
+	}
+
+	/// get [`Config::skip_votes`] or unwrap to 3
+	#[inline]
+	pub fn skip_votes(&self) -> usize {
+		usize::from(self.skip_votes.unwrap_or(3).max(1))
+	}
+
+	/// get reference to [`Config::subsonic`]
+	#[inline]
+	pub fn subsonic(&self) -> Option<&Subsonic> {
+		self.subsonic.as_ref()
+	}
+
+	/// get [`Config::history`] or unwrap to true
+	#[inline]
+	pub fn history(&self) -> bool {
+		self.history.unwrap_or(true)
+	}
+
+	/// get [`Config::library`] or unwrap to false
+	#[inline]
+	pub fn library(&self) -> bool {
+		self.library.unwrap_or(false)
+	}
+
+	/// get [`Config::skip_intro`] and [`Config::intros`] as an [`Intro`]
+	pub fn intro(&self) -> Intro {
+		let secs = |secs: u16| Duration::from_secs(u64::from(secs));
+		Intro {
+			skip: self.skip_intro.map_or(Duration::ZERO, secs),
+			paths: (self.intros.iter())
+				.map(|(path, &skip)| (path.clone(), secs(skip)))
+				.collect(),
+		}
+	}
+
+	/// get [`Config::tag_editor`]
+	#[inline]
+	pub fn tag_editor(&self) -> Option<&str> {
+		self.tag_editor.as_deref()
+	}
+
+	/// get [`Config::bpm_column`] or unwrap to false
+	#[inline]
+	pub fn bpm_column(&self) -> bool {
+		self.bpm_column.unwrap_or(false)
+	}
+
+	/// get [`Config::artist_separators`] or unwrap to `;`
+	#[inline]
+	pub fn artist_separators(&self) -> Vec<String> {
+		(self.artist_separators.clone()).unwrap_or_else(|| vec![String::from(";")])
+	}
+
+	/// get [`Config::classical`] or unwrap to false
+	#[inline]
+	pub fn classical(&self) -> bool {
+		self.classical.unwrap_or(false)
+	}
+
+	/// get [`Config::filename_patterns`]
+	#[inline]
+	pub fn filename_patterns(&self) -> Option<&[String]> {
+		self.filename_patterns.as_deref()
+	}
+
+	/// get [`Config::list_format`]
+	#[inline]
+	pub fn list_format(&self) -> Option<&str> {
+		self.list_format.as_deref()
+	}
+
+	/// get [`Config::title_format`]
+	#[inline]
+	pub fn title_format(&self) -> Option<&str> {
+		self.title_format.as_deref()
+	}
+
+	/// get [`Config::skip_fade`] or unwrap to 20 milliseconds
+	#[inline]
+	pub fn skip_fade(&self) -> Duration {
+		Duration::from_millis(u64::from(self.skip_fade.unwrap_or(20)))
+	}
+
+	/// get [`Config::buffer_frames`]
+	#[inline]
+	pub fn buffer_frames(&self) -> Option<u32> {
+		self.buffer_frames
+	}
+
+	/// get [`Config::cue_device`]
+	#[inline]
+	pub fn cue_device(&self) -> Option<&str> {
+		self.cue_device.as_deref()
+	}
+
+	/// get [`Config::cue_volume`] or unwrap to 100, clamped to 100
+	#[inline]
+	pub fn cue_volume(&self) -> u8 {
+		self.cue_volume.unwrap_or(100).min(100)
+	}
+
+	/// get [`Config::mirror`] or unwrap to false
+	#[inline]
+	pub fn mirror(&self) -> bool {
+		self.mirror.unwrap_or(false)
+	}
+
+	/// get [`Config::end`] or unwrap to [`End::Stop`]
+	#[inline]
+	pub fn end(&self) -> &End {
+		self.end.as_ref().unwrap_or(&End::Stop)
+	}
+
+	/// get [`Config::popup_width`] and [`Config::popup_height`] in percent
+	///
+	/// unwraps to the default values of 70 and 80 and clamps to 100
+	#[inline]
+	pub fn popup_size(&self) -> (u16, u16) {
+		let width = self.popup_width.unwrap_or(70).clamp(1, 100);
+		let height = self.popup_height.unwrap_or(80).clamp(1, 100);
+		(u16::from(width), u16::from(height))
+	}
 }
 
 #[cfg(test)]
 mod test {
-	use super::{Child, ColorWrap, ConfigError, List};
-	use camino::Utf8PathBuf;
+	use super::{Child, ColorWrap, Config, ConfigError, Intro, List, Resampling, Styles};
+	use camino::{Utf8Path, Utf8PathBuf};
 	use ratatui::style::Color;
-	use std::cmp::Ordering;
+	use std::{cmp::Ordering, time::Duration};
 
 	/// create [`List`]
 	///
@@ -627,7 +1383,7 @@ mod test {
 			mp3("mock/list 01/track 01.mp3"),
 		];
 
-		let children = mock.children();
+		let children = mock.children()?;
 		assert_eq!(children, comp);
 
 		Ok(())
@@ -651,4 +1407,53 @@ mod test {
 		assert!("none".parse::<ColorWrap>().is_err());
 		assert!("".parse::<ColorWrap>().is_err());
 	}
+
+	#[test]
+	fn styles() {
+		let styles = serde_json::from_str::<Styles>(r##"{ "gauge": "red", "border": "#008080" }"##);
+		let styles = styles.unwrap();
+		assert_eq!(styles.gauge(), Some(Color::Red));
+		assert_eq!(styles.border(), Some(Color::Rgb(0x00, 0x80, 0x80)));
+		assert_eq!(styles.selection(), None);
+		assert_eq!(styles.playing(), None);
+
+		assert!(serde_json::from_str::<Styles>(r#"{ "playing": "none" }"#).is_err());
+	}
+
+	#[test]
+	fn intro() {
+		let intro = Intro {
+			skip: Duration::from_secs(5),
+			paths: vec![
+				("/podcasts".into(), Duration::from_secs(30)),
+				("/podcasts/daily".into(), Duration::from_secs(90)),
+				("/podcasts/weekly/episode 01.mp3".into(), Duration::ZERO),
+			],
+		};
+
+		let skip = |path: &str| intro.skip(Utf8Path::new(path));
+		assert_eq!(skip("/music/track.mp3"), Duration::from_secs(5));
+		assert_eq!(
+			skip("/podcasts/weekly/episode 02.mp3"),
+			Duration::from_secs(30)
+		);
+		assert_eq!(skip("/podcasts/daily/episode.mp3"), Duration::from_secs(90));
+		assert_eq!(skip("/podcasts/weekly/episode 01.mp3"), Duration::ZERO);
+		assert_eq!(skip("/podcasts-old/episode.mp3"), Duration::from_secs(5));
+	}
+
+	#[test]
+	fn resampler() -> color_eyre::Result<()> {
+		let config = Config::parse(r#"{ "resampler": "cubic" }"#)?;
+		assert_eq!(config.resampler(), Resampling::Cubic);
+
+		let sinc = Config::parse(r#"{ "resampler": "sinc" }"#);
+		if cfg!(feature = "direct") {
+			assert!(sinc.is_err());
+		} else {
+			assert_eq!(sinc?.resampler(), Resampling::Sinc);
+		}
+
+		Ok(())
+	}
 }
@@ -0,0 +1,358 @@
+//! text commands to control a running instance
+//!
+//! shared by the http remote and ipc, which forward parsed [`Control`]s to the main loop
+//! and answer status requests with the latest [`Status`]
+
+use crate::state::State;
+use camino::Utf8PathBuf;
+use serde::{Deserialize, Serialize};
+use std::{
+	net::IpAddr,
+	str::FromStr,
+	sync::{
+		Arc, Mutex,
+		mpsc::{self, Receiver, Sender},
+	},
+	time::Duration,
+};
+use thiserror::Error;
+
+/// control error
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ControlError {
+	/// command is empty
+	#[error("empty command")]
+	Empty,
+	/// command isn't known
+	#[error("unknown command {0:?}")]
+	UnknownCommand(String),
+	/// argument is missing or malformed
+	#[error("invalid argument {0:?}")]
+	InvalidArgument(String),
+	/// remote clients can only request tracks and vote in party mode
+	#[error("only requests and votes are allowed in party mode")]
+	NotAllowed,
+}
+
+/// command sent to a running instance or entered into the command palette
+///
+/// parsed from `play`, `pause`, `toggle`, `next`, `prev`, `seek <time>`,
+/// `seek +<time>`, `seek -<time>`, `volume <percent>` or `vol <percent>`,
+/// `shuffle on|off`, `queue <dir>`, `tracks <json array of paths>`, `open <url>`,
+/// `request <track>`, `vote` or `rescan`.
+/// times are seconds or `m:ss`, a leading `~/` in the dir or track is the home directory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Control {
+	Play,
+	Pause,
+	Toggle,
+	Next,
+	Prev,
+	/// seek to the position
+	Seek(Duration),
+	/// seek forwards by the amount
+	Forward(Duration),
+	/// seek backwards by the amount
+	Back(Duration),
+	Volume(u8),
+	Shuffle(bool),
+	/// queue the directory and start playing
+	Queue(Utf8PathBuf),
+	/// queue the tracks in their order and start playing
+	Tracks(Vec<Utf8PathBuf>),
+	/// resolve the url with yt-dlp and play it
+	Open(String),
+	/// request the track in party mode
+	Request(Utf8PathBuf),
+	/// vote to skip the current track in party mode, with the client that voted,
+	/// which is filled in by the server
+	Vote(String),
+	/// update the library index in the background
+	Rescan,
+}
+
+impl FromStr for Control {
+	type Err = ControlError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let s = s.trim();
+		let (command, arg) = s.split_once(char::is_whitespace).unwrap_or((s, ""));
+		let arg = arg.trim();
+
+		let secs = |time: &str| {
+			// `h:mm:ss`, `m:ss` or plain seconds
+			let secs = time.split(':').try_fold(0., |secs, part| {
+				let part = part.parse::<f64>().ok().filter(|part| *part >= 0.)?;
+				Some(secs * 60. + part)
+			});
+
+			secs.and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+				.ok_or_else(|| ControlError::InvalidArgument(arg.to_owned()))
+		};
+
+		let control = match command {
+			"" => return Err(ControlError::Empty),
+			"play" => Control::Play,
+			"pause" => Control::Pause,
+			"toggle" => Control::Toggle,
+			"next" => Control::Next,
+			"prev" => Control::Prev,
+			"seek" => {
+				if let Some(amt) = arg.strip_prefix('+') {
+					Control::Forward(secs(amt)?)
+				} else if let Some(amt) = arg.strip_prefix('-') {
+					Control::Back(secs(amt)?)
+				} else {
+					Control::Seek(secs(arg)?)
+				}
+			}
+			"volume" | "vol" => match arg.parse() {
+				Ok(volume @ 0..=100) => Control::Volume(volume),
+				_ => return Err(ControlError::InvalidArgument(arg.to_owned())),
+			},
+			"shuffle" => match arg {
+				"on" => Control::Shuffle(true),
+				"off" => Control::Shuffle(false),
+				_ => return Err(ControlError::InvalidArgument(arg.to_owned())),
+			},
+			"queue" if !arg.is_empty() => match expand(arg) {
+				Some(path) => Control::Queue(path),
+				None => return Err(ControlError::InvalidArgument(arg.to_owned())),
+			},
+			"queue" => return Err(ControlError::InvalidArgument(arg.to_owned())),
+			"tracks" => match serde_json::from_str(arg) {
+				Ok(paths) => Control::Tracks(paths),
+				Err(_) => return Err(ControlError::InvalidArgument(arg.to_owned())),
+			},
+			"open" if !arg.is_empty() => Control::Open(arg.to_owned()),
+			"open" => return Err(ControlError::InvalidArgument(arg.to_owned())),
+			"request" if !arg.is_empty() => match expand(arg) {
+				Some(path) => Control::Request(path),
+				None => return Err(ControlError::InvalidArgument(arg.to_owned())),
+			},
+			"request" => return Err(ControlError::InvalidArgument(arg.to_owned())),
+			"vote" => Control::Vote(String::new()),
+			"rescan" => Control::Rescan,
+			_ => return Err(ControlError::UnknownCommand(command.to_owned())),
+		};
+
+		Ok(control)
+	}
+}
+
+/// expand a leading `~/` in `path` to the home directory
+///
+/// returns [`None`] if the home directory isn't valid utf-8
+pub fn expand(path: &str) -> Option<Utf8PathBuf> {
+	match (path.strip_prefix("~/"), dirs::home_dir()) {
+		(Some(rest), Some(home)) => Utf8PathBuf::from_path_buf(home.join(rest)).ok(),
+		_ => Some(path.into()),
+	}
+}
+
+/// current track
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrackStatus {
+	pub path: Utf8PathBuf,
+	pub title: Option<String>,
+	pub artist: Option<String>,
+	pub album: Option<String>,
+}
+
+/// snapshot of the [`State`] for other processes
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Status {
+	/// `playing`, `paused` or `stopped`
+	pub status: String,
+	pub track: Option<TrackStatus>,
+	/// elapsed time in seconds
+	pub elapsed: Option<f64>,
+	/// duration in seconds
+	pub duration: Option<f64>,
+	pub volume: u8,
+	pub muted: bool,
+	pub shuffle: bool,
+	/// queued directory
+	pub queue: Option<Utf8PathBuf>,
+}
+
+impl Status {
+	pub fn new(state: &State) -> Self {
+		let status = match (&state.track, state.paused) {
+			(None, _) => "stopped",
+			(Some(_), true) => "paused",
+			(Some(_), false) => "playing",
+		};
+
+		let track = state.track.as_ref().map(|track| TrackStatus {
+			path: track.path().to_owned(),
+			title: track.title().map(str::to_owned),
+			artist: track.artist().map(str::to_owned),
+			album: track.album().map(str::to_owned),
+		});
+
+		Status {
+			status: status.to_owned(),
+			track,
+			elapsed: state.elapsed().map(|elapsed| elapsed.as_secs_f64()),
+			duration: state.duration().map(|duration| duration.as_secs_f64()),
+			volume: state.volume,
+			muted: state.muted,
+			shuffle: state.shuffle,
+			queue: state.queue.clone(),
+		}
+	}
+}
+
+/// main loop side of the controls
+#[derive(Debug)]
+pub struct Controls {
+	rx: Receiver<Control>,
+	handle: Handle,
+}
+
+/// server side of the controls, cloned into every server
+#[derive(Debug, Clone)]
+pub struct Handle {
+	tx: Sender<Control>,
+	status: Arc<Mutex<Status>>,
+	/// remote clients can only request tracks and vote
+	party: bool,
+}
+
+impl Controls {
+	pub fn new(party: bool) -> Self {
+		let (tx, rx) = mpsc::channel();
+		let status = Arc::default();
+
+		Controls {
+			rx,
+			handle: Handle { tx, status, party },
+		}
+	}
+
+	/// handle for a server
+	pub fn handle(&self) -> Handle {
+		self.handle.clone()
+	}
+
+	/// receive the next [`Control`] sent by a server
+	pub fn recv(&self) -> Option<Control> {
+		self.rx.try_recv().ok()
+	}
+
+	/// update the [`Status`] served to other processes
+	pub fn update(&self, state: &State) {
+		*self.handle.status.lock().unwrap() = Status::new(state);
+	}
+}
+
+impl Handle {
+	/// forward `control` to the main loop
+	pub fn send(&self, control: Control) {
+		let _ = self.tx.send(control);
+	}
+
+	/// forward `control` from the remote `client` to the main loop
+	///
+	/// # Errors
+	///
+	/// returns [`ControlError::NotAllowed`] for anything but requests and votes in party mode
+	pub fn send_remote(&self, control: Control, client: IpAddr) -> Result<(), ControlError> {
+		let control = match control {
+			Control::Vote(_) => Control::Vote(client.to_string()),
+			Control::Request(_) => control,
+			_ if self.party => return Err(ControlError::NotAllowed),
+			_ => control,
+		};
+
+		self.send(control);
+		Ok(())
+	}
+
+	/// latest [`Status`]
+	pub fn status(&self) -> Status {
+		self.status.lock().unwrap().clone()
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{Control, ControlError};
+	use std::time::Duration;
+
+	#[test]
+	fn parse() {
+		assert_eq!("toggle".parse(), Ok(Control::Toggle));
+		assert_eq!(
+			" seek 90 ".parse(),
+			Ok(Control::Seek(Duration::from_secs(90)))
+		);
+		assert_eq!(
+			"seek +2.5".parse(),
+			Ok(Control::Forward(Duration::from_millis(2500)))
+		);
+		assert_eq!(
+			"seek -10".parse(),
+			Ok(Control::Back(Duration::from_secs(10)))
+		);
+		assert_eq!("volume 40".parse(), Ok(Control::Volume(40)));
+		assert_eq!("vol 80".parse(), Ok(Control::Volume(80)));
+		assert_eq!(
+			"seek 2:30".parse(),
+			Ok(Control::Seek(Duration::from_secs(150)))
+		);
+		assert_eq!(
+			"seek -1:00:05".parse(),
+			Ok(Control::Back(Duration::from_secs(3605)))
+		);
+		assert_eq!("shuffle off".parse(), Ok(Control::Shuffle(false)));
+		assert_eq!(
+			"queue mock/list 01".parse(),
+			Ok(Control::Queue("mock/list 01".into()))
+		);
+		assert_eq!(
+			r#"tracks ["/music/a.mp3", "/music/b c.mp3"]"#.parse(),
+			Ok(Control::Tracks(vec![
+				"/music/a.mp3".into(),
+				"/music/b c.mp3".into()
+			]))
+		);
+		assert_eq!(
+			"tracks a.mp3".parse::<Control>(),
+			Err(ControlError::InvalidArgument("a.mp3".to_owned()))
+		);
+		assert_eq!(
+			"open https://example.com/track".parse(),
+			Ok(Control::Open("https://example.com/track".to_owned()))
+		);
+
+		assert_eq!(
+			"request mock/list 01/track 01.mp3".parse(),
+			Ok(Control::Request("mock/list 01/track 01.mp3".into()))
+		);
+		assert_eq!("vote".parse(), Ok(Control::Vote(String::new())));
+
+		assert_eq!("".parse::<Control>(), Err(ControlError::Empty));
+		assert_eq!(
+			"stop".parse::<Control>(),
+			Err(ControlError::UnknownCommand("stop".to_owned()))
+		);
+		assert_eq!(
+			"volume 101".parse::<Control>(),
+			Err(ControlError::InvalidArgument("101".to_owned()))
+		);
+		assert_eq!(
+			"seek".parse::<Control>(),
+			Err(ControlError::InvalidArgument(String::new()))
+		);
+		assert_eq!(
+			"seek 2:x".parse::<Control>(),
+			Err(ControlError::InvalidArgument("2:x".to_owned()))
+		);
+		assert_eq!(
+			"shuffle maybe".parse::<Control>(),
+			Err(ControlError::InvalidArgument("maybe".to_owned()))
+		);
+	}
+}
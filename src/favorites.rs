@@ -0,0 +1,151 @@
+//! favorite and excluded tracks
+
+use crate::{config::CONFIG_DIR, queue::Track};
+use camino::{Utf8Path, Utf8PathBuf};
+use serde::Serialize;
+use std::{
+	collections::BTreeSet,
+	fs::{self, File},
+	io::{BufWriter, Write},
+	path::{Path, PathBuf},
+	sync::LazyLock,
+};
+use thiserror::Error;
+
+/// path for favorites file
+///
+/// also used as the queue path when the favorites are queued
+static FAVORITES_PATH: LazyLock<PathBuf> = LazyLock::new(|| CONFIG_DIR.join("favorites.json"));
+
+/// path for excluded file
+static EXCLUDED_PATH: LazyLock<PathBuf> = LazyLock::new(|| CONFIG_DIR.join("excluded.json"));
+
+/// favorites error
+#[derive(Debug, Error)]
+#[allow(clippy::enum_variant_names)]
+pub enum FavoritesError {
+	/// io error
+	#[error("io error")]
+	IoError(#[from] std::io::Error),
+	/// serde error
+	#[error("serde error")]
+	SerdeJsonError(#[from] serde_json::Error),
+}
+
+/// set of favorite tracks, persisted in [`FAVORITES_PATH`]
+#[derive(Debug, Default)]
+pub struct Favorites(BTreeSet<Utf8PathBuf>);
+
+impl Favorites {
+	/// read from file and use [`Default::default`] on error
+	pub fn init() -> Self {
+		Favorites(read(&FAVORITES_PATH))
+	}
+
+	/// path of the virtual favorites list, [`None`] if the config dir isn't valid utf-8
+	pub fn path() -> Option<Utf8PathBuf> {
+		Utf8PathBuf::from_path_buf(FAVORITES_PATH.clone()).ok()
+	}
+
+	/// returns if `path` is the virtual favorites list
+	pub fn is_path(path: &Utf8Path) -> bool {
+		path.as_std_path() == FAVORITES_PATH.as_path()
+	}
+
+	/// returns if the track at `path` is a favorite
+	#[inline]
+	pub fn contains(&self, path: &Utf8Path) -> bool {
+		self.0.contains(path)
+	}
+
+	/// add or remove the track at `path`
+	///
+	/// returns if it is a favorite afterwards
+	pub fn toggle(&mut self, path: &Utf8Path) -> bool {
+		if self.0.remove(path) {
+			false
+		} else {
+			self.0.insert(path.to_owned());
+			true
+		}
+	}
+
+	/// read all favorites that still exist into sorted [`Track`]s
+	pub fn tracks(&self) -> Vec<Track> {
+		let mut tracks = self
+			.0
+			.iter()
+			.filter_map(|path| Track::new(path.clone()).ok())
+			.collect::<Vec<_>>();
+
+		tracks.sort();
+		tracks
+	}
+
+	/// write to file
+	pub fn write(&self) -> Result<(), FavoritesError> {
+		write(&FAVORITES_PATH, &self.0)
+	}
+}
+
+/// set of tracks that are never played unless explicitly selected, persisted in [`EXCLUDED_PATH`]
+#[derive(Debug, Default)]
+pub struct Excluded(BTreeSet<Utf8PathBuf>);
+
+impl Excluded {
+	/// read from file and use [`Default::default`] on error
+	pub fn init() -> Self {
+		Excluded(read(&EXCLUDED_PATH))
+	}
+
+	/// returns if the track at `path` is excluded
+	#[inline]
+	pub fn contains(&self, path: &Utf8Path) -> bool {
+		self.0.contains(path)
+	}
+
+	/// exclude the track at `path` or include it again
+	///
+	/// returns if it is excluded afterwards
+	pub fn toggle(&mut self, path: &Utf8Path) -> bool {
+		if self.0.remove(path) {
+			false
+		} else {
+			self.0.insert(path.to_owned());
+			true
+		}
+	}
+
+	/// write to file
+	pub fn write(&self) -> Result<(), FavoritesError> {
+		write(&EXCLUDED_PATH, &self.0)
+	}
+}
+
+/// read a set of paths from `path`
+fn read(path: &Path) -> BTreeSet<Utf8PathBuf> {
+	fs::read_to_string(path)
+		.ok()
+		.and_then(|file| serde_json::from_str(&file).ok())
+		.unwrap_or_default()
+}
+
+/// write a set of paths to `path`
+fn write(path: &Path, set: &BTreeSet<Utf8PathBuf>) -> Result<(), FavoritesError> {
+	let file = if let Ok(file) = File::create(path) {
+		file
+	} else {
+		fs::create_dir_all(&*CONFIG_DIR)?;
+		File::create(path)?
+	};
+	let mut file = BufWriter::new(file);
+
+	let formatter = serde_json::ser::PrettyFormatter::with_indent(b"\t");
+	let mut json_serializer = serde_json::Serializer::with_formatter(&mut file, formatter);
+
+	set.serialize(&mut json_serializer)?;
+	writeln!(file)?;
+
+	file.flush()?;
+	Ok(())
+}
@@ -0,0 +1,356 @@
+//! replaygain scanner
+//!
+//! measures the integrated loudness of tracks as specified in
+//! [EBU R128](https://tech.ebu.ch/docs/r/r128.pdf) / [ITU-R BS.1770](https://www.itu.int/rec/R-REC-BS.1770)
+//! and writes [replaygain 2.0](https://wiki.hydrogenaud.io/index.php?title=ReplayGain_2.0_specification) tags
+
+use crate::queue::{QueueError, Track};
+use camino::Utf8Path;
+use id3::{Tag, TagLike, Version, frame::ExtendedText};
+use std::{collections::BTreeMap, f64::consts::PI, fs::File};
+use symphonia::core::{
+	audio::SampleBuffer, codecs::DecoderOptions, errors::Error as SymphoniaError,
+	formats::FormatOptions, io::MediaSourceStream, meta::MetadataOptions, probe::Hint,
+};
+use thiserror::Error;
+
+/// replaygain 2.0 reference loudness in LUFS
+const REFERENCE: f64 = -18.0;
+
+/// gain error
+#[derive(Debug, Error)]
+pub enum GainError {
+	/// io error
+	#[error("io error")]
+	IoError(#[from] std::io::Error),
+	/// queue error
+	#[error("queue error")]
+	QueueError(#[from] QueueError),
+	/// decoding error
+	#[error("couldn't decode {0:?}")]
+	DecodeError(String, #[source] SymphoniaError),
+	/// track has no audio
+	#[error("no audio track in {0:?}")]
+	NoAudio(String),
+	/// id3 error
+	#[error("id3 error")]
+	Id3Error(#[from] id3::Error),
+}
+
+/// second order iir filter
+#[derive(Debug, Clone, Copy)]
+struct Biquad {
+	b: [f64; 3],
+	a: [f64; 2],
+	z: [f64; 2],
+}
+
+impl Biquad {
+	/// high shelf of the k-weighting filter
+	fn shelf(sample_rate: f64) -> Self {
+		let f0 = 1_681.974_450_955_533;
+		let gain = 3.999_843_853_973_347;
+		let q = 0.707_175_236_955_419_6;
+
+		let k = f64::tan(PI * f0 / sample_rate);
+		let vh = f64::powf(10.0, gain / 20.0);
+		let vb = f64::powf(vh, 0.499_666_774_154_541_6);
+
+		let a0 = 1.0 + k / q + k * k;
+		Biquad {
+			b: [
+				(vh + vb * k / q + k * k) / a0,
+				2.0 * (k * k - vh) / a0,
+				(vh - vb * k / q + k * k) / a0,
+			],
+			a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+			z: [0.0; 2],
+		}
+	}
+
+	/// high pass of the k-weighting filter
+	fn high_pass(sample_rate: f64) -> Self {
+		let f0 = 38.135_470_876_024_44;
+		let q = 0.500_327_037_323_877_3;
+
+		let k = f64::tan(PI * f0 / sample_rate);
+
+		let a0 = 1.0 + k / q + k * k;
+		Biquad {
+			b: [1.0, -2.0, 1.0],
+			a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+			z: [0.0; 2],
+		}
+	}
+
+	/// transposed direct form ii
+	fn process(&mut self, x: f64) -> f64 {
+		let y = self.b[0] * x + self.z[0];
+		self.z[0] = self.b[1] * x - self.a[0] * y + self.z[1];
+		self.z[1] = self.b[2] * x - self.a[1] * y;
+		y
+	}
+}
+
+/// loudness meter for a single track
+#[derive(Debug)]
+struct Meter {
+	/// k-weighting filters per channel
+	filters: Vec<[Biquad; 2]>,
+	/// number of frames in a 100ms segment
+	segment_len: usize,
+	/// frames in the current segment
+	frames: usize,
+	/// sum of squares in the current segment
+	energy: f64,
+	/// sum of squares of every finished segment
+	segments: Vec<f64>,
+	/// sample peak
+	peak: f32,
+}
+
+impl Meter {
+	fn new(sample_rate: u32, channels: usize) -> Self {
+		let rate = f64::from(sample_rate);
+		let filters = vec![[Biquad::shelf(rate), Biquad::high_pass(rate)]; channels];
+
+		Meter {
+			filters,
+			segment_len: (sample_rate / 10) as usize,
+			frames: 0,
+			energy: 0.0,
+			segments: Vec::new(),
+			peak: 0.0,
+		}
+	}
+
+	/// push an interleaved frame
+	fn push(&mut self, frame: &[f32]) {
+		for (sample, [shelf, high_pass]) in frame.iter().zip(&mut self.filters) {
+			self.peak = self.peak.max(sample.abs());
+
+			let y = high_pass.process(shelf.process(f64::from(*sample)));
+			self.energy += y * y;
+		}
+
+		self.frames += 1;
+		if self.frames == self.segment_len {
+			self.segments.push(self.energy);
+			self.frames = 0;
+			self.energy = 0.0;
+		}
+	}
+
+	/// mean square of the 400ms gating blocks, overlapping by 75%
+	fn blocks(&self) -> impl Iterator<Item = f64> + '_ {
+		let len = (self.segment_len * 4) as f64;
+		self.segments
+			.windows(4)
+			.map(move |window| window.iter().sum::<f64>() / len)
+	}
+}
+
+/// loudness in LUFS of a mean square
+fn lufs(mean_square: f64) -> f64 {
+	-0.691 + 10.0 * mean_square.log10()
+}
+
+/// gated integrated loudness of a set of blocks
+///
+/// returns [`None`] if every block is below the absolute gate
+fn integrated(blocks: &[f64]) -> Option<f64> {
+	let gated = |threshold: f64| {
+		let (sum, len) = (blocks.iter())
+			.filter(|&&block| lufs(block) > threshold)
+			.fold((0.0, 0usize), |(sum, len), block| (sum + block, len + 1));
+		(len > 0).then(|| sum / len as f64)
+	};
+
+	let absolute = gated(-70.0)?;
+	let relative = gated(lufs(absolute) - 10.0)?;
+	Some(lufs(relative))
+}
+
+/// decode the track and measure it
+fn measure(path: &Utf8Path) -> Result<Meter, GainError> {
+	let err = |err| GainError::DecodeError(path.to_string(), err);
+
+	let file = File::open(path)?;
+	let stream = MediaSourceStream::new(Box::new(file), Default::default());
+
+	let mut hint = Hint::new();
+	if let Some(extension) = path.extension() {
+		hint.with_extension(extension);
+	}
+
+	let probed = symphonia::default::get_probe()
+		.format(
+			&hint,
+			stream,
+			&FormatOptions::default(),
+			&MetadataOptions::default(),
+		)
+		.map_err(err)?;
+	let mut format = probed.format;
+
+	let track = format
+		.default_track()
+		.ok_or_else(|| GainError::NoAudio(path.to_string()))?;
+	let track_id = track.id;
+
+	let mut decoder = symphonia::default::get_codecs()
+		.make(&track.codec_params, &DecoderOptions::default())
+		.map_err(err)?;
+
+	let mut meter = None;
+	let mut buffer = None;
+	loop {
+		let packet = match format.next_packet() {
+			Ok(packet) => packet,
+			Err(SymphoniaError::IoError(io)) if io.kind() == std::io::ErrorKind::UnexpectedEof => {
+				break;
+			}
+			Err(error) => return Err(err(error)),
+		};
+
+		if packet.track_id() != track_id {
+			continue;
+		}
+
+		let decoded = match decoder.decode(&packet) {
+			Ok(decoded) => decoded,
+			// skip corrupt packets
+			Err(SymphoniaError::DecodeError(_)) => continue,
+			Err(error) => return Err(err(error)),
+		};
+
+		let spec = *decoded.spec();
+		let channels = spec.channels.count();
+
+		let buffer =
+			buffer.get_or_insert_with(|| SampleBuffer::<f32>::new(decoded.capacity() as u64, spec));
+		buffer.copy_interleaved_ref(decoded);
+
+		let meter = meter.get_or_insert_with(|| Meter::new(spec.rate, channels));
+		for frame in buffer.samples().chunks_exact(channels) {
+			meter.push(frame);
+		}
+	}
+
+	meter.ok_or_else(|| GainError::NoAudio(path.to_string()))
+}
+
+/// replace a replaygain txxx frame
+fn set(tag: &mut Tag, description: &str, value: String) {
+	tag.remove_extended_text(Some(description), None);
+	tag.add_frame(ExtendedText {
+		description: description.to_owned(),
+		value,
+	});
+}
+
+/// write replaygain tags
+fn write(path: &Utf8Path, track: (f64, f32), album: (f64, f32)) -> Result<(), GainError> {
+	let tag = id3::no_tag_ok(Tag::read_from_path(path))?;
+	let mut tag = tag.unwrap_or_default();
+
+	let (gain, peak) = track;
+	set(&mut tag, "REPLAYGAIN_TRACK_GAIN", format!("{gain:.2} dB"));
+	set(&mut tag, "REPLAYGAIN_TRACK_PEAK", format!("{peak:.6}"));
+
+	let (gain, peak) = album;
+	set(&mut tag, "REPLAYGAIN_ALBUM_GAIN", format!("{gain:.2} dB"));
+	set(&mut tag, "REPLAYGAIN_ALBUM_PEAK", format!("{peak:.6}"));
+
+	tag.write_to_path(path, Version::Id3v24)?;
+	Ok(())
+}
+
+/// scan every track in the directory and write replaygain tags
+///
+/// tracks are grouped into albums by their parent directory
+pub fn scan<P: AsRef<Utf8Path>>(path: P) -> Result<(), GainError> {
+	let tracks = Track::directory(path)?;
+
+	let mut albums = BTreeMap::<&Utf8Path, Vec<&Track>>::new();
+	for track in &tracks {
+		let parent = track.path().parent().unwrap_or(Utf8Path::new(""));
+		albums.entry(parent).or_default().push(track);
+	}
+
+	for (album, tracks) in albums {
+		let mut measured = Vec::with_capacity(tracks.len());
+		for track in tracks {
+			match measure(track.path()) {
+				Ok(meter) => measured.push((track, meter)),
+				Err(err) => eprintln!("skipping {:?}: {err}", track.path()),
+			}
+		}
+
+		let blocks = (measured.iter())
+			.flat_map(|(_, meter)| meter.blocks())
+			.collect::<Vec<_>>();
+		let Some(album_loudness) = integrated(&blocks) else {
+			eprintln!("skipping silent album {album:?}");
+			continue;
+		};
+		let album_peak = (measured.iter())
+			.map(|(_, meter)| meter.peak)
+			.fold(0.0, f32::max);
+		let album_gain = REFERENCE - album_loudness;
+
+		for (track, meter) in &measured {
+			let blocks = meter.blocks().collect::<Vec<_>>();
+			let Some(loudness) = integrated(&blocks) else {
+				eprintln!("skipping silent track {:?}", track.path());
+				continue;
+			};
+			let gain = REFERENCE - loudness;
+
+			match write(track.path(), (gain, meter.peak), (album_gain, album_peak)) {
+				Ok(()) => println!("{gain:>+7.2} dB  {}", track.path()),
+				Err(err) => eprintln!("skipping {:?}: {err}", track.path()),
+			}
+		}
+	}
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod test {
+	use super::{Meter, integrated, lufs};
+
+	/// measure a full scale 1khz sine
+	fn sine(amplitude: f32, channels: usize) -> Option<f64> {
+		let rate = 48000;
+		let mut meter = Meter::new(rate, channels);
+
+		for i in 0..rate * 5 {
+			let t = i as f32 / rate as f32;
+			let sample = amplitude * f32::sin(2.0 * std::f32::consts::PI * 1000.0 * t);
+			meter.push(&vec![sample; channels]);
+		}
+
+		let blocks = meter.blocks().collect::<Vec<_>>();
+		integrated(&blocks)
+	}
+
+	#[test]
+	fn loudness() {
+		// a 0 dBFS 1khz sine in one channel measures -3.01 LUFS
+		let mono = sine(1.0, 1).unwrap();
+		assert!((mono + 3.01).abs() < 0.05, "{mono}");
+
+		// and in both channels +3 LU louder
+		let stereo = sine(1.0, 2).unwrap();
+		assert!((stereo - 0.0).abs() < 0.05, "{stereo}");
+
+		// -20 dBFS
+		let quiet = sine(0.1, 2).unwrap();
+		assert!((quiet + 20.0).abs() < 0.05, "{quiet}");
+
+		assert!(sine(0.0, 2).is_none());
+		assert!(lufs(1.0).is_sign_negative());
+	}
+}
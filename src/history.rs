@@ -0,0 +1,321 @@
+//! log of played tracks, so listening habits can be analyzed with other tools
+//!
+//! every play is appended to [`HISTORY_PATH`] as a line of json and exported with `maym history`
+
+use crate::{
+	config::{CONFIG_DIR, Config},
+	queue::Track,
+};
+use camino::{Utf8Path, Utf8PathBuf};
+use serde::{Deserialize, Serialize};
+use std::{
+	cmp::Reverse,
+	collections::BTreeMap,
+	fs::{self, File, OpenOptions},
+	io::{self, BufRead, BufReader, Write},
+	path::PathBuf,
+	sync::{LazyLock, OnceLock},
+	time::{Duration, SystemTime},
+};
+use thiserror::Error;
+
+/// path of the history file
+static HISTORY_PATH: LazyLock<PathBuf> = LazyLock::new(|| CONFIG_DIR.join("history.jsonl"));
+
+/// plays are recorded
+static ENABLED: OnceLock<bool> = OnceLock::new();
+
+/// history error
+#[derive(Debug, Error)]
+pub enum HistoryError {
+	/// io error
+	#[error("io error")]
+	IoError(#[from] io::Error),
+	/// serde error
+	#[error("serde error")]
+	SerdeJsonError(#[from] serde_json::Error),
+}
+
+/// format of `maym history`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Export {
+	/// a header and a line per play
+	#[default]
+	Csv,
+	/// an array of plays
+	Json,
+}
+
+/// single play of a track
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Play {
+	/// start of the play in seconds since the unix epoch
+	pub started: u64,
+	/// time listened in seconds, without pauses
+	pub listened: f64,
+	/// duration of the track in seconds
+	pub duration: Option<f64>,
+	pub path: Utf8PathBuf,
+	pub title: Option<String>,
+	pub artist: Option<String>,
+	pub album: Option<String>,
+	/// musicbrainz recording id
+	pub musicbrainz: Option<String>,
+}
+
+impl Play {
+	pub fn new(track: &Track, started: SystemTime, listened: Duration) -> Self {
+		let started = started
+			.duration_since(SystemTime::UNIX_EPOCH)
+			.unwrap_or_default();
+
+		Play {
+			started: started.as_secs(),
+			listened: listened.as_secs_f64(),
+			duration: track.duration().map(|duration| duration.as_secs_f64()),
+			path: track.path().to_owned(),
+			title: track.title().map(ToOwned::to_owned),
+			artist: track.artist().map(ToOwned::to_owned),
+			album: track.album().map(ToOwned::to_owned),
+			musicbrainz: track.musicbrainz(),
+		}
+	}
+}
+
+/// enable or disable recording plays from the [`Config`]
+pub fn load(config: &Config) {
+	let _ = ENABLED.set(config.history());
+}
+
+/// append `play` to [`HISTORY_PATH`], if the history is enabled
+pub fn record(play: &Play) {
+	if !ENABLED.get().copied().unwrap_or(false) {
+		return;
+	}
+
+	if let Err(err) = append(play) {
+		log::error!("couldn't record play of {:?}: {err}", play.path);
+	}
+}
+
+fn append(play: &Play) -> Result<(), HistoryError> {
+	fs::create_dir_all(&*CONFIG_DIR)?;
+	let mut file = OpenOptions::new()
+		.create(true)
+		.append(true)
+		.open(&*HISTORY_PATH)?;
+
+	let mut line = serde_json::to_vec(play)?;
+	line.push(b'\n');
+	file.write_all(&line)?;
+	Ok(())
+}
+
+/// read every play from [`HISTORY_PATH`], skipping malformed lines
+fn read() -> Result<Vec<Play>, HistoryError> {
+	let file = match File::open(&*HISTORY_PATH) {
+		Ok(file) => file,
+		Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+		Err(err) => return Err(err.into()),
+	};
+
+	let mut plays = Vec::new();
+	for line in BufReader::new(file).lines() {
+		if let Ok(play) = serde_json::from_str(&line?) {
+			plays.push(play);
+		}
+	}
+
+	Ok(plays)
+}
+
+/// directories of the most recently played tracks, newest first
+pub fn recent(count: usize) -> Vec<Utf8PathBuf> {
+	read().map_or_else(|_| Vec::new(), |plays| recent_dirs(&plays, count))
+}
+
+/// most played albums as their name and directory, most plays first
+pub fn albums(count: usize) -> Vec<(String, Utf8PathBuf)> {
+	read().map_or_else(|_| Vec::new(), |plays| top_albums(&plays, count))
+}
+
+fn recent_dirs(plays: &[Play], count: usize) -> Vec<Utf8PathBuf> {
+	let mut dirs = Vec::<Utf8PathBuf>::new();
+	for dir in plays.iter().rev().filter_map(|play| play.path.parent()) {
+		if dirs.len() == count {
+			break;
+		} else if !dirs.iter().any(|seen| seen == dir) {
+			dirs.push(dir.to_owned());
+		}
+	}
+
+	dirs
+}
+
+/// group `plays` by the directory of the track, named after the latest album tag
+fn top_albums(plays: &[Play], count: usize) -> Vec<(String, Utf8PathBuf)> {
+	let mut albums = BTreeMap::<&Utf8Path, (usize, Option<&str>)>::new();
+	for play in plays {
+		let Some(dir) = play.path.parent() else {
+			continue;
+		};
+
+		let (plays, name) = albums.entry(dir).or_default();
+		*plays += 1;
+		*name = play.album.as_deref().or(*name);
+	}
+
+	let mut albums = albums.into_iter().collect::<Vec<_>>();
+	albums.sort_by_key(|&(_, (plays, _))| Reverse(plays));
+	albums
+		.into_iter()
+		.take(count)
+		.map(|(dir, (_, name))| {
+			let name = name.or(dir.file_name()).unwrap_or(dir.as_str());
+			(name.to_owned(), dir.to_owned())
+		})
+		.collect()
+}
+
+/// print the history in `format` to stdout
+pub fn export(format: Export) -> Result<(), HistoryError> {
+	let plays = read()?;
+	let mut stdout = io::stdout().lock();
+	write(&plays, format, &mut stdout)?;
+	stdout.flush()?;
+	Ok(())
+}
+
+/// write `plays` in `format`, with the start as a rfc 3339 timestamp
+fn write<W: Write>(plays: &[Play], format: Export, out: &mut W) -> Result<(), HistoryError> {
+	match format {
+		Export::Csv => {
+			writeln!(
+				out,
+				"started,listened,duration,path,title,artist,album,musicbrainz"
+			)?;
+			for play in plays {
+				let fields = [
+					timestamp(play.started),
+					format!("{:.1}", play.listened),
+					play.duration
+						.map_or_else(String::new, |d| format!("{d:.1}")),
+					csv(play.path.as_str()),
+					csv(play.title.as_deref().unwrap_or_default()),
+					csv(play.artist.as_deref().unwrap_or_default()),
+					csv(play.album.as_deref().unwrap_or_default()),
+					csv(play.musicbrainz.as_deref().unwrap_or_default()),
+				];
+				writeln!(out, "{}", fields.join(","))?;
+			}
+		}
+		Export::Json => {
+			let plays = plays
+				.iter()
+				.map(|play| {
+					let mut json = serde_json::to_value(play)?;
+					json["started"] = timestamp(play.started).into();
+					Ok(json)
+				})
+				.collect::<Result<Vec<_>, serde_json::Error>>()?;
+			serde_json::to_writer_pretty(&mut *out, &plays)?;
+			writeln!(out)?;
+		}
+	}
+
+	Ok(())
+}
+
+/// quote `field` if it contains a separator, quote or line break
+fn csv(field: &str) -> String {
+	if field.contains([',', '"', '\n', '\r']) {
+		format!("\"{}\"", field.replace('"', "\"\""))
+	} else {
+		field.to_owned()
+	}
+}
+
+/// format seconds since the unix epoch as a rfc 3339 timestamp in utc
+fn timestamp(secs: u64) -> String {
+	let (days, secs) = (secs / 86400, secs % 86400);
+
+	// https://howardhinnant.github.io/date_algorithms.html#civil_from_days
+	let z = days + 719_468;
+	let era = z / 146_097;
+	let doe = z % 146_097;
+	let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+	let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+	let mp = (5 * doy + 2) / 153;
+	let day = doy - (153 * mp + 2) / 5 + 1;
+	let month = if mp < 10 { mp + 3 } else { mp - 9 };
+	let year = yoe + era * 400 + u64::from(month <= 2);
+
+	let (hour, minute, second) = (secs / 3600, secs / 60 % 60, secs % 60);
+	format!("{year:04}-{month:02}-{day:02}T{hour:02}:{minute:02}:{second:02}Z")
+}
+
+#[cfg(test)]
+mod test {
+	use super::{Export, Play, recent_dirs, timestamp, top_albums, write};
+
+	#[test]
+	fn export() {
+		assert_eq!(timestamp(0), "1970-01-01T00:00:00Z");
+		assert_eq!(timestamp(951_782_400), "2000-02-29T00:00:00Z");
+		assert_eq!(timestamp(1_791_245_045), "2026-10-06T00:04:05Z");
+
+		let plays = [Play {
+			started: 951_782_400,
+			listened: 61.25,
+			duration: Some(231.),
+			path: "mock/list 01/track 01.mp3".into(),
+			title: Some("Hello, \"World\"".to_owned()),
+			artist: Some("artist".to_owned()),
+			album: None,
+			musicbrainz: None,
+		}];
+
+		let mut csv = Vec::new();
+		write(&plays, Export::Csv, &mut csv).unwrap();
+		assert_eq!(
+			String::from_utf8(csv).unwrap(),
+			"started,listened,duration,path,title,artist,album,musicbrainz\n\
+			2000-02-29T00:00:00Z,61.2,231.0,mock/list 01/track 01.mp3,\"Hello, \"\"World\"\"\",artist,,\n"
+		);
+
+		let mut json = Vec::new();
+		write(&plays, Export::Json, &mut json).unwrap();
+		let json = serde_json::from_slice::<serde_json::Value>(&json).unwrap();
+		assert_eq!(json[0]["started"], "2000-02-29T00:00:00Z");
+		assert_eq!(json[0]["listened"], 61.25);
+		assert_eq!(json[0]["title"], "Hello, \"World\"");
+	}
+
+	#[test]
+	fn start() {
+		let play = |path: &str, album: Option<&str>| Play {
+			started: 0,
+			listened: 0.,
+			duration: None,
+			path: path.into(),
+			title: None,
+			artist: None,
+			album: album.map(ToOwned::to_owned),
+			musicbrainz: None,
+		};
+
+		let plays = [
+			play("mock/list 01/track 01.mp3", Some("first")),
+			play("mock/list 02/track 01.mp3", None),
+			play("mock/list 01/track 02.mp3", None),
+			play("mock/list 03/track 01.mp3", None),
+		];
+
+		let recent = recent_dirs(&plays, 2);
+		assert_eq!(recent, ["mock/list 03", "mock/list 01"]);
+
+		let albums = top_albums(&plays, 2);
+		assert_eq!(albums[0], ("first".to_owned(), "mock/list 01".into()));
+		assert_eq!(albums[1], ("list 02".to_owned(), "mock/list 02".into()));
+	}
+}
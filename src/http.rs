@@ -0,0 +1,279 @@
+//! small http remote, so a browser on the lan can control maym
+//!
+//! - `GET /` serves a page with buttons, opened as `/?token=<token>`
+//! - `GET /status` returns the [`Status`](crate::control::Status) as json
+//! - `POST /<command>` sends a [`Control`], the body is its argument, like `POST /seek` with `+10`.
+//!   in party mode only `POST /request` and `POST /vote` are allowed,
+//!   `open` and `rescan` are never allowed
+//!
+//! everything but the page needs the [`Http::token`] as `authorization: Bearer <token>`,
+//! and posts from another origin are rejected, so other sites can't control maym
+
+use crate::{
+	config::Http,
+	control::{Control, ControlError, Handle},
+};
+use std::{
+	io::{self, BufRead, BufReader, Read, Write},
+	net::{IpAddr, TcpListener, TcpStream},
+	time::Duration,
+};
+
+/// time to wait for a slow client
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+/// maximum size of a request body
+const MAX_BODY: u64 = 4096;
+
+/// page served at `/`
+const INDEX: &str = r#"<!doctype html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>maym</title>
+<style>
+body { font-family: sans-serif; text-align: center; margin: 2em 1em; }
+button { font-size: 1.5em; margin: 0.2em; min-width: 3em; }
+</style>
+</head>
+<body>
+<h2 id="title">maym</h2>
+<p id="artist"></p>
+<p id="time"></p>
+<p>
+<button onclick="send('seek', '-10')">-10</button>
+<button onclick="send('prev')">prev</button>
+<button onclick="send('toggle')">play/pause</button>
+<button onclick="send('next')">next</button>
+<button onclick="send('seek', '+10')">+10</button>
+</p>
+<p><input id="volume" type="range" min="0" max="100" onchange="send('volume', this.value)"></p>
+<p>
+<input id="request" placeholder="path of a track">
+<button onclick="send('request', document.getElementById('request').value)">request</button>
+<button onclick="send('vote')">vote to skip</button>
+</p>
+<script>
+const headers = { authorization: `Bearer ${new URLSearchParams(location.search).get("token") ?? ""}` };
+const time = (secs) => secs == null ? "--:--" : `${Math.floor(secs / 60)}:${String(Math.floor(secs % 60)).padStart(2, "0")}`;
+async function send(command, arg = "") {
+	await fetch(`/${command}`, { method: "POST", body: arg, headers });
+	await update();
+}
+async function update() {
+	const status = await (await fetch("/status", { headers })).json();
+	const track = status.track ?? {};
+	document.getElementById("title").textContent = track.title ?? track.path ?? "maym";
+	document.getElementById("artist").textContent = track.artist ?? "";
+	document.getElementById("time").textContent = `${status.status} ${time(status.elapsed)} / ${time(status.duration)}`;
+	document.getElementById("volume").value = status.volume;
+}
+update();
+setInterval(update, 1000);
+</script>
+</body>
+</html>
+"#;
+
+/// serve the remote on [`Http::addr`] in the background
+pub fn serve(http: Http, handle: Handle) {
+	let Http { addr, token } = http;
+	if token.is_empty() {
+		log::error!("not serving the http remote without a token");
+		return;
+	}
+
+	let listener = match TcpListener::bind(addr) {
+		Ok(listener) => listener,
+		Err(err) => {
+			log::error!("couldn't listen on {addr}: {err}");
+			return;
+		}
+	};
+
+	let spawned = std::thread::Builder::new()
+		.name("http".to_owned())
+		.spawn(move || {
+			for stream in listener.incoming().flatten() {
+				if let Err(err) = respond(stream, &handle, &token) {
+					log::warn!("http request failed: {err}");
+				}
+			}
+		});
+
+	if let Err(err) = spawned {
+		log::error!("couldn't spawn http thread: {err}");
+	}
+}
+
+/// `origin` of a request names the same server as its `host`
+///
+/// requests without an origin don't come from a browser, a `null` origin is never the same
+fn same_origin(origin: Option<&str>, host: Option<&str>) -> bool {
+	let Some(origin) = origin else {
+		return true;
+	};
+
+	let origin = origin
+		.strip_prefix("http://")
+		.or_else(|| origin.strip_prefix("https://"));
+	origin.is_some_and(|origin| host.is_some_and(|host| origin.eq_ignore_ascii_case(host)))
+}
+
+/// compare the tokens without returning early, so their prefix can't be timed
+fn authorized(token: &str, expected: &str) -> bool {
+	token.len() == expected.len()
+		&& token
+			.bytes()
+			.zip(expected.bytes())
+			.fold(0, |diff, (a, b)| diff | (a ^ b))
+			== 0
+}
+
+/// read a request from `stream` and answer it
+fn respond(mut stream: TcpStream, handle: &Handle, token: &str) -> io::Result<()> {
+	stream.set_read_timeout(Some(TIMEOUT))?;
+	stream.set_write_timeout(Some(TIMEOUT))?;
+
+	let mut reader = BufReader::new(&stream);
+	let mut request = String::new();
+	reader.read_line(&mut request)?;
+
+	let mut len = 0;
+	let (mut origin, mut host, mut bearer) = (None, None, None);
+	loop {
+		let mut header = String::new();
+		if reader.read_line(&mut header)? == 0 || header.trim().is_empty() {
+			break;
+		}
+
+		let Some((name, value)) = header.split_once(':') else {
+			continue;
+		};
+		let value = value.trim().to_owned();
+		match name.to_ascii_lowercase().as_str() {
+			"content-length" => len = value.parse().unwrap_or(0),
+			"origin" => origin = Some(value),
+			"host" => host = Some(value),
+			"authorization" => bearer = value.strip_prefix("Bearer ").map(str::to_owned),
+			_ => {}
+		}
+	}
+
+	let mut body = String::new();
+	reader.take(len.min(MAX_BODY)).read_to_string(&mut body)?;
+
+	let mut parts = request.split_whitespace();
+	let (method, path) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+
+	let path = path.split_once('?').map_or(path, |(path, _)| path);
+	if method == "GET" && path == "/" {
+		return write(&mut stream, "200 OK", "text/html; charset=utf-8", INDEX);
+	}
+
+	if !bearer.is_some_and(|bearer| authorized(&bearer, token)) {
+		return write(
+			&mut stream,
+			"401 Unauthorized",
+			"text/plain",
+			"unauthorized",
+		);
+	}
+	if method == "POST" && !same_origin(origin.as_deref(), host.as_deref()) {
+		return write(
+			&mut stream,
+			"403 Forbidden",
+			"text/plain",
+			"cross-origin request",
+		);
+	}
+
+	match (method, path) {
+		("GET", "/status") => {
+			let json = serde_json::to_string(&handle.status()).map_err(io::Error::other)?;
+			write(&mut stream, "200 OK", "application/json", &json)
+		}
+		("POST", command) => {
+			let command = format!("{} {body}", command.trim_start_matches('/'));
+			let parsed = command.parse::<Control>();
+
+			// fetching urls and rescanning the library stay local
+			if let Ok(Control::Open(_) | Control::Rescan) = parsed {
+				return write(
+					&mut stream,
+					"403 Forbidden",
+					"text/plain",
+					"not allowed over http",
+				);
+			}
+
+			let sent = parsed.and_then(|control| {
+				let client = stream
+					.peer_addr()
+					.map_or(IpAddr::from([0; 4]), |addr| addr.ip());
+				handle.send_remote(control, client)
+			});
+
+			match sent {
+				Ok(()) => write(&mut stream, "204 No Content", "text/plain", ""),
+				Err(ControlError::NotAllowed) => write(
+					&mut stream,
+					"403 Forbidden",
+					"text/plain",
+					&ControlError::NotAllowed.to_string(),
+				),
+				Err(err) => write(
+					&mut stream,
+					"400 Bad Request",
+					"text/plain",
+					&err.to_string(),
+				),
+			}
+		}
+		("GET", _) => write(&mut stream, "404 Not Found", "text/plain", "not found"),
+		_ => write(
+			&mut stream,
+			"405 Method Not Allowed",
+			"text/plain",
+			"method not allowed",
+		),
+	}
+}
+
+fn write(stream: &mut TcpStream, status: &str, content_type: &str, body: &str) -> io::Result<()> {
+	write!(
+		stream,
+		"HTTP/1.1 {status}\r\ncontent-type: {content_type}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+		body.len()
+	)?;
+	stream.flush()
+}
+
+#[cfg(test)]
+mod test {
+	use super::{authorized, same_origin};
+
+	#[test]
+	fn origin() {
+		assert!(same_origin(None, Some("192.168.1.2:8080")));
+		assert!(same_origin(
+			Some("http://192.168.1.2:8080"),
+			Some("192.168.1.2:8080")
+		));
+		assert!(!same_origin(
+			Some("https://example.com"),
+			Some("192.168.1.2:8080")
+		));
+		assert!(!same_origin(Some("null"), Some("192.168.1.2:8080")));
+		assert!(!same_origin(Some("http://192.168.1.2:8080"), None));
+	}
+
+	#[test]
+	fn token() {
+		assert!(authorized("secret", "secret"));
+		assert!(!authorized("secreT", "secret"));
+		assert!(!authorized("secret!", "secret"));
+		assert!(!authorized("", "secret"));
+	}
+}
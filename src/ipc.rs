@@ -0,0 +1,272 @@
+//! unix socket to control a running instance and query its status
+//!
+//! every connection sends a single line. `status` is answered with the [`Status`] as json,
+//! everything else is parsed as a [`Control`] and answered with `ok` or the error
+
+use crate::{
+	cli::Format,
+	config::{self, CONFIG_DIR},
+	control::{Control, Handle, Status},
+	ui::utils::fmt_duration,
+};
+use std::{
+	fs,
+	io::{self, BufRead, BufReader, Write},
+	os::unix::net::{UnixListener, UnixStream},
+	path::PathBuf,
+	sync::LazyLock,
+	time::Duration,
+};
+use thiserror::Error;
+
+/// path of the socket
+///
+/// placed in `$XDG_RUNTIME_DIR` if it is set, in [`CONFIG_DIR`] otherwise.
+/// every profile has its own socket
+static SOCKET_PATH: LazyLock<PathBuf> = LazyLock::new(|| {
+	dirs::runtime_dir()
+		.unwrap_or_else(|| CONFIG_DIR.clone())
+		.join(config::profile_file("maym", "sock"))
+});
+
+/// ipc error
+#[derive(Debug, Error)]
+pub enum IpcError {
+	/// no instance is listening on the socket
+	#[error("maym isn't running")]
+	NotRunning(#[source] io::Error),
+	/// io error
+	#[error("io error")]
+	IoError(#[from] io::Error),
+	/// serde error
+	#[error("serde error")]
+	SerdeJsonError(#[from] serde_json::Error),
+}
+
+/// listening socket, removed on drop
+#[derive(Debug)]
+pub struct Ipc;
+
+impl Ipc {
+	/// listen on [`SOCKET_PATH`] in the background
+	///
+	/// [`None`] if another instance is already listening or the socket couldn't be created
+	pub fn serve(handle: Handle) -> Option<Ipc> {
+		if is_running() {
+			log::warn!("another instance is listening on {:?}", *SOCKET_PATH);
+			return None;
+		}
+
+		// left behind by an instance that didn't exit cleanly
+		let _ = fs::remove_file(&*SOCKET_PATH);
+		let listener = match UnixListener::bind(&*SOCKET_PATH) {
+			Ok(listener) => listener,
+			Err(err) => {
+				log::error!("couldn't listen on {:?}: {err}", *SOCKET_PATH);
+				return None;
+			}
+		};
+
+		let spawned = std::thread::Builder::new()
+			.name("ipc".to_owned())
+			.spawn(move || {
+				for stream in listener.incoming().flatten() {
+					if let Err(err) = respond(stream, &handle) {
+						log::warn!("ipc request failed: {err}");
+					}
+				}
+			});
+
+		match spawned {
+			Ok(_) => Some(Ipc),
+			Err(err) => {
+				log::error!("couldn't spawn ipc thread: {err}");
+				None
+			}
+		}
+	}
+}
+
+impl Drop for Ipc {
+	fn drop(&mut self) {
+		let _ = fs::remove_file(&*SOCKET_PATH);
+	}
+}
+
+/// read a line from `stream` and answer it
+fn respond(stream: UnixStream, handle: &Handle) -> io::Result<()> {
+	stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+
+	let mut line = String::new();
+	BufReader::new(&stream).read_line(&mut line)?;
+
+	let answer = if line.trim() == "status" {
+		serde_json::to_string(&handle.status()).map_err(io::Error::other)?
+	} else {
+		match line.parse::<Control>() {
+			Ok(control) => {
+				handle.send(control);
+				"ok".to_owned()
+			}
+			Err(err) => err.to_string(),
+		}
+	};
+
+	let mut stream = &stream;
+	writeln!(stream, "{answer}")
+}
+
+/// returns if another instance is listening on [`SOCKET_PATH`]
+pub fn is_running() -> bool {
+	UnixStream::connect(&*SOCKET_PATH).is_ok()
+}
+
+/// send `line` to the running instance and return its answer
+pub fn send(line: &str) -> Result<String, IpcError> {
+	let mut stream = UnixStream::connect(&*SOCKET_PATH).map_err(IpcError::NotRunning)?;
+	writeln!(stream, "{line}")?;
+
+	let mut answer = String::new();
+	BufReader::new(&stream).read_line(&mut answer)?;
+	answer.truncate(answer.trim_end().len());
+	Ok(answer)
+}
+
+/// query the status of the running instance and print it in `format`
+pub fn status(format: Format) -> Result<(), IpcError> {
+	let line = send("status")?;
+	let status = serde_json::from_str::<Status>(&line)?;
+
+	println!("{}", fmt_status(&status, format)?);
+	Ok(())
+}
+
+/// format `status` for the terminal or a status bar
+fn fmt_status(status: &Status, format: Format) -> Result<String, serde_json::Error> {
+	let position = status
+		.elapsed
+		.zip(status.duration)
+		.map(|(elapsed, duration)| {
+			let elapsed = fmt_duration(Duration::from_secs_f64(elapsed));
+			let duration = fmt_duration(Duration::from_secs_f64(duration));
+			format!("{elapsed} / {duration}")
+		});
+
+	let formatted = match format {
+		Format::Plain => {
+			let mut lines = vec![format!("status: {}", status.status)];
+			if let Some(track) = &status.track {
+				lines.extend(track.title.as_ref().map(|title| format!("title: {title}")));
+				lines.extend(
+					track
+						.artist
+						.as_ref()
+						.map(|artist| format!("artist: {artist}")),
+				);
+				lines.extend(track.album.as_ref().map(|album| format!("album: {album}")));
+				lines.push(format!("path: {}", track.path));
+			}
+			lines.extend(position.map(|position| format!("position: {position}")));
+			lines.push(format!("volume: {}%", status.volume));
+			lines.join("\n")
+		}
+		Format::Json => serde_json::to_string(status)?,
+		Format::Waybar => {
+			let tooltip = status
+				.track
+				.as_ref()
+				.and_then(|track| track.album.clone())
+				.into_iter()
+				.chain(position)
+				.collect::<Vec<_>>()
+				.join("\n");
+			let percentage = status
+				.elapsed
+				.zip(status.duration)
+				.filter(|&(_, duration)| duration > 0.)
+				.map_or(0, |(elapsed, duration)| (elapsed / duration * 100.) as u8);
+
+			let json = serde_json::json!({
+				"text": text(status),
+				"tooltip": tooltip,
+				"class": status.status,
+				"alt": status.status,
+				"percentage": percentage,
+			});
+			json.to_string()
+		}
+		Format::Polybar => match status.status.as_str() {
+			"playing" => format!("▶ {}", text(status)),
+			"paused" => format!("⏸ {}", text(status)),
+			_ => String::new(),
+		},
+	};
+
+	Ok(formatted)
+}
+
+/// `artist - title` of the current track, falling back to the file name
+fn text(status: &Status) -> String {
+	let Some(track) = &status.track else {
+		return String::new();
+	};
+
+	match (&track.artist, &track.title) {
+		(Some(artist), Some(title)) => format!("{artist} - {title}"),
+		(None, Some(title)) => title.clone(),
+		_ => track
+			.path
+			.file_name()
+			.unwrap_or(track.path.as_str())
+			.to_owned(),
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::fmt_status;
+	use crate::{
+		cli::Format,
+		control::{Status, TrackStatus},
+	};
+
+	#[test]
+	fn formats() {
+		let status = Status {
+			status: "paused".to_owned(),
+			track: Some(TrackStatus {
+				path: "mock/list 01/track 01.mp3".into(),
+				title: Some("title".to_owned()),
+				artist: Some("artist".to_owned()),
+				album: Some("album".to_owned()),
+			}),
+			elapsed: Some(30.),
+			duration: Some(120.),
+			volume: 40,
+			..Default::default()
+		};
+
+		assert_eq!(
+			fmt_status(&status, Format::Plain).unwrap(),
+			"status: paused\ntitle: title\nartist: artist\nalbum: album\n\
+			path: mock/list 01/track 01.mp3\nposition: 00:30 / 02:00\nvolume: 40%"
+		);
+		assert_eq!(
+			fmt_status(&status, Format::Polybar).unwrap(),
+			"⏸ artist - title"
+		);
+
+		let waybar = fmt_status(&status, Format::Waybar).unwrap();
+		let waybar = serde_json::from_str::<serde_json::Value>(&waybar).unwrap();
+		assert_eq!(waybar["text"], "artist - title");
+		assert_eq!(waybar["tooltip"], "album\n00:30 / 02:00");
+		assert_eq!(waybar["class"], "paused");
+		assert_eq!(waybar["percentage"], 25);
+
+		let stopped = Status {
+			status: "stopped".to_owned(),
+			..Default::default()
+		};
+		assert_eq!(fmt_status(&stopped, Format::Polybar).unwrap(), "");
+	}
+}
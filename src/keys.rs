@@ -0,0 +1,90 @@
+//! global media keys read straight from the input devices
+//!
+//! for setups without a d-bus session, where nothing forwards the media keys over mpris.
+//! the keys aren't grabbed, so other programs still see them.
+//! reading `/dev/input/event*` needs the user to be in the `input` group
+
+use crate::control::{Control, Handle};
+use evdev::{Device, EventSummary, KeyCode};
+
+/// [`Control`] a media key is bound to
+fn control(key: KeyCode) -> Option<Control> {
+	let control = match key {
+		KeyCode::KEY_PLAYPAUSE => Control::Toggle,
+		KeyCode::KEY_PLAYCD => Control::Play,
+		KeyCode::KEY_PAUSECD | KeyCode::KEY_STOPCD => Control::Pause,
+		KeyCode::KEY_NEXTSONG => Control::Next,
+		KeyCode::KEY_PREVIOUSSONG => Control::Prev,
+		_ => return None,
+	};
+
+	Some(control)
+}
+
+/// listen for media keys on every input device that has them
+/// and forward them to the main loop
+pub fn listen(handle: &Handle) {
+	let devices = evdev::enumerate()
+		.map(|(_, device)| device)
+		.filter(|device| {
+			device
+				.supported_keys()
+				.is_some_and(|keys| keys.contains(KeyCode::KEY_PLAYPAUSE))
+		})
+		.collect::<Vec<_>>();
+
+	if devices.is_empty() {
+		log::warn!("no readable input device has media keys");
+		return;
+	}
+
+	for device in devices {
+		let handle = handle.clone();
+		let spawned = std::thread::Builder::new()
+			.name("keys".to_owned())
+			.spawn(move || read(device, &handle));
+
+		if let Err(err) = spawned {
+			log::error!("couldn't spawn media key thread: {err}");
+		}
+	}
+}
+
+/// forward key presses on `device` until it goes away
+fn read(mut device: Device, handle: &Handle) {
+	let name = device.name().unwrap_or("unknown device").to_owned();
+
+	loop {
+		let events = match device.fetch_events() {
+			Ok(events) => events,
+			Err(err) => {
+				log::warn!("stopped reading media keys from {name:?}: {err}");
+				return;
+			}
+		};
+
+		for event in events {
+			// 1 is a press, 2 a repeat and 0 a release
+			if let EventSummary::Key(_, key, 1) = event.destructure()
+				&& let Some(control) = control(key)
+			{
+				handle.send(control);
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::control;
+	use crate::control::Control;
+	use evdev::KeyCode;
+
+	#[test]
+	fn keys() {
+		assert_eq!(control(KeyCode::KEY_PLAYPAUSE), Some(Control::Toggle));
+		assert_eq!(control(KeyCode::KEY_STOPCD), Some(Control::Pause));
+		assert_eq!(control(KeyCode::KEY_NEXTSONG), Some(Control::Next));
+		assert_eq!(control(KeyCode::KEY_A), None);
+	}
+}
@@ -0,0 +1,426 @@
+//! sqlite index of the tracks in the root lists, so searching the library doesn't walk
+//! every directory and read every tag again
+//!
+//! the index is kept in [`LIBRARY_PATH`] and updated by a background scan at startup,
+//! with `maym rescan` or the `rescan` command, which only read the tags of tracks that
+//! were modified since they were indexed
+
+use crate::{config::CONFIG_DIR, queue::Track};
+use camino::{Utf8Path, Utf8PathBuf};
+use id3::{Tag, TagLike, frame::Popularimeter};
+use rusqlite::{Connection, params};
+use std::{
+	collections::HashMap,
+	fs, io,
+	path::{Path, PathBuf},
+	sync::{
+		LazyLock, OnceLock,
+		atomic::{AtomicBool, AtomicUsize, Ordering},
+	},
+	thread,
+	time::{Duration, SystemTime},
+};
+use thiserror::Error;
+use walkdir::WalkDir;
+
+/// path of the library database
+static LIBRARY_PATH: LazyLock<PathBuf> = LazyLock::new(|| CONFIG_DIR.join("library.db"));
+
+/// the library is indexed
+static ENABLED: OnceLock<bool> = OnceLock::new();
+
+/// a background scan is running
+static SCANNING: AtomicBool = AtomicBool::new(false);
+
+/// tracks checked by the running background scan
+static CHECKED: AtomicUsize = AtomicUsize::new(0);
+
+/// time to wait for the background scan to release the database
+const BUSY_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// tracks written per transaction, so the scan doesn't lock out counting plays
+const BATCH: usize = 256;
+
+const SCHEMA: &str = "
+create table if not exists tracks (
+	path text primary key,
+	modified integer,
+	title text,
+	artist text,
+	album text,
+	track integer,
+	disc integer,
+	genre text,
+	duration real,
+	plays integer not null default 0,
+	rating integer
+);
+";
+
+/// library error
+#[derive(Debug, Error)]
+pub enum LibraryError {
+	/// io error
+	#[error("io error")]
+	IoError(#[from] io::Error),
+	/// sqlite error
+	#[error("sqlite error")]
+	Sqlite(#[from] rusqlite::Error),
+}
+
+/// connection to the library database
+#[derive(Debug)]
+pub struct Library {
+	conn: Connection,
+}
+
+impl Library {
+	/// open the database at [`LIBRARY_PATH`], creating it if it doesn't exist
+	pub fn open() -> Result<Self, LibraryError> {
+		fs::create_dir_all(&*CONFIG_DIR)?;
+		Library::open_at(&LIBRARY_PATH)
+	}
+
+	fn open_at(path: &Path) -> Result<Self, LibraryError> {
+		let conn = Connection::open(path)?;
+		conn.busy_timeout(BUSY_TIMEOUT)?;
+		conn.execute_batch(SCHEMA)?;
+		Ok(Library { conn })
+	}
+
+	/// index the tracks in `roots`, returns how many were read
+	///
+	/// tracks are only read if they were modified since they were indexed,
+	/// tracks that weren't found are removed, unless their root couldn't be walked
+	/// completely, like when it isn't mounted. `progress` is called with
+	/// the number of tracks checked so far
+	pub fn scan(
+		&mut self,
+		roots: &[Utf8PathBuf],
+		mut progress: impl FnMut(usize),
+	) -> Result<usize, LibraryError> {
+		let mut indexed = HashMap::new();
+		{
+			let mut stmt = self.conn.prepare("select path, modified from tracks")?;
+			let rows = stmt.query_map([], |row| {
+				Ok((row.get::<_, String>(0)?, row.get::<_, Option<i64>>(1)?))
+			})?;
+			for row in rows {
+				let (path, modified) = row?;
+				indexed.insert(Utf8PathBuf::from(path), modified);
+			}
+		}
+
+		let mut failed = Vec::new();
+		let mut checked = 0;
+		let mut batch = Vec::with_capacity(BATCH);
+		let mut read = 0;
+		for root in roots {
+			for entry in WalkDir::new(root) {
+				let entry = match entry {
+					Ok(entry) => entry,
+					Err(err) => {
+						log::warn!("couldn't walk all of {root:?}: {err}");
+						failed.push(root);
+						continue;
+					}
+				};
+
+				if !entry.file_type().is_file() {
+					continue;
+				}
+				let Ok(path) = Utf8PathBuf::try_from(entry.into_path()) else {
+					continue;
+				};
+				if path.extension() != Some("mp3") {
+					continue;
+				}
+
+				checked += 1;
+				progress(checked);
+
+				let modified = modified(&path);
+				if indexed
+					.remove(&path)
+					.is_some_and(|indexed| indexed == modified)
+				{
+					continue;
+				}
+
+				let Ok(track) = Track::new(path) else {
+					continue;
+				};
+				batch.push((track, modified));
+				if batch.len() == BATCH {
+					read += self.write(&mut batch)?;
+				}
+			}
+		}
+		read += self.write(&mut batch)?;
+
+		// tracks under a root that was walked completely don't exist anymore,
+		// and tracks outside of every root were removed from the config
+		let stale = indexed
+			.into_keys()
+			.filter(|path| !failed.iter().any(|root| path.starts_with(root)))
+			.collect::<Vec<_>>();
+		for stale in stale.chunks(BATCH) {
+			let tx = self.conn.transaction()?;
+			for path in stale {
+				tx.execute("delete from tracks where path = ?1", [path.as_str()])?;
+			}
+			tx.commit()?;
+		}
+
+		Ok(read)
+	}
+
+	/// write the tracks of `batch` in one transaction and clear it, returns how many were written
+	fn write(&mut self, batch: &mut Vec<(Track, Option<i64>)>) -> Result<usize, LibraryError> {
+		let tx = self.conn.transaction()?;
+		for (track, modified) in batch.iter() {
+			tx.execute(
+				"insert into tracks (path, modified, title, artist, album, track, disc, genre, duration, rating)
+				values (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+				on conflict (path) do update set
+					modified = ?2, title = ?3, artist = ?4, album = ?5, track = ?6,
+					disc = ?7, genre = ?8, duration = coalesce(?9, duration), rating = ?10",
+				params![
+					track.path().as_str(),
+					modified,
+					track.title(),
+					track.artist(),
+					track.album(),
+					track.track(),
+					track.disc(),
+					track.genre(),
+					track.duration().map(|duration| duration.as_secs_f64()),
+					track.rating(),
+				],
+			)?;
+		}
+		tx.commit()?;
+
+		let written = batch.len();
+		batch.clear();
+		Ok(written)
+	}
+
+	/// every indexed track, sorted by path
+	///
+	/// the tags are read from the index instead of the files
+	pub fn tracks(&self) -> Result<Vec<Track>, LibraryError> {
+		let mut stmt = self.conn.prepare(
+			"select path, title, artist, album, track, disc, genre, rating from tracks order by path",
+		)?;
+		let rows = stmt.query_map([], |row| {
+			let mut tag = Tag::new();
+			if let Some(title) = row.get::<_, Option<String>>(1)? {
+				tag.set_title(title);
+			}
+			if let Some(artist) = row.get::<_, Option<String>>(2)? {
+				tag.set_artist(artist);
+			}
+			if let Some(album) = row.get::<_, Option<String>>(3)? {
+				tag.set_album(album);
+			}
+			if let Some(track) = row.get::<_, Option<u32>>(4)? {
+				tag.set_track(track);
+			}
+			if let Some(disc) = row.get::<_, Option<u32>>(5)? {
+				tag.set_disc(disc);
+			}
+			if let Some(genre) = row.get::<_, Option<String>>(6)? {
+				tag.set_genre(genre);
+			}
+			if let Some(rating) = row.get::<_, Option<u8>>(7)? {
+				tag.add_frame(popularimeter(rating));
+			}
+
+			let path = Utf8PathBuf::from(row.get::<_, String>(0)?);
+			Ok(Track::with_tag(path, tag))
+		})?;
+
+		Ok(rows.collect::<Result<_, _>>()?)
+	}
+
+	/// count a play of the track at `path`
+	pub fn played(&self, path: &Utf8Path) -> Result<(), LibraryError> {
+		self.conn.execute(
+			"update tracks set plays = plays + 1 where path = ?1",
+			[path.as_str()],
+		)?;
+		Ok(())
+	}
+}
+
+/// popularimeter tag for a rating from 1 to 5 stars, as [`Track::rating`] reads it
+fn popularimeter(stars: u8) -> Popularimeter {
+	let rating = match stars {
+		0 => 0,
+		1 => 1,
+		2 => 64,
+		3 => 128,
+		4 => 196,
+		_ => 255,
+	};
+
+	Popularimeter {
+		user: String::new(),
+		rating,
+		counter: 0,
+	}
+}
+
+/// modification time in milliseconds since the unix epoch
+fn modified(path: &Utf8Path) -> Option<i64> {
+	let modified = path.metadata().and_then(|meta| meta.modified()).ok()?;
+	let since = modified.duration_since(SystemTime::UNIX_EPOCH).ok()?;
+	i64::try_from(since.as_millis()).ok()
+}
+
+/// enable the library and scan the `roots` in the background
+pub fn load(roots: Vec<Utf8PathBuf>) {
+	let _ = ENABLED.set(true);
+	rescan(roots);
+}
+
+/// update the index of the `roots` in the background, unless a scan is already running
+pub fn rescan(roots: Vec<Utf8PathBuf>) {
+	if SCANNING.swap(true, Ordering::AcqRel) {
+		log::warn!("the library is already being indexed");
+		return;
+	}
+	CHECKED.store(0, Ordering::Release);
+
+	let spawned = thread::Builder::new()
+		.name("library".to_owned())
+		.spawn(move || {
+			let scanned = Library::open().and_then(|mut library| {
+				library.scan(&roots, |checked| CHECKED.store(checked, Ordering::Release))
+			});
+			match scanned {
+				Ok(read) => log::info!("indexed {read} tracks of the library"),
+				Err(err) => log::error!("couldn't index the library: {err}"),
+			}
+
+			SCANNING.store(false, Ordering::Release);
+		});
+	if let Err(err) = spawned {
+		log::error!("couldn't spawn the library scan: {err}");
+		SCANNING.store(false, Ordering::Release);
+	}
+}
+
+/// tracks checked by the background scan, if one is running
+pub fn progress() -> Option<usize> {
+	SCANNING
+		.load(Ordering::Acquire)
+		.then(|| CHECKED.load(Ordering::Acquire))
+}
+
+/// update the index of the `roots` and print the progress, for `maym rescan`
+pub fn cli(roots: &[Utf8PathBuf]) -> Result<(), LibraryError> {
+	let mut library = Library::open()?;
+	let mut total = 0;
+	let read = library.scan(roots, |checked| {
+		total = checked;
+		if checked % 100 == 0 {
+			eprint!("\rchecked {checked} tracks");
+		}
+	})?;
+
+	eprintln!("\rchecked {total} tracks");
+	println!("read the tags of {read} new or modified tracks");
+	Ok(())
+}
+
+fn enabled() -> bool {
+	ENABLED.get().copied().unwrap_or(false)
+}
+
+/// every indexed track, if the library is enabled and was indexed
+pub fn tracks() -> Option<Vec<Track>> {
+	if !enabled() {
+		return None;
+	}
+
+	match Library::open().and_then(|library| library.tracks()) {
+		Ok(tracks) if tracks.is_empty() => None,
+		Ok(tracks) => Some(tracks),
+		Err(err) => {
+			log::error!("couldn't read the library: {err}");
+			None
+		}
+	}
+}
+
+/// count a play of `track`, if the library is enabled
+pub fn played(track: &Track) {
+	if !enabled() {
+		return;
+	}
+
+	if let Err(err) = Library::open().and_then(|library| library.played(track.path())) {
+		log::error!("couldn't count the play of {:?}: {err}", track.path());
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::Library;
+	use camino::{Utf8Path, Utf8PathBuf};
+	use rusqlite::OptionalExtension;
+
+	fn plays(library: &Library, path: &Utf8Path) -> rusqlite::Result<Option<u32>> {
+		library
+			.conn
+			.query_row(
+				"select plays from tracks where path = ?1",
+				[path.as_str()],
+				|row| row.get(0),
+			)
+			.optional()
+	}
+
+	#[test]
+	fn scan() -> color_eyre::Result<()> {
+		let dir = std::env::temp_dir().join(format!("maym-library-{}", std::process::id()));
+		std::fs::create_dir_all(&dir)?;
+		let mut library = Library::open_at(&dir.join("library.db"))?;
+
+		let roots = [Utf8PathBuf::from("mock/list 01")];
+		let mut checked = 0;
+		assert_eq!(library.scan(&roots, |count| checked = count)?, 6);
+		assert_eq!(checked, 6);
+		// unmodified tracks aren't read again
+		assert_eq!(library.scan(&roots, |_| {})?, 0);
+
+		let tracks = library.tracks()?;
+		assert_eq!(tracks.len(), 6);
+		let track = &tracks[0];
+		assert_eq!(track.path(), "mock/list 01/sub 01/track 04.mp3");
+
+		assert_eq!(plays(&library, track.path())?, Some(0));
+		library.played(track.path())?;
+		assert_eq!(plays(&library, track.path())?, Some(1));
+		assert_eq!(plays(&library, "mock/none.mp3".into())?, None);
+
+		// tracks under a root that can't be walked are kept
+		let missing = Utf8PathBuf::from("mock/missing");
+		library.conn.execute(
+			"insert into tracks (path, rating) values (?1, 4)",
+			[missing.join("track.mp3").as_str()],
+		)?;
+		library.scan(&[roots[0].clone(), missing], |_| {})?;
+		assert_eq!(library.tracks()?.len(), 7);
+		assert_eq!(library.tracks()?[6].rating(), Some(4));
+
+		// tracks outside the roots are removed
+		library.scan(&[Utf8PathBuf::from("mock/list 01/sub 01")], |_| {})?;
+		assert_eq!(library.tracks()?.len(), 2);
+
+		std::fs::remove_dir_all(&dir)?;
+		Ok(())
+	}
+}
@@ -0,0 +1,101 @@
+//! logging to a file in [`CONFIG_DIR`]
+//!
+//! recent warnings and errors are also kept in memory to show them in the log popup
+
+use crate::config::CONFIG_DIR;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::{
+	collections::VecDeque,
+	fs::{self, File, OpenOptions},
+	io::Write,
+	path::PathBuf,
+	sync::{LazyLock, Mutex},
+	time::{SystemTime, UNIX_EPOCH},
+};
+
+/// path for log file
+static LOG_PATH: LazyLock<PathBuf> = LazyLock::new(|| CONFIG_DIR.join("maym.log"));
+
+/// size at which the log file is rotated
+const MAX_SIZE: u64 = 1024 * 1024;
+
+/// amount of entries kept for the log popup
+const RECENT: usize = 256;
+
+static LOGGER: Logger = Logger {
+	file: Mutex::new(None),
+	recent: Mutex::new(VecDeque::new()),
+};
+
+struct Logger {
+	file: Mutex<Option<File>>,
+	recent: Mutex<VecDeque<(Level, String)>>,
+}
+
+impl Log for Logger {
+	fn enabled(&self, metadata: &Metadata) -> bool {
+		// only log warnings of dependencies
+		if metadata.target().starts_with(env!("CARGO_CRATE_NAME")) {
+			metadata.level() <= Level::Info
+		} else {
+			metadata.level() <= Level::Warn
+		}
+	}
+
+	fn log(&self, record: &Record) {
+		if !self.enabled(record.metadata()) {
+			return;
+		}
+
+		let level = record.level();
+		let message = record.args().to_string();
+
+		if let Some(file) = &mut *self.file.lock().unwrap() {
+			let time = SystemTime::now()
+				.duration_since(UNIX_EPOCH)
+				.unwrap_or_default()
+				.as_secs();
+			let _ = writeln!(file, "{time} {level:<5} {}: {message}", record.target());
+		}
+
+		if level <= Level::Warn {
+			let mut recent = self.recent.lock().unwrap();
+			if recent.len() == RECENT {
+				recent.pop_front();
+			}
+			recent.push_back((level, message));
+		}
+	}
+
+	fn flush(&self) {
+		if let Some(file) = &mut *self.file.lock().unwrap() {
+			let _ = file.flush();
+		}
+	}
+}
+
+/// open the log file and install the logger
+///
+/// moves the log file to `maym.log.1` once it grows past 1MiB
+pub fn init() {
+	if fs::metadata(&*LOG_PATH).is_ok_and(|metadata| metadata.len() > MAX_SIZE) {
+		let _ = fs::rename(&*LOG_PATH, LOG_PATH.with_extension("log.1"));
+	}
+
+	let file = OpenOptions::new()
+		.create(true)
+		.append(true)
+		.open(&*LOG_PATH)
+		.ok();
+	*LOGGER.file.lock().unwrap() = file;
+
+	if log::set_logger(&LOGGER).is_ok() {
+		log::set_max_level(LevelFilter::Info);
+	}
+}
+
+/// recent warnings and errors, newest first
+pub fn recent() -> Vec<(Level, String)> {
+	let recent = LOGGER.recent.lock().unwrap();
+	recent.iter().rev().cloned().collect()
+}
@@ -1,36 +1,80 @@
+#[cfg(unix)]
+use self::ipc::Ipc;
+#[cfg(feature = "mixer")]
+use self::mixer::Mixer;
+#[cfg(mpris)]
+use self::mpris::{Mpris, MprisEvent};
 use self::{
-	config::Config,
-	player::Player,
-	queue::{Queue, QueueError},
+	cli::{Cli, Command},
+	config::{Config, ConfigError, End},
+	control::{Control, Controls},
+	party::Party,
+	player::{Playable, PlaybackStatus, Player, Preview},
+	queue::{Filter, Queue, QueueError, Track},
 	state::{State, StateError},
-	ui::Ui,
-};
-#[cfg(feature = "mpris")]
-use self::{
-	mpris::{Mpris, MprisEvent},
-	player::PlaybackStatus,
+	ui::{Hover, Prompt, Ui},
 };
+use camino::{Utf8Path, Utf8PathBuf};
 use color_eyre::eyre::Context;
 use ratatui::{
 	DefaultTerminal,
 	crossterm::{
-		event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseEventKind},
-		execute,
+		event::{
+			self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, KeyboardEnhancementFlags,
+			MediaKeyCode, MouseEventKind, PopKeyboardEnhancementFlags,
+			PushKeyboardEnhancementFlags,
+		},
+		execute, terminal,
 	},
 };
-#[cfg(feature = "mpris")]
+#[cfg(unix)]
+use signal_hook::{
+	consts::{SIGHUP, SIGINT, SIGTERM, SIGTSTP, SIGUSR1, SIGUSR2},
+	iterator::Signals,
+};
+#[cfg(mpris)]
 use std::sync::{Arc, Mutex};
-use std::time::{Duration, Instant};
+use std::{
+	io::BufRead,
+	process::Stdio,
+	sync::mpsc::{self, TryRecvError},
+	time::{Duration, Instant},
+};
 use thiserror::Error;
 
+mod bus;
+mod cli;
 mod config;
-#[cfg(feature = "mpris")]
+mod control;
+mod favorites;
+mod gain;
+mod history;
+mod http;
+#[cfg(unix)]
+mod ipc;
+#[cfg(global_keys)]
+mod keys;
+#[cfg(feature = "library")]
+mod library;
+mod logger;
+#[cfg(feature = "mixer")]
+mod mixer;
+#[cfg(mpris)]
 mod mpris;
+mod party;
 mod player;
+mod playlist;
 mod queue;
+mod remote;
 mod state;
 mod ui;
 
+/// time before the end of a track at which the next track is preloaded
+const PRELOAD: Duration = Duration::from_secs(10);
+
+/// time the input thread waits for an event before checking if it should pause
+const INPUT_POLL: Duration = Duration::from_millis(50);
+
 #[derive(Debug, Error)]
 enum MusicError {
 	#[error("quit")]
@@ -47,43 +91,109 @@ enum MusicError {
 struct Application {
 	pub player: Player,
 	pub config: Config,
-	#[cfg(feature = "mpris")]
+	#[cfg(mpris)]
 	pub state: Arc<Mutex<State>>,
-	#[cfg(not(feature = "mpris"))]
+	#[cfg(not(mpris))]
 	pub state: State,
 	pub queue: Queue,
 	pub ui: Ui,
-	#[cfg(feature = "mpris")]
+	#[cfg(mpris)]
 	mpris: Mpris,
+	#[cfg(feature = "mixer")]
+	mixer: Mixer,
+	#[cfg(unix)]
+	signals: Signals,
+	#[cfg(unix)]
+	_ipc: Option<Ipc>,
 	tick: Duration,
+	controls: Controls,
+	/// keyboard enhancement flags are pushed
+	enhanced: bool,
+	/// suspend the tui once the events are handled
+	suspend: Option<Suspend>,
+	/// track previewed from a popup, the playback is ducked while it plays
+	preview: Option<Preview>,
+	/// changing the queue, the tags and the config is disabled
+	kiosk: bool,
+
+	// comm
+	resolve_tx: mpsc::Sender<Utf8PathBuf>,
+	resolved_rx: mpsc::Receiver<Utf8PathBuf>,
 }
 
 impl Application {
-	pub fn new() -> color_eyre::Result<Self> {
+	pub fn new(kiosk: bool) -> color_eyre::Result<Self> {
 		let config = Config::init()?;
 		ui::utils::style::load(&config);
+		remote::load(&config);
+		history::load(&config);
+		queue::load(&config);
+		player::load(&config);
+
+		if config.library() {
+			#[cfg(feature = "library")]
+			library::load(config.roots());
+			#[cfg(not(feature = "library"))]
+			log::warn!("indexing the library needs the library feature");
+		}
 
 		let state = State::init();
-		let queue = Queue::with_state(&state)?;
-		let player = Player::with_state(&queue, &state);
+		let mut queue = Queue::with_state(&state)?;
+		if config.party() {
+			queue.set_party(Party::new(config.skip_votes()));
+		}
+		let player = Player::with_state(&queue, &state, &config);
 
-		let ui = Ui::new(&queue, &config);
+		let mut ui = Ui::new(&queue, &config);
+		ui.change_recent(&state.recent);
+		if kiosk {
+			ui.lock();
+		}
 
-		#[cfg(feature = "mpris")]
+		#[cfg(mpris)]
 		let state = Arc::new(Mutex::new(state));
-		#[cfg(feature = "mpris")]
+		#[cfg(mpris)]
 		let mpris = Mpris::new(Arc::clone(&state));
 
-		let tick = Duration::from_millis(100);
+		#[cfg(unix)]
+		let signals = Signals::new([SIGTERM, SIGHUP, SIGINT, SIGTSTP, SIGUSR1, SIGUSR2])?;
+
+		let tick = config.tick();
+		let controls = Controls::new(config.party());
+		if let Some(http) = config.http() {
+			http::serve(http.clone(), controls.handle());
+		}
+		#[cfg(unix)]
+		let ipc = Ipc::serve(controls.handle());
+		#[cfg(global_keys)]
+		if config.global_media_keys() {
+			keys::listen(&controls.handle());
+		}
+
+		let (resolve_tx, resolved_rx) = mpsc::channel();
 		let app = Application {
 			player,
 			config,
 			state,
 			queue,
 			ui,
-			#[cfg(feature = "mpris")]
+			#[cfg(mpris)]
 			mpris,
+			#[cfg(feature = "mixer")]
+			mixer: Mixer::new(),
+			#[cfg(unix)]
+			signals,
+			#[cfg(unix)]
+			_ipc: ipc,
 			tick,
+			controls,
+			enhanced: false,
+			suspend: None,
+			preview: None,
+			kiosk,
+
+			resolve_tx,
+			resolved_rx,
 		};
 		Ok(app)
 	}
@@ -93,13 +203,22 @@ impl Application {
 		let mut skip_done = false;
 		let mut ticks = 0;
 
+		let input = Input::spawn()?;
+
+		// only redraw after input or when the displayed state changed
+		let mut dirty = true;
+		let mut frame = None;
+
 		loop {
-			#[cfg(feature = "mpris")]
-			terminal.draw(|f| self.ui.draw_lock(f, &self.state, &self.queue))?;
-			#[cfg(not(feature = "mpris"))]
-			terminal.draw(|f| self.ui.draw(f, &self.state, &self.queue))?;
+			if dirty {
+				#[cfg(mpris)]
+				terminal.draw(|f| self.ui.draw_lock(f, &self.state, &self.queue))?;
+				#[cfg(not(mpris))]
+				terminal.draw(|f| self.ui.draw(f, &self.state, &self.queue))?;
+				dirty = false;
+			}
 
-			#[cfg(feature = "mpris")]
+			#[cfg(mpris)]
 			if let Some(event) = self.mpris.recv() {
 				match event {
 					MprisEvent::Next => {
@@ -121,58 +240,379 @@ impl Application {
 					MprisEvent::Shuffle(shuffle) => {
 						self.queue.set_shuffle(shuffle);
 					}
+					MprisEvent::Loop(repeat) => self.queue.set_repeat(repeat),
 					MprisEvent::Volume(vol) => {
 						self.player.set_volume(vol);
 					}
+					MprisEvent::OpenUri(uri) => self.open(uri),
 				}
 			}
 
-			let timeout = self.tick.saturating_sub(last.elapsed());
-			if event::poll(timeout)? {
-				match event::read()? {
-					Event::Key(key) if key.kind == KeyEventKind::Press => {
-						self.handle(key, &mut skip_done)?;
+			if self.ui.poll() {
+				dirty = true;
+			}
+
+			if self.preview.as_ref().is_some_and(Preview::done) {
+				self.preview = None;
+				self.player.duck(false);
+			}
+
+			while let Some(control) = self.controls.recv() {
+				self.control(control, &mut skip_done);
+				dirty = true;
+			}
+
+			// a resolved url is played after the current track
+			if let Ok(path) = self.resolved_rx.try_recv() {
+				let played = Track::new(path.clone())
+					.and_then(|track| self.queue.play_next(track, &mut self.player));
+				match played {
+					Ok(()) => skip_done = true,
+					Err(err) => log::warn!("couldn't queue {path:?}: {err}"),
+				}
+				dirty = true;
+			}
+
+			#[cfg(unix)]
+			for signal in self.signals.pending() {
+				match signal {
+					SIGTERM | SIGHUP | SIGINT => return Err(MusicError::Quit),
+					SIGUSR1 => self.player.toggle(),
+					SIGUSR2 => {
+						self.queue.next(&mut self.player);
+						skip_done = true;
 					}
-					Event::Mouse(mouse) => match mouse.kind {
-						MouseEventKind::ScrollDown => self.ui.down(),
-						MouseEventKind::ScrollUp => self.ui.up(),
-						_ => {}
-					},
+					SIGTSTP => self.suspend = Some(Suspend::Stop),
 					_ => {}
 				}
 			}
 
+			let timeout = self.tick.saturating_sub(last.elapsed());
+			if let Ok(event) = input.rx.recv_timeout(timeout) {
+				dirty = true;
+
+				// handle every queued up event before redrawing
+				for event in std::iter::once(event).chain(input.rx.try_iter()) {
+					let event = event?;
+					// some terminals reflow the old content, so start from a blank screen
+					if let Event::Resize(..) = event {
+						terminal.clear()?;
+					}
+					self.event(event, &mut skip_done)?;
+				}
+			}
+
+			match self.suspend.take() {
+				Some(Suspend::Run(mut command)) => {
+					match self.suspended(terminal, &input, || command.status())? {
+						Ok(status) if !status.success() => {
+							log::warn!("{command:?} exited with {status}")
+						}
+						Ok(_) => {}
+						Err(err) => log::error!("couldn't run {command:?}: {err}"),
+					}
+					dirty = true;
+				}
+				#[cfg(unix)]
+				Some(Suspend::Stop) => {
+					// returns once the process is continued with SIGCONT, like `fg` in a shell
+					let stopped = self.suspended(terminal, &input, || {
+						signal_hook::low_level::emulate_default_handler(SIGTSTP)
+					})?;
+					if let Err(err) = stopped {
+						log::error!("couldn't suspend: {err}");
+					}
+					dirty = true;
+				}
+				None => {}
+			}
+
+			let mut ended = false;
 			if last.elapsed() >= self.tick {
-				#[cfg(feature = "mpris")]
+				#[cfg(mpris)]
 				let state = &mut self.state.lock().unwrap();
-				#[cfg(not(feature = "mpris"))]
+				#[cfg(not(mpris))]
 				let state = &mut self.state;
 
-				#[cfg(feature = "mpris")]
-				state.tick(&mut self.player, &self.queue, &mut self.ui, &mut self.mpris);
-				#[cfg(not(feature = "mpris"))]
-				state.tick(&mut self.player, &self.queue, &mut self.ui, &mut ());
+				#[cfg(feature = "mixer")]
+				self.mixer.sync(&mut self.player);
+
+				self.queue.reload_track();
+
+				let events = state.tick(&mut self.player, &mut self.queue);
+				let changed = !events.is_empty();
+				bus::publish(
+					&events,
+					state,
+					&self.queue,
+					&mut [
+						&mut self.ui,
+						#[cfg(mpris)]
+						&mut self.mpris,
+					],
+				);
+
+				if self.config.speak()
+					&& let Some(text) = state.take_speech()
+				{
+					speak(&text);
+				}
+
+				self.controls.update(state);
+
+				let now = state.frame();
+				if changed || frame != Some(now) {
+					frame = Some(now);
+					dirty = true;
+				}
 
 				if !skip_done {
-					self.queue.done(&mut self.player);
+					ended = self.queue.done(&mut self.player);
 				} else {
 					skip_done = false;
 				}
 
+				// open the next track ahead of time, so slow storage doesn't cause a gap
+				if state
+					.elapsed_duration()
+					.is_some_and(|(elapsed, duration)| duration.saturating_sub(elapsed) < PRELOAD)
+					&& let Some(track) = self.queue.upcoming()
+					&& remote::prefetch(track.path())
+				{
+					self.player.preload(track);
+				}
+
 				last = Instant::now();
 
 				// todo amt
-				if ticks >= 10 {
+				if changed || ticks >= 10 {
 					state.write()?;
 					ticks = 0;
 				} else {
 					ticks += 1;
 				}
 			}
+
+			if ended {
+				self.end()?;
+			}
+		}
+	}
+
+	/// run `f` with the terminal handed back, like while another program uses it
+	fn suspended<T>(
+		&self,
+		terminal: &mut DefaultTerminal,
+		input: &Input,
+		f: impl FnOnce() -> T,
+	) -> Result<T, MusicError> {
+		input.pause();
+
+		let mut stdout = std::io::stdout();
+		if self.enhanced {
+			execute!(stdout, PopKeyboardEnhancementFlags)?;
+		}
+		execute!(stdout, event::DisableMouseCapture)?;
+		ratatui::restore();
+
+		let result = f();
+
+		terminal::enable_raw_mode()?;
+		execute!(
+			stdout,
+			terminal::EnterAlternateScreen,
+			event::EnableMouseCapture
+		)?;
+		if self.enhanced {
+			let flags = KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES;
+			execute!(stdout, PushKeyboardEnhancementFlags(flags))?;
+		}
+		terminal.clear()?;
+
+		input.resume();
+		Ok(result)
+	}
+
+	/// open the current track in the [`Config::tag_editor`] once the tui is suspended
+	fn edit_tags(&mut self) {
+		let Some(track) = self.queue.track() else {
+			return;
+		};
+		let Some(editor) = self.config.tag_editor() else {
+			log::warn!("no tag editor configured");
+			return;
+		};
+
+		// the path is passed as an argument, so it doesn't need to be quoted
+		let mut command = std::process::Command::new("sh");
+		command
+			.arg("-c")
+			.arg(format!("{editor} \"$1\""))
+			.arg("sh")
+			.arg(track.path());
+		self.suspend = Some(Suspend::Run(command));
+	}
+
+	/// handle the end of the queue as set in the [`Config`]
+	fn end(&mut self) -> Result<(), MusicError> {
+		match self.config.end() {
+			End::Stop => self.queue.stop(&mut self.player),
+			End::Clear => {
+				self.queue.empty();
+				self.ui.change_queue(&self.queue);
+			}
+			End::Quit => return Err(MusicError::Quit),
+			End::Hook(command) => {
+				hook(command);
+				self.queue.stop(&mut self.player);
+			}
+		}
+
+		Ok(())
+	}
+
+	fn event(&mut self, event: Event, skip_done: &mut bool) -> Result<(), MusicError> {
+		match event {
+			Event::Key(key) if key.kind == KeyEventKind::Press && self.ui.is_finder() => {
+				*skip_done |= key.code == KeyCode::Enter;
+				self.ui.finder_key(key, &mut self.player, &mut self.queue)?;
+			}
+			Event::Key(key) if key.kind == KeyEventKind::Press && self.ui.is_prompt() => {
+				self.prompt(key, skip_done);
+			}
+			Event::Key(key) if key.kind == KeyEventKind::Press => {
+				self.handle(key, skip_done)?;
+			}
+			Event::Mouse(mouse) => {
+				let hover = self.ui.hover(mouse.column, mouse.row);
+				self.scroll(mouse.kind, mouse.modifiers, hover);
+			}
+			_ => {}
+		}
+
+		Ok(())
+	}
+
+	/// edit the open prompt and apply it once it's submitted
+	fn prompt(&mut self, key: KeyEvent, skip_done: &mut bool) {
+		if key.modifiers == KeyModifiers::CONTROL && key.code == KeyCode::Char('c') {
+			self.ui.prompt_key(KeyCode::Esc);
+			return;
+		}
+
+		match self.ui.prompt_key(key.code) {
+			Some((Prompt::Filter, text)) => self.filter(&text),
+			Some((Prompt::Search, text)) => self.ui.search(text),
+			Some((Prompt::List, text)) if !text.trim().is_empty() => self.add_list(text.trim()),
+			Some((Prompt::Command, text)) if !text.trim().is_empty() => {
+				// the same commands as the ipc server and the http remote
+				match text.parse::<Control>() {
+					Ok(control) => self.control(control, skip_done),
+					Err(err) => log::warn!("invalid command {text:?}: {err}"),
+				}
+			}
+			_ => {}
 		}
 	}
 
+	/// apply the filter entered into the prompt
+	fn filter(&mut self, text: &str) {
+		if text.trim().is_empty() {
+			self.queue.set_filter(None);
+			return;
+		}
+
+		match text.parse::<Filter>() {
+			Ok(filter) => self.queue.set_filter(Some(filter)),
+			Err(err) => log::warn!("invalid filter {text:?}: {err}"),
+		}
+	}
+
+	/// add the directory entered into the prompt to the root lists
+	fn add_list(&mut self, text: &str) {
+		let Some(path) = control::expand(text) else {
+			log::warn!("invalid path {text:?}");
+			return;
+		};
+
+		let added = camino::absolute_utf8(path)
+			.map_err(ConfigError::from)
+			.and_then(|path| self.config.add_list(path));
+		match added {
+			Ok(()) => self.ui.change_lists(self.config.lists()),
+			Err(err) => log::error!("couldn't add list {text:?}: {err}"),
+		}
+	}
+
+	/// preview the track selected in the popup, or stop the preview if it's already playing
+	fn preview(&mut self) {
+		let Some(path) = self.ui.selected_track(&self.queue) else {
+			return;
+		};
+
+		let previous = self.preview.take();
+		if previous.is_some_and(|preview| preview.path() == path) {
+			self.player.duck(false);
+			return;
+		}
+
+		let preview = Preview::start(path);
+		self.player.duck(!preview.cue());
+		self.preview = Some(preview);
+	}
+
+	/// remove the root list at `path` from the config
+	fn remove_list(&mut self, path: &Utf8Path) {
+		match self.config.remove_list(path) {
+			Ok(()) => self.ui.change_lists(self.config.lists()),
+			Err(err) => log::error!("couldn't remove list {path:?}: {err}"),
+		}
+	}
+
+	fn scroll(&mut self, kind: MouseEventKind, modifiers: KeyModifiers, hover: Hover) {
+		let seek = self.config.seek();
+		let vol = self.config.vol();
+
+		#[cfg(mpris)]
+		let state = &self.state.lock().unwrap();
+		#[cfg(not(mpris))]
+		let state = &self.state;
+
+		match (kind, modifiers, hover) {
+			(MouseEventKind::ScrollDown, _, Hover::Popup) => self.ui.down(),
+			(MouseEventKind::ScrollUp, _, Hover::Popup) => self.ui.up(),
+			(MouseEventKind::ScrollDown, KeyModifiers::SHIFT, Hover::Seek) => {
+				self.queue.seek_d(&mut self.player, state, seek);
+			}
+			(MouseEventKind::ScrollUp, KeyModifiers::SHIFT, Hover::Seek) => {
+				self.queue.seek_i(&mut self.player, state, seek);
+			}
+			(MouseEventKind::ScrollDown, _, Hover::Seek) => self.player.d_vol(vol),
+			(MouseEventKind::ScrollUp, _, Hover::Seek) => self.player.i_vol(vol),
+			_ => {}
+		}
+	}
+
+	/// returns if `key` changes the queue, the tags or the config, or leaves maym,
+	/// which isn't allowed in kiosk mode
+	fn destructive(key: KeyEvent) -> bool {
+		matches!(
+			(key.code, key.modifiers),
+			(
+				KeyCode::Char('f' | 'x' | 's' | 'o' | 'a'),
+				KeyModifiers::NONE
+			) | (KeyCode::Char('E' | 'F' | 'O' | 'R' | ':'), _)
+				| (KeyCode::Char('z'), KeyModifiers::CONTROL)
+				| (KeyCode::Delete, KeyModifiers::NONE)
+				| (KeyCode::Up | KeyCode::Down, KeyModifiers::ALT)
+		)
+	}
+
 	fn handle(&mut self, key: KeyEvent, skip_done: &mut bool) -> Result<(), MusicError> {
+		if self.kiosk && Application::destructive(key) {
+			return Ok(());
+		}
+
 		let seek = self.config.seek();
 		let vol = self.config.vol();
 
@@ -186,36 +626,123 @@ impl Application {
 				self.player.toggle();
 			}
 			(KeyCode::Char('m'), KeyModifiers::NONE) => self.player.mute(),
+			(KeyCode::Char('f'), KeyModifiers::NONE) => {
+				if self.queue.toggle_favorite().is_some()
+					&& let Err(err) = self.queue.favorites().write()
+				{
+					log::error!("couldn't write favorites: {err}");
+				}
+			}
+			(KeyCode::Char('x'), KeyModifiers::NONE) => {
+				if self.queue.toggle_excluded().is_some()
+					&& let Err(err) = self.queue.excluded().write()
+				{
+					log::error!("couldn't write excluded tracks: {err}");
+				}
+			}
+			(KeyCode::Media(MediaKeyCode::PlayPause), _) => self.player.toggle(),
+			(KeyCode::Media(MediaKeyCode::Play), _) => self.player.pause(PlaybackStatus::Play),
+			(KeyCode::Media(MediaKeyCode::Pause | MediaKeyCode::Stop), _) => {
+				self.player.pause(PlaybackStatus::Paused);
+			}
+			(KeyCode::Media(MediaKeyCode::MuteVolume), _) => self.player.mute(),
+			(KeyCode::Media(MediaKeyCode::RaiseVolume), _) => self.player.i_vol(vol),
+			(KeyCode::Media(MediaKeyCode::LowerVolume), _) => self.player.d_vol(vol),
+			(KeyCode::Char('['), KeyModifiers::NONE) => self.player.shift_balance(-10),
+			(KeyCode::Char(']'), KeyModifiers::NONE) => self.player.shift_balance(10),
+			(KeyCode::Char('='), KeyModifiers::NONE) => self.player.set_balance(0),
+			(KeyCode::Char('M'), _) => self.player.toggle_mono(),
+			(KeyCode::Char('R'), _) => {
+				let dir = self.config.record_dir();
+				self.player.toggle_recording(&dir);
+			}
 			(KeyCode::Up, KeyModifiers::SHIFT) => self.player.i_vol(vol),
 			(KeyCode::Down, KeyModifiers::SHIFT) => self.player.d_vol(vol),
 			// queue
-			(KeyCode::Right, KeyModifiers::SHIFT) => {
+			(KeyCode::Right, KeyModifiers::SHIFT)
+			| (KeyCode::Media(MediaKeyCode::TrackNext), _) => {
 				self.queue.next(&mut self.player);
 				*skip_done = true;
 			}
-			(KeyCode::Left, KeyModifiers::SHIFT) => {
+			(KeyCode::Left, KeyModifiers::SHIFT)
+			| (KeyCode::Media(MediaKeyCode::TrackPrevious), _) => {
 				self.queue.last(&mut self.player);
 				*skip_done = true;
 			}
 			(KeyCode::Char('0'), KeyModifiers::NONE) => {
 				self.queue.restart(&mut self.player);
 			}
+			(KeyCode::Char('r'), KeyModifiers::NONE) => {
+				let replay = self.config.replay();
+
+				#[cfg(mpris)]
+				let state = self.state.lock().unwrap();
+				#[cfg(mpris)]
+				self.queue.seek_d(&mut self.player, &state, replay);
+				#[cfg(not(mpris))]
+				self.queue.seek_d(&mut self.player, &self.state, replay);
+			}
 			(KeyCode::Char('s'), KeyModifiers::NONE) => {
 				self.queue.shuffle();
 			}
+			(KeyCode::Char('o'), KeyModifiers::NONE) => {
+				self.queue.cycle_order();
+				self.ui.change_queue(&self.queue);
+			}
+			(KeyCode::Char('p'), KeyModifiers::NONE) => self.queue.cycle_repeat(),
 			// ui
-			(KeyCode::Esc, KeyModifiers::NONE) => self.ui.esc(),
+			(KeyCode::Esc, KeyModifiers::NONE) => {
+				if self.ui.is_popup() {
+					self.ui.esc();
+				} else {
+					self.queue.set_filter(None);
+				}
+			}
+			(KeyCode::Char('F'), _) => self.ui.prompt(Prompt::Filter),
+			(KeyCode::Char(':'), _) => self.ui.prompt(Prompt::Command),
+			(KeyCode::Char('O'), _) => {
+				if let Some(dir) = self.queue.track().and_then(|track| track.path().parent()) {
+					reveal(dir);
+				}
+			}
+			(KeyCode::Char('E'), _) => self.edit_tags(),
+			(KeyCode::Char('/'), _) if self.ui.is_searchable() => self.ui.prompt(Prompt::Search),
+			(KeyCode::Char('n'), KeyModifiers::NONE) => self.ui.next_match(),
+			(KeyCode::Char('N'), _) => self.ui.prev_match(),
+			(KeyCode::Char('p'), KeyModifiers::CONTROL) => self.ui.finder(&self.queue),
 			(KeyCode::Char('i'), KeyModifiers::NONE) => self.ui.tags(),
 			(KeyCode::Char('y'), KeyModifiers::NONE) => self.ui.lyrics(),
 			(KeyCode::Char('t'), KeyModifiers::NONE) => self.ui.tracks(),
 			(KeyCode::Char('l'), KeyModifiers::NONE) => self.ui.lists(),
+			(KeyCode::Char('g'), KeyModifiers::NONE) => self.ui.log(),
+			(KeyCode::Char('J'), _) => self.ui.party(),
+			(KeyCode::Tab, _) => self.ui.cycle(true),
+			(KeyCode::BackTab, _) => self.ui.cycle(false),
+			(KeyCode::Char('z'), KeyModifiers::NONE) => self.ui.toggle_mini(),
+			(KeyCode::Char('U'), _) => self.ui.toggle_sidebar(),
+			// raw mode doesn't turn ctrl+z into SIGTSTP
+			#[cfg(unix)]
+			(KeyCode::Char('z'), KeyModifiers::CONTROL) => self.suspend = Some(Suspend::Stop),
+			(KeyCode::Char('e'), KeyModifiers::NONE) => self.ui.cycle_time(),
 			(KeyCode::Down, KeyModifiers::NONE) => self.ui.down(),
 			(KeyCode::Up, KeyModifiers::NONE) => self.ui.up(),
 			(KeyCode::PageDown, KeyModifiers::NONE) => self.ui.pg_down(),
 			(KeyCode::PageUp, KeyModifiers::NONE) => self.ui.pg_up(),
+			(KeyCode::Char('d'), KeyModifiers::CONTROL) => self.ui.half_down(),
+			(KeyCode::Char('u'), KeyModifiers::CONTROL) => self.ui.half_up(),
 			(KeyCode::Home, KeyModifiers::NONE) => self.ui.home(),
 			(KeyCode::End, KeyModifiers::NONE) => self.ui.end(),
 			(KeyCode::Backspace, KeyModifiers::NONE) => self.ui.left(),
+			(KeyCode::Delete, KeyModifiers::NONE) => match self.ui.root() {
+				Some(root) => self.remove_list(&root),
+				None => self.ui.delete(&mut self.queue),
+			},
+			(KeyCode::Char('a'), KeyModifiers::NONE) if self.ui.is_lists() => {
+				self.ui.prompt(Prompt::List);
+			}
+			(KeyCode::Char('v'), KeyModifiers::NONE) if self.ui.is_selectable() => self.preview(),
+			(KeyCode::Up, KeyModifiers::ALT) => self.ui.move_up(&mut self.queue),
+			(KeyCode::Down, KeyModifiers::ALT) => self.ui.move_down(&mut self.queue),
 			(KeyCode::Enter, KeyModifiers::NONE) => {
 				self.ui.enter(&mut self.player, &mut self.queue)?;
 				*skip_done = true;
@@ -233,11 +760,11 @@ impl Application {
 				if self.ui.is_popup() {
 					self.ui.right(&self.queue);
 				} else {
-					#[cfg(feature = "mpris")]
+					#[cfg(mpris)]
 					let state = self.state.lock().unwrap();
-					#[cfg(feature = "mpris")]
+					#[cfg(mpris)]
 					self.queue.seek_i(&mut self.player, &state, seek);
-					#[cfg(not(feature = "mpris"))]
+					#[cfg(not(mpris))]
 					self.queue.seek_i(&mut self.player, &self.state, seek);
 				}
 			}
@@ -245,11 +772,11 @@ impl Application {
 				if self.ui.is_popup() {
 					self.ui.left();
 				} else {
-					#[cfg(feature = "mpris")]
+					#[cfg(mpris)]
 					let state = self.state.lock().unwrap();
-					#[cfg(feature = "mpris")]
+					#[cfg(mpris)]
 					self.queue.seek_d(&mut self.player, &state, seek);
-					#[cfg(not(feature = "mpris"))]
+					#[cfg(not(mpris))]
 					self.queue.seek_d(&mut self.player, &self.state, seek);
 				}
 			}
@@ -260,14 +787,173 @@ impl Application {
 		Ok(())
 	}
 
+	/// apply a [`Control`] sent by another process
+	fn control(&mut self, control: Control, skip_done: &mut bool) {
+		#[cfg(mpris)]
+		let guard = self.state.lock().unwrap();
+		#[cfg(mpris)]
+		let state = &*guard;
+		#[cfg(not(mpris))]
+		let state = &self.state;
+
+		if self.kiosk
+			&& matches!(
+				control,
+				Control::Queue(_) | Control::Tracks(_) | Control::Open(_) | Control::Rescan
+			) {
+			log::warn!("{control:?} isn't allowed in kiosk mode");
+			return;
+		}
+
+		match control {
+			Control::Play => self.player.pause(PlaybackStatus::Play),
+			Control::Pause => self.player.pause(PlaybackStatus::Paused),
+			Control::Toggle => self.player.toggle(),
+			Control::Next => {
+				self.queue.next(&mut self.player);
+				*skip_done = true;
+			}
+			Control::Prev => {
+				self.queue.last(&mut self.player);
+				*skip_done = true;
+			}
+			Control::Seek(position) => {
+				if self.queue.track().is_some() {
+					self.player.seek(position);
+				}
+			}
+			Control::Forward(amt) => self.queue.seek_i(&mut self.player, state, amt),
+			Control::Back(amt) => self.queue.seek_d(&mut self.player, state, amt),
+			Control::Volume(volume) => self.player.set_volume(volume),
+			Control::Shuffle(shuffle) => self.queue.set_shuffle(shuffle),
+			Control::Queue(path) => {
+				let queued = self.queue.queue(&path);
+				match queued {
+					Ok(()) => self.queue.next(&mut self.player),
+					Err(err) => log::warn!("couldn't queue {path:?}: {err}"),
+				}
+			}
+			Control::Tracks(paths) => {
+				#[cfg(mpris)]
+				drop(guard);
+				if let Err(err) = self.queue_paths(paths) {
+					log::warn!("couldn't queue the tracks: {err}");
+				}
+			}
+			Control::Open(url) => remote::resolve(url, self.resolve_tx.clone()),
+			Control::Request(path) => {
+				#[cfg(mpris)]
+				drop(guard);
+				self.request(&path);
+			}
+			Control::Vote(client) => {
+				if self.queue.vote(client, &mut self.player) {
+					*skip_done = true;
+				}
+			}
+			#[cfg(feature = "library")]
+			Control::Rescan => library::rescan(self.config.roots()),
+			#[cfg(not(feature = "library"))]
+			Control::Rescan => log::warn!("indexing the library needs the library feature"),
+		}
+	}
+
+	/// request the track at `path` in party mode, only tracks in the root lists can be requested
+	fn request(&mut self, path: &Utf8Path) {
+		let listed = path.canonicalize_utf8().ok().filter(|path| {
+			let lists = self.config.lists();
+			lists.iter().any(|list| path.starts_with(&list.path))
+		});
+		let Some(path) = listed else {
+			log::warn!("couldn't request {path:?}, it isn't in the lists");
+			return;
+		};
+
+		match Track::new(path) {
+			Ok(track) => {
+				let title = track.to_string();
+				if self.queue.request(track) {
+					log::info!("requested {title}");
+					if self.queue.track().is_none() {
+						self.queue.next(&mut self.player);
+					}
+				}
+			}
+			Err(err) => log::warn!("couldn't request track: {err}"),
+		}
+	}
+
+	/// resolve `url` with yt-dlp and play it once it is downloaded
+	fn open(&mut self, url: String) {
+		remote::resolve(url, self.resolve_tx.clone());
+	}
+
+	/// queue the newline separated paths on stdin in their order and start playing
+	fn stdin(&mut self) -> Result<(), MusicError> {
+		let paths = stdin_paths()?;
+		self.queue_paths(paths)?;
+		Ok(())
+	}
+
+	/// queue the tracks at `paths` in their order and start playing
+	///
+	/// paths that aren't tracks are skipped
+	fn queue_paths(&mut self, paths: Vec<Utf8PathBuf>) -> Result<(), QueueError> {
+		let mut tracks = Vec::new();
+		for path in paths {
+			match Track::new(path.clone()) {
+				Ok(track) => tracks.push(track),
+				Err(err) => log::warn!("skipping {path:?}: {err}"),
+			}
+		}
+
+		self.queue.queue_tracks(tracks)?;
+		self.queue.next(&mut self.player);
+		Ok(())
+	}
+
 	pub fn start(&mut self) -> color_eyre::Result<()> {
 		execute!(std::io::stdout(), event::EnableMouseCapture)?;
 
-		match ratatui::run(|terminal| self.run(terminal)) {
-			Err(MusicError::Quit) | Ok(()) => Ok(()),
+		// media keys are only reported with the kitty keyboard protocol
+		self.enhanced =
+			self.config.media_keys() && terminal::supports_keyboard_enhancement().unwrap_or(false);
+
+		let result = ratatui::run(|terminal| {
+			// the flags are kept per screen, so they have to be pushed
+			// once the alternate screen is entered and popped before it is left
+			if self.enhanced {
+				let flags = KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES;
+				execute!(std::io::stdout(), PushKeyboardEnhancementFlags(flags))?;
+			}
+
+			let result = self.run(terminal);
+
+			if self.enhanced {
+				execute!(std::io::stdout(), PopKeyboardEnhancementFlags)?;
+			}
+			result
+		});
+
+		match result {
+			Err(MusicError::Quit) | Ok(()) => {
+				self.write()?;
+				Ok(())
+			}
 			Err(err) => Err(color_eyre::Report::from(err)),
 		}
 	}
+
+	/// record the current play and write [`State`] to disk
+	fn write(&mut self) -> Result<(), StateError> {
+		#[cfg(mpris)]
+		let mut state = self.state.lock().unwrap();
+		#[cfg(not(mpris))]
+		let state = &mut self.state;
+
+		state.record();
+		state.write()
+	}
 }
 
 impl Drop for Application {
@@ -276,6 +962,131 @@ impl Drop for Application {
 	}
 }
 
+/// reason to hand the terminal back
+#[derive(Debug)]
+enum Suspend {
+	/// stop the process until it's continued, like ctrl+z in a shell
+	#[cfg(unix)]
+	Stop,
+	/// run a program in the terminal and wait for it, like the tag editor
+	Run(std::process::Command),
+}
+
+/// reads terminal events on a separate thread, so they are handled as soon as they arrive
+///
+/// reading is paused while another program uses the terminal
+struct Input {
+	rx: mpsc::Receiver<std::io::Result<Event>>,
+	/// `true` pauses reading, `false` resumes it
+	pause_tx: mpsc::Sender<bool>,
+	/// acknowledges a pause once the thread stopped reading
+	paused_rx: mpsc::Receiver<()>,
+}
+
+impl Input {
+	fn spawn() -> std::io::Result<Self> {
+		let (tx, rx) = mpsc::channel();
+		let (pause_tx, pause_rx) = mpsc::channel();
+		let (paused_tx, paused_rx) = mpsc::channel();
+
+		std::thread::Builder::new()
+			.name("input".to_owned())
+			.spawn(move || {
+				loop {
+					match pause_rx.try_recv() {
+						Ok(true) => {
+							let _ = paused_tx.send(());
+							loop {
+								match pause_rx.recv() {
+									Ok(false) => break,
+									Ok(true) => {}
+									Err(_) => return,
+								}
+							}
+						}
+						Ok(false) | Err(TryRecvError::Empty) => {}
+						Err(TryRecvError::Disconnected) => return,
+					}
+
+					// poll with a timeout, so a pause doesn't wait for the next event
+					match event::poll(INPUT_POLL) {
+						Ok(false) => continue,
+						Ok(true) => {}
+						Err(err) => {
+							let _ = tx.send(Err(err));
+							return;
+						}
+					}
+
+					let event = event::read();
+					let err = event.is_err();
+					if tx.send(event).is_err() || err {
+						return;
+					}
+				}
+			})?;
+
+		Ok(Input {
+			rx,
+			pause_tx,
+			paused_rx,
+		})
+	}
+
+	/// stop reading events, waiting until the thread doesn't read anymore
+	fn pause(&self) {
+		if self.pause_tx.send(true).is_ok() {
+			let _ = self.paused_rx.recv();
+		}
+	}
+
+	fn resume(&self) {
+		let _ = self.pause_tx.send(false);
+	}
+}
+
+/// run `command` in the background, discarding its output
+fn background(mut command: std::process::Command) {
+	let child = command
+		.stdin(Stdio::null())
+		.stdout(Stdio::null())
+		.stderr(Stdio::null())
+		.spawn();
+
+	match child {
+		// reap the process once it's done
+		Ok(mut child) => drop(std::thread::spawn(move || child.wait())),
+		Err(err) => log::warn!("couldn't run {command:?}: {err}"),
+	}
+}
+
+/// run `command` with `sh -c` in the background
+fn hook(command: &str) {
+	let mut sh = std::process::Command::new("sh");
+	sh.arg("-c").arg(command);
+	background(sh);
+}
+
+/// speak `text` with `spd-say` in the background
+fn speak(text: &str) {
+	let mut spd = std::process::Command::new("spd-say");
+	spd.arg("--").arg(text);
+	background(spd);
+}
+
+/// show `dir` in the file manager
+fn reveal(dir: &Utf8Path) {
+	let opener = if cfg!(target_os = "macos") {
+		"open"
+	} else {
+		"xdg-open"
+	};
+
+	let mut open = std::process::Command::new(opener);
+	open.arg(dir);
+	background(open);
+}
+
 fn install() -> color_eyre::Result<()> {
 	color_eyre::install()?;
 
@@ -283,6 +1094,9 @@ fn install() -> color_eyre::Result<()> {
 	std::panic::set_hook(Box::new(move |info| {
 		hook(info);
 
+		log::error!("{info}");
+		log::logger().flush();
+
 		let _ = execute!(std::io::stdout(), event::DisableMouseCapture);
 
 		let thread = std::thread::current();
@@ -295,11 +1109,100 @@ fn install() -> color_eyre::Result<()> {
 	Ok(())
 }
 
+/// forward `command` to the instance that is already running
+///
+/// returns `false` if none is running and a new instance should start
+#[cfg(unix)]
+fn handoff(command: &Command) -> color_eyre::Result<bool> {
+	if !ipc::is_running() {
+		return Ok(false);
+	}
+
+	let control = match command {
+		Command::Queue(dir) => format!("queue {}", camino::absolute_utf8(dir)?),
+		Command::Open(url) => format!("open {url}"),
+		Command::Stdin => format!("tracks {}", serde_json::to_string(&stdin_paths()?)?),
+		_ => color_eyre::eyre::bail!(
+			"maym is already running, start another instance with --new-instance"
+		),
+	};
+
+	let answer = ipc::send(&control)?;
+	if answer != "ok" {
+		color_eyre::eyre::bail!("running instance refused {control:?}: {answer}");
+	}
+
+	println!("forwarded to the running instance");
+	Ok(true)
+}
+
+/// absolute paths of the mp3 files in the newline separated paths on stdin
+fn stdin_paths() -> std::io::Result<Vec<Utf8PathBuf>> {
+	let mut paths = Vec::new();
+	for line in std::io::stdin().lock().lines() {
+		let line = line?;
+		let path = Utf8Path::new(line.trim_end_matches('\r'));
+		if path.as_str().is_empty() || path.extension() != Some("mp3") {
+			continue;
+		}
+
+		paths.push(camino::absolute_utf8(path)?);
+	}
+
+	Ok(paths)
+}
+
 fn main() -> color_eyre::Result<()> {
 	install()?;
 
-	let mut app = Application::new().wrap_err("maym error")?;
-	app.start().wrap_err("maym error")?;
+	let cli = Cli::parse().wrap_err("maym error")?;
+	if let Some(profile) = cli.profile {
+		config::set_profile(profile);
+	}
+
+	match cli.command {
+		command @ (Command::Run | Command::Open(_) | Command::Stdin | Command::Queue(_)) => {
+			#[cfg(unix)]
+			if !cli.new_instance && handoff(&command).wrap_err("maym error")? {
+				return Ok(());
+			}
+
+			player::stream_properties();
+			logger::init();
+
+			let mut app = Application::new(cli.kiosk).wrap_err("maym error")?;
+			match command {
+				Command::Open(url) => app.open(url),
+				Command::Stdin => app.stdin().wrap_err("maym error")?,
+				Command::Queue(dir) => {
+					let dir = camino::absolute_utf8(dir).wrap_err("maym error")?;
+					app.queue.queue(dir).wrap_err("maym error")?;
+					app.queue.next(&mut app.player);
+				}
+				_ => {}
+			}
+			app.start().wrap_err("maym error")?;
+		}
+		Command::ScanGain(dir) => gain::scan(dir).wrap_err("maym error")?,
+		Command::History(format) => history::export(format).wrap_err("maym error")?,
+		Command::Import(file) => {
+			for path in playlist::import(&file).wrap_err("maym error")? {
+				let tracks = playlist::read(&path).map_or(0, |tracks| tracks.len());
+				println!("imported {:?} with {tracks} tracks", playlist::name(&path));
+			}
+		}
+		#[cfg(feature = "library")]
+		Command::Rescan => {
+			let config = Config::init().wrap_err("maym error")?;
+			library::cli(&config.roots()).wrap_err("maym error")?;
+		}
+		#[cfg(not(feature = "library"))]
+		Command::Rescan => color_eyre::eyre::bail!("maym rescan needs the library feature"),
+		#[cfg(unix)]
+		Command::Status(format) => ipc::status(format).wrap_err("maym error")?,
+		#[cfg(not(unix))]
+		Command::Status(_) => color_eyre::eyre::bail!("maym status is only supported on unix"),
+	}
 
 	Ok(())
 }
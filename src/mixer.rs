@@ -0,0 +1,201 @@
+//! sync the volume with the sink input of maym in pulseaudio or pipewire
+//!
+//! talks to the sound server with `pactl`, which also works with pipewire-pulse
+
+use crate::player::Playable;
+use serde_json::Value;
+use std::{
+	io::{BufRead, BufReader},
+	process::{Child, Command, Stdio},
+	sync::mpsc::{self, Receiver, Sender},
+};
+
+/// keeps the volume of the [`Player`](crate::player::Player) and its sink input in sync
+#[derive(Debug)]
+pub struct Mixer {
+	/// `pactl subscribe`, killed on drop
+	monitor: Option<Child>,
+	/// index of the sink input of maym
+	index: Option<u32>,
+	/// last volume known to both sides
+	volume: Option<u8>,
+
+	// comm
+	from_monitor_rx: Receiver<(u32, u8)>,
+	to_setter_tx: Sender<(u32, u8)>,
+}
+
+impl Mixer {
+	pub fn new() -> Self {
+		let (to_main_tx, from_monitor_rx) = mpsc::channel();
+		let (to_setter_tx, from_main_rx) = mpsc::channel::<(u32, u8)>();
+
+		let monitor = Command::new("pactl")
+			.arg("subscribe")
+			.stdin(Stdio::null())
+			.stdout(Stdio::piped())
+			.stderr(Stdio::null())
+			.spawn();
+
+		let monitor = match monitor {
+			Ok(mut child) => {
+				let stdout = child.stdout.take().expect("stdout should be piped");
+				std::thread::Builder::new()
+					.name("mixer".to_owned())
+					.spawn(move || {
+						// the sink input may already exist
+						query(&to_main_tx);
+
+						for line in BufReader::new(stdout).lines() {
+							let Ok(line) = line else { break };
+							if line.contains("sink-input") && !query(&to_main_tx) {
+								break;
+							}
+						}
+					})
+					.ok()
+					.map(|_| child)
+			}
+			Err(err) => {
+				log::warn!("couldn't run pactl: {err}");
+				None
+			}
+		};
+
+		// setting the volume waits for pactl, so it doesn't block the main thread
+		let _ = std::thread::Builder::new()
+			.name("mixer-set".to_owned())
+			.spawn(move || {
+				for (index, volume) in from_main_rx {
+					let _ = Command::new("pactl")
+						.args(["set-sink-input-volume", &index.to_string()])
+						.arg(format!("{volume}%"))
+						.stdin(Stdio::null())
+						.stdout(Stdio::null())
+						.stderr(Stdio::null())
+						.status();
+				}
+			});
+
+		Mixer {
+			monitor,
+			index: None,
+			volume: None,
+
+			from_monitor_rx,
+			to_setter_tx,
+		}
+	}
+
+	/// apply volume changes of the sink input to the `player` and vice versa
+	///
+	/// once the sink input is found, it takes on the volume of the `player`
+	/// and the `player` stops applying the volume itself, so it isn't applied twice
+	pub fn sync<P: Playable>(&mut self, player: &mut P) {
+		while let Ok((index, volume)) = self.from_monitor_rx.try_recv() {
+			if self.index != Some(index) {
+				self.index = Some(index);
+				self.volume = None;
+				player.set_mixed(true);
+			} else if self.volume != Some(volume) {
+				self.volume = Some(volume);
+				player.set_volume(volume);
+			}
+		}
+
+		let volume = player.volume();
+		if let Some(index) = self.index
+			&& self.volume != Some(volume)
+		{
+			self.volume = Some(volume);
+			let _ = self.to_setter_tx.send((index, volume));
+		}
+	}
+}
+
+impl Drop for Mixer {
+	fn drop(&mut self) {
+		if let Some(mut monitor) = self.monitor.take() {
+			let _ = monitor.kill();
+			let _ = monitor.wait();
+		}
+	}
+}
+
+/// query the sink input of maym and send it to the main thread
+///
+/// returns false if the main thread is gone
+fn query(tx: &Sender<(u32, u8)>) -> bool {
+	let output = Command::new("pactl")
+		.args(["-f", "json", "list", "sink-inputs"])
+		.stdin(Stdio::null())
+		.stderr(Stdio::null())
+		.output();
+
+	let Some(sink_input) = output
+		.ok()
+		.and_then(|output| sink_input(&output.stdout, std::process::id()))
+	else {
+		return true;
+	};
+
+	tx.send(sink_input).is_ok()
+}
+
+/// find the sink input of the process `pid` in the json output of `pactl list sink-inputs`
+///
+/// returns its index and its volume averaged over all channels, clamped to 100%
+fn sink_input(json: &[u8], pid: u32) -> Option<(u32, u8)> {
+	let inputs = serde_json::from_slice::<Vec<Value>>(json).ok()?;
+	let pid = pid.to_string();
+
+	let input = inputs.iter().find(|input| {
+		input["properties"]["application.process.id"].as_str() == Some(pid.as_str())
+	})?;
+
+	let index = u32::try_from(input["index"].as_u64()?).ok()?;
+
+	let channels = input["volume"].as_object()?;
+	let values = channels
+		.values()
+		.filter_map(|channel| channel["value"].as_f64())
+		.collect::<Vec<_>>();
+	if values.is_empty() {
+		return None;
+	}
+
+	// pulseaudio uses 65536 as 100%
+	let average = values.iter().sum::<f64>() / values.len() as f64;
+	let volume = (average / 65536. * 100.).round().clamp(0., 100.) as u8;
+
+	Some((index, volume))
+}
+
+#[cfg(test)]
+mod test {
+	use super::sink_input;
+
+	#[test]
+	fn parse() {
+		let json = br#"[
+			{
+				"index": 12,
+				"volume": { "mono": { "value": 65536, "value_percent": "100%" } },
+				"properties": { "application.process.id": "1" }
+			},
+			{
+				"index": 57,
+				"volume": {
+					"front-left": { "value": 32768, "value_percent": "50%" },
+					"front-right": { "value": 19661, "value_percent": "30%" }
+				},
+				"properties": { "application.name": "maym", "application.process.id": "42" }
+			}
+		]"#;
+
+		assert_eq!(sink_input(json, 42), Some((57, 40)));
+		assert_eq!(sink_input(json, 1), Some((12, 100)));
+		assert_eq!(sink_input(json, 7), None);
+		assert_eq!(sink_input(b"no json", 42), None);
+	}
+}
@@ -1,5 +1,12 @@
-use crate::state::State;
+use crate::{
+	bus::{Event, Subscribe},
+	config,
+	queue::{Queue, Repeat},
+	state::State,
+};
+use camino::Utf8Path;
 use std::{
+	borrow::Cow,
 	collections::HashMap,
 	sync::{
 		Arc, Mutex,
@@ -7,7 +14,10 @@ use std::{
 	},
 	time::Duration,
 };
-use zbus::{connection, interface, zvariant::Value};
+use zbus::{
+	connection, fdo::Properties, interface, names::InterfaceName, object_server::SignalEmitter,
+	zvariant::Value,
+};
 
 struct MprisRoot;
 
@@ -31,7 +41,7 @@ impl MprisRoot {
 
 	#[zbus(property)]
 	fn supported_uri_schemes(&self) -> Vec<&str> {
-		Vec::new()
+		vec!["http", "https"]
 	}
 
 	#[zbus(property)]
@@ -66,7 +76,27 @@ impl MprisPlayer {
 
 	#[zbus(property)]
 	fn loop_status(&self) -> &'static str {
-		"Playlist"
+		let state = self.state.lock().unwrap();
+		match state.repeat {
+			Repeat::None => "None",
+			Repeat::Track => "Track",
+			Repeat::Playlist => "Playlist",
+		}
+	}
+
+	#[zbus(property)]
+	fn set_loop_status(&self, status: &str) -> zbus::fdo::Result<()> {
+		let repeat = match status {
+			"None" => Repeat::None,
+			"Track" => Repeat::Track,
+			"Playlist" => Repeat::Playlist,
+			_ => {
+				let err = format!("unknown loop status {status:?}");
+				return Err(zbus::fdo::Error::InvalidArgs(err));
+			}
+		};
+		self.tx.send(MprisEvent::Loop(repeat)).unwrap();
+		Ok(())
 	}
 
 	#[zbus(property)]
@@ -110,16 +140,34 @@ impl MprisPlayer {
 				map.insert("xesam:album", Value::Str(album.into()));
 			}
 
-			if let Some(artist) = track.artist().map(Arc::<str>::from) {
-				map.insert("xesam:artist", Value::Str(artist.into()));
+			let artists = track.artists();
+			if !artists.is_empty() {
+				let artists = artists.into_iter().map(String::from).collect::<Vec<_>>();
+				map.insert("xesam:artist", Value::from(artists));
 			}
 
-			if let Some(title) = track.title().map(Arc::<str>::from) {
+			if let Some(title) = track.heading().map(Arc::<str>::from) {
 				map.insert("xesam:title", Value::Str(title.into()));
 			}
 
-			if let Some(track) = track.track() {
-				map.insert("xesam:discNumber", Value::U32(track));
+			if let Some(number) = track.track() {
+				map.insert("xesam:trackNumber", Value::U32(number));
+			}
+
+			if let Some(disc) = track.disc() {
+				map.insert("xesam:discNumber", Value::U32(disc));
+			}
+
+			if let Some(genre) = track.genre() {
+				map.insert("xesam:genre", Value::from(vec![genre.into_owned()]));
+			}
+
+			if let Some(date) = track.date() {
+				map.insert("xesam:contentCreated", Value::from(date.to_string()));
+			}
+
+			if let Some(url) = file_url(track.path()) {
+				map.insert("xesam:url", Value::from(url));
 			}
 		}
 
@@ -163,12 +211,14 @@ impl MprisPlayer {
 
 	#[zbus(property)]
 	fn can_go_next(&self) -> bool {
-		true
+		let state = self.state.lock().unwrap();
+		state.can_next
 	}
 
 	#[zbus(property)]
 	fn can_go_previous(&self) -> bool {
-		true
+		let state = self.state.lock().unwrap();
+		state.can_last
 	}
 
 	#[zbus(property)]
@@ -211,6 +261,13 @@ impl MprisPlayer {
 		self.tx.send(MprisEvent::Toggle).unwrap();
 	}
 
+	#[zbus(signal)]
+	async fn seeked(emitter: &SignalEmitter<'_>, position: i64) -> zbus::Result<()>;
+
+	fn open_uri(&self, uri: String) {
+		self.tx.send(MprisEvent::OpenUri(uri)).unwrap();
+	}
+
 	fn seek(&self, offset: i64) {
 		let event = if offset < 0 {
 			let offset = offset.unsigned_abs();
@@ -224,6 +281,24 @@ impl MprisPlayer {
 	}
 }
 
+/// `file://` url of `path`
+fn file_url(path: &Utf8Path) -> Option<String> {
+	let path = std::path::absolute(path).ok()?;
+	let path = path.to_str()?;
+
+	let mut url = String::from("file://");
+	for byte in path.bytes() {
+		match byte {
+			b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'.' | b'_' | b'~' => {
+				url.push(char::from(byte));
+			}
+			_ => url.push_str(&format!("%{byte:02X}")),
+		}
+	}
+
+	Some(url)
+}
+
 pub enum MprisEvent {
 	Next,
 	Prev,
@@ -233,15 +308,22 @@ pub enum MprisEvent {
 	Seek(Duration),
 	SeekBack(Duration),
 	Shuffle(bool),
+	Loop(Repeat),
 	Volume(u8),
+	OpenUri(String),
 }
 
 #[derive(Debug)]
 pub enum MprisUpdate {
 	PlayerStatus,
 	Shuffle,
+	Loop,
 	Volume,
 	Metadata,
+	/// [`State::can_next`] or [`State::can_last`] changed
+	Controls,
+	/// the position jumped or playback was paused or resumed
+	Seeked,
 }
 
 #[derive(Debug)]
@@ -263,7 +345,9 @@ impl Mpris {
 		let (tx_up, rx_up) = channel::<MprisUpdate>();
 
 		smol::spawn(async {
-			let _ = Mpris::serve(root, player, rx_up).await;
+			if let Err(err) = Mpris::serve(root, player, rx_up).await {
+				log::error!("mpris: {err}");
+			}
 		})
 		.detach();
 
@@ -275,8 +359,13 @@ impl Mpris {
 		player: MprisPlayer,
 		updates: Receiver<MprisUpdate>,
 	) -> Result<(), zbus::Error> {
+		// instances with a profile need a bus name of their own
+		let name = match config::profile() {
+			Some(profile) => format!("org.mpris.MediaPlayer2.maym_{profile}"),
+			None => "org.mpris.MediaPlayer2.maym".to_owned(),
+		};
 		let connection = connection::Builder::session()?
-			.name("org.mpris.MediaPlayer2.maym")?
+			.name(name)?
 			.serve_at("/org/mpris/MediaPlayer2", root)?
 			.serve_at("/org/mpris/MediaPlayer2", player)?
 			.build()
@@ -289,23 +378,58 @@ impl Mpris {
 		let player_interface = player_interface_ref.get().await;
 
 		let signal_context = player_interface_ref.signal_emitter();
-		for update in updates {
-			match update {
-				MprisUpdate::PlayerStatus => {
-					player_interface
-						.playback_status_changed(signal_context)
-						.await?;
-				}
-				MprisUpdate::Metadata => {
-					player_interface.metadata_changed(signal_context).await?;
-				}
-				MprisUpdate::Shuffle => {
-					player_interface.shuffle_changed(signal_context).await?;
-				}
-				MprisUpdate::Volume => {
-					player_interface.volume_changed(signal_context).await?;
+		let interface = InterfaceName::from_static_str_unchecked("org.mpris.MediaPlayer2.Player");
+		while let Ok(update) = updates.recv() {
+			let mut changed = HashMap::new();
+			let mut seeked = false;
+
+			// updates of the same tick are batched into a single signal
+			for update in std::iter::once(update).chain(updates.try_iter()) {
+				match update {
+					MprisUpdate::PlayerStatus => {
+						let status = player_interface.playback_status();
+						changed.insert("PlaybackStatus", Value::from(status));
+					}
+					MprisUpdate::Metadata => {
+						let metadata = player_interface.metadata();
+						changed.insert("Metadata", Value::from(metadata));
+					}
+					MprisUpdate::Shuffle => {
+						let shuffle = player_interface.shuffle();
+						changed.insert("Shuffle", Value::from(shuffle));
+					}
+					MprisUpdate::Loop => {
+						let status = player_interface.loop_status();
+						changed.insert("LoopStatus", Value::from(status));
+					}
+					MprisUpdate::Volume => {
+						let volume = player_interface.volume();
+						changed.insert("Volume", Value::from(volume));
+					}
+					MprisUpdate::Controls => {
+						let next = player_interface.can_go_next();
+						let previous = player_interface.can_go_previous();
+						changed.insert("CanGoNext", Value::from(next));
+						changed.insert("CanGoPrevious", Value::from(previous));
+					}
+					MprisUpdate::Seeked => seeked = true,
 				}
 			}
+
+			if !changed.is_empty() {
+				let invalidated = Cow::Borrowed(&[][..]);
+				Properties::properties_changed(
+					signal_context,
+					interface.clone(),
+					changed,
+					invalidated,
+				)
+				.await?;
+			}
+
+			if seeked {
+				MprisPlayer::seeked(signal_context, player_interface.position()).await?;
+			}
 		}
 
 		Ok(())
@@ -319,3 +443,39 @@ impl Mpris {
 		self.rx.try_recv().ok()
 	}
 }
+
+impl Subscribe for Mpris {
+	fn notify(&mut self, event: Event, _: &State, _: &Queue) {
+		match event {
+			Event::TrackChanged | Event::TagsChanged => self.update(MprisUpdate::Metadata),
+			Event::Paused(_) => {
+				self.update(MprisUpdate::PlayerStatus);
+				// applets interpolate the position while playing, so resync them on pause too
+				self.update(MprisUpdate::Seeked);
+			}
+			Event::VolumeChanged(_) | Event::Muted(_) => self.update(MprisUpdate::Volume),
+			Event::Seeked => self.update(MprisUpdate::Seeked),
+			Event::ShuffleChanged(_) => self.update(MprisUpdate::Shuffle),
+			Event::RepeatChanged(_) => self.update(MprisUpdate::Loop),
+			Event::ControlsChanged => self.update(MprisUpdate::Controls),
+			Event::QueueChanged | Event::TracksEdited => {}
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::file_url;
+
+	#[test]
+	fn url() {
+		assert_eq!(
+			file_url("/music/ABBA/01 Dancing Queen (live).mp3".into()).as_deref(),
+			Some("file:///music/ABBA/01%20Dancing%20Queen%20%28live%29.mp3")
+		);
+		assert_eq!(
+			file_url("/music/Björk.mp3".into()).as_deref(),
+			Some("file:///music/Bj%C3%B6rk.mp3")
+		);
+	}
+}
@@ -0,0 +1,86 @@
+//! party mode, where remote clients can only request tracks and vote to skip
+
+use crate::queue::Track;
+use std::collections::{HashSet, VecDeque};
+
+/// requests and skip votes of the remote clients
+#[derive(Debug)]
+pub struct Party {
+	/// requested tracks, played before the rest of the queue
+	requests: VecDeque<Track>,
+	/// clients that voted to skip the current track
+	votes: HashSet<String>,
+	/// votes needed to skip the current track
+	needed: usize,
+}
+
+impl Party {
+	pub fn new(needed: usize) -> Self {
+		Party {
+			requests: VecDeque::new(),
+			votes: HashSet::new(),
+			needed: needed.max(1),
+		}
+	}
+
+	/// append `track` to the requests, returns false if it already is requested
+	pub fn request(&mut self, track: Track) -> bool {
+		if self.requests.contains(&track) {
+			return false;
+		}
+
+		self.requests.push_back(track);
+		true
+	}
+
+	/// take the request that is played next
+	pub fn next(&mut self) -> Option<Track> {
+		self.requests.pop_front()
+	}
+
+	/// count the vote of `client`, returns if enough clients voted to skip the current track
+	pub fn vote(&mut self, client: String) -> bool {
+		self.votes.insert(client);
+		self.votes.len() >= self.needed
+	}
+
+	/// forget the votes once the current track changed
+	pub fn reset(&mut self) {
+		self.votes.clear();
+	}
+
+	pub fn requests(&self) -> &VecDeque<Track> {
+		&self.requests
+	}
+
+	/// votes and votes needed to skip the current track
+	pub fn votes(&self) -> (usize, usize) {
+		(self.votes.len(), self.needed)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::Party;
+	use crate::queue::Track;
+
+	#[test]
+	fn party() {
+		let mut party = Party::new(2);
+		let track = Track::new("mock/list 01/track 01.mp3".into()).unwrap();
+		assert!(party.request(track.clone()));
+		assert!(!party.request(track.clone()));
+		assert_eq!(party.requests().len(), 1);
+
+		assert!(!party.vote("10.0.0.2".to_owned()));
+		// every client is counted once
+		assert!(!party.vote("10.0.0.2".to_owned()));
+		assert!(party.vote("10.0.0.3".to_owned()));
+		assert_eq!(party.votes(), (2, 2));
+
+		party.reset();
+		assert_eq!(party.votes(), (0, 2));
+		assert_eq!(party.next(), Some(track));
+		assert_eq!(party.next(), None);
+	}
+}
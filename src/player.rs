@@ -1,18 +1,38 @@
-use crate::{
-	queue::{Queue, Track},
-	state::State,
-};
+//! audio playback
+//!
+//! the default backend streams tracks from disk with creek,
+//! the `direct` feature swaps it for a backend that decodes with symphonia directly
+
+use crate::{config::Config, queue::Track};
 use cpal::{
-	StreamConfig,
+	BufferSize, BuildStreamError, Device, FromSample, I24, SampleFormat, SizedSample, Stream,
+	StreamConfig, StreamError, SupportedBufferSize, SupportedStreamConfig, U24,
 	traits::{DeviceTrait, HostTrait, StreamTrait},
 };
-use creek::{ReadDiskStream, ReadStreamOptions, SeekMode, SymphoniaDecoder, read::ReadError};
-use rtrb::{Consumer, Producer, RingBuffer};
-use rubato::{
-	Async, FixedAsync, PolynomialDegree, Resampler,
-	audioadapter_buffers::direct::{SequentialSliceOfSlices, SequentialSliceOfVecs},
+use std::{
+	path::Path,
+	sync::{
+		Arc, OnceLock,
+		atomic::{AtomicUsize, Ordering},
+		mpsc::{self, Receiver},
+	},
+	time::Duration,
 };
-use std::{collections::VecDeque, convert::identity, fmt::Debug, time::Duration};
+
+mod cue;
+#[cfg(feature = "direct")]
+mod direct;
+#[cfg(not(feature = "direct"))]
+mod disk;
+mod outbox;
+mod preview;
+mod record;
+
+#[cfg(feature = "direct")]
+pub use self::direct::Player;
+#[cfg(not(feature = "direct"))]
+pub use self::disk::Player;
+pub use self::preview::Preview;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PlaybackStatus {
@@ -29,442 +49,648 @@ impl PlaybackStatus {
 	}
 }
 
-enum ToProcess {
-	UseStream {
-		stream: Box<ReadDiskStream<SymphoniaDecoder>>,
-		status: PlaybackStatus,
-	},
-	Status(PlaybackStatus),
-	Volume(f32),
-	SeekTo(Duration),
+/// frames per callback of the output streams from the [`Config`]
+static BUFFER_FRAMES: OnceLock<u32> = OnceLock::new();
+
+/// load the output settings from the [`Config`]
+pub fn load(config: &Config) {
+	if let Some(frames) = config.buffer_frames() {
+		let _ = BUFFER_FRAMES.set(frames);
+	}
+	cue::load(config);
 }
 
-enum FromProcess {
-	Playhead(Duration),
-	IsDone,
+/// buffer size for an output with `supported` buffer sizes, clamped to the supported range
+fn buffer_size(frames: Option<u32>, supported: &SupportedBufferSize) -> BufferSize {
+	match (frames, *supported) {
+		(None, _) => BufferSize::Default,
+		(Some(frames), SupportedBufferSize::Range { min, max }) => {
+			BufferSize::Fixed(frames.clamp(min, max))
+		}
+		(Some(frames), SupportedBufferSize::Unknown) => BufferSize::Fixed(frames),
+	}
 }
 
-struct Process {
-	stream: Option<Box<ReadDiskStream<SymphoniaDecoder>>>,
-	buffer: VecDeque<f32>,
-	stream_config: StreamConfig,
-	resampler: Option<Async<f32>>,
-	resample_buffer_in: [Vec<f32>; 2],
-	resample_buffer_out: [Vec<f32>; 2],
-
-	// status
-	status: PlaybackStatus,
-	volume: f32,
-	done: bool,
-
-	// comm
-	from_main_rx: Consumer<ToProcess>,
-	to_main_tx: Producer<FromProcess>,
+/// stream config for a `supported` config of a device, with the configured buffer size
+fn stream_config(supported: SupportedStreamConfig) -> StreamConfig {
+	let buffer_size = buffer_size(BUFFER_FRAMES.get().copied(), supported.buffer_size());
+	StreamConfig {
+		buffer_size,
+		..StreamConfig::from(supported)
+	}
 }
 
-impl Process {
-	pub fn new(
-		stream_config: StreamConfig,
-		from_main_rx: Consumer<ToProcess>,
-		to_main_tx: Producer<FromProcess>,
-	) -> Self {
-		Process {
-			stream: None,
-			buffer: VecDeque::new(),
-			stream_config,
-			resampler: None,
-			resample_buffer_in: [Vec::new(), Vec::new()],
-			resample_buffer_out: [Vec::new(), Vec::new()],
-
-			status: PlaybackStatus::Paused,
-			volume: 0.45,
-			done: false,
-
-			from_main_rx,
-			to_main_tx,
+/// freedesktop icon name announced to sound servers
+const ICON: &str = "multimedia-player";
+
+/// describe the audio stream to pulseaudio and pipewire, so volume mixers show maym
+/// with a music role instead of a generic alsa stream
+///
+/// cpal opens the stream through alsa, so the properties are passed to the alsa plugins
+/// of the sound servers in the environment. they are only read when the stream is opened,
+/// so the media name stays `maym`. variables set by the user are kept
+pub fn stream_properties() {
+	let pulse = format!(
+		"application.name=maym application.icon_name={ICON} media.role=music media.name=maym"
+	);
+	let pipewire = format!(
+		"{{ application.name=maym application.icon-name={ICON} media.role=Music media.name=maym }}"
+	);
+
+	for (key, value) in [("PULSE_PROP", pulse), ("PIPEWIRE_PROPS", pipewire)] {
+		if std::env::var_os(key).is_none() {
+			// SAFETY: called at startup, before any other thread is spawned
+			unsafe { std::env::set_var(key, value) };
 		}
 	}
+}
 
-	pub fn process(&mut self, data: &mut [f32]) {
-		while let Ok(msg) = self.from_main_rx.pop() {
-			match msg {
-				ToProcess::UseStream { stream, status } => {
-					let duration = Process::playhead(&stream);
-					let _ = self.to_main_tx.push(FromProcess::Playhead(duration));
-
-					let cpal_sample_rate = self.stream_config.sample_rate;
-					let stream_sample_rate = stream.info().sample_rate.unwrap();
-
-					if cpal_sample_rate != stream_sample_rate {
-						let ratio = f64::from(cpal_sample_rate) / f64::from(stream_sample_rate);
-						let block_size = stream.block_size();
-
-						let resampler = Async::<f32>::new_poly(
-							ratio,
-							1.0,
-							PolynomialDegree::Linear,
-							block_size,
-							2,
-							FixedAsync::Input,
-						)
-						.unwrap();
-
-						let frames = resampler.output_frames_max();
-
-						self.resample_buffer_in[0].resize(block_size, 0.0);
-						self.resample_buffer_in[1].resize(block_size, 0.0);
+/// state of the output stream
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Output {
+	/// still being opened
+	#[default]
+	Opening,
+	/// there is no output device, opening it is retried periodically
+	NoDevice,
+	/// playing to the output device
+	Ready,
+	/// the output stream couldn't be built
+	Failed,
+}
 
-						self.resample_buffer_out[0].resize(frames, 0.0);
-						self.resample_buffer_out[1].resize(frames, 0.0);
+/// message from the thread opening the output stream
+enum FromOutput {
+	/// there is no output device yet
+	NoDevice,
+	/// stream was opened with this sample rate
+	///
+	Ready(u32),
+	/// stream couldn't be built
+	Failed,
+}
 
-						self.buffer.clear();
-						self.buffer.reserve(frames * 2);
+/// interval between attempts to find an output device
+const RETRY: Duration = Duration::from_secs(2);
+
+/// gain of the playback while it is ducked for a [`Preview`]
+const DUCK: f32 = 0.2;
+
+/// open the default output device on a background thread, so a slow device doesn't delay the ui
+///
+/// retries until there is a device. `process` is constructed once the stream config is known
+fn spawn_output<F, P>(dropouts: Arc<Counter>, process: F) -> Receiver<FromOutput>
+where
+	F: FnOnce(&StreamConfig) -> P + Send + 'static,
+	P: FnMut(&mut [f32]) + Send + 'static,
+{
+	let (tx, rx) = mpsc::channel();
+
+	std::thread::Builder::new()
+		.name("output".to_owned())
+		.spawn(move || {
+			let mut reported = false;
+			let (device, config) = loop {
+				let host = cpal::default_host();
+				let found = host.default_output_device().and_then(|device| {
+					let config = device.default_output_config().ok()?;
+					Some((device, config))
+				});
+
+				if let Some(found) = found {
+					break found;
+				}
 
-						self.resampler = Some(resampler);
-					} else {
-						self.buffer.clear();
-						self.buffer.reserve(stream.block_size() * 2);
-						self.resampler = None;
+				if !reported {
+					reported = true;
+					if tx.send(FromOutput::NoDevice).is_err() {
+						return;
 					}
-
-					self.status = status;
-					self.done = false;
-					self.stream = Some(stream);
-				}
-				ToProcess::Status(status) => {
-					self.status = status;
 				}
-				ToProcess::Volume(volume) => {
-					debug_assert!((0.0..=1.0).contains(&volume));
-					self.volume = volume;
+				std::thread::sleep(RETRY);
+			};
+
+			let sample_format = config.sample_format();
+			let stream_config = stream_config(config);
+			let mut process = process(&stream_config);
+
+			let mut mirror = cue::Mirror::open(&stream_config);
+			let process = move |data: &mut [f32]| {
+				process(data);
+				if let Some(mirror) = &mut mirror {
+					mirror.push(data);
 				}
-				ToProcess::SeekTo(duration) => {
-					if let Some(stream) = &mut self.stream {
-						let sample_rate = stream.info().sample_rate.unwrap();
-						let frame = duration.as_secs_f64() * sample_rate as f64;
-						stream.seek(frame as usize, SeekMode::Auto).unwrap();
+			};
 
-						self.buffer.clear();
-
-						let _ = self.to_main_tx.push(FromProcess::Playhead(duration));
-					}
-				}
-			}
-		}
+			let stream = output_stream(
+				&device,
+				&stream_config,
+				sample_format,
+				process,
+				move |err| match err {
+					StreamError::BufferUnderrun => dropouts.underrun(),
+					_ => panic!("an error occured {err:?}"),
+				},
+			);
 
-		if let Some(stream) = &mut self.stream {
-			if self.done
-				|| !stream.is_ready().is_ok_and(identity)
-				|| self.status == PlaybackStatus::Paused
-			{
-				data.fill(0.0);
-				return;
-			}
+			let played = stream.map_err(|err| err.to_string()).and_then(|stream| {
+				stream.play().map_err(|err| err.to_string())?;
+				Ok(stream)
+			});
 
-			while self.buffer.len() < data.len() {
-				let block_size = stream.block_size();
-				let read_data = match stream.read(block_size) {
-					Ok(read_data) => read_data,
-					Err(ReadError::EndOfFile) => {
-						let len = self.buffer.len();
-						for sample in &mut data[..len] {
-							*sample = self.buffer.pop_front().unwrap() * self.volume.powi(3);
-						}
-						data[len..].fill(0.0);
-
-						self.done = true;
-						let _ = self.to_main_tx.push(FromProcess::IsDone);
-						return;
-					}
-					err @ Err(_) => err.unwrap(),
-				};
-
-				let ch1 = read_data.read_channel(0);
-				let ch2 = read_data.read_channel(if read_data.num_channels() == 1 { 0 } else { 1 });
-
-				if let Some(resampler) = &mut self.resampler {
-					let [in_ch1, in_ch2] = &mut self.resample_buffer_in;
-
-					let ch1 = if ch1.len() < block_size {
-						in_ch1[..ch1.len()].copy_from_slice(ch2);
-						in_ch1
-					} else {
-						ch1
-					};
-
-					let ch2 = if ch2.len() < block_size {
-						in_ch2[..ch2.len()].copy_from_slice(ch2);
-						in_ch2
-					} else {
-						ch2
-					};
-
-					let chs = [ch1, ch2];
-					let buf_in = SequentialSliceOfSlices::new(
-						&chs,
-						resampler.nbr_channels(),
-						resampler.input_frames_next(),
-					)
-					.unwrap();
-
-					let mut buf_out = SequentialSliceOfVecs::new_mut(
-						&mut self.resample_buffer_out,
-						resampler.nbr_channels(),
-						resampler.output_frames_next(),
-					)
-					.unwrap();
-
-					let (_, out_len) = resampler
-						.process_into_buffer(&buf_in, &mut buf_out, None)
-						.unwrap();
-
-					let [ch1, ch2] = &self.resample_buffer_out;
-
-					for i in 0..out_len {
-						self.buffer.push_back(ch1[i]);
-						self.buffer.push_back(ch2[i]);
-					}
-				} else {
-					for i in 0..read_data.num_frames() {
-						self.buffer.push_back(ch1[i]);
-						self.buffer.push_back(ch2[i]);
-					}
+			match played {
+				Ok(stream) => {
+					std::mem::forget(stream);
+					let _ = tx.send(FromOutput::Ready(stream_config.sample_rate));
+				}
+				Err(err) => {
+					log::error!("couldn't open the audio output: {err}");
+					let _ = tx.send(FromOutput::Failed);
 				}
 			}
+		})
+		.unwrap();
 
-			for sample in data {
-				*sample = self.buffer.pop_front().unwrap() * self.volume.powi(3);
-			}
+	rx
+}
 
-			let duration = Process::playhead(stream);
-			let _ = self.to_main_tx.push(FromProcess::Playhead(duration));
+/// build an output stream in the native `format` of the device
+///
+/// `process` always produces f32 samples, which are converted in the callback
+fn output_stream<P, E>(
+	device: &Device,
+	config: &StreamConfig,
+	format: SampleFormat,
+	process: P,
+	error: E,
+) -> Result<Stream, BuildStreamError>
+where
+	P: FnMut(&mut [f32]) + Send + 'static,
+	E: FnMut(StreamError) + Send + 'static,
+{
+	match format {
+		SampleFormat::F32 => {
+			let mut process = process;
+			let callback = move |data: &mut [f32], _: &_| process(data);
+			device.build_output_stream(config, callback, error, None)
 		}
+		SampleFormat::F64 => output_stream_as::<f64, _, _>(device, config, process, error),
+		SampleFormat::I8 => output_stream_as::<i8, _, _>(device, config, process, error),
+		SampleFormat::I16 => output_stream_as::<i16, _, _>(device, config, process, error),
+		SampleFormat::I24 => output_stream_as::<I24, _, _>(device, config, process, error),
+		SampleFormat::I32 => output_stream_as::<i32, _, _>(device, config, process, error),
+		SampleFormat::I64 => output_stream_as::<i64, _, _>(device, config, process, error),
+		SampleFormat::U8 => output_stream_as::<u8, _, _>(device, config, process, error),
+		SampleFormat::U16 => output_stream_as::<u16, _, _>(device, config, process, error),
+		SampleFormat::U24 => output_stream_as::<U24, _, _>(device, config, process, error),
+		SampleFormat::U32 => output_stream_as::<u32, _, _>(device, config, process, error),
+		SampleFormat::U64 => output_stream_as::<u64, _, _>(device, config, process, error),
+		_ => Err(BuildStreamError::StreamConfigNotSupported),
 	}
+}
+
+/// output stream of sample type `T`, converting from f32
+fn output_stream_as<T, P, E>(
+	device: &Device,
+	config: &StreamConfig,
+	mut process: P,
+	error: E,
+) -> Result<Stream, BuildStreamError>
+where
+	T: SizedSample + FromSample<f32>,
+	P: FnMut(&mut [f32]) + Send + 'static,
+	E: FnMut(StreamError) + Send + 'static,
+{
+	let mut buffer = Vec::new();
+	let callback = move |data: &mut [T], _: &_| {
+		buffer.resize(data.len(), 0.0);
+		process(&mut buffer);
+
+		for (sample, &value) in data.iter_mut().zip(&buffer) {
+			*sample = T::from_sample(value);
+		}
+	};
 
-	fn playhead<D: creek::Decoder>(stream: &ReadDiskStream<D>) -> Duration {
-		let sample_rate = stream.info().sample_rate.unwrap();
-		let playhead = stream.playhead() as f64 / sample_rate as f64;
-		Duration::from_secs_f64(playhead)
+	device.build_output_stream(config, callback, error, None)
+}
+
+/// write a stereo frame to an output `frame` with any number of channels
+///
+/// mono outputs get a downmix, channels past the second one stay silent
+fn write_frame(frame: &mut [f32], [left, right]: [f32; 2]) {
+	if let [mono] = frame {
+		*mono = (left + right) / 2.;
+	} else {
+		frame[0] = left;
+		frame[1] = right;
+		frame[2..].fill(0.);
 	}
 }
 
-pub struct Player {
-	// state
-	muted: bool,
-	volume: u8,
-	done: bool,
-	status: PlaybackStatus,
-	elapsed: Option<Duration>,
-	duration: Option<Duration>,
-
-	// comm
-	to_process_tx: Producer<ToProcess>,
-	from_process_rx: Consumer<FromProcess>,
+/// audio dropouts since startup
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Dropouts {
+	/// buffer underruns reported by cpal
+	pub underruns: usize,
+	/// times the decoded stream couldn't keep up with playback
+	pub stalls: usize,
 }
 
-impl Debug for Player {
-	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-		f.debug_struct("Player").finish_non_exhaustive()
+impl Dropouts {
+	/// sum of underruns and stalls
+	pub fn total(self) -> usize {
+		self.underruns + self.stalls
 	}
 }
 
-impl Player {
-	pub fn new() -> Self {
-		let (to_process_tx, from_main_rx) = RingBuffer::<ToProcess>::new(64);
-		let (to_main_tx, from_process_rx) = RingBuffer::<FromProcess>::new(256);
+/// [`Dropouts`] counted on the audio threads
+#[derive(Debug, Default)]
+struct Counter {
+	underruns: AtomicUsize,
+	stalls: AtomicUsize,
+}
 
-		let host = cpal::default_host();
-		let device = host.default_output_device().unwrap();
+impl Counter {
+	fn underrun(&self) {
+		self.underruns.fetch_add(1, Ordering::Relaxed);
+	}
 
-		let default_output_config = device.default_output_config().unwrap();
-		let stream_config = StreamConfig::from(default_output_config);
+	fn stall(&self) {
+		self.stalls.fetch_add(1, Ordering::Relaxed);
+	}
 
-		let mut process = Process::new(stream_config.clone(), from_main_rx, to_main_tx);
+	fn get(&self) -> Dropouts {
+		Dropouts {
+			underruns: self.underruns.load(Ordering::Relaxed),
+			stalls: self.stalls.load(Ordering::Relaxed),
+		}
+	}
+}
 
-		let stream = device
-			.build_output_stream(
-				&stream_config,
-				move |data: &mut [f32], _: &cpal::OutputCallbackInfo| process.process(data),
-				|err| match err {
-					cpal::StreamError::BufferUnderrun => {}
-					_ => panic!("an error occured {err:?}"),
-				},
-				None,
-			)
-			.unwrap();
+/// stereo balance and mono downmix, applied after the volume stage
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Mix {
+	left: f32,
+	right: f32,
+	mono: bool,
+}
 
-		stream.play().unwrap();
-		std::mem::forget(stream);
+impl Mix {
+	/// `balance` ranges from -100 (only left) to 100 (only right)
+	fn new(balance: i8, mono: bool) -> Self {
+		let balance = f32::from(balance.clamp(-100, 100)) / 100.;
+		Mix {
+			left: f32::min(1., 1. - balance),
+			right: f32::min(1., 1. + balance),
+			mono,
+		}
+	}
 
-		Player {
-			muted: false,
-			volume: 45,
-			done: false,
+	fn apply(self, [left, right]: [f32; 2]) -> [f32; 2] {
+		let (left, right) = if self.mono {
+			let mid = (left + right) / 2.;
+			(mid, mid)
+		} else {
+			(left, right)
+		};
 
-			status: PlaybackStatus::Paused,
-			elapsed: None,
-			duration: None,
+		[left * self.left, right * self.right]
+	}
+}
+
+impl Default for Mix {
+	fn default() -> Self {
+		Mix::new(0, false)
+	}
+}
 
-			to_process_tx,
-			from_process_rx,
+/// soft limiter applied after the volume stage
+///
+/// samples above the threshold are compressed with tanh, so they approach but never exceed full scale
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Limiter {
+	threshold: f32,
+}
+
+impl Limiter {
+	fn new(threshold: f32) -> Self {
+		Limiter {
+			threshold: threshold.clamp(0.1, 1.0),
 		}
 	}
 
-	pub fn with_state(queue: &Queue, state: &State) -> Self {
-		let mut player = Player::new();
-		player.state(queue, state);
+	fn apply(self, sample: f32) -> f32 {
+		let magnitude = sample.abs();
+		if magnitude <= self.threshold {
+			return sample;
+		}
 
-		player
+		let headroom = 1. - self.threshold;
+		if headroom <= 0. {
+			return sample.clamp(-1., 1.);
+		}
+
+		let limited = self.threshold + headroom * ((magnitude - self.threshold) / headroom).tanh();
+		limited.copysign(sample)
 	}
+}
 
-	pub fn update(&mut self) {
-		while let Ok(msg) = self.from_process_rx.pop() {
-			match msg {
-				FromProcess::Playhead(duration) => {
-					self.elapsed = Some(duration);
-				}
-				FromProcess::IsDone => {
-					self.done = true;
-				}
-			}
+/// silence detector, used to skip silence at the start and end of a track
+/// and to end a track on a [`Runout`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Silence {
+	/// amplitude below which a sample is silent
+	threshold: f32,
+	/// the most silence that is skipped at either end,
+	/// or the silence after which the track ends
+	duration: Duration,
+}
+
+impl Silence {
+	/// `threshold` in dBFS
+	fn new((threshold, duration): (f32, Duration)) -> Self {
+		Silence {
+			threshold: 10f32.powf(threshold / 20.),
+			duration,
 		}
 	}
 
-	fn state(&mut self, queue: &Queue, state: &State) {
-		self.volume = state.volume;
+	fn silent(self, [left, right]: [f32; 2]) -> bool {
+		left.abs() < self.threshold && right.abs() < self.threshold
+	}
 
-		let volume = if state.muted {
-			0.
-		} else {
-			state.volume as f32 / 100.
-		};
-		let _ = self.to_process_tx.push(ToProcess::Volume(volume));
+	/// [`Silence::duration`] in frames at `sample_rate`
+	fn frames(self, sample_rate: u32) -> u64 {
+		(self.duration.as_secs_f64() * f64::from(sample_rate)) as u64
+	}
+}
 
-		if let Some(track) = queue.track() {
-			let start = state.elapsed();
-			let start = start.unwrap_or_default();
+/// ends a track once its end has been silent for a while, like the run-out of a vinyl rip
+///
+/// only silence in the last [`Runout::window`] of the track is counted,
+/// so pauses earlier in the track don't end it
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Runout {
+	silence: Silence,
+	/// part at the end of the track in which silence is counted
+	window: Duration,
+	/// frames of silence counted in a row
+	silent: u64,
+}
 
-			self.revive(track, start);
+impl Runout {
+	/// `threshold` in dBFS, the track ends after `after` of silence in the last `window`
+	fn new((threshold, after, window): (f32, Duration, Duration)) -> Self {
+		Runout {
+			silence: Silence::new((threshold, after)),
+			window,
+			silent: 0,
 		}
 	}
 
-	fn revive(&mut self, track: &Track, start: Duration) {
-		self.replace_inner(track, PlaybackStatus::Paused, start);
+	fn silent(self, frame: [f32; 2]) -> bool {
+		self.silence.silent(frame)
 	}
 
-	fn replace_inner(&mut self, track: &Track, status: PlaybackStatus, start: Duration) {
-		let opts = ReadStreamOptions::default();
+	/// count `frames` frames that are all `silent` or not, `remaining` before the end of the track
+	fn count(&mut self, silent: bool, frames: u64, remaining: Option<Duration>) {
+		let within = remaining.is_some_and(|remaining| remaining <= self.window);
+		if silent && within {
+			self.silent += frames;
+		} else {
+			self.silent = 0;
+		}
+	}
 
-		let mut read_stream = ReadDiskStream::new(track.path(), 0, opts).unwrap();
+	/// the counted silence ends the track at `sample_rate`
+	fn ran_out(self, sample_rate: u32) -> bool {
+		self.silent >= self.silence.frames(sample_rate)
+	}
 
-		// seek to the specified position in the track
-		let sample_rate = read_stream.info().sample_rate.unwrap();
-		let start_frame = start.as_secs_f64() * sample_rate as f64;
-		read_stream
-			.seek(start_frame as usize, SeekMode::Auto)
-			.unwrap();
+	/// start counting again, after the track was replaced or seeked
+	fn reset(&mut self) {
+		self.silent = 0;
+	}
+}
 
-		// wait until the buffer is filled before sending it to the process thread
-		read_stream.block_until_ready().unwrap();
+/// audio playback backend
+///
+/// implemented by [`Player`] and used by [`Queue`](crate::queue::Queue), [`State`](crate::state::State) and the ui,
+/// so they don't depend on a concrete backend
+pub trait Playable {
+	/// replace the current track and start playing
+	fn replace(&mut self, track: &Track);
 
-		let num_frames = read_stream.info().num_frames;
-		let secs = num_frames as f64 / sample_rate as f64;
-		self.duration = Some(Duration::from_secs_f64(secs));
-		self.elapsed = Some(start);
+	/// process messages from the audio backend
+	fn update(&mut self) {}
 
-		self.status = status;
-		self.done = false;
+	/// prepare a track that is likely to be played next, so replacing it is instant
+	fn preload(&mut self, track: &Track) {
+		let _ = track;
+	}
+
+	/// seek to position in the current track
+	fn seek(&mut self, position: Duration);
 
-		self.to_process_tx
-			.push(ToProcess::UseStream {
-				stream: Box::new(read_stream),
-				status,
-			})
-			.unwrap();
+	/// set [`PlaybackStatus`]
+	fn pause(&mut self, status: PlaybackStatus);
+
+	/// toggle between [`PlaybackStatus::Play`] and [`PlaybackStatus::Paused`]
+	fn toggle(&mut self) {
+		let status = if self.paused() {
+			PlaybackStatus::Play
+		} else {
+			PlaybackStatus::Paused
+		};
+		self.pause(status);
 	}
 
-	pub fn done(&self) -> bool {
-		self.duration.is_some() && self.done
+	/// returns if playback is paused
+	fn paused(&self) -> bool;
+
+	/// returns if the current track has finished playing
+	fn done(&self) -> bool;
+
+	/// time elapsed in the current track
+	fn elapsed(&self) -> Option<Duration>;
+
+	/// duration of the current track
+	fn duration(&self) -> Option<Duration>;
+
+	/// volume in percent
+	fn volume(&self) -> u8;
+
+	/// set volume in percent
+	fn set_volume(&mut self, vol: u8);
+
+	/// the system mixer applies the volume, so the backend plays at full volume
+	fn set_mixed(&mut self, mixed: bool) {
+		let _ = mixed;
 	}
 
-	pub fn seek(&mut self, position: Duration) {
-		let _ = self.to_process_tx.push(ToProcess::SeekTo(position));
+	/// increase volume by `amt` percent
+	fn i_vol(&mut self, amt: u8) {
+		let vol = u8::min(100, self.volume().saturating_add(amt));
+		self.set_volume(vol);
 	}
 
-	pub fn toggle(&mut self) {
-		let status = self.status.invert();
-		self.status = status;
-		let _ = self.to_process_tx.push(ToProcess::Status(status));
+	/// decrease volume by `amt` percent
+	fn d_vol(&mut self, amt: u8) {
+		let vol = self.volume().saturating_sub(amt);
+		self.set_volume(vol);
 	}
 
-	#[cfg(feature = "mpris")]
-	pub fn pause(&mut self, status: PlaybackStatus) {
-		self.status = status;
-		let _ = self.to_process_tx.push(ToProcess::Status(status));
+	/// toggle mute
+	fn mute(&mut self);
+
+	/// returns if muted
+	fn muted(&self) -> bool;
+
+	/// stereo balance from -100 (left) to 100 (right)
+	fn balance(&self) -> i8;
+
+	/// set stereo balance, clamped to -100..=100
+	fn set_balance(&mut self, balance: i8);
+
+	/// shift stereo balance by `amt`, negative values shift to the left
+	fn shift_balance(&mut self, amt: i8) {
+		let balance = self.balance().saturating_add(amt).clamp(-100, 100);
+		self.set_balance(balance);
 	}
 
-	pub fn volume(&self) -> u8 {
-		self.volume
+	/// returns if both channels are mixed down to mono
+	fn mono(&self) -> bool;
+
+	/// toggle mono downmix
+	fn toggle_mono(&mut self);
+
+	/// lower the volume while a [`Preview`] plays, without changing [`Playable::volume`]
+	fn duck(&mut self, duck: bool) {
+		let _ = duck;
 	}
 
-	pub fn paused(&self) -> bool {
-		self.status == PlaybackStatus::Paused
+	/// returns if the output device or the current track is still being opened
+	fn loading(&self) -> bool {
+		false
 	}
 
-	pub fn duration(&self) -> Option<Duration> {
-		self.duration
+	/// state of the output stream
+	fn output(&self) -> Output {
+		Output::Ready
 	}
 
-	pub fn elapsed(&self) -> Option<Duration> {
-		self.elapsed
+	/// start recording the output to a wav file in `dir` or stop recording
+	fn toggle_recording(&mut self, dir: &Path) {
+		let _ = dir;
 	}
 
-	pub fn mute(&mut self) {
-		let muted = !self.muted;
-		self.muted = muted;
+	/// returns if the output is being recorded
+	fn recording(&self) -> bool {
+		false
+	}
 
-		let vol = if muted { 0. } else { self.volume as f32 / 100. };
-		let _ = self.to_process_tx.push(ToProcess::Volume(vol));
+	/// audio dropouts since startup
+	fn dropouts(&self) -> Dropouts {
+		Dropouts::default()
 	}
+}
 
-	pub fn muted(&self) -> bool {
-		self.muted
+#[cfg(test)]
+mod test {
+	use super::{Limiter, Mix, Runout, Silence};
+	use cpal::{BufferSize, SupportedBufferSize};
+	use std::time::Duration;
+
+	#[test]
+	fn buffer_size() {
+		let range = SupportedBufferSize::Range { min: 64, max: 4096 };
+		assert_eq!(super::buffer_size(None, &range), BufferSize::Default);
+		assert_eq!(
+			super::buffer_size(Some(256), &range),
+			BufferSize::Fixed(256)
+		);
+		assert_eq!(super::buffer_size(Some(16), &range), BufferSize::Fixed(64));
+		assert_eq!(
+			super::buffer_size(Some(8192), &range),
+			BufferSize::Fixed(4096)
+		);
+
+		let unknown = SupportedBufferSize::Unknown;
+		assert_eq!(
+			super::buffer_size(Some(16), &unknown),
+			BufferSize::Fixed(16)
+		);
 	}
 
-	pub fn i_vol(&mut self, amt: u8) {
-		let vol = u8::min(100, self.volume.saturating_add(amt));
-		self.volume = vol;
+	#[test]
+	fn mix() {
+		let frame = [1.0, 0.5];
+		assert_eq!(Mix::default().apply(frame), frame);
+
+		assert_eq!(Mix::new(-100, false).apply(frame), [1.0, 0.0]);
+		assert_eq!(Mix::new(50, false).apply(frame), [0.5, 0.5]);
+		assert_eq!(Mix::new(i8::MAX, false).apply(frame), [0.0, 0.5]);
 
-		let _ = self
-			.to_process_tx
-			.push(ToProcess::Volume(vol as f32 / 100.));
+		assert_eq!(Mix::new(0, true).apply(frame), [0.75, 0.75]);
+		assert_eq!(Mix::new(100, true).apply(frame), [0.0, 0.75]);
 	}
 
-	pub fn d_vol(&mut self, amt: u8) {
-		let vol = self.volume.saturating_sub(amt);
-		self.volume = vol;
+	#[test]
+	fn limiter() {
+		let limiter = Limiter::new(0.5);
+		assert_eq!(limiter.apply(0.25), 0.25);
+		assert_eq!(limiter.apply(-0.5), -0.5);
+
+		for sample in [0.75, 1.0, 2.0, 100.0] {
+			let limited = limiter.apply(sample);
+			assert!(limited > 0.5 && limited <= 1.0, "{sample} -> {limited}");
+			assert_eq!(limiter.apply(-sample), -limited);
+		}
 
-		let _ = self
-			.to_process_tx
-			.push(ToProcess::Volume(vol as f32 / 100.));
+		let hard = Limiter::new(1.0);
+		assert_eq!(hard.apply(0.75), 0.75);
+		assert_eq!(hard.apply(-2.0), -1.0);
 	}
 
-	#[cfg(feature = "mpris")]
-	pub fn set_volume(&mut self, vol: u8) {
-		self.volume = vol;
+	#[test]
+	fn trim() {
+		let trim = Silence::new((-60., Duration::from_secs(2)));
+		assert!(trim.silent([0.0, -0.0005]));
+		assert!(!trim.silent([0.0, 0.01]));
+		assert!(!trim.silent([-0.5, 0.0]));
 
-		let _ = self
-			.to_process_tx
-			.push(ToProcess::Volume(vol as f32 / 100.));
-	}
-}
+		assert_eq!(trim.frames(44100), 88200);
 
-pub trait Playable {
-	fn replace(&mut self, track: &Track);
-}
+		let runout = Silence::new((-40., Duration::from_secs(5)));
+		assert!(runout.silent([0.005, -0.005]));
+		assert!(!runout.silent([0.02, 0.0]));
+		assert_eq!(runout.frames(48000), 240_000);
+	}
 
-impl Playable for Player {
-	fn replace(&mut self, track: &Track) {
-		self.replace_inner(track, PlaybackStatus::Play, Duration::ZERO);
+	#[test]
+	fn runout() {
+		let secs = |secs| Some(Duration::from_secs(secs));
+		// 5 seconds of silence in the last 20 seconds, at 10 frames per second
+		let mut runout = Runout::new((-40., Duration::from_secs(5), Duration::from_secs(20)));
+
+		// silence in the middle of the track doesn't end it
+		runout.count(true, 100, secs(60));
+		assert!(!runout.ran_out(10));
+		runout.count(false, 10, secs(50));
+		runout.count(true, 40, secs(20));
+		assert!(!runout.ran_out(10));
+
+		// neither does silence that is interrupted
+		runout.count(false, 1, secs(16));
+		runout.count(true, 40, secs(16));
+		assert!(!runout.ran_out(10));
+
+		runout.count(true, 10, secs(12));
+		assert!(runout.ran_out(10));
+
+		// tracks without a known length never run out
+		runout.reset();
+		runout.count(true, 100, None);
+		assert!(!runout.ran_out(10));
 	}
 }
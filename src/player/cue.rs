@@ -0,0 +1,137 @@
+//! second output device, like headphones, for previews and mirroring the playback
+
+use super::write_frame;
+use crate::config::Config;
+use cpal::{
+	Device, StreamConfig,
+	traits::{DeviceTrait, HostTrait, StreamTrait},
+};
+use rtrb::{Producer, RingBuffer};
+use std::sync::OnceLock;
+
+/// capacity of the frame buffer between the main output and the mirror
+const BUFFER: usize = 1 << 13;
+
+/// cue device from the [`Config`]
+static CUE: OnceLock<Cue> = OnceLock::new();
+
+#[derive(Debug)]
+struct Cue {
+	/// name of the device
+	device: String,
+	gain: f32,
+	/// play the playback on the cue device as well
+	mirror: bool,
+}
+
+/// load the cue device from the [`Config`]
+pub fn load(config: &Config) {
+	let Some(device) = config.cue_device() else {
+		return;
+	};
+
+	let _ = CUE.set(Cue {
+		device: device.to_owned(),
+		gain: f32::from(config.cue_volume()) / 100.,
+		mirror: config.mirror(),
+	});
+}
+
+/// returns if a cue device is configured
+pub fn configured() -> bool {
+	CUE.get().is_some()
+}
+
+/// find the cue device, returns it with its gain
+pub fn device() -> Option<(Device, f32)> {
+	let cue = CUE.get()?;
+
+	let found = cpal::default_host()
+		.output_devices()
+		.ok()
+		.and_then(|mut devices| {
+			devices.find(|device| {
+				(device.description()).is_ok_and(|description| description.name() == cue.device)
+			})
+		});
+
+	if found.is_none() {
+		log::warn!("couldn't find the cue device {:?}", cue.device);
+	}
+	found.map(|device| (device, cue.gain))
+}
+
+/// sink copying the main output to the cue device
+pub struct Mirror {
+	tx: Producer<[f32; 2]>,
+	/// channels of the main output
+	channels: usize,
+}
+
+impl Mirror {
+	/// open the mirror on the cue device at the sample rate of the main output `config`
+	///
+	/// returns [`None`] if mirroring is off or the device doesn't support the sample rate
+	pub fn open(config: &StreamConfig) -> Option<Mirror> {
+		if !CUE.get().is_some_and(|cue| cue.mirror) {
+			return None;
+		}
+
+		let (device, gain) = device()?;
+		let supported = device
+			.supported_output_configs()
+			.ok()
+			.and_then(|mut configs| {
+				configs.find_map(|range| range.try_with_sample_rate(config.sample_rate))
+			});
+		let Some(supported) = supported else {
+			log::warn!("the cue device doesn't support {} hz", config.sample_rate);
+			return None;
+		};
+
+		let sample_format = supported.sample_format();
+		let cue_config = super::stream_config(supported);
+		let channels = usize::from(cue_config.channels);
+
+		let (tx, mut rx) = RingBuffer::<[f32; 2]>::new(BUFFER);
+		let process = move |data: &mut [f32]| {
+			for frame in data.chunks_exact_mut(channels) {
+				let [left, right] = rx.pop().unwrap_or_default();
+				write_frame(frame, [left * gain, right * gain]);
+			}
+		};
+
+		let stream = super::output_stream(&device, &cue_config, sample_format, process, |err| {
+			log::warn!("cue stream error: {err}");
+		});
+		let played = stream.map_err(|err| err.to_string()).and_then(|stream| {
+			stream.play().map_err(|err| err.to_string())?;
+			Ok(stream)
+		});
+
+		match played {
+			Ok(stream) => {
+				// lives as long as the main output, which is never dropped either
+				std::mem::forget(stream);
+				Some(Mirror {
+					tx,
+					channels: usize::from(config.channels),
+				})
+			}
+			Err(err) => {
+				log::error!("couldn't open the cue device: {err}");
+				None
+			}
+		}
+	}
+
+	/// copy the frames of the main output, dropping them if the cue device falls behind
+	pub fn push(&mut self, data: &[f32]) {
+		for frame in data.chunks_exact(self.channels) {
+			let right = frame[if self.channels == 1 { 0 } else { 1 }];
+			if self.tx.push([frame[0], right]).is_err() {
+				break;
+			}
+		}
+	}
+}
@@ -0,0 +1,1057 @@
+//! symphonia backend
+//!
+//! decodes tracks on a separate thread and hands the frames to the cpal callback,
+//! without going through creek's disk streaming
+
+use super::{
+	Counter, DUCK, Dropouts, FromOutput, Limiter, Mix, Output, Playable, PlaybackStatus, Runout,
+	Silence,
+	outbox::{Coalesce, Outbox},
+	record::{self, Tap},
+};
+use crate::{
+	config::{Config, Intro, Resampling},
+	queue::{Queue, Track},
+	state::State,
+};
+use camino::{Utf8Path, Utf8PathBuf};
+use rtrb::{Consumer, Producer, RingBuffer};
+use std::{
+	fmt::Debug,
+	fs::File,
+	path::Path,
+	sync::{
+		Arc,
+		atomic::{AtomicU64, Ordering},
+		mpsc::{self, Receiver, Sender, TryRecvError},
+	},
+	time::Duration,
+};
+use symphonia::core::{
+	audio::SampleBuffer,
+	codecs::{Decoder, DecoderOptions},
+	errors::Error as SymphoniaError,
+	formats::{FormatOptions, FormatReader, SeekMode, SeekTo},
+	io::MediaSourceStream,
+	meta::MetadataOptions,
+	probe::Hint,
+	units::TimeBase,
+};
+
+/// capacity of the frame buffer between the decoder and the cpal callback
+const BUFFER: usize = 1 << 15;
+
+enum Frame {
+	/// start of a new track or seek
+	Start {
+		epoch: u64,
+		position: u64,
+	},
+	Sample([f32; 2]),
+	/// end of the track
+	End,
+}
+
+enum ToDecoder {
+	/// open the track at `path` and decode it from `start`
+	Load {
+		epoch: u64,
+		path: Utf8PathBuf,
+		start: Duration,
+	},
+	Seek {
+		epoch: u64,
+		position: Duration,
+	},
+	Trim(Option<Silence>),
+	Runout(Option<Runout>),
+	Resampler(Resampling),
+}
+
+/// result of a [`ToDecoder::Load`]
+enum FromDecoder {
+	Opened {
+		epoch: u64,
+		duration: Option<Duration>,
+	},
+	Failed {
+		epoch: u64,
+		err: SymphoniaError,
+	},
+}
+
+enum ToProcess {
+	Status(PlaybackStatus),
+	Volume(f32),
+	Mix(Mix),
+	Limiter(Option<Limiter>),
+	Record(Option<Tap>),
+	/// length of the fade-out when the track is replaced during playback
+	Fade(Duration),
+	/// fade out the frames still buffered instead of dropping them at the next epoch
+	FadeOut,
+}
+
+impl Coalesce for ToProcess {
+	fn replaces(&self, earlier: &Self) -> bool {
+		matches!(
+			(self, earlier),
+			(ToProcess::Status(_), ToProcess::Status(_))
+				| (ToProcess::Volume(_), ToProcess::Volume(_))
+				| (ToProcess::Mix(_), ToProcess::Mix(_))
+		)
+	}
+}
+
+enum FromProcess {
+	Playhead(Duration),
+	IsDone,
+}
+
+/// an opened track
+struct Stream {
+	format: Box<dyn FormatReader>,
+	decoder: Box<dyn Decoder>,
+	track_id: u32,
+	sample_rate: u32,
+	time_base: Option<TimeBase>,
+	duration: Option<Duration>,
+}
+
+impl Stream {
+	fn open(path: &Utf8Path) -> Result<Stream, SymphoniaError> {
+		let file = File::open(path)?;
+		let stream = MediaSourceStream::new(Box::new(file), Default::default());
+
+		let mut hint = Hint::new();
+		if let Some(extension) = path.extension() {
+			hint.with_extension(extension);
+		}
+
+		let probed = symphonia::default::get_probe().format(
+			&hint,
+			stream,
+			&FormatOptions::default(),
+			&MetadataOptions::default(),
+		)?;
+		let format = probed.format;
+
+		let track = format
+			.default_track()
+			.ok_or(SymphoniaError::Unsupported("no audio track"))?;
+		let params = &track.codec_params;
+
+		let sample_rate = params
+			.sample_rate
+			.ok_or(SymphoniaError::Unsupported("unknown sample rate"))?;
+		let duration = params
+			.n_frames
+			.map(|frames| Duration::from_secs_f64(frames as f64 / f64::from(sample_rate)));
+
+		let track_id = track.id;
+		let time_base = params.time_base;
+		let decoder = symphonia::default::get_codecs().make(params, &DecoderOptions::default())?;
+
+		Ok(Stream {
+			format,
+			decoder,
+			track_id,
+			sample_rate,
+			time_base,
+			duration,
+		})
+	}
+
+	/// time remaining after the packet starting at `ts`
+	fn remaining(&self, ts: u64) -> Option<Duration> {
+		let time = self.time_base?.calc_time(ts);
+		let time = Duration::from_secs(time.seconds) + Duration::from_secs_f64(time.frac);
+		Some(self.duration?.saturating_sub(time))
+	}
+
+	fn seek(&mut self, position: Duration) {
+		let to = SeekTo::Time {
+			time: position.into(),
+			track_id: Some(self.track_id),
+		};
+
+		// seeking past the end just ends the track
+		let _ = self.format.seek(SeekMode::Accurate, to);
+		self.decoder.reset();
+	}
+}
+
+/// linear or cubic interpolation resampler
+///
+/// the cubic one interpolates between the two frames before the newest one,
+/// so it lags a frame behind
+struct Interpolate {
+	cubic: bool,
+	step: f64,
+	position: f64,
+	/// the last three frames, oldest first
+	last: [[f32; 2]; 3],
+}
+
+impl Interpolate {
+	fn new(from: u32, to: u32, cubic: bool) -> Self {
+		Interpolate {
+			cubic,
+			step: f64::from(from) / f64::from(to),
+			position: 0.0,
+			last: [[0.0; 2]; 3],
+		}
+	}
+
+	fn process(&mut self, frame: [f32; 2], mut out: impl FnMut([f32; 2])) {
+		let [p0, p1, p2] = self.last;
+
+		while self.position < 1.0 {
+			let t = self.position as f32;
+			let sample = |ch: usize| {
+				if self.cubic {
+					let [p0, p1, p2, p3] = [p0[ch], p1[ch], p2[ch], frame[ch]];
+
+					// catmull-rom spline through the four frames
+					let a = -0.5 * p0 + 1.5 * p1 - 1.5 * p2 + 0.5 * p3;
+					let b = p0 - 2.5 * p1 + 2.0 * p2 - 0.5 * p3;
+					let c = -0.5 * p0 + 0.5 * p2;
+					((a * t + b) * t + c) * t + p1
+				} else {
+					p2[ch] + (frame[ch] - p2[ch]) * t
+				}
+			};
+			out([sample(0), sample(1)]);
+
+			self.position += self.step;
+		}
+
+		self.position -= 1.0;
+		self.last = [p1, p2, frame];
+	}
+}
+
+struct Decode {
+	stream: Option<(Box<Stream>, Interpolate)>,
+	buffer: Option<SampleBuffer<f32>>,
+	sample_rate: u32,
+	resampler: Resampling,
+	trim: Option<Silence>,
+	/// frames of leading silence that may still be skipped
+	leading: u64,
+	/// frames of trailing silence held back, in case the track continues after it
+	pending: u64,
+	runout: Option<Runout>,
+
+	// comm
+	from_main_rx: Receiver<ToDecoder>,
+	to_main_tx: Sender<FromDecoder>,
+	to_process_tx: Producer<Frame>,
+}
+
+impl Decode {
+	fn run(mut self) {
+		loop {
+			let msg = if self.stream.is_some() {
+				match self.from_main_rx.try_recv() {
+					Ok(msg) => Some(msg),
+					Err(TryRecvError::Empty) => None,
+					Err(TryRecvError::Disconnected) => return,
+				}
+			} else {
+				match self.from_main_rx.recv() {
+					Ok(msg) => Some(msg),
+					Err(_) => return,
+				}
+			};
+
+			match msg {
+				Some(ToDecoder::Load { epoch, path, start }) => {
+					let mut stream = match Stream::open(&path) {
+						Ok(stream) => Box::new(stream),
+						Err(err) => {
+							self.stream = None;
+							let _ = self.to_main_tx.send(FromDecoder::Failed { epoch, err });
+							continue;
+						}
+					};
+
+					let duration = stream.duration;
+					let _ = self
+						.to_main_tx
+						.send(FromDecoder::Opened { epoch, duration });
+
+					if !start.is_zero() {
+						stream.seek(start);
+					}
+
+					self.leading = match self.trim {
+						Some(trim) if start.is_zero() => trim.frames(stream.sample_rate),
+						_ => 0,
+					};
+					self.pending = 0;
+					if let Some(runout) = &mut self.runout {
+						runout.reset();
+					}
+
+					let cubic = self.resampler != Resampling::Linear;
+					let resampler = Interpolate::new(stream.sample_rate, self.sample_rate, cubic);
+					self.stream = Some((stream, resampler));
+					self.buffer = None;
+
+					self.start(epoch, start);
+				}
+				Some(ToDecoder::Seek { epoch, position }) => {
+					if let Some((stream, _)) = &mut self.stream {
+						stream.seek(position);
+						self.leading = 0;
+						self.pending = 0;
+						if let Some(runout) = &mut self.runout {
+							runout.reset();
+						}
+						self.start(epoch, position);
+					}
+				}
+				Some(ToDecoder::Trim(trim)) => self.trim = trim,
+				Some(ToDecoder::Runout(runout)) => self.runout = runout,
+				Some(ToDecoder::Resampler(resampler)) => self.resampler = resampler,
+				None => self.decode(),
+			}
+		}
+	}
+
+	fn start(&mut self, epoch: u64, position: Duration) {
+		let position = position.as_secs_f64() * f64::from(self.sample_rate);
+		let position = position as u64;
+
+		self.push(Frame::Start { epoch, position });
+	}
+
+	/// decode the next packet
+	fn decode(&mut self) {
+		let Some((stream, resampler)) = &mut self.stream else {
+			return;
+		};
+
+		let packet = match stream.format.next_packet() {
+			Ok(packet) => packet,
+			// reset on a stream discontinuity
+			Err(SymphoniaError::ResetRequired) => {
+				stream.decoder.reset();
+				return;
+			}
+			Err(SymphoniaError::IoError(err))
+				if err.kind() == std::io::ErrorKind::UnexpectedEof =>
+			{
+				self.stream = None;
+				self.push(Frame::End);
+				return;
+			}
+			// a read error ends the track all the same
+			Err(err) => {
+				log::error!("couldn't read packet: {err}");
+				self.stream = None;
+				self.push(Frame::End);
+				return;
+			}
+		};
+
+		if packet.track_id() != stream.track_id {
+			return;
+		}
+
+		let decoded = match stream.decoder.decode(&packet) {
+			Ok(decoded) => decoded,
+			Err(SymphoniaError::DecodeError(err)) => {
+				log::warn!("skipping corrupt packet: {err}");
+				return;
+			}
+			Err(err) => {
+				log::error!("couldn't decode packet: {err}");
+				self.stream = None;
+				self.push(Frame::End);
+				return;
+			}
+		};
+
+		let spec = *decoded.spec();
+		let channels = spec.channels.count();
+
+		let buffer =
+			(self.buffer).get_or_insert_with(|| SampleBuffer::new(decoded.capacity() as u64, spec));
+		buffer.copy_interleaved_ref(decoded);
+
+		// silence in the last frames of the track is held back and dropped at the end
+		let remaining = stream.remaining(packet.ts());
+		let trailing = self
+			.trim
+			.is_some_and(|trim| remaining.is_some_and(|remaining| remaining <= trim.duration));
+
+		let tx = &mut self.to_process_tx;
+		for frame in buffer.samples().chunks_exact(channels) {
+			let frame = [frame[0], frame[if channels == 1 { 0 } else { 1 }]];
+
+			// sustained silence ends the track like the end of the file
+			if let Some(runout) = &mut self.runout {
+				runout.count(runout.silent(frame), 1, remaining);
+				if runout.ran_out(stream.sample_rate) {
+					self.stream = None;
+					self.push(Frame::End);
+					return;
+				}
+			}
+
+			if let Some(trim) = self.trim {
+				let silent = trim.silent(frame);
+				if silent && self.leading > 0 {
+					self.leading -= 1;
+					continue;
+				}
+				self.leading = 0;
+
+				if silent && trailing {
+					self.pending += 1;
+					continue;
+				}
+
+				for _ in 0..std::mem::take(&mut self.pending) {
+					resampler.process([0.0; 2], |frame| Decode::wait(tx, Frame::Sample(frame)));
+				}
+			}
+
+			resampler.process(frame, |frame| Decode::wait(tx, Frame::Sample(frame)));
+		}
+	}
+
+	fn push(&mut self, frame: Frame) {
+		Decode::wait(&mut self.to_process_tx, frame);
+	}
+
+	/// push a frame, waiting for the callback if the buffer is full
+	fn wait(tx: &mut Producer<Frame>, mut frame: Frame) {
+		while let Err(rtrb::PushError::Full(ret)) = tx.push(frame) {
+			frame = ret;
+			std::thread::sleep(Duration::from_millis(5));
+		}
+	}
+}
+
+/// track fading out before the frames of the next one are played
+struct Fading {
+	/// length of the fade-out
+	frames: usize,
+	/// frames left until the fade-out is done
+	left: usize,
+}
+
+struct Process {
+	channels: usize,
+	sample_rate: u32,
+
+	// status
+	status: PlaybackStatus,
+	volume: f32,
+	mix: Mix,
+	limiter: Option<Limiter>,
+	/// recording of the output
+	tap: Option<Tap>,
+	done: bool,
+	/// decoder couldn't keep up
+	stalled: bool,
+	epoch: u64,
+	position: u64,
+	/// length of the fade-out when the track is replaced during playback
+	fade: Duration,
+	fading: Option<Fading>,
+
+	// comm
+	dropouts: Arc<Counter>,
+	current_epoch: Arc<AtomicU64>,
+	frames_rx: Consumer<Frame>,
+	from_main_rx: Consumer<ToProcess>,
+	to_main_tx: Producer<FromProcess>,
+}
+
+impl Process {
+	pub fn process(&mut self, data: &mut [f32]) {
+		while let Ok(msg) = self.from_main_rx.pop() {
+			match msg {
+				ToProcess::Status(status) => {
+					self.status = status;
+				}
+				ToProcess::Volume(volume) => {
+					debug_assert!((0.0..=1.0).contains(&volume));
+					self.volume = volume;
+				}
+				ToProcess::Mix(mix) => {
+					self.mix = mix;
+				}
+				ToProcess::Limiter(limiter) => {
+					self.limiter = limiter;
+				}
+				ToProcess::Record(tap) => {
+					self.tap = tap;
+				}
+				ToProcess::Fade(fade) => {
+					self.fade = fade;
+				}
+				ToProcess::FadeOut => {
+					let frames = (self.fade.as_secs_f64() * f64::from(self.sample_rate)) as usize;
+					let playing = !self.done && self.status == PlaybackStatus::Play;
+
+					// skipped again while fading, the old track keeps fading out
+					if self.fading.is_none() && playing && frames > 0 {
+						self.fading = Some(Fading {
+							frames,
+							left: frames,
+						});
+					}
+				}
+			}
+		}
+
+		let volume = self.volume.powi(3);
+		if self.fading.is_some() {
+			for frame in data.chunks_mut(self.channels) {
+				self.fade_frame(frame, volume);
+			}
+			return;
+		}
+
+		// drop frames from before the last replace or seek
+		let epoch = self.current_epoch.load(Ordering::Acquire);
+		while self.epoch != epoch {
+			match self.frames_rx.pop() {
+				Ok(Frame::Start {
+					epoch: start,
+					position,
+				}) if start == epoch => self.start(epoch, position),
+				Ok(_) => {}
+				Err(_) => break,
+			}
+		}
+
+		if self.epoch != epoch || self.done || self.status == PlaybackStatus::Paused {
+			data.fill(0.0);
+			return;
+		}
+
+		for frame in data.chunks_mut(self.channels) {
+			if self.done {
+				frame.fill(0.0);
+				continue;
+			}
+
+			match self.frames_rx.pop() {
+				Ok(Frame::Sample(samples)) => {
+					self.output(frame, samples.map(|sample| sample * volume));
+					self.position += 1;
+					self.stalled = false;
+				}
+				Ok(Frame::End) => {
+					frame.fill(0.0);
+
+					self.done = true;
+					let _ = self.to_main_tx.push(FromProcess::IsDone);
+				}
+				Ok(Frame::Start { .. }) | Err(_) => {
+					frame.fill(0.0);
+
+					if !self.stalled {
+						self.stalled = true;
+						self.dropouts.stall();
+					}
+				}
+			}
+		}
+
+		let playhead = self.position as f64 / f64::from(self.sample_rate);
+		let playhead = Duration::from_secs_f64(playhead);
+		let _ = self.to_main_tx.push(FromProcess::Playhead(playhead));
+	}
+
+	/// play the frames of the epoch from `position`
+	fn start(&mut self, epoch: u64, position: u64) {
+		self.epoch = epoch;
+		self.position = position;
+		self.done = false;
+		// waiting for the first frames isn't a stall
+		self.stalled = true;
+	}
+
+	/// mix and limit `samples` into the output `frame` and record it
+	fn output(&mut self, frame: &mut [f32], samples: [f32; 2]) {
+		let samples = self.mix.apply(samples);
+		for (i, sample) in frame.iter_mut().enumerate() {
+			*sample = samples[usize::min(i, 1)];
+			if let Some(limiter) = self.limiter {
+				*sample = limiter.apply(*sample);
+			}
+		}
+		if let Some(tap) = &mut self.tap {
+			tap.push([frame[0], frame[usize::min(1, frame.len() - 1)]]);
+		}
+	}
+
+	/// play the next frame of the track fading out, or silence once it is done
+	///
+	/// the fade-out ends early if the track ends or not enough of it is buffered
+	fn fade_frame(&mut self, frame: &mut [f32], volume: f32) {
+		let Some(fading) = &mut self.fading else {
+			frame.fill(0.0);
+			return;
+		};
+
+		match self.frames_rx.pop() {
+			Ok(Frame::Sample(samples)) => {
+				let gain = fading.left as f32 / fading.frames as f32;
+				fading.left = fading.left.saturating_sub(1);
+				if fading.left == 0 {
+					self.fading = None;
+				}
+
+				self.output(frame, samples.map(|sample| sample * volume * gain));
+			}
+			Ok(Frame::Start { epoch, position }) => {
+				self.fading = None;
+				frame.fill(0.0);
+
+				if epoch == self.current_epoch.load(Ordering::Acquire) {
+					self.start(epoch, position);
+				}
+			}
+			Ok(Frame::End) | Err(_) => {
+				self.fading = None;
+				frame.fill(0.0);
+			}
+		}
+	}
+}
+
+pub struct Player {
+	// state
+	muted: bool,
+	/// volume is lowered for a [`Preview`](super::Preview)
+	ducked: bool,
+	/// volume is applied by the system mixer
+	mixed: bool,
+	volume: u8,
+	balance: i8,
+	mono: bool,
+	done: bool,
+	status: PlaybackStatus,
+	elapsed: Option<Duration>,
+	duration: Option<Duration>,
+	epoch: u64,
+	output: Output,
+	/// sample rate of the output stream, only valid once it is [`Output::Ready`]
+	sample_rate: u32,
+	/// output is being recorded
+	recording: bool,
+	/// messages from the thread opening the output stream
+	output_rx: Receiver<FromOutput>,
+	/// time skipped at the start of a track
+	intro: Intro,
+	/// epoch of the track being opened by the decoder, with the duration from its tags
+	opening: Option<(u64, Option<Duration>)>,
+
+	// comm
+	dropouts: Arc<Counter>,
+	current_epoch: Arc<AtomicU64>,
+	to_decoder_tx: Sender<ToDecoder>,
+	from_decoder_rx: Receiver<FromDecoder>,
+	to_process_tx: Outbox<ToProcess>,
+	from_process_rx: Consumer<FromProcess>,
+}
+
+impl Debug for Player {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("Player").finish_non_exhaustive()
+	}
+}
+
+impl Player {
+	pub fn new() -> Self {
+		let (to_process_tx, from_main_rx) = RingBuffer::<ToProcess>::new(64);
+		let (to_main_tx, from_process_rx) = RingBuffer::<FromProcess>::new(256);
+		let (frames_tx, frames_rx) = RingBuffer::<Frame>::new(BUFFER);
+		let (to_decoder_tx, from_main_decoder_rx) = mpsc::channel();
+		let (to_main_decoder_tx, from_decoder_rx) = mpsc::channel();
+
+		let current_epoch = Arc::new(AtomicU64::new(0));
+		let dropouts = Arc::new(Counter::default());
+
+		let epoch = Arc::clone(&current_epoch);
+		let counter = Arc::clone(&dropouts);
+		let output_rx = super::spawn_output(Arc::clone(&dropouts), move |stream_config| {
+			let mut process = Process {
+				channels: usize::from(stream_config.channels),
+				sample_rate: stream_config.sample_rate,
+
+				status: PlaybackStatus::Paused,
+				volume: 0.45,
+				mix: Mix::default(),
+				limiter: None,
+				tap: None,
+				done: false,
+				stalled: false,
+				epoch: 0,
+				position: 0,
+				fade: Duration::ZERO,
+				fading: None,
+
+				dropouts: counter,
+				current_epoch: epoch,
+				frames_rx,
+				from_main_rx,
+				to_main_tx,
+			};
+
+			let decode = Decode {
+				stream: None,
+				buffer: None,
+				sample_rate: stream_config.sample_rate,
+				resampler: Resampling::Linear,
+				trim: None,
+				leading: 0,
+				pending: 0,
+				runout: None,
+
+				from_main_rx: from_main_decoder_rx,
+				to_main_tx: to_main_decoder_tx,
+				to_process_tx: frames_tx,
+			};
+
+			let spawned = std::thread::Builder::new()
+				.name("decoder".to_owned())
+				.spawn(move || decode.run());
+			if let Err(err) = spawned {
+				log::error!("couldn't spawn the decoder: {err}");
+			}
+
+			move |data: &mut [f32]| process.process(data)
+		});
+
+		Player {
+			muted: false,
+			ducked: false,
+			mixed: false,
+			volume: 45,
+			balance: 0,
+			mono: false,
+			done: false,
+			status: PlaybackStatus::Paused,
+			elapsed: None,
+			duration: None,
+			epoch: 0,
+			output: Output::Opening,
+			sample_rate: 0,
+			recording: false,
+			output_rx,
+			intro: Intro::default(),
+			opening: None,
+
+			dropouts,
+			current_epoch,
+			to_decoder_tx,
+			from_decoder_rx,
+			to_process_tx: Outbox::new(to_process_tx),
+			from_process_rx,
+		}
+	}
+
+	pub fn with_state(queue: &Queue, state: &State, config: &Config) -> Self {
+		// tracks aren't streamed from disk, so there is no buffering to configure
+		let mut player = Player::new();
+		player.intro = config.intro();
+
+		let resampler = config.resampler();
+		let _ = player.to_decoder_tx.send(ToDecoder::Resampler(resampler));
+
+		let limiter = config.limiter().map(Limiter::new);
+		player.to_process_tx.send(ToProcess::Limiter(limiter));
+		player
+			.to_process_tx
+			.send(ToProcess::Fade(config.skip_fade()));
+
+		let trim = config.trim_silence().map(Silence::new);
+		let _ = player.to_decoder_tx.send(ToDecoder::Trim(trim));
+
+		let runout = config.end_on_silence().map(Runout::new);
+		let _ = player.to_decoder_tx.send(ToDecoder::Runout(runout));
+
+		player.state(queue, state);
+
+		player
+	}
+
+	fn state(&mut self, queue: &Queue, state: &State) {
+		self.volume = state.volume;
+		self.muted = state.muted;
+		self.to_process_tx.send(ToProcess::Volume(self.gain()));
+
+		self.balance = state.balance.clamp(-100, 100);
+		self.mono = state.mono;
+		self.to_process_tx
+			.send(ToProcess::Mix(Mix::new(self.balance, self.mono)));
+
+		if let Some(track) = queue.track() {
+			let start = state.elapsed();
+			let start = start.unwrap_or_default();
+
+			self.revive(track, start);
+		}
+	}
+
+	fn revive(&mut self, track: &Track, start: Duration) {
+		self.replace_inner(track, PlaybackStatus::Paused, start);
+	}
+
+	/// gain applied by the process thread
+	///
+	/// the volume is left out while the system mixer applies it
+	fn gain(&self) -> f32 {
+		let gain = if self.muted {
+			0.
+		} else if self.mixed {
+			1.
+		} else {
+			f32::from(self.volume) / 100.
+		};
+
+		if self.ducked { gain * DUCK } else { gain }
+	}
+
+	/// start a new epoch, making the callback drop every frame still buffered
+	fn next_epoch(&mut self) -> u64 {
+		self.epoch += 1;
+		self.current_epoch.store(self.epoch, Ordering::Release);
+		self.epoch
+	}
+
+	/// let the decoder open `track` and play it from `start`
+	///
+	/// the duration is known once the decoder opened it, see [`Player::opened`]
+	fn replace_inner(&mut self, track: &Track, status: PlaybackStatus, start: Duration) {
+		self.duration = None;
+		self.elapsed = Some(start);
+
+		self.status = status;
+		self.done = false;
+
+		// sent before the epoch changes, so the buffered frames are still there to fade out
+		self.to_process_tx.send(ToProcess::FadeOut);
+		let epoch = self.next_epoch();
+		self.opening = Some((epoch, track.duration()));
+		self.to_process_tx.send(ToProcess::Status(status));
+		let _ = self.to_decoder_tx.send(ToDecoder::Load {
+			epoch,
+			path: track.path().to_owned(),
+			start,
+		});
+	}
+
+	/// handle the track the decoder opened, or couldn't open
+	fn opened(&mut self, msg: FromDecoder) {
+		let (FromDecoder::Opened { epoch, .. } | FromDecoder::Failed { epoch, .. }) = msg;
+		// a later track is already being opened
+		let Some((_, fallback)) = self.opening.take_if(|(opening, _)| *opening == epoch) else {
+			return;
+		};
+
+		match msg {
+			FromDecoder::Opened { duration, .. } => self.duration = duration.or(fallback),
+			FromDecoder::Failed { err, .. } => {
+				log::error!("couldn't open the track: {err}");
+				self.pause(PlaybackStatus::Paused);
+			}
+		}
+	}
+}
+
+impl Playable for Player {
+	fn replace(&mut self, track: &Track) {
+		let start = self.intro.get(track);
+		self.replace_inner(track, PlaybackStatus::Play, start);
+	}
+
+	fn update(&mut self) {
+		while let Ok(msg) = self.output_rx.try_recv() {
+			match msg {
+				FromOutput::NoDevice => {
+					log::warn!("no audio output device, retrying");
+					self.output = Output::NoDevice;
+				}
+				FromOutput::Ready(sample_rate) => {
+					self.sample_rate = sample_rate;
+					self.output = Output::Ready;
+				}
+				FromOutput::Failed => self.output = Output::Failed,
+			}
+		}
+
+		while let Ok(msg) = self.from_decoder_rx.try_recv() {
+			self.opened(msg);
+		}
+
+		// messages that didn't fit while the process thread was busy or not running yet
+		self.to_process_tx.flush();
+
+		while let Ok(msg) = self.from_process_rx.pop() {
+			match msg {
+				FromProcess::Playhead(duration) => {
+					self.elapsed = Some(duration);
+				}
+				FromProcess::IsDone => {
+					self.done = true;
+				}
+			}
+		}
+	}
+
+	fn seek(&mut self, position: Duration) {
+		let epoch = self.next_epoch();
+		self.elapsed = Some(position);
+
+		let _ = self.to_decoder_tx.send(ToDecoder::Seek { epoch, position });
+	}
+
+	fn pause(&mut self, status: PlaybackStatus) {
+		self.status = status;
+		self.to_process_tx.send(ToProcess::Status(status));
+	}
+
+	fn toggle(&mut self) {
+		self.pause(self.status.invert());
+	}
+
+	fn paused(&self) -> bool {
+		self.status == PlaybackStatus::Paused
+	}
+
+	fn done(&self) -> bool {
+		self.duration.is_some() && self.done
+	}
+
+	fn elapsed(&self) -> Option<Duration> {
+		self.elapsed
+	}
+
+	fn duration(&self) -> Option<Duration> {
+		self.duration
+	}
+
+	fn volume(&self) -> u8 {
+		self.volume
+	}
+
+	fn set_volume(&mut self, vol: u8) {
+		self.volume = vol;
+		self.to_process_tx.send(ToProcess::Volume(self.gain()));
+	}
+
+	fn set_mixed(&mut self, mixed: bool) {
+		self.mixed = mixed;
+		self.to_process_tx.send(ToProcess::Volume(self.gain()));
+	}
+
+	fn mute(&mut self) {
+		self.muted = !self.muted;
+		self.to_process_tx.send(ToProcess::Volume(self.gain()));
+	}
+
+	fn muted(&self) -> bool {
+		self.muted
+	}
+
+	fn balance(&self) -> i8 {
+		self.balance
+	}
+
+	fn set_balance(&mut self, balance: i8) {
+		self.balance = balance.clamp(-100, 100);
+		self.to_process_tx
+			.send(ToProcess::Mix(Mix::new(self.balance, self.mono)));
+	}
+
+	fn mono(&self) -> bool {
+		self.mono
+	}
+
+	fn toggle_mono(&mut self) {
+		self.mono = !self.mono;
+		self.to_process_tx
+			.send(ToProcess::Mix(Mix::new(self.balance, self.mono)));
+	}
+
+	fn duck(&mut self, duck: bool) {
+		self.ducked = duck;
+		self.to_process_tx.send(ToProcess::Volume(self.gain()));
+	}
+
+	fn loading(&self) -> bool {
+		self.output == Output::Opening || self.opening.is_some()
+	}
+
+	fn output(&self) -> Output {
+		self.output
+	}
+
+	fn toggle_recording(&mut self, dir: &Path) {
+		if self.recording {
+			self.recording = false;
+			self.to_process_tx.send(ToProcess::Record(None));
+			return;
+		}
+
+		if self.output != Output::Ready {
+			return;
+		}
+
+		match record::start(dir, self.sample_rate) {
+			Ok(tap) => {
+				self.recording = true;
+				self.to_process_tx.send(ToProcess::Record(Some(tap)));
+			}
+			Err(err) => log::error!("couldn't start recording: {err}"),
+		}
+	}
+
+	fn recording(&self) -> bool {
+		self.recording
+	}
+
+	fn dropouts(&self) -> Dropouts {
+		self.dropouts.get()
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::Interpolate;
+
+	#[test]
+	fn resample() {
+		let mut out = Vec::new();
+		let mut linear = Interpolate::new(1, 2, false);
+		for sample in [1.0, 1.0, 0.0] {
+			linear.process([sample; 2], |[l, _]| out.push(l));
+		}
+		assert_eq!(out, [0.0, 0.5, 1.0, 1.0, 1.0, 0.5]);
+
+		let mut out = Vec::new();
+		let mut linear = Interpolate::new(2, 1, false);
+		for sample in [1.0, 2.0, 3.0, 4.0] {
+			linear.process([sample; 2], |[l, _]| out.push(l));
+		}
+		assert_eq!(out, [0.0, 2.0]);
+
+		// a ramp stays a ramp, a frame late
+		let mut out = Vec::new();
+		let mut cubic = Interpolate::new(1, 2, true);
+		for sample in [0.0, 1.0, 2.0, 3.0, 4.0] {
+			cubic.process([sample; 2], |[l, _]| out.push(l));
+		}
+		assert_eq!(out[6..], [1.0, 1.5, 2.0, 2.5]);
+	}
+}
@@ -0,0 +1,971 @@
+//! creek disk streaming backend
+
+use super::{
+	Counter, DUCK, Dropouts, FromOutput, Limiter, Mix, Output, Playable, PlaybackStatus, Runout,
+	Silence,
+	outbox::{Coalesce, Outbox},
+	record::{self, Tap},
+};
+use crate::{
+	config::{Buffering, Config, Intro, Resampling},
+	queue::{Queue, Track},
+	state::State,
+};
+use camino::Utf8PathBuf;
+use creek::{ReadDiskStream, ReadStreamOptions, SeekMode, SymphoniaDecoder, read::ReadError};
+use rtrb::{Consumer, Producer, RingBuffer};
+use rubato::{
+	Async, FixedAsync, PolynomialDegree, Resampler, SincInterpolationParameters,
+	SincInterpolationType, WindowFunction,
+	audioadapter_buffers::direct::{SequentialSliceOfSlices, SequentialSliceOfVecs},
+};
+use std::{
+	collections::VecDeque,
+	convert::identity,
+	fmt::Debug,
+	path::Path,
+	sync::{
+		Arc,
+		mpsc::{self, Receiver, TryRecvError},
+	},
+	time::Duration,
+};
+
+enum ToProcess {
+	UseStream {
+		stream: Box<ReadDiskStream<SymphoniaDecoder>>,
+		status: PlaybackStatus,
+		/// built on the main thread, as a sinc resampler is expensive to construct
+		resampler: Option<Box<Async<f32>>>,
+	},
+	Status(PlaybackStatus),
+	Volume(f32),
+	Mix(Mix),
+	Limiter(Option<Limiter>),
+	Trim(Option<Silence>),
+	Runout(Option<Runout>),
+	Record(Option<Tap>),
+	/// length of the fade-out when the stream is replaced during playback
+	Fade(Duration),
+	SeekTo(Duration),
+}
+
+impl Coalesce for ToProcess {
+	fn replaces(&self, earlier: &Self) -> bool {
+		matches!(
+			(self, earlier),
+			(ToProcess::Status(_), ToProcess::Status(_))
+				| (ToProcess::Volume(_), ToProcess::Volume(_))
+				| (ToProcess::Mix(_), ToProcess::Mix(_))
+				| (ToProcess::SeekTo(_), ToProcess::SeekTo(_))
+		)
+	}
+}
+
+/// current stream fading out before it is replaced
+struct Fading {
+	stream: Box<ReadDiskStream<SymphoniaDecoder>>,
+	status: PlaybackStatus,
+	resampler: Option<Box<Async<f32>>>,
+	/// length of the fade-out
+	frames: usize,
+	/// frames left until the stream is replaced
+	left: usize,
+}
+
+/// maximum number of silent blocks skipped in one callback,
+/// so trimming doesn't outrun the disk cache
+const TRIM_BLOCKS: usize = 16;
+
+enum FromProcess {
+	Playhead(Duration),
+	IsDone,
+}
+
+struct Process {
+	stream: Option<Box<ReadDiskStream<SymphoniaDecoder>>>,
+	buffer: VecDeque<f32>,
+	resampler: Option<Async<f32>>,
+	resample_buffer_in: [Vec<f32>; 2],
+	resample_buffer_out: [Vec<f32>; 2],
+	/// sample rate of the output stream
+	sample_rate: u32,
+
+	// status
+	status: PlaybackStatus,
+	volume: f32,
+	mix: Mix,
+	limiter: Option<Limiter>,
+	trim: Option<Silence>,
+	/// recording of the output
+	tap: Option<Tap>,
+	/// still at the leading silence of the track
+	leading: bool,
+	/// frames of trailing silence held back, in case the track continues after it
+	pending: usize,
+	runout: Option<Runout>,
+	done: bool,
+	/// stream isn't ready to be read from
+	stalled: bool,
+	/// length of the fade-out when the stream is replaced during playback
+	fade: Duration,
+	fading: Option<Fading>,
+
+	// comm
+	dropouts: Arc<Counter>,
+	from_main_rx: Consumer<ToProcess>,
+	to_main_tx: Producer<FromProcess>,
+}
+
+impl Process {
+	pub fn new(
+		dropouts: Arc<Counter>,
+		from_main_rx: Consumer<ToProcess>,
+		to_main_tx: Producer<FromProcess>,
+	) -> Self {
+		Process {
+			stream: None,
+			buffer: VecDeque::new(),
+			resampler: None,
+			resample_buffer_in: [Vec::new(), Vec::new()],
+			resample_buffer_out: [Vec::new(), Vec::new()],
+			sample_rate: 0,
+
+			status: PlaybackStatus::Paused,
+			volume: 0.45,
+			mix: Mix::default(),
+			limiter: None,
+			trim: None,
+			tap: None,
+			leading: false,
+			pending: 0,
+			runout: None,
+			done: false,
+			stalled: false,
+			fade: Duration::ZERO,
+			fading: None,
+
+			dropouts,
+			from_main_rx,
+			to_main_tx,
+		}
+	}
+
+	/// start playing `stream`
+	fn use_stream(
+		&mut self,
+		stream: Box<ReadDiskStream<SymphoniaDecoder>>,
+		status: PlaybackStatus,
+		resampler: Option<Box<Async<f32>>>,
+	) {
+		let duration = Process::playhead(&stream);
+		let _ = self.to_main_tx.push(FromProcess::Playhead(duration));
+
+		if let Some(resampler) = resampler {
+			let block_size = stream.block_size();
+			let frames = resampler.output_frames_max();
+
+			self.resample_buffer_in[0].resize(block_size, 0.0);
+			self.resample_buffer_in[1].resize(block_size, 0.0);
+
+			self.resample_buffer_out[0].resize(frames, 0.0);
+			self.resample_buffer_out[1].resize(frames, 0.0);
+
+			self.buffer.clear();
+			self.buffer.reserve(frames * 2);
+
+			self.resampler = Some(*resampler);
+		} else {
+			self.buffer.clear();
+			self.buffer.reserve(stream.block_size() * 2);
+			self.resampler = None;
+		}
+
+		self.leading = stream.playhead() == 0;
+		self.pending = 0;
+		if let Some(runout) = &mut self.runout {
+			runout.reset();
+		}
+
+		self.status = status;
+		self.done = false;
+		self.stream = Some(stream);
+	}
+
+	/// replace the faded out stream with the next one
+	fn finish_fade(&mut self) {
+		if let Some(fading) = self.fading.take() {
+			self.use_stream(fading.stream, fading.status, fading.resampler);
+		}
+	}
+
+	pub fn process(&mut self, data: &mut [f32]) {
+		while let Ok(msg) = self.from_main_rx.pop() {
+			match msg {
+				ToProcess::UseStream {
+					stream,
+					status,
+					resampler,
+				} => {
+					let playing =
+						self.stream.is_some() && !self.done && self.status == PlaybackStatus::Play;
+					let frames = (self.fade.as_secs_f64() * f64::from(self.sample_rate)) as usize;
+
+					match &mut self.fading {
+						// skipped again while fading, the old stream keeps fading out
+						Some(fading) => {
+							fading.stream = stream;
+							fading.status = status;
+							fading.resampler = resampler;
+						}
+						None if playing && frames > 0 => {
+							self.fading = Some(Fading {
+								stream,
+								status,
+								resampler,
+								frames,
+								left: frames,
+							});
+						}
+						None => self.use_stream(stream, status, resampler),
+					}
+				}
+				ToProcess::Status(status) => match &mut self.fading {
+					Some(fading) => fading.status = status,
+					None => self.status = status,
+				},
+				ToProcess::Volume(volume) => {
+					debug_assert!((0.0..=1.0).contains(&volume));
+					self.volume = volume;
+				}
+				ToProcess::Mix(mix) => {
+					self.mix = mix;
+				}
+				ToProcess::Limiter(limiter) => {
+					self.limiter = limiter;
+				}
+				ToProcess::Trim(trim) => {
+					self.trim = trim;
+				}
+				ToProcess::Runout(runout) => {
+					self.runout = runout;
+				}
+				ToProcess::Record(tap) => {
+					self.tap = tap;
+				}
+				ToProcess::Fade(fade) => {
+					self.fade = fade;
+				}
+				ToProcess::SeekTo(duration) => {
+					// seeks are meant for the next stream
+					self.finish_fade();
+
+					if let Some(stream) = &mut self.stream {
+						let sample_rate = stream.info().sample_rate.unwrap();
+						let frame = duration.as_secs_f64() * sample_rate as f64;
+						stream.seek(frame as usize, SeekMode::Auto).unwrap();
+
+						self.buffer.clear();
+						self.leading = false;
+						self.pending = 0;
+						if let Some(runout) = &mut self.runout {
+							runout.reset();
+						}
+						// waiting for the cache after a seek isn't a stall
+						self.stalled = true;
+
+						let _ = self.to_main_tx.push(FromProcess::Playhead(duration));
+					}
+				}
+			}
+		}
+
+		if let Some(stream) = &mut self.stream {
+			let ready = stream.is_ready().is_ok_and(identity);
+			let playing = !self.done && self.status == PlaybackStatus::Play;
+
+			if !ready && playing && !self.stalled {
+				self.dropouts.stall();
+			}
+			self.stalled = !ready;
+
+			if !ready || !playing {
+				data.fill(0.0);
+				self.finish_fade();
+				return;
+			}
+
+			let mut skipped = 0;
+			while self.buffer.len() < data.len() {
+				let block_size = stream.block_size();
+				let sample_rate = stream.info().sample_rate.unwrap();
+				let remaining = stream.info().num_frames.saturating_sub(stream.playhead());
+				let playhead = stream.playhead() + block_size;
+
+				// sustained silence ends the track like the end of the file
+				let ran_out = (self.runout).is_some_and(|runout| runout.ran_out(sample_rate));
+				let read = if ran_out {
+					Err(ReadError::EndOfFile)
+				} else {
+					stream.read(block_size)
+				};
+
+				let read_data = match read {
+					Ok(read_data) => read_data,
+					Err(ReadError::EndOfFile) => {
+						let len = self.buffer.len();
+						Process::fill(
+							&mut self.buffer,
+							self.volume,
+							self.mix,
+							self.limiter,
+							&mut data[..len],
+						);
+						data[len..].fill(0.0);
+
+						// the old stream ended before it faded out
+						if self.fading.is_some() {
+							self.finish_fade();
+							return;
+						}
+
+						self.done = true;
+						let _ = self.to_main_tx.push(FromProcess::IsDone);
+						return;
+					}
+					err @ Err(_) => err.unwrap(),
+				};
+
+				let ch1 = read_data.read_channel(0);
+				let ch2 = read_data.read_channel(if read_data.num_channels() == 1 { 0 } else { 1 });
+
+				if let Some(runout) = &mut self.runout {
+					let silent = ch1.iter().zip(ch2).all(|(&l, &r)| runout.silent([l, r]));
+					let remaining = remaining as f64 / f64::from(sample_rate);
+					let remaining = Duration::from_secs_f64(remaining);
+					runout.count(silent, ch1.len() as u64, Some(remaining));
+				}
+
+				let (leading, trailing) = match self.trim {
+					Some(trim) if ch1.iter().zip(ch2).all(|(&l, &r)| trim.silent([l, r])) => {
+						let max = trim.frames(sample_rate) as usize;
+						(self.leading && playhead <= max, remaining <= max)
+					}
+					_ => (false, false),
+				};
+				self.leading = leading;
+
+				if !trailing && self.pending > 0 {
+					let pending = std::mem::take(&mut self.pending);
+					self.buffer.extend(std::iter::repeat_n(0.0, pending * 2));
+				}
+
+				let len = self.buffer.len();
+
+				if let Some(resampler) = &mut self.resampler {
+					let [in_ch1, in_ch2] = &mut self.resample_buffer_in;
+
+					let ch1 = if ch1.len() < block_size {
+						in_ch1[..ch1.len()].copy_from_slice(ch2);
+						in_ch1
+					} else {
+						ch1
+					};
+
+					let ch2 = if ch2.len() < block_size {
+						in_ch2[..ch2.len()].copy_from_slice(ch2);
+						in_ch2
+					} else {
+						ch2
+					};
+
+					let chs = [ch1, ch2];
+					let buf_in = SequentialSliceOfSlices::new(
+						&chs,
+						resampler.nbr_channels(),
+						resampler.input_frames_next(),
+					)
+					.unwrap();
+
+					let mut buf_out = SequentialSliceOfVecs::new_mut(
+						&mut self.resample_buffer_out,
+						resampler.nbr_channels(),
+						resampler.output_frames_next(),
+					)
+					.unwrap();
+
+					let (_, out_len) = resampler
+						.process_into_buffer(&buf_in, &mut buf_out, None)
+						.unwrap();
+
+					let [ch1, ch2] = &self.resample_buffer_out;
+
+					for i in 0..out_len {
+						self.buffer.push_back(ch1[i]);
+						self.buffer.push_back(ch2[i]);
+					}
+				} else {
+					for i in 0..read_data.num_frames() {
+						self.buffer.push_back(ch1[i]);
+						self.buffer.push_back(ch2[i]);
+					}
+				}
+
+				if leading || trailing {
+					if trailing {
+						self.pending += (self.buffer.len() - len) / 2;
+					}
+					self.buffer.truncate(len);
+
+					skipped += 1;
+					if skipped == TRIM_BLOCKS {
+						data.fill(0.0);
+						return;
+					}
+				}
+			}
+
+			Process::fill(&mut self.buffer, self.volume, self.mix, self.limiter, data);
+
+			if let Some(fading) = &mut self.fading {
+				for frame in data.chunks_exact_mut(2) {
+					let gain = fading.left as f32 / fading.frames as f32;
+					frame.iter_mut().for_each(|sample| *sample *= gain);
+					fading.left = fading.left.saturating_sub(1);
+				}
+			}
+
+			if let Some(tap) = &mut self.tap {
+				for frame in data.chunks_exact(2) {
+					tap.push([frame[0], frame[1]]);
+				}
+			}
+
+			let duration = Process::playhead(stream);
+			let _ = self.to_main_tx.push(FromProcess::Playhead(duration));
+
+			if self.fading.as_ref().is_some_and(|fading| fading.left == 0) {
+				self.finish_fade();
+			}
+		}
+	}
+
+	/// fill `data` from `buffer`, applying volume and [`Mix`]
+	fn fill(
+		buffer: &mut VecDeque<f32>,
+		volume: f32,
+		mix: Mix,
+		limiter: Option<Limiter>,
+		data: &mut [f32],
+	) {
+		let volume = volume.powi(3);
+		for frame in data.chunks_exact_mut(2) {
+			let left = buffer.pop_front().unwrap() * volume;
+			let right = buffer.pop_front().unwrap() * volume;
+			frame.copy_from_slice(&mix.apply([left, right]));
+
+			if let Some(limiter) = limiter {
+				frame
+					.iter_mut()
+					.for_each(|sample| *sample = limiter.apply(*sample));
+			}
+		}
+	}
+
+	fn playhead<D: creek::Decoder>(stream: &ReadDiskStream<D>) -> Duration {
+		let sample_rate = stream.info().sample_rate.unwrap();
+		let playhead = stream.playhead() as f64 / sample_rate as f64;
+		Duration::from_secs_f64(playhead)
+	}
+}
+
+/// stream that is opened on another thread, [`None`] if it failed
+type Opening = Receiver<Option<ReadDiskStream<SymphoniaDecoder>>>;
+
+pub struct Player {
+	// state
+	muted: bool,
+	/// volume is lowered for a [`Preview`](super::Preview)
+	ducked: bool,
+	/// volume is applied by the system mixer
+	mixed: bool,
+	volume: u8,
+	balance: i8,
+	mono: bool,
+	done: bool,
+	status: PlaybackStatus,
+	elapsed: Option<Duration>,
+	duration: Option<Duration>,
+	buffering: Buffering,
+	resampling: Resampling,
+	/// sample rate of the output stream, only valid once it is [`Output::Ready`]
+	sample_rate: u32,
+	output: Output,
+	/// output is being recorded
+	recording: bool,
+	/// messages from the thread opening the output stream
+	output_rx: Receiver<FromOutput>,
+	/// track being opened in the background, with its start position
+	///
+	/// it is sent to the process thread by [`Playable::update`] once it is ready
+	loading: Option<(Duration, Opening)>,
+	/// track being opened ahead of time by [`Playable::preload`], with its start position
+	preload: Option<(Utf8PathBuf, Duration, Opening)>,
+	/// time skipped at the start of a track
+	intro: Intro,
+
+	// comm
+	dropouts: Arc<Counter>,
+	to_process_tx: Outbox<ToProcess>,
+	from_process_rx: Consumer<FromProcess>,
+}
+
+impl Debug for Player {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.debug_struct("Player").finish_non_exhaustive()
+	}
+}
+
+impl Player {
+	pub fn new() -> Self {
+		let (to_process_tx, from_main_rx) = RingBuffer::<ToProcess>::new(64);
+		let (to_main_tx, from_process_rx) = RingBuffer::<FromProcess>::new(256);
+
+		let dropouts = Arc::new(Counter::default());
+		let mut process = Process::new(Arc::clone(&dropouts), from_main_rx, to_main_tx);
+
+		let output_rx = super::spawn_output(Arc::clone(&dropouts), move |config| {
+			process.sample_rate = config.sample_rate;
+			move |data: &mut [f32]| process.process(data)
+		});
+
+		Player {
+			muted: false,
+			ducked: false,
+			mixed: false,
+			volume: 45,
+			balance: 0,
+			mono: false,
+			done: false,
+
+			status: PlaybackStatus::Paused,
+			elapsed: None,
+			duration: None,
+			buffering: Buffering::default(),
+			resampling: Resampling::default(),
+			sample_rate: 0,
+			output: Output::Opening,
+			recording: false,
+			output_rx,
+			loading: None,
+			preload: None,
+			intro: Intro::default(),
+
+			dropouts,
+			to_process_tx: Outbox::new(to_process_tx),
+			from_process_rx,
+		}
+	}
+
+	pub fn with_state(queue: &Queue, state: &State, config: &Config) -> Self {
+		let mut player = Player::new();
+		player.buffering = config.buffering();
+		player.resampling = config.resampler();
+		player.intro = config.intro();
+
+		let limiter = config.limiter().map(Limiter::new);
+		player.to_process_tx.send(ToProcess::Limiter(limiter));
+
+		let trim = config.trim_silence().map(Silence::new);
+		player.to_process_tx.send(ToProcess::Trim(trim));
+
+		let runout = config.end_on_silence().map(Runout::new);
+		player.to_process_tx.send(ToProcess::Runout(runout));
+
+		player
+			.to_process_tx
+			.send(ToProcess::Fade(config.skip_fade()));
+
+		player.state(queue, state);
+
+		player
+	}
+
+	fn state(&mut self, queue: &Queue, state: &State) {
+		self.volume = state.volume;
+		self.muted = state.muted;
+		self.to_process_tx.send(ToProcess::Volume(self.gain()));
+
+		self.balance = state.balance.clamp(-100, 100);
+		self.mono = state.mono;
+		self.to_process_tx
+			.send(ToProcess::Mix(Mix::new(self.balance, self.mono)));
+
+		if let Some(track) = queue.track() {
+			let start = state.elapsed();
+			let start = start.unwrap_or_default();
+
+			self.replace_inner(track, PlaybackStatus::Paused, start);
+		}
+	}
+
+	/// open `track` on a separate thread, so a slow disk doesn't block the ui
+	///
+	/// the track is opened on the current thread if the thread can't be spawned
+	fn open_in_background(&self, track: &Track, start: Duration, name: &str) -> Opening {
+		let (tx, rx) = mpsc::channel();
+		let path = track.path().to_owned();
+		let opts = self.options();
+
+		let spawned = std::thread::Builder::new().name(name.to_owned()).spawn({
+			let tx = tx.clone();
+			move || {
+				let _ = tx.send(open(path, opts, start));
+			}
+		});
+
+		if spawned.is_err() {
+			let _ = tx.send(open(track.path().to_owned(), self.options(), start));
+		}
+
+		rx
+	}
+
+	/// gain applied by the process thread
+	///
+	/// the volume is left out while the system mixer applies it
+	fn gain(&self) -> f32 {
+		let gain = if self.muted {
+			0.
+		} else if self.mixed {
+			1.
+		} else {
+			f32::from(self.volume) / 100.
+		};
+
+		if self.ducked { gain * DUCK } else { gain }
+	}
+
+	/// [`ReadStreamOptions`] from the configured [`Buffering`]
+	fn options(&self) -> ReadStreamOptions<SymphoniaDecoder> {
+		let mut opts = ReadStreamOptions::default();
+		let Buffering {
+			block_size,
+			cache_blocks,
+			caches,
+			look_ahead_blocks,
+		} = self.buffering;
+
+		if let Some(block_size) = block_size {
+			opts.block_size = block_size.max(1);
+		}
+		if let Some(cache_blocks) = cache_blocks {
+			opts.num_cache_blocks = cache_blocks;
+		}
+		if let Some(caches) = caches {
+			opts.num_caches = caches;
+		}
+		if let Some(look_ahead_blocks) = look_ahead_blocks {
+			opts.num_look_ahead_blocks = look_ahead_blocks.max(1);
+		}
+
+		opts
+	}
+
+	/// open `track` in the background and play it from `start` once it is ready
+	///
+	/// the current track keeps playing until then, while the ui shows it as loading
+	fn replace_inner(&mut self, track: &Track, status: PlaybackStatus, start: Duration) {
+		let opening = (self.preload.take())
+			.filter(|(path, preloaded, _)| *preloaded == start && path == track.path())
+			.map(|(_, _, rx)| rx)
+			.unwrap_or_else(|| self.open_in_background(track, start, "open"));
+
+		self.status = status;
+		self.elapsed = Some(start);
+		self.duration = None;
+		self.loading = Some((start, opening));
+
+		// a preloaded track is usually ready already
+		self.poll_loading();
+	}
+
+	/// send the track being opened to the process thread, if it and the output are ready
+	fn poll_loading(&mut self) {
+		if self.output != Output::Ready {
+			return;
+		}
+		let Some((start, loading)) = self.loading.take() else {
+			return;
+		};
+
+		match loading.try_recv() {
+			Ok(Some(read_stream)) => {
+				let position = self.elapsed.unwrap_or(start);
+				self.use_stream(read_stream, self.status, start);
+				if position != start {
+					self.seek(position);
+				}
+			}
+			Err(TryRecvError::Empty) => self.loading = Some((start, loading)),
+			Ok(None) | Err(TryRecvError::Disconnected) => {
+				log::error!("couldn't open the track");
+				self.pause(PlaybackStatus::Paused);
+			}
+		}
+	}
+
+	/// send an opened stream to the process thread
+	fn use_stream(
+		&mut self,
+		read_stream: ReadDiskStream<SymphoniaDecoder>,
+		status: PlaybackStatus,
+		start: Duration,
+	) {
+		let sample_rate = read_stream.info().sample_rate.unwrap();
+
+		let num_frames = read_stream.info().num_frames;
+		let secs = num_frames as f64 / sample_rate as f64;
+		self.duration = Some(Duration::from_secs_f64(secs));
+		self.elapsed = Some(start);
+
+		self.status = status;
+		self.done = false;
+
+		let output_rate = self.sample_rate;
+		let resampler = (sample_rate != output_rate).then(|| {
+			let block_size = read_stream.block_size();
+			Box::new(resampler(
+				self.resampling,
+				sample_rate,
+				output_rate,
+				block_size,
+			))
+		});
+
+		self.to_process_tx.send(ToProcess::UseStream {
+			stream: Box::new(read_stream),
+			status,
+			resampler,
+		});
+	}
+}
+
+/// open the track at `path`, seek to `start` and wait until the buffer is filled
+fn open(
+	path: Utf8PathBuf,
+	opts: ReadStreamOptions<SymphoniaDecoder>,
+	start: Duration,
+) -> Option<ReadDiskStream<SymphoniaDecoder>> {
+	let mut read_stream = ReadDiskStream::new(path, 0, opts).ok()?;
+
+	if !start.is_zero() {
+		let sample_rate = read_stream.info().sample_rate?;
+		let start_frame = (start.as_secs_f64() * sample_rate as f64).round();
+		read_stream
+			.seek(start_frame as usize, SeekMode::Auto)
+			.ok()?;
+	}
+
+	read_stream.block_until_ready().ok()?;
+	Some(read_stream)
+}
+
+/// construct a stereo resampler from sample rate `from` to `to`
+fn resampler(quality: Resampling, from: u32, to: u32, block_size: usize) -> Async<f32> {
+	let ratio = f64::from(to) / f64::from(from);
+
+	match quality {
+		Resampling::Linear | Resampling::Cubic => {
+			let degree = if quality == Resampling::Linear {
+				PolynomialDegree::Linear
+			} else {
+				PolynomialDegree::Cubic
+			};
+
+			Async::new_poly(ratio, 1.0, degree, block_size, 2, FixedAsync::Input).unwrap()
+		}
+		Resampling::Sinc => {
+			let parameters = SincInterpolationParameters {
+				sinc_len: 256,
+				f_cutoff: 0.95,
+				oversampling_factor: 128,
+				interpolation: SincInterpolationType::Cubic,
+				window: WindowFunction::BlackmanHarris2,
+			};
+
+			Async::new_sinc(ratio, 1.0, &parameters, block_size, 2, FixedAsync::Input).unwrap()
+		}
+	}
+}
+
+impl Playable for Player {
+	fn replace(&mut self, track: &Track) {
+		let start = self.intro.get(track);
+		self.replace_inner(track, PlaybackStatus::Play, start);
+	}
+
+	fn preload(&mut self, track: &Track) {
+		if (self.preload.as_ref()).is_some_and(|(path, _, _)| path == track.path()) {
+			return;
+		}
+
+		// open and fill the buffer in the background
+		let start = self.intro.get(track);
+		let opening = self.open_in_background(track, start, "preload");
+		self.preload = Some((track.path().to_owned(), start, opening));
+	}
+
+	fn update(&mut self) {
+		while let Ok(msg) = self.output_rx.try_recv() {
+			match msg {
+				FromOutput::NoDevice => {
+					log::warn!("no audio output device, retrying");
+					self.output = Output::NoDevice;
+				}
+				FromOutput::Ready(sample_rate) => {
+					self.sample_rate = sample_rate;
+					self.output = Output::Ready;
+				}
+				FromOutput::Failed => self.output = Output::Failed,
+			}
+		}
+
+		// messages that didn't fit while the process thread was busy or not running yet
+		self.to_process_tx.flush();
+
+		while let Ok(msg) = self.from_process_rx.pop() {
+			match msg {
+				// messages about the previous track while the next one is loading
+				FromProcess::Playhead(_) | FromProcess::IsDone if self.loading.is_some() => {}
+				FromProcess::Playhead(duration) => {
+					self.elapsed = Some(duration);
+				}
+				FromProcess::IsDone => {
+					self.done = true;
+				}
+			}
+		}
+
+		self.poll_loading();
+	}
+
+	fn seek(&mut self, position: Duration) {
+		// applied once the track is open
+		if self.loading.is_some() {
+			self.elapsed = Some(position);
+			return;
+		}
+
+		self.to_process_tx.send(ToProcess::SeekTo(position));
+	}
+
+	fn pause(&mut self, status: PlaybackStatus) {
+		self.status = status;
+		self.to_process_tx.send(ToProcess::Status(status));
+	}
+
+	fn toggle(&mut self) {
+		self.pause(self.status.invert());
+	}
+
+	fn paused(&self) -> bool {
+		self.status == PlaybackStatus::Paused
+	}
+
+	fn done(&self) -> bool {
+		self.duration.is_some() && self.done
+	}
+
+	fn elapsed(&self) -> Option<Duration> {
+		self.elapsed
+	}
+
+	fn duration(&self) -> Option<Duration> {
+		self.duration
+	}
+
+	fn volume(&self) -> u8 {
+		self.volume
+	}
+
+	fn set_volume(&mut self, vol: u8) {
+		self.volume = vol;
+		self.to_process_tx.send(ToProcess::Volume(self.gain()));
+	}
+
+	fn set_mixed(&mut self, mixed: bool) {
+		self.mixed = mixed;
+		self.to_process_tx.send(ToProcess::Volume(self.gain()));
+	}
+
+	fn mute(&mut self) {
+		self.muted = !self.muted;
+		self.to_process_tx.send(ToProcess::Volume(self.gain()));
+	}
+
+	fn muted(&self) -> bool {
+		self.muted
+	}
+
+	fn balance(&self) -> i8 {
+		self.balance
+	}
+
+	fn set_balance(&mut self, balance: i8) {
+		self.balance = balance.clamp(-100, 100);
+		self.to_process_tx
+			.send(ToProcess::Mix(Mix::new(self.balance, self.mono)));
+	}
+
+	fn mono(&self) -> bool {
+		self.mono
+	}
+
+	fn toggle_mono(&mut self) {
+		self.mono = !self.mono;
+		self.to_process_tx
+			.send(ToProcess::Mix(Mix::new(self.balance, self.mono)));
+	}
+
+	fn duck(&mut self, duck: bool) {
+		self.ducked = duck;
+		self.to_process_tx.send(ToProcess::Volume(self.gain()));
+	}
+
+	fn loading(&self) -> bool {
+		self.output == Output::Opening || self.loading.is_some()
+	}
+
+	fn output(&self) -> Output {
+		self.output
+	}
+
+	fn toggle_recording(&mut self, dir: &Path) {
+		if self.recording {
+			self.recording = false;
+			self.to_process_tx.send(ToProcess::Record(None));
+			return;
+		}
+
+		if self.output != Output::Ready {
+			return;
+		}
+
+		match record::start(dir, self.sample_rate) {
+			Ok(tap) => {
+				self.recording = true;
+				self.to_process_tx.send(ToProcess::Record(Some(tap)));
+			}
+			Err(err) => log::error!("couldn't start recording: {err}"),
+		}
+	}
+
+	fn recording(&self) -> bool {
+		self.recording
+	}
+
+	fn dropouts(&self) -> Dropouts {
+		self.dropouts.get()
+	}
+}
@@ -0,0 +1,95 @@
+//! messages to the process thread that are kept until the ring buffer has room
+
+use rtrb::{Producer, PushError};
+use std::collections::VecDeque;
+
+/// message that only matters in its latest version
+pub trait Coalesce {
+	/// `self` makes the `earlier` message obsolete
+	fn replaces(&self, earlier: &Self) -> bool;
+}
+
+/// [`Producer`] that doesn't drop messages when the ring buffer is full
+///
+/// messages that don't fit are kept in order and retried by [`Outbox::flush`],
+/// a kept message is dropped once a message that [replaces](Coalesce::replaces) it is sent
+pub struct Outbox<T> {
+	tx: Producer<T>,
+	backlog: VecDeque<T>,
+}
+
+impl<T: Coalesce> Outbox<T> {
+	pub fn new(tx: Producer<T>) -> Self {
+		Outbox {
+			tx,
+			backlog: VecDeque::new(),
+		}
+	}
+
+	/// send `msg` after every message that is still kept
+	pub fn send(&mut self, msg: T) {
+		self.flush();
+
+		let msg = if self.backlog.is_empty() {
+			match self.tx.push(msg) {
+				Ok(()) => return,
+				Err(PushError::Full(msg)) => msg,
+			}
+		} else {
+			msg
+		};
+
+		self.backlog.retain(|earlier| !msg.replaces(earlier));
+		self.backlog.push_back(msg);
+	}
+
+	/// retry the kept messages
+	pub fn flush(&mut self) {
+		while let Some(msg) = self.backlog.pop_front() {
+			if let Err(PushError::Full(msg)) = self.tx.push(msg) {
+				self.backlog.push_front(msg);
+				break;
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{Coalesce, Outbox};
+	use rtrb::RingBuffer;
+
+	#[derive(Debug, PartialEq, Eq)]
+	enum Msg {
+		Volume(u8),
+		Stream(u8),
+	}
+
+	impl Coalesce for Msg {
+		fn replaces(&self, earlier: &Self) -> bool {
+			matches!((self, earlier), (Msg::Volume(_), Msg::Volume(_)))
+		}
+	}
+
+	#[test]
+	fn outbox() {
+		let (tx, mut rx) = RingBuffer::new(2);
+		let mut outbox = Outbox::new(tx);
+
+		outbox.send(Msg::Volume(10));
+		outbox.send(Msg::Stream(1));
+		// the ring buffer is full
+		outbox.send(Msg::Volume(20));
+		outbox.send(Msg::Stream(2));
+		outbox.send(Msg::Volume(30));
+
+		assert_eq!(rx.pop(), Ok(Msg::Volume(10)));
+		assert_eq!(rx.pop(), Ok(Msg::Stream(1)));
+		assert!(rx.pop().is_err());
+
+		outbox.flush();
+		assert_eq!(rx.pop(), Ok(Msg::Stream(2)));
+		assert_eq!(rx.pop(), Ok(Msg::Volume(30)));
+		assert!(rx.pop().is_err());
+	}
+}
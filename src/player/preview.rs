@@ -0,0 +1,271 @@
+//! short preview of a track on a second output stream
+//!
+//! the preview is decoded up front and played through its own cpal stream on the cue device,
+//! or on the default device with the main playback ducked underneath
+
+use super::{cue, write_frame};
+use camino::{Utf8Path, Utf8PathBuf};
+use cpal::{
+	StreamConfig,
+	traits::{DeviceTrait, HostTrait, StreamTrait},
+};
+use std::{
+	fs::File,
+	sync::{
+		Arc,
+		atomic::{AtomicBool, Ordering},
+	},
+	time::Duration,
+};
+use symphonia::core::{
+	audio::SampleBuffer,
+	codecs::DecoderOptions,
+	errors::Error as SymphoniaError,
+	formats::{FormatOptions, SeekMode, SeekTo},
+	io::MediaSourceStream,
+	meta::MetadataOptions,
+	probe::Hint,
+	units::Time,
+};
+
+/// length of a preview
+const LENGTH: Duration = Duration::from_secs(10);
+
+/// position of the preview, as a fraction of the duration of the track
+const START: f64 = 0.3;
+
+/// interval in which the preview thread checks if it should stop
+const POLL: Duration = Duration::from_millis(50);
+
+/// decoded stereo frames of a preview
+struct Clip {
+	frames: Vec<[f32; 2]>,
+	sample_rate: u32,
+}
+
+impl Clip {
+	/// decode [`LENGTH`] of the track at `path`, starting at [`START`] into it
+	fn decode(path: &Utf8Path) -> Result<Clip, SymphoniaError> {
+		let file = File::open(path)?;
+		let stream = MediaSourceStream::new(Box::new(file), Default::default());
+
+		let mut hint = Hint::new();
+		if let Some(extension) = path.extension() {
+			hint.with_extension(extension);
+		}
+
+		let probed = symphonia::default::get_probe().format(
+			&hint,
+			stream,
+			&FormatOptions::default(),
+			&MetadataOptions::default(),
+		)?;
+		let mut format = probed.format;
+
+		let track = format
+			.default_track()
+			.ok_or(SymphoniaError::Unsupported("no audio track"))?;
+		let params = &track.codec_params;
+		let track_id = track.id;
+
+		let sample_rate = params
+			.sample_rate
+			.ok_or(SymphoniaError::Unsupported("unknown sample rate"))?;
+		let start = params
+			.n_frames
+			.map_or(0., |frames| frames as f64 / f64::from(sample_rate) * START);
+
+		let mut decoder =
+			symphonia::default::get_codecs().make(params, &DecoderOptions::default())?;
+
+		let to = SeekTo::Time {
+			time: Time::from(start),
+			track_id: Some(track_id),
+		};
+		// a failed seek previews the start of the track instead
+		let _ = format.seek(SeekMode::Coarse, to);
+
+		let length = (LENGTH.as_secs_f64() * f64::from(sample_rate)) as usize;
+		let mut frames = Vec::with_capacity(length);
+		let mut buffer = None::<SampleBuffer<f32>>;
+
+		while frames.len() < length {
+			let packet = match format.next_packet() {
+				Ok(packet) => packet,
+				Err(SymphoniaError::ResetRequired) => {
+					decoder.reset();
+					continue;
+				}
+				Err(_) => break,
+			};
+
+			if packet.track_id() != track_id {
+				continue;
+			}
+
+			let decoded = match decoder.decode(&packet) {
+				Ok(decoded) => decoded,
+				Err(SymphoniaError::DecodeError(_)) => continue,
+				Err(err) => return Err(err),
+			};
+
+			let spec = *decoded.spec();
+			let channels = spec.channels.count();
+
+			let buffer =
+				buffer.get_or_insert_with(|| SampleBuffer::new(decoded.capacity() as u64, spec));
+			buffer.copy_interleaved_ref(decoded);
+
+			let decoded = buffer.samples().chunks_exact(channels);
+			frames
+				.extend(decoded.map(|frame| [frame[0], frame[if channels == 1 { 0 } else { 1 }]]));
+		}
+
+		frames.truncate(length);
+		Ok(Clip {
+			frames,
+			sample_rate,
+		})
+	}
+
+	/// callback playing the clip at the rate and channels of `config` with `gain`,
+	/// sets `done` at the end
+	fn process(
+		self,
+		config: &StreamConfig,
+		gain: f32,
+		done: Arc<AtomicBool>,
+	) -> impl FnMut(&mut [f32]) + use<> {
+		let step = f64::from(self.sample_rate) / f64::from(config.sample_rate);
+		let channels = usize::from(config.channels);
+		let mut position = 0.0_f64;
+
+		move |data: &mut [f32]| {
+			for frame in data.chunks_exact_mut(channels) {
+				let idx = position as usize;
+				let [left, right] = match (self.frames.get(idx), self.frames.get(idx + 1)) {
+					(Some(&[l0, r0]), Some(&[l1, r1])) => {
+						let t = position.fract() as f32;
+						[l0 + (l1 - l0) * t, r0 + (r1 - r0) * t]
+					}
+					(Some(&frame), None) => frame,
+					(None, _) => {
+						done.store(true, Ordering::Release);
+						[0.; 2]
+					}
+				};
+				position += step;
+
+				write_frame(frame, [left * gain, right * gain]);
+			}
+		}
+	}
+}
+
+/// preview that plays on a background thread until it is done or dropped
+#[derive(Debug)]
+pub struct Preview {
+	path: Utf8PathBuf,
+	/// played on the cue device instead of the default device
+	cue: bool,
+	stop: Arc<AtomicBool>,
+	done: Arc<AtomicBool>,
+}
+
+impl Preview {
+	/// decode and start playing a preview of the track at `path`
+	pub fn start(path: Utf8PathBuf) -> Self {
+		let stop = Arc::new(AtomicBool::new(false));
+		let done = Arc::new(AtomicBool::new(false));
+
+		let preview = Preview {
+			path: path.clone(),
+			cue: cue::configured(),
+			stop: Arc::clone(&stop),
+			done: Arc::clone(&done),
+		};
+
+		std::thread::Builder::new()
+			.name("preview".to_owned())
+			.spawn(move || {
+				if let Err(err) = Preview::play(&path, &stop, Arc::clone(&done)) {
+					log::warn!("couldn't preview {path:?}: {err}");
+				}
+				done.store(true, Ordering::Release);
+			})
+			.unwrap();
+
+		preview
+	}
+
+	fn play(path: &Utf8Path, stop: &AtomicBool, done: Arc<AtomicBool>) -> Result<(), String> {
+		let clip = Clip::decode(path).map_err(|err| err.to_string())?;
+		if stop.load(Ordering::Acquire) {
+			return Ok(());
+		}
+
+		let (device, gain) = match cue::device() {
+			Some(cue) => cue,
+			None => {
+				let device = cpal::default_host().default_output_device();
+				(device.ok_or("no output device")?, 1.)
+			}
+		};
+		let config = device
+			.default_output_config()
+			.map_err(|err| err.to_string())?;
+
+		let sample_format = config.sample_format();
+		let config = super::stream_config(config);
+		let process = clip.process(&config, gain, Arc::clone(&done));
+
+		let stream = super::output_stream(&device, &config, sample_format, process, |err| {
+			log::warn!("preview stream error: {err}");
+		})
+		.map_err(|err| err.to_string())?;
+		stream.play().map_err(|err| err.to_string())?;
+
+		while !stop.load(Ordering::Acquire) && !done.load(Ordering::Acquire) {
+			std::thread::sleep(POLL);
+		}
+
+		Ok(())
+	}
+
+	/// track that is previewed
+	pub fn path(&self) -> &Utf8Path {
+		&self.path
+	}
+
+	/// returns if the preview plays on the cue device, so the playback doesn't have to be ducked
+	pub fn cue(&self) -> bool {
+		self.cue
+	}
+
+	/// returns if the preview has finished playing or failed
+	pub fn done(&self) -> bool {
+		self.done.load(Ordering::Acquire)
+	}
+}
+
+impl Drop for Preview {
+	fn drop(&mut self) {
+		self.stop.store(true, Ordering::Release);
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{Clip, LENGTH};
+	use camino::Utf8Path;
+
+	#[test]
+	fn clip() {
+		let path = Utf8Path::new("mock/list 01/track 01.mp3");
+		let clip = Clip::decode(path).unwrap();
+
+		let length = LENGTH.as_secs_f64() * f64::from(clip.sample_rate);
+		assert!(clip.frames.len() <= length as usize);
+		assert!(!clip.frames.is_empty());
+	}
+}
@@ -0,0 +1,146 @@
+//! record the post-mix output to a wav file
+
+use rtrb::{Consumer, Producer, RingBuffer};
+use std::{
+	fs::{self, File},
+	io::{self, BufWriter, Seek, SeekFrom, Write},
+	path::Path,
+	time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// seconds of audio buffered between the audio callback and the writer thread
+const BUFFER_SECS: usize = 2;
+
+/// interval at which the writer thread checks for new samples
+const POLL: Duration = Duration::from_millis(50);
+
+/// audio callback side of a recording
+///
+/// the recording is finished once it is dropped
+pub struct Tap(Producer<f32>);
+
+impl Tap {
+	/// record a stereo frame, dropping it if the writer thread can't keep up
+	pub fn push(&mut self, [left, right]: [f32; 2]) {
+		if self.0.slots() >= 2 {
+			let _ = self.0.push(left);
+			let _ = self.0.push(right);
+		}
+	}
+}
+
+/// start recording into a new file in `dir`
+///
+/// returns the [`Tap`] to hand to the audio callback
+pub fn start(dir: &Path, sample_rate: u32) -> io::Result<Tap> {
+	fs::create_dir_all(dir)?;
+
+	let secs = (SystemTime::now().duration_since(UNIX_EPOCH))
+		.unwrap_or_default()
+		.as_secs();
+	let path = dir.join(format!("maym-{secs}.wav"));
+
+	let mut file = BufWriter::new(File::create(&path)?);
+	file.write_all(&header(sample_rate, 0))?;
+
+	let len = sample_rate as usize * 2 * BUFFER_SECS;
+	let (producer, consumer) = RingBuffer::new(len);
+
+	let display = path.display().to_string();
+	std::thread::Builder::new()
+		.name("record".to_owned())
+		.spawn(move || {
+			if let Err(err) = write(file, consumer, sample_rate) {
+				log::error!("couldn't record to {display}: {err}");
+			}
+		})?;
+
+	Ok(Tap(producer))
+}
+
+/// write samples until the [`Tap`] is dropped, then fill in the sizes of the header
+fn write(
+	mut file: BufWriter<File>,
+	mut consumer: Consumer<f32>,
+	sample_rate: u32,
+) -> io::Result<()> {
+	let mut len = 0u32;
+
+	loop {
+		// check before reading, so samples pushed right before the drop are still written
+		let abandoned = consumer.is_abandoned();
+
+		let slots = consumer.slots();
+		if slots == 0 {
+			if abandoned {
+				break;
+			}
+
+			std::thread::sleep(POLL);
+			continue;
+		}
+
+		let chunk = consumer
+			.read_chunk(slots)
+			.expect("slots should be available");
+		for sample in chunk {
+			let sample = (sample.clamp(-1., 1.) * f32::from(i16::MAX)) as i16;
+			file.write_all(&sample.to_le_bytes())?;
+			len = len.saturating_add(2);
+		}
+	}
+
+	file.seek(SeekFrom::Start(0))?;
+	file.write_all(&header(sample_rate, len))?;
+	file.flush()
+}
+
+/// canonical 44 byte wav header for 16 bit stereo pcm with `len` bytes of data
+fn header(sample_rate: u32, len: u32) -> [u8; 44] {
+	const CHANNELS: u16 = 2;
+	const BITS: u16 = 16;
+
+	let block_align = CHANNELS * BITS / 8;
+	let byte_rate = sample_rate * u32::from(block_align);
+
+	let mut header = [0; 44];
+	header[0..4].copy_from_slice(b"RIFF");
+	header[4..8].copy_from_slice(&len.saturating_add(36).to_le_bytes());
+	header[8..12].copy_from_slice(b"WAVE");
+	header[12..16].copy_from_slice(b"fmt ");
+	header[16..20].copy_from_slice(&16u32.to_le_bytes());
+	// pcm
+	header[20..22].copy_from_slice(&1u16.to_le_bytes());
+	header[22..24].copy_from_slice(&CHANNELS.to_le_bytes());
+	header[24..28].copy_from_slice(&sample_rate.to_le_bytes());
+	header[28..32].copy_from_slice(&byte_rate.to_le_bytes());
+	header[32..34].copy_from_slice(&block_align.to_le_bytes());
+	header[34..36].copy_from_slice(&BITS.to_le_bytes());
+	header[36..40].copy_from_slice(b"data");
+	header[40..44].copy_from_slice(&len.to_le_bytes());
+	header
+}
+
+#[cfg(test)]
+mod test {
+	use super::header;
+
+	#[test]
+	fn wav_header() {
+		let header = header(44100, 1000);
+		assert_eq!(&header[0..4], b"RIFF");
+		assert_eq!(u32::from_le_bytes(header[4..8].try_into().unwrap()), 1036);
+		assert_eq!(&header[8..16], b"WAVEfmt ");
+		assert_eq!(
+			u32::from_le_bytes(header[24..28].try_into().unwrap()),
+			44100
+		);
+		assert_eq!(
+			u32::from_le_bytes(header[28..32].try_into().unwrap()),
+			176_400
+		);
+		assert_eq!(u16::from_le_bytes(header[32..34].try_into().unwrap()), 4);
+		assert_eq!(&header[36..40], b"data");
+		assert_eq!(u32::from_le_bytes(header[40..44].try_into().unwrap()), 1000);
+	}
+}
@@ -0,0 +1,300 @@
+//! playlists imported from other players
+//!
+//! xspf, pls, rhythmbox and quod libet playlists are converted into json lists of paths
+//! in [`PLAYLIST_DIR`], which are queued like the favorites and keep their order
+
+use crate::{
+	config::CONFIG_DIR,
+	queue::Track,
+	remote::{self, decode, elements, unescape},
+};
+use camino::{Utf8Path, Utf8PathBuf};
+use std::{
+	fs::{self, File},
+	io::{BufWriter, Write},
+	path::PathBuf,
+	sync::LazyLock,
+};
+use thiserror::Error;
+
+/// directory of the imported playlists
+static PLAYLIST_DIR: LazyLock<PathBuf> = LazyLock::new(|| CONFIG_DIR.join("playlists"));
+
+/// playlist error
+#[derive(Debug, Error)]
+pub enum PlaylistError {
+	/// none of the entries is a mp3 file that exists
+	#[error("no playable tracks in {0:?}")]
+	Empty(Utf8PathBuf),
+	/// io error
+	#[error("io error")]
+	IoError(#[from] std::io::Error),
+	/// serde error
+	#[error("serde error")]
+	SerdeJsonError(#[from] serde_json::Error),
+}
+
+/// playlist parsed from another player
+#[derive(Debug, PartialEq, Eq)]
+pub struct Playlist {
+	pub name: String,
+	pub tracks: Vec<Utf8PathBuf>,
+}
+
+/// returns if `path` is a playlist file that can be imported from the lists
+pub fn is_importable(path: &Utf8Path) -> bool {
+	matches!(path.extension(), Some("xspf" | "pls"))
+}
+
+/// returns if `path` is an imported playlist
+pub fn is_path(path: &Utf8Path) -> bool {
+	path.parent()
+		.is_some_and(|dir| dir == PLAYLIST_DIR.as_path())
+		&& path.extension() == Some("json")
+}
+
+/// all imported playlists, sorted by name
+pub fn all() -> Vec<Utf8PathBuf> {
+	let Ok(read) = fs::read_dir(&*PLAYLIST_DIR) else {
+		return Vec::new();
+	};
+
+	let mut playlists = read
+		.flatten()
+		.map(|entry| entry.path())
+		.flat_map(Utf8PathBuf::try_from)
+		.filter(|path| is_path(path))
+		.collect::<Vec<_>>();
+	playlists.sort();
+	playlists
+}
+
+/// name of the imported playlist at `path`
+pub fn name(path: &Utf8Path) -> &str {
+	path.file_stem().unwrap_or(path.as_str())
+}
+
+/// paths of the imported playlist at `path`, in order
+///
+/// returns [`None`] if `path` isn't an imported playlist
+pub fn read(path: &Utf8Path) -> Option<Vec<Utf8PathBuf>> {
+	if !is_path(path) {
+		return None;
+	}
+
+	let file = fs::read_to_string(path).ok()?;
+	serde_json::from_str(&file).ok()
+}
+
+/// read the imported playlist at `path` into [`Track`]s, skipping tracks that no longer exist
+pub fn tracks(path: &Utf8Path) -> Vec<Track> {
+	(read(path).into_iter().flatten())
+		.filter_map(|path| Track::new(path).ok())
+		.collect()
+}
+
+/// import every playlist in the file at `path`
+///
+/// entries that aren't existing mp3 files are skipped, as maym can't play them.
+/// returns the paths of the imported playlists
+pub fn import(path: &Utf8Path) -> Result<Vec<Utf8PathBuf>, PlaylistError> {
+	let text = fs::read_to_string(path)?;
+	let dir = path.parent().unwrap_or(Utf8Path::new(""));
+	let name = path.file_stem().map_or_else(|| path.to_string(), decode);
+
+	let mut imported = Vec::new();
+	for mut playlist in parse(&text, &name, dir) {
+		playlist
+			.tracks
+			.retain(|track| track.extension() == Some("mp3") && track.is_file());
+		if playlist.tracks.is_empty() {
+			continue;
+		}
+
+		imported.push(write(&playlist)?);
+	}
+
+	if imported.is_empty() {
+		return Err(PlaylistError::Empty(path.to_owned()));
+	}
+
+	Ok(imported)
+}
+
+/// write `playlist` to [`PLAYLIST_DIR`], replacing a playlist with the same name
+fn write(playlist: &Playlist) -> Result<Utf8PathBuf, PlaylistError> {
+	fs::create_dir_all(&*PLAYLIST_DIR)?;
+	let path = PLAYLIST_DIR.join(format!("{}.json", remote::file_name(&playlist.name)));
+	let path = Utf8PathBuf::from_path_buf(path)
+		.map_err(|path| std::io::Error::other(format!("not utf-8: {path:?}")))?;
+
+	let mut file = BufWriter::new(File::create(&path)?);
+	serde_json::to_writer_pretty(&mut file, &playlist.tracks)?;
+	writeln!(file)?;
+	file.flush()?;
+
+	Ok(path)
+}
+
+/// parse the playlists in `text`, detecting the format by its content
+///
+/// `name` is used for formats without a title, relative paths are resolved against `dir`
+fn parse(text: &str, name: &str, dir: &Utf8Path) -> Vec<Playlist> {
+	let location = |location: &str| self::location(location, dir);
+
+	if text.contains("<rhythmdb-playlists") {
+		rhythmbox(text, location)
+	} else if text.contains("<playlist") {
+		let name = elements(text, "title")
+			.first()
+			.map_or_else(|| name.to_owned(), |title| unescape(title.trim()));
+		let tracks = elements(text, "location")
+			.into_iter()
+			.filter_map(location)
+			.collect();
+		vec![Playlist { name, tracks }]
+	} else if text.trim_start().starts_with("[playlist]") {
+		let tracks = pls(text).into_iter().filter_map(location).collect();
+		let name = name.to_owned();
+		vec![Playlist { name, tracks }]
+	} else {
+		// quod libet playlists without a format are a path per line
+		let tracks = text
+			.lines()
+			.filter(|line| !line.starts_with('#'))
+			.filter_map(location)
+			.collect();
+		let name = name.to_owned();
+		vec![Playlist { name, tracks }]
+	}
+}
+
+/// entries of a pls playlist, ordered by their number
+fn pls(text: &str) -> Vec<&str> {
+	let mut entries = text
+		.lines()
+		.filter_map(|line| {
+			let (key, value) = line.split_once('=')?;
+			let number = key.trim().strip_prefix("File")?.parse::<u32>().ok()?;
+			Some((number, value))
+		})
+		.collect::<Vec<_>>();
+	entries.sort_by_key(|&(number, _)| number);
+	entries.into_iter().map(|(_, value)| value).collect()
+}
+
+/// static playlists of the rhythmbox `playlists.xml`
+fn rhythmbox(text: &str, location: impl Fn(&str) -> Option<Utf8PathBuf>) -> Vec<Playlist> {
+	let mut playlists = Vec::new();
+
+	let mut rest = text;
+	while let Some(start) = rest.find("<playlist ") {
+		rest = &rest[start..];
+		let Some(open) = rest.find('>') else { break };
+		let tag = &rest[..open];
+		rest = &rest[open + 1..];
+		if tag.ends_with('/') {
+			continue;
+		}
+
+		let (inner, next) = rest.split_once("</playlist>").unwrap_or((rest, ""));
+		rest = next;
+
+		// automatic playlists are queries, not lists of tracks
+		if attribute(tag, "type") != Some("static") {
+			continue;
+		}
+
+		let name = attribute(tag, "name").map_or_else(String::new, unescape);
+		let tracks = elements(inner, "location")
+			.into_iter()
+			.filter_map(&location)
+			.collect();
+		playlists.push(Playlist { name, tracks });
+	}
+
+	playlists
+}
+
+/// value of the attribute `name` in the opening `tag`
+fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+	let start = tag.find(&format!(" {name}=\""))? + name.len() + 3;
+	let len = tag[start..].find('"')?;
+	Some(&tag[start..start + len])
+}
+
+/// path of a playlist entry, which is a `file://` url or a path relative to `dir`
+///
+/// returns [`None`] for empty entries and urls of other schemes
+fn location(location: &str, dir: &Utf8Path) -> Option<Utf8PathBuf> {
+	let location = unescape(location.trim());
+	if location.is_empty() {
+		return None;
+	}
+
+	if let Some(path) = location.strip_prefix("file://") {
+		// skip the host, usually empty or localhost
+		let path = &path[path.find('/')?..];
+		Some(Utf8PathBuf::from(decode(path)))
+	} else if location.contains("://") {
+		None
+	} else {
+		Some(dir.join(location))
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{Playlist, parse};
+	use camino::Utf8Path;
+
+	#[test]
+	fn formats() {
+		let dir = Utf8Path::new("/music");
+
+		let xspf = r#"<?xml version="1.0" encoding="UTF-8"?>
+			<playlist version="1" xmlns="http://xspf.org/ns/0/">
+				<title>Road &amp; Trip</title>
+				<trackList>
+					<track><location>file:///music/ABBA/01%20Dancing%20Queen.mp3</location></track>
+					<track><location>Blur/02 Song 2.mp3</location></track>
+					<track><location>https://example.com/stream.mp3</location></track>
+				</trackList>
+			</playlist>"#;
+		assert_eq!(
+			parse(xspf, "road", dir),
+			[Playlist {
+				name: "Road & Trip".to_owned(),
+				tracks: vec![
+					"/music/ABBA/01 Dancing Queen.mp3".into(),
+					"/music/Blur/02 Song 2.mp3".into()
+				],
+			}]
+		);
+
+		let pls = "[playlist]\nFile2=/music/b.mp3\nTitle2=b\nFile1=a.mp3\nNumberOfEntries=2\n";
+		assert_eq!(
+			parse(pls, "mix", dir)[0].tracks,
+			[Utf8Path::new("/music/a.mp3"), Utf8Path::new("/music/b.mp3")]
+		);
+
+		let rhythmbox = r#"<?xml version="1.0"?>
+			<rhythmdb-playlists>
+				<playlist name="Recently Added" type="automatic"><conjunction/></playlist>
+				<playlist name="Rock &amp; Roll" show-browser="true" type="static">
+					<location>file://localhost/music/rock.mp3</location>
+				</playlist>
+				<playlist name="Empty" type="static"/>
+			</rhythmdb-playlists>"#;
+		assert_eq!(
+			parse(rhythmbox, "playlists", dir),
+			[Playlist {
+				name: "Rock & Roll".to_owned(),
+				tracks: vec!["/music/rock.mp3".into()],
+			}]
+		);
+
+		let quodlibet = "/music/a.mp3\n\n/music/b.mp3\n";
+		assert_eq!(parse(quodlibet, "ql", dir)[0].tracks.len(), 2);
+	}
+}
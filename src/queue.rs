@@ -1,24 +1,57 @@
 //! queue and track
 
 use crate::{
-	player::{Playable, Player},
+	config::Config,
+	favorites::{Excluded, Favorites},
+	party::Party,
+	player::{Playable, PlaybackStatus},
+	playlist, remote,
 	state::State,
 	ui::utils as ui,
 };
 use arrayvec::ArrayVec;
 use camino::{Utf8Path, Utf8PathBuf};
-use id3::{Tag, TagLike};
+use id3::{Tag, TagLike, Timestamp};
+use rand::seq::SliceRandom;
 use ratatui::text::Line;
 use serde::{Deserialize, Deserializer, Serialize};
 use std::{
+	borrow::Cow,
 	fmt::{Debug, Display},
-	sync::Arc,
-	time::Duration,
+	sync::{Arc, OnceLock},
+	time::{Duration, SystemTime},
 };
 use thiserror::Error;
 use unicase::UniCase;
 use walkdir::WalkDir;
 
+mod filter;
+mod format;
+mod info;
+mod name;
+
+pub use self::{filter::Filter, info::StreamInfo};
+
+/// show classical tracks as `composer: work — movement`
+static CLASSICAL: OnceLock<bool> = OnceLock::new();
+
+/// separators between multiple artists in one artist tag
+static SEPARATORS: OnceLock<Vec<String>> = OnceLock::new();
+
+/// load how tracks are read and displayed from the [`Config`]
+pub fn load(config: &Config) {
+	let _ = CLASSICAL.set(config.classical());
+	let separators = config.artist_separators();
+	let _ = SEPARATORS.set(
+		separators
+			.into_iter()
+			.filter(|sep| !sep.is_empty())
+			.collect(),
+	);
+	name::load(config);
+	format::load(config);
+}
+
 /// queue error
 #[derive(Debug, Error)]
 pub enum QueueError {
@@ -51,6 +84,33 @@ pub struct TrackInner {
 	pub path: Utf8PathBuf,
 	/// id3 tags
 	tag: Tag,
+	/// duration, cached once the track was loaded
+	duration: OnceLock<Duration>,
+	/// stream info, probed on first use
+	info: OnceLock<Option<StreamInfo>>,
+	/// modification time of the file when the tags were read
+	modified: Option<SystemTime>,
+}
+
+impl TrackInner {
+	fn new(path: Utf8PathBuf, mut tag: Tag) -> Self {
+		if let Some(stem) = path.file_stem() {
+			name::fill(stem, &mut tag);
+		}
+
+		let modified = TrackInner::modified(&path);
+		TrackInner {
+			path,
+			tag,
+			duration: OnceLock::new(),
+			info: OnceLock::new(),
+			modified,
+		}
+	}
+
+	fn modified(path: &Utf8Path) -> Option<SystemTime> {
+		path.metadata().and_then(|meta| meta.modified()).ok()
+	}
 }
 
 impl Serialize for Track {
@@ -76,10 +136,16 @@ impl Track {
 		}
 
 		let tag = Tag::read_from_path(&path).unwrap_or_default();
-		let track = TrackInner { path, tag };
+		let track = TrackInner::new(path, tag);
 		Ok(Track(Arc::new(track)))
 	}
 
+	/// create [`Track`] from tags that were already read, without reading the file
+	#[cfg(feature = "library")]
+	pub fn with_tag(path: Utf8PathBuf, tag: Tag) -> Self {
+		Track(Arc::new(TrackInner::new(path, tag)))
+	}
+
 	/// deserialize into [`Option`] of [`Track`]
 	///
 	/// serializes into [`None`] if path doesn't exist
@@ -120,15 +186,20 @@ impl Track {
 
 	/// format track into a [`ratatui::text::Line`] struct
 	///
-	/// takes [`Queue`] to highlight currently playing track
-	pub fn line(&self, queue: &Queue) -> Line<'_> {
-		let fmt = self.to_string();
-		if let Some(track) = queue.track() {
-			if track == self {
-				ui::widgets::line(fmt, ui::style::accent().bold())
-			} else {
-				Line::from(fmt)
-			}
+	/// takes [`Queue`] to highlight currently playing track, mark favorites
+	/// and grey out excluded tracks and truncates it to `width` terminal columns
+	pub fn line(&self, queue: &Queue, width: usize) -> Line<'_> {
+		let fmt = if queue.is_favorite(self) {
+			let fmt = ui::widgets::truncate(self.to_string(), width.saturating_sub(2));
+			Cow::Owned(format!("♥ {fmt}"))
+		} else {
+			ui::widgets::truncate(self.to_string(), width)
+		};
+
+		if queue.track().is_some_and(|track| track == self) {
+			ui::widgets::line(fmt, ui::style::playing())
+		} else if queue.is_excluded(self) {
+			ui::widgets::line(fmt, ui::style::dim().crossed_out())
 		} else {
 			Line::from(fmt)
 		}
@@ -139,11 +210,39 @@ impl Track {
 		&self.0.path
 	}
 
+	/// modification time of the file when the tags were read
+	#[inline]
+	pub fn modified(&self) -> Option<SystemTime> {
+		self.0.modified
+	}
+
+	/// read the tags again if the file was modified since
+	///
+	/// returns [`None`] if it wasn't modified or doesn't exist anymore
+	pub fn reload(&self) -> Option<Track> {
+		let modified = TrackInner::modified(&self.0.path);
+		if modified.is_none() || modified == self.0.modified {
+			return None;
+		}
+
+		let tag = Tag::read_from_path(&self.0.path).unwrap_or_default();
+		let track = TrackInner::new(self.0.path.clone(), tag);
+		if let Some(&duration) = self.0.duration.get() {
+			let _ = track.duration.set(duration);
+		}
+		Some(Track(Arc::new(track)))
+	}
+
 	/// [id3 track tag](https://mutagen-specs.readthedocs.io/en/latest/id3/id3v2.4.0-frames.html#trck)
 	pub fn track(&self) -> Option<u32> {
 		self.0.tag.track()
 	}
 
+	/// [id3 disc tag](https://mutagen-specs.readthedocs.io/en/latest/id3/id3v2.4.0-frames.html#tpos)
+	pub fn disc(&self) -> Option<u32> {
+		self.0.tag.disc()
+	}
+
 	/// reference to [id3 title tag](https://mutagen-specs.readthedocs.io/en/latest/id3/id3v2.4.0-frames.html#tit2)
 	pub fn title(&self) -> Option<&str> {
 		self.0.tag.title()
@@ -154,15 +253,236 @@ impl Track {
 		self.0.tag.artist()
 	}
 
+	/// every artist of the [id3 artist tags](https://mutagen-specs.readthedocs.io/en/latest/id3/id3v2.4.0-frames.html#tpe1)
+	///
+	/// values are split at null bytes and the [`Config::artist_separators`]
+	pub fn artists(&self) -> Vec<&str> {
+		let separators = SEPARATORS.get_or_init(|| vec![String::from(";")]);
+
+		let values = (self.0.tag.frames())
+			.filter(|frame| frame.id() == "TPE1")
+			.filter_map(|frame| frame.content().text())
+			.flat_map(|text| text.split('\0'));
+
+		let mut artists = Vec::new();
+		for mut value in values {
+			while let Some((start, len)) = (separators.iter())
+				.filter_map(|sep| value.find(sep.as_str()).map(|start| (start, sep.len())))
+				.min()
+			{
+				artists.push(&value[..start]);
+				value = &value[start + len..];
+			}
+			artists.push(value);
+		}
+
+		artists
+			.into_iter()
+			.map(str::trim)
+			.filter(|artist| !artist.is_empty())
+			.collect()
+	}
+
+	/// first of the [`Track::artists`], followed by the number of other artists like `artist +2`
+	pub fn credit(&self) -> Option<Cow<'_, str>> {
+		match *self.artists() {
+			[] => None,
+			[artist] => Some(Cow::Borrowed(artist)),
+			[artist, ref rest @ ..] => Some(Cow::Owned(format!("{artist} +{}", rest.len()))),
+		}
+	}
+
 	/// reference to [id3 album tag](https://mutagen-specs.readthedocs.io/en/latest/id3/id3v2.4.0-frames.html#talb)
 	pub fn album(&self) -> Option<&str> {
 		self.0.tag.album()
 	}
 
+	/// [id3 bpm tag](https://mutagen-specs.readthedocs.io/en/latest/id3/id3v2.4.0-frames.html#tbpm),
+	/// fractional values are rounded
+	pub fn bpm(&self) -> Option<u32> {
+		let bpm = self.text("TBPM")?.trim().parse::<f32>().ok()?;
+		(bpm > 0.).then(|| bpm.round() as u32)
+	}
+
+	/// reference to [id3 composer tag](https://mutagen-specs.readthedocs.io/en/latest/id3/id3v2.4.0-frames.html#tcom)
+	pub fn composer(&self) -> Option<&str> {
+		self.text("TCOM")
+	}
+
+	/// reference to [id3 conductor tag](https://mutagen-specs.readthedocs.io/en/latest/id3/id3v2.4.0-frames.html#tpe3)
+	pub fn conductor(&self) -> Option<&str> {
+		self.text("TPE3")
+	}
+
+	/// reference to [id3 content group tag](https://mutagen-specs.readthedocs.io/en/latest/id3/id3v2.4.0-frames.html#tit1),
+	/// which classical releases use for the work
+	pub fn work(&self) -> Option<&str> {
+		self.text("TIT1")
+	}
+
+	fn text(&self, id: &str) -> Option<&str> {
+		self.0.tag.get(id).and_then(|frame| frame.content().text())
+	}
+
+	/// title as it's displayed
+	///
+	/// formatted with the [`Config::title_format`] if one is set, otherwise as
+	/// `composer: work — title` if the track has a composer and [`Config::classical`] is set
+	pub fn heading(&self) -> Option<Cow<'_, str>> {
+		if let Some(heading) = format::title(self) {
+			return Some(Cow::Owned(heading));
+		}
+
+		let title = self.title()?;
+		if !CLASSICAL.get().copied().unwrap_or(false) {
+			return Some(Cow::Borrowed(title));
+		}
+
+		let heading = match (self.composer(), self.work()) {
+			(Some(composer), Some(work)) => format!("{composer}: {work} — {title}"),
+			(Some(composer), None) => format!("{composer}: {title}"),
+			(None, _) => return Some(Cow::Borrowed(title)),
+		};
+		Some(Cow::Owned(heading))
+	}
+
 	/// reference to [id3 lyrics tag](https://mutagen-specs.readthedocs.io/en/latest/id3/id3v2.4.0-frames.html#uslt)
 	pub fn lyrics(&self) -> Option<&str> {
 		self.0.tag.lyrics().next().map(|lyr| &*lyr.text)
 	}
+
+	/// [id3 genre tag](https://mutagen-specs.readthedocs.io/en/latest/id3/id3v2.4.0-frames.html#tcon)
+	///
+	/// resolves id3v1 genre references like `(17)` to their name
+	pub fn genre(&self) -> Option<Cow<'_, str>> {
+		self.0.tag.genre_parsed()
+	}
+
+	/// track duration
+	///
+	/// uses the duration cached via [`Track::cache_duration`] and falls
+	/// back to the [id3 length tag](https://mutagen-specs.readthedocs.io/en/latest/id3/id3v2.4.0-frames.html#tlen)
+	pub fn duration(&self) -> Option<Duration> {
+		let length = || {
+			self.0
+				.tag
+				.duration()
+				.map(u64::from)
+				.map(Duration::from_millis)
+		};
+		self.0.duration.get().copied().or_else(length)
+	}
+
+	/// technical [`StreamInfo`], probed from the file on first use
+	pub fn info(&self) -> Option<&StreamInfo> {
+		let info = self.0.info.get_or_init(|| StreamInfo::probe(&self.0.path));
+		info.as_ref()
+	}
+
+	/// cache the actual duration once the track was loaded
+	pub fn cache_duration(&self, duration: Duration) {
+		let _ = self.0.duration.set(duration);
+	}
+
+	/// rating from 1 to 5 stars of the first [id3 popularimeter tag](https://mutagen-specs.readthedocs.io/en/latest/id3/id3v2.4.0-frames.html#popm)
+	///
+	/// returns [`None`] if the track is unrated
+	pub fn rating(&self) -> Option<u8> {
+		let popularimeter =
+			(self.0.tag.frames()).find_map(|frame| frame.content().popularimeter())?;
+		match popularimeter.rating {
+			0 => None,
+			1..=31 => Some(1),
+			32..=95 => Some(2),
+			96..=159 => Some(3),
+			160..=223 => Some(4),
+			224..=255 => Some(5),
+		}
+	}
+
+	/// musicbrainz recording id of the [id3 unique file identifier tag](https://mutagen-specs.readthedocs.io/en/latest/id3/id3v2.4.0-frames.html#ufid)
+	pub fn musicbrainz(&self) -> Option<String> {
+		(self.0.tag.unique_file_identifiers())
+			.find(|ufid| ufid.owner_identifier == "http://musicbrainz.org")
+			.and_then(|ufid| String::from_utf8(ufid.identifier.clone()).ok())
+	}
+
+	/// content based [`Identity`] of the track
+	pub fn identity(&self) -> Identity {
+		Identity {
+			musicbrainz: self.musicbrainz(),
+			title: self.title().map(ToOwned::to_owned),
+			artist: self.artist().map(ToOwned::to_owned),
+			album: self.album().map(ToOwned::to_owned),
+			duration: self.duration().map(|duration| duration.as_secs()),
+		}
+	}
+
+	/// year of the [id3 recording time tag](https://mutagen-specs.readthedocs.io/en/latest/id3/id3v2.4.0-frames.html#tdrc)
+	///
+	/// falls back to the id3v2.3 [year tag](https://mutagen-specs.readthedocs.io/en/latest/id3/id3v2.3.0.html#tyer)
+	pub fn year(&self) -> Option<i32> {
+		self.date().map(|date| date.year)
+	}
+
+	/// [id3 recording time tag](https://mutagen-specs.readthedocs.io/en/latest/id3/id3v2.4.0-frames.html#tdrc)
+	///
+	/// falls back to the id3v2.3 [year tag](https://mutagen-specs.readthedocs.io/en/latest/id3/id3v2.3.0.html#tyer)
+	pub fn date(&self) -> Option<Timestamp> {
+		self.0.tag.date_recorded().or_else(|| {
+			let year = self.0.tag.year()?;
+			Some(Timestamp {
+				year,
+				month: None,
+				day: None,
+				hour: None,
+				minute: None,
+				second: None,
+			})
+		})
+	}
+}
+
+/// content based identity of a [`Track`]
+///
+/// used to find a track again when its path doesn't resolve anymore, e.g. after it was moved
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Identity {
+	/// musicbrainz recording id
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	musicbrainz: Option<String>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	title: Option<String>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	artist: Option<String>,
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	album: Option<String>,
+	/// duration in seconds
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	duration: Option<u64>,
+}
+
+impl Identity {
+	/// returns if `other` is most likely the same track
+	///
+	/// compares the musicbrainz ids if both have one, otherwise title, artist, album and duration.
+	/// a track without a title never matches, as the remaining tags are too unspecific
+	pub fn matches(&self, other: &Identity) -> bool {
+		if let Some((one, two)) = self.musicbrainz.as_ref().zip(other.musicbrainz.as_ref()) {
+			return one == two;
+		}
+
+		let duration = match self.duration.zip(other.duration) {
+			Some((one, two)) => one.abs_diff(two) <= 1,
+			None => true,
+		};
+
+		self.title.is_some()
+			&& self.title == other.title
+			&& self.artist == other.artist
+			&& self.album == other.album
+			&& duration
+	}
 }
 
 impl Debug for Track {
@@ -171,9 +491,12 @@ impl Debug for Track {
 		dbg.field("path", &self.0.path);
 
 		self.track().map(|track| dbg.field("track", &track));
+		self.disc().map(|disc| dbg.field("disc", &disc));
 		self.title().map(|title| dbg.field("title", &title));
 		self.artist().map(|artist| dbg.field("artist", &artist));
 		self.album().map(|album| dbg.field("album", &album));
+		self.genre().map(|genre| dbg.field("genre", &genre));
+		self.year().map(|year| dbg.field("year", &year));
 
 		dbg.finish()
 	}
@@ -181,12 +504,21 @@ impl Debug for Track {
 
 impl Display for Track {
 	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		if let Some(formatted) = format::list(self) {
+			return f.write_str(&formatted);
+		}
+
 		if let Some(track) = self.track() {
-			write!(f, "{track:#02} ")?;
+			match self.disc() {
+				Some(disc) if disc > 1 => write!(f, "{disc}.{track:#02} ")?,
+				_ => write!(f, "{track:#02} ")?,
+			}
 		}
 
-		let title = self.title().unwrap_or("unknown title");
-		let artist = self.artist().unwrap_or("unknown artist");
+		let title = self.heading();
+		let title = title.as_deref().unwrap_or("unknown title");
+		let artist = self.credit();
+		let artist = artist.as_deref().unwrap_or("unknown artist");
 
 		write!(f, "{title} ~ {artist}")
 	}
@@ -220,6 +552,8 @@ impl PartialEq<Utf8Path> for Track {
 
 impl Ord for Track {
 	fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+		// tracks without a disc tag are treated as being on the first disc
+		let discs = (self.disc().unwrap_or(1), other.disc().unwrap_or(1));
 		let tracks = self.track().zip(other.track());
 		let titles = self
 			.title()
@@ -234,7 +568,8 @@ impl Ord for Track {
 			.zip(other.album())
 			.map(|(s, o)| (UniCase::new(s), UniCase::new(o)));
 
-		(tracks.map_or(std::cmp::Ordering::Equal, |(s, o)| s.cmp(&o)))
+		(discs.0.cmp(&discs.1))
+			.then_with(|| tracks.map_or(std::cmp::Ordering::Equal, |(s, o)| s.cmp(&o)))
 			.then_with(|| titles.map_or(std::cmp::Ordering::Equal, |(s, o)| s.cmp(&o)))
 			.then_with(|| artist.map_or(std::cmp::Ordering::Equal, |(s, o)| s.cmp(&o)))
 			.then_with(|| albums.map_or(std::cmp::Ordering::Equal, |(s, o)| s.cmp(&o)))
@@ -247,6 +582,83 @@ impl PartialOrd for Track {
 	}
 }
 
+/// order of the [`Queue::tracks`]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Order {
+	/// order via the [`Ord`] impl of [`Track`]
+	#[default]
+	Default,
+	/// group by [`Track::genre`]
+	Genre,
+	/// group by [`Track::year`]
+	Year,
+	/// ascending [`Track::bpm`]
+	Bpm,
+	/// group by directory and [`Track::album`], in random album order
+	///
+	/// tracks within an album keep the [`Ord`] impl of [`Track`]
+	Album,
+}
+
+impl Order {
+	/// cycle to the next [`Order`]
+	fn next(self) -> Self {
+		match self {
+			Order::Default => Order::Genre,
+			Order::Genre => Order::Year,
+			Order::Year => Order::Bpm,
+			Order::Bpm => Order::Album,
+			Order::Album => Order::Default,
+		}
+	}
+
+	/// name to display in the ui
+	pub fn name(self) -> Option<&'static str> {
+		match self {
+			Order::Default => None,
+			Order::Genre => Some("genre"),
+			Order::Year => Some("year"),
+			Order::Bpm => Some("bpm"),
+			Order::Album => Some("random album"),
+		}
+	}
+
+	/// compare two [`Track`]s
+	///
+	/// tracks missing the tag are sorted last
+	fn cmp(self, one: &Track, two: &Track) -> std::cmp::Ordering {
+		let group = match self {
+			Order::Default => std::cmp::Ordering::Equal,
+			Order::Genre => {
+				let one = one.genre();
+				let two = two.genre();
+				let one = one.as_deref().map(UniCase::new);
+				let two = two.as_deref().map(UniCase::new);
+				Order::cmp_none_last(one, two)
+			}
+			Order::Year => Order::cmp_none_last(one.year(), two.year()),
+			Order::Bpm => Order::cmp_none_last(one.bpm(), two.bpm()),
+			Order::Album => Order::album(one).cmp(&Order::album(two)),
+		};
+
+		group.then_with(|| one.cmp(two))
+	}
+
+	/// album a [`Track`] belongs to, i.e. its directory and album tag
+	fn album(track: &Track) -> (Option<&Utf8Path>, Option<UniCase<&str>>) {
+		(track.path().parent(), track.album().map(UniCase::new))
+	}
+
+	fn cmp_none_last<T: Ord>(one: Option<T>, two: Option<T>) -> std::cmp::Ordering {
+		match (one, two) {
+			(Some(one), Some(two)) => one.cmp(&two),
+			(Some(_), None) => std::cmp::Ordering::Less,
+			(None, Some(_)) => std::cmp::Ordering::Greater,
+			(None, None) => std::cmp::Ordering::Equal,
+		}
+	}
+}
+
 #[derive(Debug)]
 struct History {
 	queue: ArrayVec<usize, 100>,
@@ -287,12 +699,25 @@ impl History {
 		}
 	}
 
+	fn peek(&self) -> Option<usize> {
+		self.queue.get(self.index + 1).copied()
+	}
+
+	/// tracks that [`Self::next`] goes forward through
+	fn ahead(&self) -> &[usize] {
+		self.queue.get(self.index + 1..).unwrap_or_default()
+	}
+
 	fn next(&mut self) -> Option<usize> {
 		let next = self.queue.get(self.index + 1)?;
 		self.index += 1;
 		Some(*next)
 	}
 
+	fn has_prev(&self) -> bool {
+		self.index > 0
+	}
+
 	fn prev(&mut self) -> Option<usize> {
 		let prev = self.index.checked_sub(1)?;
 		self.index = prev;
@@ -300,6 +725,41 @@ impl History {
 	}
 }
 
+/// what happens once the current track is done
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Repeat {
+	/// stop once the last track is done
+	///
+	/// the shuffled queue never ends, so this is the same as [`Repeat::Playlist`] there
+	None,
+	/// play the current track again
+	Track,
+	/// start over after the last track
+	#[default]
+	Playlist,
+}
+
+impl Repeat {
+	/// cycle to the next [`Repeat`]
+	fn next(self) -> Self {
+		match self {
+			Repeat::Playlist => Repeat::Track,
+			Repeat::Track => Repeat::None,
+			Repeat::None => Repeat::Playlist,
+		}
+	}
+
+	/// name to display in the ui
+	pub fn name(self) -> &'static str {
+		match self {
+			Repeat::None => "no repeat",
+			Repeat::Track => "repeat track",
+			Repeat::Playlist => "repeat",
+		}
+	}
+}
+
 /// struct managing playback queue
 #[derive(Debug)]
 pub struct Queue {
@@ -313,22 +773,55 @@ pub struct Queue {
 	current: Option<usize>,
 	/// do shuffle queue
 	shuffle: bool,
+	/// order of the track list
+	order: Order,
+	/// what happens once the current track is done
+	repeat: Repeat,
+	/// next track picked ahead of time in shuffle mode
+	upcoming: Option<usize>,
+	/// favorite tracks
+	favorites: Favorites,
+	/// tracks skipped by [`Queue::next`] and [`Queue::last`]
+	excluded: Excluded,
+	/// only tracks matching the filter are picked by [`Queue::next`] and [`Queue::last`]
+	filter: Option<Filter>,
+	/// tracks were removed or moved, so the track list diverges from the directory
+	edited: bool,
+	/// requests and skip votes in party mode
+	party: Option<Party>,
 }
 
 impl Queue {
 	/// initialize [`Queue`] with a [`State`] struct
 	pub fn with_state(state: &State) -> color_eyre::Result<Self> {
+		let favorites = Favorites::init();
+
 		let (tracks, path) = if let Some(path) = state.queue.as_deref()
 			&& path.exists()
 		{
-			let tracks = Track::directory(path)?;
+			// restore the edited track list, skipping tracks that no longer exist
+			let tracks = if let Some(tracks) = &state.tracks {
+				(tracks.iter().cloned())
+					.filter_map(|path| Track::new(path).ok())
+					.collect()
+			} else {
+				Queue::load(path, &favorites)?
+			};
 			(tracks, Some(path.to_owned()))
 		} else {
 			(Vec::new(), None)
 		};
+		let edited = path.is_some() && state.tracks.is_some();
 
+		// fall back to the identity if the track was moved
 		let current = (state.track.as_ref())
-			.and_then(|current| tracks.iter().position(|track| track == current));
+			.and_then(|current| tracks.iter().position(|track| track == current))
+			.or_else(|| {
+				let identity = state.identity.as_ref()?;
+				tracks
+					.iter()
+					.position(|track| identity.matches(&track.identity()))
+			});
 
 		let mut history = History::new();
 		if let Some(index) = current {
@@ -341,10 +834,38 @@ impl Queue {
 			history,
 			current,
 			shuffle: state.shuffle,
+			order: Order::Default,
+			repeat: state.repeat,
+			upcoming: None,
+			favorites,
+			excluded: Excluded::init(),
+			filter: None,
+			edited,
+			party: None,
 		};
 		Ok(queue)
 	}
 
+	/// read the tracks of a directory, of the virtual favorites list or of an imported playlist
+	fn load(path: &Utf8Path, favorites: &Favorites) -> Result<Vec<Track>, QueueError> {
+		if Favorites::is_path(path) {
+			Ok(favorites.tracks())
+		} else if playlist::is_path(path) {
+			Ok(playlist::tracks(path))
+		} else {
+			Track::directory(path)
+		}
+	}
+
+	/// read the tags of the current track again if its file was modified
+	pub fn reload_track(&mut self) {
+		if let Some(current) = self.current
+			&& let Some(track) = self.tracks[current].reload()
+		{
+			self.tracks[current] = track;
+		}
+	}
+
 	/// returns if shuffle is active
 	#[inline]
 	pub fn is_shuffle(&self) -> bool {
@@ -362,7 +883,6 @@ impl Queue {
 	/// set shuffle
 	///
 	/// also clears [`Queue::next`] and [`Queue::last`]
-	#[cfg(feature = "mpris")]
 	pub fn set_shuffle(&mut self, shuffle: bool) {
 		if self.shuffle != shuffle {
 			self.history.clear(self.current);
@@ -370,6 +890,73 @@ impl Queue {
 		}
 	}
 
+	/// return the [`Repeat`] mode
+	#[inline]
+	pub fn repeat(&self) -> Repeat {
+		self.repeat
+	}
+
+	/// cycle the [`Repeat`] mode
+	pub fn cycle_repeat(&mut self) {
+		self.repeat = self.repeat.next();
+	}
+
+	/// set the [`Repeat`] mode
+	#[cfg(mpris)]
+	pub fn set_repeat(&mut self, repeat: Repeat) {
+		self.repeat = repeat;
+	}
+
+	/// return the [`Order`] of the track list
+	#[inline]
+	pub fn order(&self) -> Order {
+		self.order
+	}
+
+	/// cycle the [`Order`] of the track list
+	///
+	/// also clears [`Queue::next`] and [`Queue::last`]
+	pub fn cycle_order(&mut self) {
+		self.order = self.order.next();
+		self.sort();
+	}
+
+	/// sort the track list by [`Queue::order`], keeping the current track
+	///
+	/// with [`Order::Album`] the albums are shuffled afterwards
+	fn sort(&mut self) {
+		let current = self.track().cloned();
+
+		let order = self.order;
+		if order == Order::Default
+			&& let Some(paths) = self.path.as_deref().and_then(playlist::read)
+		{
+			// imported playlists keep their own order
+			self.tracks
+				.sort_by_key(|track| paths.iter().position(|path| track == path));
+		} else {
+			self.tracks.sort_by(|one, two| order.cmp(one, two));
+			if order == Order::Album {
+				self.shuffle_albums();
+			}
+		}
+
+		self.current = current.and_then(|current| self.tracks.iter().position(|t| t == &current));
+		self.history.clear(self.current);
+	}
+
+	/// randomize the order of albums in the sorted track list, keeping in-album order
+	fn shuffle_albums(&mut self) {
+		let tracks = std::mem::take(&mut self.tracks);
+		let mut albums = tracks
+			.chunk_by(|one, two| Order::album(one) == Order::album(two))
+			.map(<[Track]>::to_vec)
+			.collect::<Vec<_>>();
+
+		albums.shuffle(&mut rand::rng());
+		self.tracks = albums.into_iter().flatten().collect();
+	}
+
 	/// return queue path
 	#[inline]
 	pub fn path(&self) -> Option<&Utf8Path> {
@@ -382,6 +969,79 @@ impl Queue {
 		&self.tracks
 	}
 
+	/// returns if tracks were removed or moved since the directory was queued
+	#[inline]
+	pub fn is_edited(&self) -> bool {
+		self.edited
+	}
+
+	/// remove the track at `index` from the track list
+	///
+	/// returns [`None`] if the index is out of bounds or the track is currently playing
+	pub fn remove(&mut self, index: usize) -> Option<Track> {
+		if index >= self.tracks.len() || self.current == Some(index) {
+			return None;
+		}
+
+		let current = self.track().cloned();
+		let track = self.tracks.remove(index);
+		self.edited(current);
+
+		Some(track)
+	}
+
+	/// move the track at `from` to `to`
+	///
+	/// returns false if either index is out of bounds
+	pub fn move_track(&mut self, from: usize, to: usize) -> bool {
+		if from >= self.tracks.len() || to >= self.tracks.len() {
+			return false;
+		}
+
+		let current = self.track().cloned();
+		let track = self.tracks.remove(from);
+		self.tracks.insert(to, track);
+		self.edited(current);
+
+		true
+	}
+
+	/// mark the track list as edited and find the `current` track again
+	///
+	/// also clears [`Queue::next`] and [`Queue::last`]
+	fn edited(&mut self, current: Option<Track>) {
+		self.current = current.and_then(|current| self.tracks.iter().position(|t| t == &current));
+		self.history.clear(self.current);
+		self.upcoming = None;
+		self.edited = true;
+	}
+
+	/// requests and skip votes, if party mode is on
+	pub fn party(&self) -> Option<&Party> {
+		self.party.as_ref()
+	}
+
+	/// turn on party mode
+	pub fn set_party(&mut self, party: Party) {
+		self.party = Some(party);
+	}
+
+	/// request `track` in party mode, returns false if it already is requested
+	pub fn request(&mut self, track: Track) -> bool {
+		self.party
+			.as_mut()
+			.is_some_and(|party| party.request(track))
+	}
+
+	/// count the skip vote of `client` in party mode and skip once enough clients voted
+	pub fn vote<P: Playable + ?Sized>(&mut self, client: String, player: &mut P) -> bool {
+		let skip = self.party.as_mut().is_some_and(|party| party.vote(client));
+		if skip {
+			self.next(player);
+		}
+		skip
+	}
+
 	/// return currently playing track
 	#[inline]
 	pub fn track(&self) -> Option<&Track> {
@@ -394,7 +1054,91 @@ impl Queue {
 		self.current
 	}
 
-	/// queue a new directory
+	/// remaining duration of the tracks after the current one
+	///
+	/// tracks without a known [`Track::duration`] are skipped.
+	/// returns [`None`] when shuffling, as the remaining tracks are unknown
+	pub fn remaining(&self) -> Option<Duration> {
+		if self.shuffle {
+			return None;
+		}
+
+		let next = self.current.map_or(0, |idx| idx + 1);
+		let remaining = self.tracks[next.min(self.tracks.len())..]
+			.iter()
+			.filter_map(Track::duration)
+			.sum();
+		Some(remaining)
+	}
+
+	/// return favorite tracks
+	#[inline]
+	pub fn favorites(&self) -> &Favorites {
+		&self.favorites
+	}
+
+	/// returns if `track` is a favorite
+	#[inline]
+	pub fn is_favorite(&self, track: &Track) -> bool {
+		self.favorites.contains(track.path())
+	}
+
+	/// add the current track to the favorites or remove it
+	///
+	/// returns [`None`] if no track is playing, otherwise if it is a favorite afterwards
+	pub fn toggle_favorite(&mut self) -> Option<bool> {
+		let track = self.track()?.path().to_owned();
+		Some(self.favorites.toggle(&track))
+	}
+
+	/// return excluded tracks
+	#[inline]
+	pub fn excluded(&self) -> &Excluded {
+		&self.excluded
+	}
+
+	/// returns if `track` is excluded
+	#[inline]
+	pub fn is_excluded(&self, track: &Track) -> bool {
+		self.excluded.contains(track.path())
+	}
+
+	/// exclude the current track or include it again
+	///
+	/// returns [`None`] if no track is playing, otherwise if it is excluded afterwards
+	pub fn toggle_excluded(&mut self) -> Option<bool> {
+		let track = self.track()?.path().to_owned();
+		let excluded = self.excluded.toggle(&track);
+
+		self.upcoming = None;
+		Some(excluded)
+	}
+
+	/// return the active [`Filter`]
+	#[inline]
+	pub fn filter(&self) -> Option<&Filter> {
+		self.filter.as_ref()
+	}
+
+	/// set or clear the [`Filter`]
+	pub fn set_filter(&mut self, filter: Option<Filter>) {
+		self.filter = filter;
+		self.upcoming = None;
+	}
+
+	/// returns if the track at `index` may be picked by [`Queue::next`] and [`Queue::last`]
+	fn playable(&self, index: usize) -> bool {
+		let track = &self.tracks[index];
+		!self.is_excluded(track)
+			&& self
+				.filter
+				.as_ref()
+				.is_none_or(|filter| filter.matches(track))
+	}
+
+	/// queue a new directory or the virtual favorites list
+	///
+	/// also clears the [`Filter`]
 	///
 	/// # Errors
 	///
@@ -403,33 +1147,70 @@ impl Queue {
 		&mut self,
 		path: P,
 	) -> Result<(), QueueError> {
-		let tracks = Track::directory(&path)?;
+		let tracks = Queue::load(path.as_ref(), &self.favorites)?;
 
 		self.path = Some(path.into());
 		self.tracks = tracks;
 		self.current = None;
-		self.history.clear(None);
+		self.filter = None;
+		self.edited = false;
+		self.sort();
 
 		Ok(())
 	}
 
-	/// select track by path
-	///
-	/// also clears [`Queue::next`] and [`Queue::last`]
+	/// queue `tracks` in their order, as an edited track list of their common directory
 	///
 	/// # Errors
 	///
-	/// returns [`QueueError`] if the track of the path isn't in the [`Queue::tracks`]
-	pub fn select_path<P: Playable>(
-		&mut self,
-		path: &Utf8Path,
-		player: &mut P,
-	) -> Result<(), QueueError> {
-		let Some(index) = self.tracks.iter().position(|iter| iter == path) else {
-			return Err(QueueError::NoTrack(path.to_owned()));
-		};
+	/// returns [`QueueError::NoTracks`] if `tracks` is empty
+	pub fn queue_tracks(&mut self, tracks: Vec<Track>) -> Result<(), QueueError> {
+		let path = Queue::ancestor(&tracks).ok_or(QueueError::NoTracks)?;
 
-		self.replace(index, player);
+		self.path = Some(path);
+		self.tracks = tracks;
+		self.current = None;
+		self.filter = None;
+		self.upcoming = None;
+		self.edited = true;
+		if self.order == Order::Default {
+			self.history.clear(None);
+		} else {
+			self.sort();
+		}
+
+		Ok(())
+	}
+
+	/// deepest directory containing all `tracks`
+	fn ancestor(tracks: &[Track]) -> Option<Utf8PathBuf> {
+		let mut ancestor = tracks.first()?.path().parent()?;
+		for track in tracks {
+			while !track.path().starts_with(ancestor) {
+				ancestor = ancestor.parent()?;
+			}
+		}
+
+		Some(ancestor.to_owned())
+	}
+
+	/// select track by path
+	///
+	/// also clears [`Queue::next`] and [`Queue::last`]
+	///
+	/// # Errors
+	///
+	/// returns [`QueueError`] if the track of the path isn't in the [`Queue::tracks`]
+	pub fn select_path<P: Playable + ?Sized>(
+		&mut self,
+		path: &Utf8Path,
+		player: &mut P,
+	) -> Result<(), QueueError> {
+		let Some(index) = self.tracks.iter().position(|iter| iter == path) else {
+			return Err(QueueError::NoTrack(path.to_owned()));
+		};
+
+		self.replace(index, player);
 
 		self.history.clear(self.current);
 
@@ -443,7 +1224,7 @@ impl Queue {
 	/// # Errors
 	///
 	/// returns [`QueueError`] if the index is out bounds
-	pub fn select_idx<P: Playable>(
+	pub fn select_idx<P: Playable + ?Sized>(
 		&mut self,
 		index: usize,
 		player: &mut P,
@@ -458,20 +1239,20 @@ impl Queue {
 
 	/// select last track sequentially
 	///
-	/// returns [`None`] on an empty track list,
-	/// or if no track is currently playing
+	/// skips excluded tracks and returns [`None`] on an empty track list,
+	/// if every track is excluded or if no track is currently playing
 	fn last_track_sequential(&self) -> Option<usize> {
-		if self.tracks.is_empty() {
-			return None;
-		}
+		let len = self.tracks.len();
+		let current = self.current?;
 
-		self.current.map(|idx| {
-			if idx == 0 {
-				self.tracks.len().saturating_sub(1)
-			} else {
-				idx.saturating_sub(1)
-			}
-		})
+		(1..=len)
+			.map(|offset| (current + len - offset) % len)
+			.find(|&idx| self.playable(idx))
+	}
+
+	/// returns if [`Queue::last`] would play a track
+	pub fn has_last(&self) -> bool {
+		self.history.has_prev() || (!self.shuffle && self.last_track_sequential().is_some())
 	}
 
 	/// play last track
@@ -482,7 +1263,7 @@ impl Queue {
 	/// 3. give up
 	///
 	/// if it finds a track to play, it pushes it to [`Queue::next`]
-	pub fn last<P: Playable>(&mut self, player: &mut P) {
+	pub fn last<P: Playable + ?Sized>(&mut self, player: &mut P) {
 		let last = if let Some(last) = self.history.prev() {
 			Some(last)
 		} else if !self.shuffle {
@@ -500,34 +1281,45 @@ impl Queue {
 	///
 	/// # Errors
 	///
-	/// returns [`QueueError`] if [`Queue::tracks`] is empty
+	/// returns [`None`] if [`Queue::tracks`] is empty or every track is excluded
 	fn next_track_sequential(&self) -> Option<usize> {
-		if self.tracks.is_empty() {
-			return None;
-		}
+		self.sequential_after(self.current).next()
+	}
 
-		let idx = self.current.map_or(0, |idx| (idx + 1) % self.tracks.len());
-		Some(idx)
+	/// playable tracks in order after `after`, wrapping around once
+	fn sequential_after(&self, after: Option<usize>) -> impl Iterator<Item = usize> {
+		let len = self.tracks.len();
+		let start = after.map_or(0, |idx| idx + 1);
+
+		(0..len)
+			.map(move |offset| (start + offset) % len)
+			.filter(|&idx| self.playable(idx))
 	}
 
 	/// get next track randomly
 	///
 	/// # Errors
 	///
-	/// returns [`QueueError`] if [`Queue::tracks`] is empty
+	/// returns [`None`] if [`Queue::tracks`] is empty or every track is excluded.
+	/// the current track is only picked again if it's the only one left
 	fn next_track_shuffle(&self) -> Option<usize> {
-		if self.tracks.is_empty() {
-			return None;
-		} else if self.tracks.len() <= 1 {
-			return Some(0);
-		}
+		let candidates = (0..self.tracks.len())
+			.filter(|&idx| self.current != Some(idx) && self.playable(idx))
+			.collect::<Vec<_>>();
 
-		loop {
-			let track = rand::random_range(..self.tracks.len());
-			if self.current.is_none_or(|current| current != track) {
-				return Some(track);
-			}
+		if candidates.is_empty() {
+			return self.current.filter(|&idx| self.playable(idx));
 		}
+
+		let pick = rand::random_range(..candidates.len());
+		Some(candidates[pick])
+	}
+
+	/// take the shuffled track picked by [`Queue::upcoming`], if it's still valid
+	fn take_upcoming(&mut self) -> Option<usize> {
+		let upcoming = self.upcoming.take()?;
+		(upcoming < self.tracks.len() && self.current != Some(upcoming) && self.playable(upcoming))
+			.then_some(upcoming)
 	}
 
 	/// get next track
@@ -536,7 +1328,7 @@ impl Queue {
 			Some(track)
 		} else if !self.shuffle {
 			self.next_track_sequential()
-		} else if let Some(index) = self.next_track_shuffle() {
+		} else if let Some(index) = self.take_upcoming().or_else(|| self.next_track_shuffle()) {
 			self.history.push(index);
 			Some(index)
 		} else {
@@ -544,24 +1336,145 @@ impl Queue {
 		}
 	}
 
+	/// predict the track played after the current one
+	///
+	/// in shuffle mode the next track is picked ahead of time
+	pub fn upcoming(&mut self) -> Option<&Track> {
+		let requested = self
+			.party
+			.as_ref()
+			.is_some_and(|party| !party.requests().is_empty());
+		if requested {
+			return self.party.as_ref()?.requests().front();
+		}
+
+		let index = if let Some(track) = self.history.peek() {
+			track
+		} else if !self.shuffle {
+			self.next_track_sequential()?
+		} else {
+			let index = self.take_upcoming().or_else(|| self.next_track_shuffle())?;
+			self.upcoming = Some(index);
+			index
+		};
+
+		self.tracks.get(index)
+	}
+
+	/// the next `count` tracks, in the order [`Queue::next`] would play them, without picking them
+	///
+	/// in shuffle mode only the track picked by [`Queue::upcoming`] is known ahead of time
+	pub fn up_next(&self, count: usize) -> Vec<&Track> {
+		let mut up_next = self
+			.party
+			.as_ref()
+			.map(|party| party.requests().iter().take(count).collect::<Vec<_>>())
+			.unwrap_or_default();
+
+		let ahead = self.history.ahead();
+		up_next.extend(ahead.iter().map(|&index| &self.tracks[index]));
+
+		if !self.shuffle {
+			let last = ahead.last().copied().or(self.current);
+			let rest = self
+				.sequential_after(last)
+				.take_while(|&index| self.current != Some(index));
+			up_next.extend(rest.map(|index| &self.tracks[index]));
+		} else if ahead.is_empty()
+			&& let Some(index) = self.upcoming
+			&& self.tracks.len() > index
+			&& self.current != Some(index)
+			&& self.playable(index)
+		{
+			up_next.push(&self.tracks[index]);
+		}
+
+		up_next.truncate(count);
+		up_next
+	}
+
 	/// replace current track
 	///
-	/// replaces track in [`Player`] via [`Player::replace`]
+	/// replaces track in the player via [`Playable::replace`]
 	/// and pushes last track to [`Queue::last`]
-	fn replace<P: Playable>(&mut self, index: usize, player: &mut P) {
+	fn replace<P: Playable + ?Sized>(&mut self, index: usize, player: &mut P) {
+		remote::fetch(self.tracks[index].path());
 		player.replace(&self.tracks[index]);
 		self.current = Some(index);
+
+		if let Some(party) = &mut self.party {
+			party.reset();
+		}
+	}
+
+	/// play `track` right away, appending it to the track list if it isn't queued
+	fn play<P: Playable + ?Sized>(&mut self, track: Track, player: &mut P) {
+		let index = match self.tracks.iter().position(|queued| queued == &track) {
+			Some(index) => index,
+			None => {
+				let current = self.track().cloned();
+				self.tracks.push(track);
+				self.edited(current);
+				self.tracks.len() - 1
+			}
+		};
+
+		self.replace(index, player);
+		self.history.clear(self.current);
+	}
+
+	/// play `track` right away, inserting it after the current track if it isn't queued
+	///
+	/// # Errors
+	///
+	/// returns [`QueueError`] if the queue is empty and `track` can't be queued on its own
+	pub fn play_next<P: Playable + ?Sized>(
+		&mut self,
+		track: Track,
+		player: &mut P,
+	) -> Result<(), QueueError> {
+		if self.tracks.is_empty() {
+			self.queue_tracks(vec![track])?;
+			self.next(player);
+			return Ok(());
+		}
+
+		let index = match self.tracks.iter().position(|queued| queued == &track) {
+			Some(index) => index,
+			None => {
+				let current = self.track().cloned();
+				let index = self.current.map_or(0, |current| current + 1);
+				self.tracks.insert(index, track);
+				self.edited(current);
+				index
+			}
+		};
+
+		self.replace(index, player);
+		self.history.clear(self.current);
+		Ok(())
+	}
+
+	/// returns if [`Queue::next`] would play a track
+	///
+	/// the queue loops, so this is the case as long as any track is playable
+	pub fn has_next(&self) -> bool {
+		self.history.peek().is_some() || (0..self.tracks.len()).any(|idx| self.playable(idx))
 	}
 
 	/// play next track
-	pub fn next<P: Playable>(&mut self, player: &mut P) {
-		if let Some(track) = self.next_track() {
+	///
+	/// in party mode the requests are played first
+	pub fn next<P: Playable + ?Sized>(&mut self, player: &mut P) {
+		if let Some(request) = self.party.as_mut().and_then(Party::next) {
+			self.play(request, player);
+		} else if let Some(track) = self.next_track() {
 			self.replace(track, player);
 		}
 	}
 
 	/// restart current track
-	pub fn restart(&self, player: &mut Player) {
+	pub fn restart<P: Playable + ?Sized>(&self, player: &mut P) {
 		if self.current.is_some() {
 			let start = Duration::ZERO;
 			player.seek(start);
@@ -569,7 +1482,7 @@ impl Queue {
 	}
 
 	/// seek backwards in current track
-	pub fn seek_d(&self, player: &mut Player, state: &State, amt: Duration) {
+	pub fn seek_d<P: Playable + ?Sized>(&self, player: &mut P, state: &State, amt: Duration) {
 		if self.current.is_some()
 			&& let Some(elapsed) = state.elapsed()
 		{
@@ -579,7 +1492,7 @@ impl Queue {
 	}
 
 	/// seek forward in current track
-	pub fn seek_i(&mut self, player: &mut Player, state: &State, amt: Duration) {
+	pub fn seek_i<P: Playable + ?Sized>(&mut self, player: &mut P, state: &State, amt: Duration) {
 		if self.current.is_some()
 			&& let Some((elapsed, duration)) = state.elapsed_duration()
 		{
@@ -594,30 +1507,140 @@ impl Queue {
 	}
 
 	/// if [`State::done()`], play next track
-	pub fn done(&mut self, player: &mut Player) {
-		if player.done() {
-			self.next(player);
+	///
+	/// with [`Repeat::None`] nothing is played after the last track and `true` is returned,
+	/// so the caller can decide what happens at the end of the queue
+	pub fn done<P: Playable + ?Sized>(&mut self, player: &mut P) -> bool {
+		if !player.done() {
+			return false;
 		}
+
+		match (self.repeat, self.current) {
+			(Repeat::Track, Some(current)) => self.replace(current, player),
+			(Repeat::None, Some(current)) if !self.shuffle && self.history.peek().is_none() => {
+				let wrapped = self
+					.next_track_sequential()
+					.is_none_or(|next| next <= current);
+				if wrapped {
+					return true;
+				}
+				self.next(player);
+			}
+			_ => self.next(player),
+		}
+
+		false
+	}
+
+	/// start over at the first track, paused
+	pub fn stop<P: Playable + ?Sized>(&mut self, player: &mut P) {
+		self.next(player);
+		player.pause(PlaybackStatus::Paused);
+	}
+
+	/// remove every track from the queue
+	pub fn empty(&mut self) {
+		self.path = None;
+		self.tracks.clear();
+		self.history = History::new();
+		self.current = None;
+		self.filter = None;
+		self.edited = false;
 	}
 }
 
 #[cfg(test)]
 mod test {
-	use super::{History, Queue, QueueError, Track};
-	use crate::{player::Playable, state};
+	use super::{History, Identity, Order, Queue, QueueError, Repeat, Track};
+	use crate::{
+		favorites::{Excluded, Favorites},
+		player::{Playable, PlaybackStatus},
+		state,
+	};
 	use camino::{Utf8Path, Utf8PathBuf};
-	use std::cmp::Ordering;
+	use std::{cmp::Ordering, time::Duration};
 
-	struct Player;
+	#[derive(Debug, Default)]
+	struct Player {
+		current: Option<Utf8PathBuf>,
+		position: Duration,
+		paused: bool,
+		done: bool,
+		volume: u8,
+		muted: bool,
+		balance: i8,
+		mono: bool,
+	}
 
 	impl Player {
 		fn new() -> Player {
-			Player
+			Player::default()
 		}
 	}
 
 	impl Playable for Player {
-		fn replace(&mut self, _track: &Track) {}
+		fn replace(&mut self, track: &Track) {
+			self.current = Some(track.path().to_owned());
+			self.position = Duration::ZERO;
+			self.paused = false;
+			self.done = false;
+		}
+
+		fn seek(&mut self, position: Duration) {
+			self.position = position;
+		}
+
+		fn pause(&mut self, status: PlaybackStatus) {
+			self.paused = status == PlaybackStatus::Paused;
+		}
+
+		fn paused(&self) -> bool {
+			self.paused
+		}
+
+		fn done(&self) -> bool {
+			self.done
+		}
+
+		fn elapsed(&self) -> Option<Duration> {
+			self.current.as_ref().map(|_| self.position)
+		}
+
+		fn duration(&self) -> Option<Duration> {
+			None
+		}
+
+		fn volume(&self) -> u8 {
+			self.volume
+		}
+
+		fn set_volume(&mut self, vol: u8) {
+			self.volume = vol;
+		}
+
+		fn mute(&mut self) {
+			self.muted = !self.muted;
+		}
+
+		fn muted(&self) -> bool {
+			self.muted
+		}
+
+		fn balance(&self) -> i8 {
+			self.balance
+		}
+
+		fn set_balance(&mut self, balance: i8) {
+			self.balance = balance;
+		}
+
+		fn mono(&self) -> bool {
+			self.mono
+		}
+
+		fn toggle_mono(&mut self) {
+			self.mono = !self.mono;
+		}
 	}
 
 	/// create [`Track`] by reading from disk
@@ -655,6 +1678,14 @@ mod test {
 			history: History::new(),
 			current: None,
 			shuffle: false,
+			order: Order::Default,
+			repeat: Repeat::Playlist,
+			upcoming: None,
+			favorites: Favorites::default(),
+			excluded: Excluded::default(),
+			filter: None,
+			edited: false,
+			party: None,
 		};
 		Ok(queue)
 	}
@@ -701,9 +1732,12 @@ mod test {
 		let mut queue = queue("mock/list 01")?;
 
 		assert_eq!(queue.track(), None);
+		assert!(queue.has_next());
+		assert!(!queue.has_last());
 
 		queue.next(&mut player);
 		assert_eq!(queue.history.queue.len(), 0);
+		assert!(queue.has_last());
 
 		queue.last(&mut player);
 		assert_eq!(queue.track(), Some(&t5));
@@ -720,6 +1754,74 @@ mod test {
 		Ok(())
 	}
 
+	#[test]
+	fn repeat() -> color_eyre::Result<()> {
+		let t0 = track("mock/list 01/track 00.mp3")?;
+		let t1 = track("mock/list 01/track 01.mp3")?;
+		let t5 = track("mock/list 01/sub 01/track 05.mp3")?;
+
+		let mut player = Player::new();
+		let mut queue = queue("mock/list 01")?;
+
+		queue.next(&mut player);
+		player.done = true;
+		queue.done(&mut player);
+		assert_eq!(queue.track(), Some(&t1));
+
+		queue.cycle_repeat();
+		assert_eq!(queue.repeat(), Repeat::Track);
+		player.done = true;
+		queue.done(&mut player);
+		assert_eq!(queue.track(), Some(&t1));
+		assert!(!player.paused);
+
+		queue.cycle_repeat();
+		assert_eq!(queue.repeat(), Repeat::None);
+		queue.last(&mut player);
+		queue.last(&mut player);
+		assert_eq!(queue.track(), Some(&t5));
+		player.done = true;
+		assert!(queue.done(&mut player));
+		assert_eq!(queue.track(), Some(&t5));
+		queue.stop(&mut player);
+		assert_eq!(queue.track(), Some(&t0));
+		assert!(player.paused);
+
+		queue.empty();
+		assert_eq!(queue.track(), None);
+		assert!(!queue.done(&mut player));
+
+		Ok(())
+	}
+
+	#[test]
+	fn queue_tracks() -> color_eyre::Result<()> {
+		let t0 = track("mock/list 01/track 00.mp3")?;
+		let t4 = track("mock/list 01/sub 01/track 04.mp3")?;
+		let t3 = track("mock/list 01/sub 02/track 03.mp3")?;
+
+		let mut player = Player::new();
+		let mut queue = queue("mock/list 02")?;
+
+		queue.queue_tracks(vec![t4.clone(), t3.clone()])?;
+		assert_eq!(queue.path(), Some(Utf8Path::new("mock/list 01")));
+		assert!(queue.is_edited());
+
+		queue.next(&mut player);
+		assert_eq!(queue.track(), Some(&t4));
+		queue.next(&mut player);
+		assert_eq!(queue.track(), Some(&t3));
+
+		queue.queue_tracks(vec![t0])?;
+		assert_eq!(queue.path(), Some(Utf8Path::new("mock/list 01")));
+		assert!(matches!(
+			queue.queue_tracks(Vec::new()),
+			Err(QueueError::NoTracks)
+		));
+
+		Ok(())
+	}
+
 	#[test]
 	fn shuf() -> color_eyre::Result<()> {
 		let mut player = Player::new();
@@ -798,6 +1900,239 @@ mod test {
 		Ok(())
 	}
 
+	#[test]
+	fn upcoming() -> color_eyre::Result<()> {
+		let mut player = Player::new();
+		let mut queue = queue("mock/list 01")?;
+
+		let upcoming = queue.upcoming().cloned();
+		queue.next(&mut player);
+		assert_eq!(queue.track().cloned(), upcoming);
+		assert_eq!(queue.upcoming().cloned().as_ref(), queue.tracks.get(1));
+
+		queue.shuffle();
+		for _ in 0..20 {
+			let upcoming = queue.upcoming().cloned();
+			assert_eq!(queue.upcoming().cloned(), upcoming);
+			assert_ne!(queue.track().cloned(), upcoming);
+
+			queue.next(&mut player);
+			assert_eq!(queue.track().cloned(), upcoming);
+		}
+
+		// after going back, the history is played again
+		queue.last(&mut player);
+		let upcoming = queue.upcoming().cloned();
+		queue.next(&mut player);
+		assert_eq!(queue.track().cloned(), upcoming);
+
+		Ok(())
+	}
+
+	#[test]
+	fn up_next() -> color_eyre::Result<()> {
+		let mut player = Player::new();
+		let mut queue = queue("mock/list 01")?;
+
+		queue.next(&mut player);
+		let tracks = queue.tracks.iter().skip(1).collect::<Vec<_>>();
+		assert_eq!(queue.up_next(10), tracks);
+		assert_eq!(queue.up_next(2), tracks[..2]);
+
+		queue.shuffle();
+		let upcoming = queue.upcoming().cloned();
+		assert_eq!(queue.up_next(10), Vec::from_iter(upcoming.as_ref()));
+
+		// the history comes first and isn't consumed
+		queue.next(&mut player);
+		let second = queue.track().cloned();
+		queue.next(&mut player);
+		let third = queue.track().cloned();
+		queue.last(&mut player);
+		queue.last(&mut player);
+		let up_next = queue.up_next(2).into_iter().cloned().collect::<Vec<_>>();
+		assert_eq!(
+			up_next,
+			[second, third].into_iter().flatten().collect::<Vec<_>>()
+		);
+		assert_eq!(queue.up_next(2).len(), 2);
+
+		Ok(())
+	}
+
+	#[test]
+	fn favorites() -> color_eyre::Result<()> {
+		let t0 = track("mock/list 01/track 00.mp3")?;
+		let t1 = track("mock/list 01/track 01.mp3")?;
+
+		let mut player = Player::new();
+		let mut queue = queue("mock/list 01")?;
+		assert_eq!(queue.toggle_favorite(), None);
+
+		queue.next(&mut player);
+		assert_eq!(queue.toggle_favorite(), Some(true));
+		queue.next(&mut player);
+		assert_eq!(queue.toggle_favorite(), Some(true));
+		assert!(queue.is_favorite(&t0) && queue.is_favorite(&t1));
+
+		let path = Favorites::path().expect("config dir should be utf-8");
+		queue.queue(path)?;
+		assert_eq!(queue.tracks(), [t0.clone(), t1.clone()]);
+
+		queue.select_idx(1, &mut player)?;
+		assert_eq!(queue.toggle_favorite(), Some(false));
+		assert!(queue.is_favorite(&t0) && !queue.is_favorite(&t1));
+
+		Ok(())
+	}
+
+	#[test]
+	fn excluded() -> color_eyre::Result<()> {
+		let t0 = track("mock/list 01/track 00.mp3")?;
+		let t1 = track("mock/list 01/track 01.mp3")?;
+		let t2 = track("mock/list 01/sub 02/track 02.mp3")?;
+
+		let mut player = Player::new();
+		let mut queue = queue("mock/list 01")?;
+
+		queue.select_idx(1, &mut player)?;
+		assert_eq!(queue.toggle_excluded(), Some(true));
+
+		queue.last(&mut player);
+		assert_eq!(queue.track(), Some(&t0));
+		queue.next(&mut player);
+		assert_eq!(queue.track(), Some(&t2));
+		queue.last(&mut player);
+		assert_eq!(queue.track(), Some(&t0));
+
+		queue.shuffle();
+		for _ in 0..20 {
+			queue.next(&mut player);
+			assert_ne!(queue.track(), Some(&t1));
+		}
+
+		// explicitly selecting an excluded track still plays it
+		queue.select_idx(1, &mut player)?;
+		assert_eq!(queue.track(), Some(&t1));
+
+		Ok(())
+	}
+
+	#[test]
+	fn identity() {
+		let identity = Identity {
+			musicbrainz: None,
+			title: Some("title".to_owned()),
+			artist: Some("artist".to_owned()),
+			album: None,
+			duration: Some(200),
+		};
+
+		let moved = Identity {
+			duration: None,
+			..identity.clone()
+		};
+		assert!(identity.matches(&moved));
+
+		let longer = Identity {
+			duration: Some(230),
+			..identity.clone()
+		};
+		assert!(!identity.matches(&longer));
+
+		let untitled = Identity {
+			title: None,
+			..identity.clone()
+		};
+		assert!(!untitled.matches(&untitled));
+
+		let one = Identity {
+			musicbrainz: Some("one".to_owned()),
+			..identity.clone()
+		};
+		let two = Identity {
+			musicbrainz: Some("two".to_owned()),
+			..identity.clone()
+		};
+		assert!(!one.matches(&two));
+		assert!(one.matches(&identity));
+	}
+
+	#[test]
+	fn filter() -> color_eyre::Result<()> {
+		let t0 = track("mock/list 01/track 00.mp3")?;
+
+		let mut player = Player::new();
+		let mut queue = queue("mock/list 01")?;
+
+		// the mock tracks are unrated
+		queue.set_filter(Some("rating>=1".parse()?));
+		queue.next(&mut player);
+		assert_eq!(queue.track(), None);
+
+		queue.set_filter(Some("rating=0".parse()?));
+		queue.next(&mut player);
+		assert_eq!(queue.track(), Some(&t0));
+
+		queue.set_filter(None);
+		assert_eq!(queue.filter(), None);
+
+		Ok(())
+	}
+
+	#[test]
+	fn remaining() -> color_eyre::Result<()> {
+		let mut player = Player::new();
+		let mut queue = queue("mock/list 01")?;
+
+		for (i, track) in queue.tracks().iter().enumerate() {
+			track.cache_duration(Duration::from_secs(i as u64 + 1));
+		}
+		assert_eq!(queue.remaining(), Some(Duration::from_secs(21)));
+
+		queue.next(&mut player);
+		queue.next(&mut player);
+		assert_eq!(queue.remaining(), Some(Duration::from_secs(18)));
+
+		queue.last(&mut player);
+		queue.last(&mut player);
+		assert_eq!(queue.remaining(), Some(Duration::ZERO));
+
+		queue.shuffle();
+		assert_eq!(queue.remaining(), None);
+
+		Ok(())
+	}
+
+	#[test]
+	fn done() -> color_eyre::Result<()> {
+		let mut player = Player::new();
+		let mut queue = queue("mock/list 01")?;
+
+		queue.next(&mut player);
+		assert_eq!(player.current.as_deref(), Some(queue.tracks[0].path()));
+
+		queue.done(&mut player);
+		assert_eq!(queue.current, Some(0));
+
+		player.done = true;
+		queue.done(&mut player);
+		assert_eq!(queue.current, Some(1));
+		assert_eq!(player.current.as_deref(), Some(queue.tracks[1].path()));
+		assert!(!player.done());
+
+		player.seek(Duration::from_secs(4));
+		queue.restart(&mut player);
+		assert_eq!(player.elapsed(), Some(Duration::ZERO));
+
+		player.toggle();
+		assert!(player.paused());
+		player.i_vol(120);
+		assert_eq!(player.volume(), 100);
+
+		Ok(())
+	}
+
 	#[test]
 	fn dot_queue() -> color_eyre::Result<()> {
 		let mut queue = queue("mock/list 01")?;
@@ -854,6 +2189,74 @@ mod test {
 		Ok(())
 	}
 
+	#[test]
+	fn edit() -> color_eyre::Result<()> {
+		let t0 = track("mock/list 01/track 00.mp3")?;
+		let t1 = track("mock/list 01/track 01.mp3")?;
+
+		let mut player = Player::new();
+		let mut queue = queue("mock/list 01")?;
+		assert!(!queue.is_edited());
+
+		queue.select_path(t1.path(), &mut player)?;
+		assert_eq!(queue.index(), Some(1));
+
+		// the current track can't be removed
+		assert!(queue.remove(1).is_none());
+		assert!(queue.remove(6).is_none());
+		assert!(!queue.is_edited());
+
+		assert_eq!(queue.remove(0), Some(t0));
+		assert_eq!(queue.tracks().len(), 5);
+		assert_eq!(queue.track(), Some(&t1));
+		assert_eq!(queue.index(), Some(0));
+		assert!(queue.is_edited());
+
+		assert!(queue.move_track(0, 4));
+		assert_eq!(queue.index(), Some(4));
+		assert!(!queue.move_track(4, 5));
+
+		let mut state = state::test::mock(Some("mock/list 01"), Some("mock/list 01/track 01.mp3"))?;
+		state.tracks = Some(queue.tracks().iter().map(|t| t.path().to_owned()).collect());
+		let restored = Queue::with_state(&state)?;
+		assert!(restored.is_edited());
+		assert_eq!(restored.tracks(), queue.tracks());
+		assert_eq!(restored.index(), Some(4));
+
+		queue.queue("mock/list 01")?;
+		assert!(!queue.is_edited());
+
+		Ok(())
+	}
+
+	#[test]
+	fn play_next() -> color_eyre::Result<()> {
+		let t1 = track("mock/list 02/track 01.mp3")?;
+		let other = track("mock/list 01/track 00.mp3")?;
+
+		let mut player = Player::new();
+		let mut queue = queue("mock/list 02")?;
+		queue.select_path(t1.path(), &mut player)?;
+
+		queue.play_next(other.clone(), &mut player)?;
+		assert_eq!(queue.tracks().len(), 6);
+		assert_eq!(queue.index(), Some(2));
+		assert_eq!(queue.track(), Some(&other));
+		assert_eq!(queue.tracks()[1], t1);
+
+		// tracks that are already queued aren't added again
+		queue.play_next(t1.clone(), &mut player)?;
+		assert_eq!(queue.tracks().len(), 6);
+		assert_eq!(queue.index(), Some(1));
+
+		queue.empty();
+		queue.play_next(other.clone(), &mut player)?;
+		assert_eq!(queue.tracks(), [other]);
+		assert_eq!(queue.index(), Some(0));
+
+		Ok(())
+	}
+
 	/// create [`serde_json`] string deserializer
 	fn deserializer(val: &str) -> serde_json::de::Deserializer<serde_json::de::StrRead<'_>> {
 		serde_json::de::Deserializer::from_str(val)
@@ -901,7 +2304,15 @@ mod test {
 	/// track!(#1, alb = "album");
 	/// ```
 	macro_rules! track {
-		($(# $tr:expr, )? $(tit = $tit:expr, )? $(art = $art:expr, )? $(alb = $alb:expr, )?) => {
+		(
+			$(# $tr:expr, )?
+			$(tit = $tit:expr, )?
+			$(art = $art:expr, )?
+			$(alb = $alb:expr, )?
+			$(gen = $gen:expr, )?
+			$(year = $year:expr, )?
+			$(disc = $disc:expr, )?
+		) => {
 			{
 				use id3::{Tag, TagLike};
 
@@ -910,9 +2321,12 @@ mod test {
 				$( tag.set_title($tit); )?
 				$( tag.set_artist($art); )?
 				$( tag.set_album($alb); )?
+				$( tag.set_genre($gen); )?
+				$( tag.set_year($year); )?
+				$( tag.set_disc($disc); )?
 
 				let path = "/dev/null".into();
-				let track = super::TrackInner { path, tag };
+				let track = super::TrackInner::new(path, tag);
 				let track = Track(std::sync::Arc::new(track));
 
 				track
@@ -969,6 +2383,70 @@ mod test {
 		assert_eq!(fou.cmp(&two), Ordering::Greater);
 	}
 
+	#[test]
+	fn order() {
+		let one = track!(tit = "00", gen = "rock", year = 2001,);
+		let two = track!(tit = "01", gen = "Jazz",);
+		let thr = track!(tit = "02", year = 1999,);
+		let fou = track!(tit = "03", gen = "(17)", year = 2001,);
+
+		assert_eq!(fou.genre().as_deref(), Some("Rock"));
+
+		assert_eq!(Order::Default.cmp(&one, &two), Ordering::Less);
+		assert_eq!(Order::Default.cmp(&thr, &two), Ordering::Greater);
+
+		assert_eq!(Order::Genre.cmp(&one, &two), Ordering::Greater);
+		assert_eq!(Order::Genre.cmp(&one, &fou), Ordering::Less);
+		assert_eq!(Order::Genre.cmp(&thr, &two), Ordering::Greater);
+
+		assert_eq!(Order::Year.cmp(&one, &thr), Ordering::Greater);
+		assert_eq!(Order::Year.cmp(&one, &fou), Ordering::Less);
+		assert_eq!(Order::Year.cmp(&two, &thr), Ordering::Greater);
+	}
+
+	#[test]
+	fn album_shuffle() -> color_eyre::Result<()> {
+		let mut queue = queue("mock/list 01")?;
+		queue.tracks = vec![
+			track!(#2, alb = "one",),
+			track!(#1, alb = "two",),
+			track!(#1, alb = "one",),
+			track!(#1, alb = "thr",),
+			track!(#2, alb = "two",),
+			track!(#3, alb = "one",),
+		];
+		queue.order = Order::Album;
+		queue.sort();
+
+		// every album is contiguous and keeps its in-album order
+		let albums = queue
+			.tracks()
+			.chunk_by(|one, two| one.album() == two.album())
+			.collect::<Vec<_>>();
+		assert_eq!(albums.len(), 3);
+		for album in albums {
+			assert!(album.is_sorted());
+		}
+
+		Ok(())
+	}
+
+	#[test]
+	fn ord_disc() {
+		let one = track!(#2, disc = 1,);
+		let two = track!(#1, disc = 2,);
+		let thr = track!(#3);
+		let fou = track!(#3, disc = 2,);
+
+		assert_eq!(one.cmp(&two), Ordering::Less);
+		assert_eq!(two.cmp(&thr), Ordering::Greater);
+		assert_eq!(thr.cmp(&one), Ordering::Greater);
+		assert_eq!(two.cmp(&fou), Ordering::Less);
+
+		assert_eq!(one.to_string(), "02 unknown title ~ unknown artist");
+		assert_eq!(fou.to_string(), "2.03 unknown title ~ unknown artist");
+	}
+
 	#[test]
 	fn ord_unicode() {
 		let one = track!("ä");
@@ -986,4 +2464,16 @@ mod test {
 		assert_eq!(two.cmp(&fou), Ordering::Equal);
 		assert_eq!(fou.cmp(&two), Ordering::Equal);
 	}
+
+	#[test]
+	fn artists() {
+		let one = track!("title", "A; B\0C");
+		assert_eq!(one.artists(), ["A", "B", "C"]);
+		assert_eq!(one.credit().as_deref(), Some("A +2"));
+		assert_eq!(one.to_string(), "title ~ A +2");
+
+		let two = track!("title", " ; ");
+		assert_eq!(two.artists(), Vec::<&str>::new());
+		assert_eq!(two.to_string(), "title ~ unknown artist");
+	}
 }
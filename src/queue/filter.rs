@@ -0,0 +1,215 @@
+//! [`Filter`] restricting which tracks of the [`Queue`](super::Queue) are played
+
+use super::Track;
+use std::{cmp::Ordering, fmt::Display, str::FromStr};
+use thiserror::Error;
+use unicase::UniCase;
+
+/// filter error
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum FilterError {
+	/// filter is empty
+	#[error("empty filter")]
+	Empty,
+	/// key before the colon isn't known
+	#[error("unknown filter key {0:?}")]
+	UnknownKey(String),
+	/// rating isn't a number from 0 to 5
+	#[error("invalid rating {0:?}")]
+	InvalidRating(String),
+	/// bpm isn't a number or a range like `120-140`
+	#[error("invalid bpm {0:?}")]
+	InvalidBpm(String),
+}
+
+/// comparison of a [`Filter::Rating`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cmp {
+	Lt,
+	Le,
+	Eq,
+	Ge,
+	Gt,
+}
+
+impl Cmp {
+	fn matches(self, ordering: Ordering) -> bool {
+		match self {
+			Cmp::Lt => ordering.is_lt(),
+			Cmp::Le => ordering.is_le(),
+			Cmp::Eq => ordering.is_eq(),
+			Cmp::Ge => ordering.is_ge(),
+			Cmp::Gt => ordering.is_gt(),
+		}
+	}
+
+	fn as_str(self) -> &'static str {
+		match self {
+			Cmp::Lt => "<",
+			Cmp::Le => "<=",
+			Cmp::Eq => "=",
+			Cmp::Ge => ">=",
+			Cmp::Gt => ">",
+		}
+	}
+}
+
+/// predicate on a [`Track`]
+///
+/// parsed from `artist:foo`, `album:foo`, `title:foo`, `genre:foo`, `rating>=4`
+/// or `bpm:120-140`, text without a key matches the title, artist or album.
+/// text is matched case-insensitively
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Filter {
+	Any(String),
+	Artist(String),
+	Album(String),
+	Title(String),
+	Genre(String),
+	/// tracks without a rating count as 0
+	Rating(Cmp, u8),
+	/// inclusive range, tracks without a bpm never match
+	Bpm(u32, u32),
+}
+
+impl Filter {
+	/// returns if `track` matches the filter
+	pub fn matches(&self, track: &Track) -> bool {
+		match self {
+			Filter::Any(text) => [track.title(), track.album()]
+				.into_iter()
+				.chain(track.artists().into_iter().map(Some))
+				.any(|tag| contains(tag, text)),
+			Filter::Artist(text) => {
+				(track.artists().into_iter()).any(|artist| contains(Some(artist), text))
+			}
+			Filter::Album(text) => contains(track.album(), text),
+			Filter::Title(text) => contains(track.title(), text),
+			Filter::Genre(text) => contains(track.genre().as_deref(), text),
+			Filter::Rating(cmp, rating) => {
+				let ordering = track.rating().unwrap_or(0).cmp(rating);
+				cmp.matches(ordering)
+			}
+			Filter::Bpm(min, max) => track.bpm().is_some_and(|bpm| (min..=max).contains(&&bpm)),
+		}
+	}
+}
+
+/// case-insensitively check if `tag` contains `text`
+fn contains(tag: Option<&str>, text: &str) -> bool {
+	tag.is_some_and(|tag| tag.to_lowercase().contains(&text.to_lowercase()))
+}
+
+impl FromStr for Filter {
+	type Err = FilterError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let s = s.trim();
+		if s.is_empty() {
+			return Err(FilterError::Empty);
+		}
+
+		if let Some(rest) = s.strip_prefix("rating") {
+			let rest = rest.trim_start();
+			let (cmp, rating) = [
+				("<=", Cmp::Le),
+				(">=", Cmp::Ge),
+				("<", Cmp::Lt),
+				(">", Cmp::Gt),
+				("=", Cmp::Eq),
+				(":", Cmp::Eq),
+			]
+			.into_iter()
+			.find_map(|(op, cmp)| rest.strip_prefix(op).map(|rating| (cmp, rating.trim())))
+			.ok_or_else(|| FilterError::InvalidRating(rest.to_owned()))?;
+
+			return match rating.parse() {
+				Ok(rating @ 0..=5) => Ok(Filter::Rating(cmp, rating)),
+				_ => Err(FilterError::InvalidRating(rating.to_owned())),
+			};
+		}
+
+		let Some((key, text)) = s.split_once(':') else {
+			return Ok(Filter::Any(s.to_owned()));
+		};
+
+		let text = text.trim().to_owned();
+		if text.is_empty() {
+			return Err(FilterError::Empty);
+		}
+
+		let key = UniCase::new(key.trim());
+		if key == UniCase::new("artist") {
+			Ok(Filter::Artist(text))
+		} else if key == UniCase::new("album") {
+			Ok(Filter::Album(text))
+		} else if key == UniCase::new("title") {
+			Ok(Filter::Title(text))
+		} else if key == UniCase::new("genre") {
+			Ok(Filter::Genre(text))
+		} else if key == UniCase::new("bpm") {
+			let (min, max) = text.split_once('-').unwrap_or((&text, &text));
+			match (min.trim().parse(), max.trim().parse()) {
+				(Ok(min), Ok(max)) if min <= max => Ok(Filter::Bpm(min, max)),
+				_ => Err(FilterError::InvalidBpm(text)),
+			}
+		} else {
+			Err(FilterError::UnknownKey(key.into_inner().to_owned()))
+		}
+	}
+}
+
+impl Display for Filter {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Filter::Any(text) => f.write_str(text),
+			Filter::Artist(text) => write!(f, "artist:{text}"),
+			Filter::Album(text) => write!(f, "album:{text}"),
+			Filter::Title(text) => write!(f, "title:{text}"),
+			Filter::Genre(text) => write!(f, "genre:{text}"),
+			Filter::Rating(cmp, rating) => write!(f, "rating{}{rating}", cmp.as_str()),
+			Filter::Bpm(min, max) if min == max => write!(f, "bpm:{min}"),
+			Filter::Bpm(min, max) => write!(f, "bpm:{min}-{max}"),
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{Cmp, Filter, FilterError};
+
+	#[test]
+	fn parse() {
+		assert_eq!("artist:foo".parse(), Ok(Filter::Artist("foo".to_owned())));
+		assert_eq!(
+			"Album: bar baz ".parse(),
+			Ok(Filter::Album("bar baz".to_owned()))
+		);
+		assert_eq!("foo".parse(), Ok(Filter::Any("foo".to_owned())));
+		assert_eq!("rating>=4".parse(), Ok(Filter::Rating(Cmp::Ge, 4)));
+		assert_eq!("rating < 2".parse(), Ok(Filter::Rating(Cmp::Lt, 2)));
+		assert_eq!("rating:5".parse(), Ok(Filter::Rating(Cmp::Eq, 5)));
+
+		assert_eq!("  ".parse::<Filter>(), Err(FilterError::Empty));
+		assert_eq!("artist:".parse::<Filter>(), Err(FilterError::Empty));
+		assert_eq!(
+			"year:2000".parse::<Filter>(),
+			Err(FilterError::UnknownKey("year".to_owned()))
+		);
+		assert_eq!(
+			"rating>=6".parse::<Filter>(),
+			Err(FilterError::InvalidRating("6".to_owned()))
+		);
+
+		let filter = "rating >= 4".parse::<Filter>().unwrap();
+		assert_eq!(filter.to_string(), "rating>=4");
+
+		assert_eq!("bpm:120 - 140".parse(), Ok(Filter::Bpm(120, 140)));
+		assert_eq!("bpm:128".parse(), Ok(Filter::Bpm(128, 128)));
+		assert_eq!(
+			"bpm:140-120".parse::<Filter>(),
+			Err(FilterError::InvalidBpm("140-120".to_owned()))
+		);
+		assert_eq!(Filter::Bpm(120, 140).to_string(), "bpm:120-140");
+	}
+}
@@ -0,0 +1,199 @@
+//! [`Format`] templates like `{track} — {title} [{album}]` tracks are displayed with
+
+use super::Track;
+use crate::config::Config;
+use std::{str::FromStr, sync::OnceLock};
+use thiserror::Error;
+
+/// formats from the [`Config`]
+static FORMATS: OnceLock<Formats> = OnceLock::new();
+
+#[derive(Debug, Default)]
+struct Formats {
+	/// [`Config::list_format`]
+	list: Option<Format>,
+	/// [`Config::title_format`]
+	title: Option<Format>,
+}
+
+/// format error
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum FormatError {
+	/// placeholder isn't known
+	#[error("unknown placeholder {0:?}")]
+	UnknownField(String),
+	/// placeholder isn't closed
+	#[error("unclosed placeholder")]
+	Unclosed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+	Track,
+	Disc,
+	Title,
+	Artist,
+	Album,
+	Genre,
+	Composer,
+	Filename,
+}
+
+impl Field {
+	fn value(self, track: &Track) -> Option<String> {
+		match self {
+			Field::Track => track.track().map(|track| format!("{track:#02}")),
+			Field::Disc => track.disc().map(|disc| disc.to_string()),
+			Field::Title => track.title().map(ToOwned::to_owned),
+			Field::Artist => track.credit().map(|credit| credit.into_owned()),
+			Field::Album => track.album().map(ToOwned::to_owned),
+			Field::Genre => track.genre().map(|genre| genre.into_owned()),
+			Field::Composer => track.composer().map(ToOwned::to_owned),
+			Field::Filename => track.path().file_stem().map(ToOwned::to_owned),
+		}
+	}
+}
+
+impl FromStr for Field {
+	type Err = FormatError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let field = match s.trim() {
+			"track" => Field::Track,
+			"disc" => Field::Disc,
+			"title" => Field::Title,
+			"artist" => Field::Artist,
+			"album" => Field::Album,
+			"genre" => Field::Genre,
+			"composer" => Field::Composer,
+			"filename" => Field::Filename,
+			field => return Err(FormatError::UnknownField(field.to_owned())),
+		};
+		Ok(field)
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+	Text(String),
+	/// placeholder with its fallbacks
+	Field(Vec<Field>),
+}
+
+/// template like `{track} — {title|filename} [{album}]`
+///
+/// the placeholders are `{track}`, `{disc}`, `{title}`, `{artist}`, `{album}`, `{genre}`,
+/// `{composer}` and `{filename}`. fallbacks are separated by `|` and tried in order,
+/// placeholders without any value are left empty
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Format(Vec<Part>);
+
+impl Format {
+	/// fill the placeholders with the tags of `track`
+	///
+	/// returns [`None`] if none of the placeholders has a value
+	fn render(&self, track: &Track) -> Option<String> {
+		let mut rendered = String::new();
+		let mut filled = false;
+
+		for part in &self.0 {
+			match part {
+				Part::Text(text) => rendered.push_str(text),
+				Part::Field(fields) => {
+					if let Some(value) = fields.iter().find_map(|field| field.value(track)) {
+						rendered.push_str(&value);
+						filled = true;
+					}
+				}
+			}
+		}
+
+		filled.then_some(rendered)
+	}
+}
+
+impl FromStr for Format {
+	type Err = FormatError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let mut parts = Vec::new();
+		let mut rest = s;
+
+		while let Some(start) = rest.find('{') {
+			if start > 0 {
+				parts.push(Part::Text(rest[..start].to_owned()));
+			}
+
+			let end = start + rest[start..].find('}').ok_or(FormatError::Unclosed)?;
+			let fields = rest[start + 1..end]
+				.split('|')
+				.map(str::parse)
+				.collect::<Result<_, _>>()?;
+			parts.push(Part::Field(fields));
+			rest = &rest[end + 1..];
+		}
+
+		if !rest.is_empty() {
+			parts.push(Part::Text(rest.to_owned()));
+		}
+
+		Ok(Format(parts))
+	}
+}
+
+/// parse the formats from the [`Config`], ignoring invalid ones
+pub fn load(config: &Config) {
+	let parse = |format: Option<&str>| {
+		let format = format?;
+		format
+			.parse()
+			.inspect_err(|err| log::warn!("invalid format {format:?}: {err}"))
+			.ok()
+	};
+
+	let _ = FORMATS.set(Formats {
+		list: parse(config.list_format()),
+		title: parse(config.title_format()),
+	});
+}
+
+/// `track` in the [`Config::list_format`], if one is configured
+pub fn list(track: &Track) -> Option<String> {
+	FORMATS.get()?.list.as_ref()?.render(track)
+}
+
+/// `track` in the [`Config::title_format`], if one is configured
+pub fn title(track: &Track) -> Option<String> {
+	FORMATS.get()?.title.as_ref()?.render(track)
+}
+
+#[cfg(test)]
+mod test {
+	use super::{Format, FormatError};
+	use crate::queue::Track;
+
+	#[test]
+	fn format() {
+		let track = Track::new("mock/list 01/track 01.mp3".into()).unwrap();
+		let title = track.title().unwrap();
+
+		let format = "{title} [{genre}]".parse::<Format>().unwrap();
+		let rendered = format.render(&track).unwrap();
+		assert!(rendered.starts_with(&format!("{title} [")));
+
+		let format = "{composer|title}".parse::<Format>().unwrap();
+		assert_eq!(format.render(&track).as_deref(), Some(title));
+
+		let format = "{composer|filename}".parse::<Format>().unwrap();
+		assert_eq!(format.render(&track).as_deref(), Some("track 01"));
+
+		let format = "- {composer} -".parse::<Format>().unwrap();
+		assert_eq!(format.render(&track), None);
+
+		assert_eq!(
+			"{title|name}".parse::<Format>(),
+			Err(FormatError::UnknownField("name".to_owned()))
+		);
+		assert_eq!("{title".parse::<Format>(), Err(FormatError::Unclosed));
+	}
+}
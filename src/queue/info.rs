@@ -0,0 +1,74 @@
+//! technical [`StreamInfo`] of a track
+
+use camino::Utf8Path;
+use std::{fs::File, time::Duration};
+use symphonia::core::{
+	formats::FormatOptions, io::MediaSourceStream, meta::MetadataOptions, probe::Hint,
+};
+
+/// technical info about the audio stream of a track
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamInfo {
+	/// short name of the codec
+	pub codec: Option<&'static str>,
+	pub sample_rate: Option<u32>,
+	/// bit depth, only known for lossless codecs
+	pub bits_per_sample: Option<u32>,
+	pub channels: Option<usize>,
+	/// average bitrate in bits per second
+	pub bitrate: Option<u64>,
+	/// length of the stream, if the header has a frame count
+	pub duration: Option<Duration>,
+	/// file size in bytes
+	pub size: u64,
+}
+
+impl StreamInfo {
+	/// probe the file at `path` with symphonia
+	///
+	/// only reads the headers, the stream isn't decoded
+	pub fn probe(path: &Utf8Path) -> Option<StreamInfo> {
+		let file = File::open(path).ok()?;
+		let size = file.metadata().ok()?.len();
+		let stream = MediaSourceStream::new(Box::new(file), Default::default());
+
+		let mut hint = Hint::new();
+		if let Some(extension) = path.extension() {
+			hint.with_extension(extension);
+		}
+
+		let probed = symphonia::default::get_probe()
+			.format(
+				&hint,
+				stream,
+				&FormatOptions::default(),
+				&MetadataOptions::default(),
+			)
+			.ok()?;
+
+		let track = probed.format.default_track()?;
+		let params = &track.codec_params;
+
+		let codec = (symphonia::default::get_codecs())
+			.get_codec(params.codec)
+			.map(|codec| codec.short_name);
+
+		let duration = params
+			.n_frames
+			.zip(params.sample_rate)
+			.map(|(frames, rate)| Duration::from_secs_f64(frames as f64 / f64::from(rate)));
+		let bitrate = duration
+			.filter(|duration| !duration.is_zero())
+			.map(|duration| (size as f64 * 8. / duration.as_secs_f64()) as u64);
+
+		Some(StreamInfo {
+			codec,
+			sample_rate: params.sample_rate,
+			bits_per_sample: params.bits_per_sample,
+			channels: params.channels.map(|channels| channels.count()),
+			bitrate,
+			duration,
+			size,
+		})
+	}
+}
@@ -0,0 +1,226 @@
+//! [`Pattern`]s deriving tags of untagged tracks from their file name
+
+use crate::config::Config;
+use id3::{Tag, TagLike};
+use std::{str::FromStr, sync::OnceLock};
+use thiserror::Error;
+
+/// patterns used if none are configured
+const DEFAULT_PATTERNS: [&str; 3] = [
+	"{track} - {artist} - {title}",
+	"{artist} - {title}",
+	"{track} {title}",
+];
+
+/// patterns from the [`Config`], tried in order
+static PATTERNS: OnceLock<Vec<Pattern>> = OnceLock::new();
+
+/// pattern error
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum PatternError {
+	/// placeholder isn't known
+	#[error("unknown placeholder {0:?}")]
+	UnknownField(String),
+	/// placeholder isn't closed
+	#[error("unclosed placeholder")]
+	Unclosed,
+	/// two placeholders aren't separated by text
+	#[error("placeholders have to be separated")]
+	Adjacent,
+	/// pattern doesn't contain a title
+	#[error("pattern has no title")]
+	NoTitle,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+	Track,
+	Artist,
+	Title,
+	Album,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+	Text(String),
+	Field(Field),
+}
+
+/// file name pattern like `{track} - {artist} - {title}`
+///
+/// the placeholders are `{track}`, `{artist}`, `{title}` and `{album}`,
+/// every placeholder but the last one ends at the first occurrence of the following text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern(Vec<Part>);
+
+impl Pattern {
+	/// match `name` against the pattern and fill the missing tags of `tag`
+	///
+	/// returns `false` and leaves `tag` as it is if `name` doesn't match
+	fn apply(&self, name: &str, tag: &mut Tag) -> bool {
+		let mut fields = Vec::new();
+		let mut rest = name;
+		let mut parts = self.0.iter().peekable();
+
+		while let Some(part) = parts.next() {
+			match part {
+				Part::Text(text) => match rest.strip_prefix(text.as_str()) {
+					Some(stripped) => rest = stripped,
+					None => return false,
+				},
+				Part::Field(field) => {
+					let raw = match parts.peek() {
+						Some(Part::Text(text)) => match rest.find(text.as_str()) {
+							Some(end) => &rest[..end],
+							None => return false,
+						},
+						_ => rest,
+					};
+					rest = &rest[raw.len()..];
+
+					let value = raw.trim();
+					if value.is_empty() || (*field == Field::Track && value.parse::<u32>().is_err())
+					{
+						return false;
+					}
+					fields.push((*field, value));
+				}
+			}
+		}
+
+		if !rest.is_empty() {
+			return false;
+		}
+
+		for (field, value) in fields {
+			match field {
+				Field::Track if tag.track().is_none() => {
+					tag.set_track(value.parse().expect("track should be a number"));
+				}
+				Field::Artist if tag.artist().is_none() => tag.set_artist(value),
+				Field::Title => tag.set_title(value),
+				Field::Album if tag.album().is_none() => tag.set_album(value),
+				_ => {}
+			}
+		}
+
+		true
+	}
+}
+
+impl FromStr for Pattern {
+	type Err = PatternError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let mut parts = Vec::new();
+		let mut rest = s;
+
+		while let Some(start) = rest.find('{') {
+			if start > 0 {
+				parts.push(Part::Text(rest[..start].to_owned()));
+			} else if let Some(Part::Field(_)) = parts.last() {
+				return Err(PatternError::Adjacent);
+			}
+
+			let end = start + rest[start..].find('}').ok_or(PatternError::Unclosed)?;
+			let field = match &rest[start + 1..end] {
+				"track" => Field::Track,
+				"artist" => Field::Artist,
+				"title" => Field::Title,
+				"album" => Field::Album,
+				field => return Err(PatternError::UnknownField(field.to_owned())),
+			};
+			parts.push(Part::Field(field));
+			rest = &rest[end + 1..];
+		}
+
+		if !rest.is_empty() {
+			parts.push(Part::Text(rest.to_owned()));
+		}
+
+		if !parts.contains(&Part::Field(Field::Title)) {
+			return Err(PatternError::NoTitle);
+		}
+
+		Ok(Pattern(parts))
+	}
+}
+
+/// parse the file name patterns from the [`Config`], skipping invalid ones
+pub fn load(config: &Config) {
+	let patterns = match config.filename_patterns() {
+		Some(patterns) => patterns.iter().map(String::as_str).collect(),
+		None => DEFAULT_PATTERNS.to_vec(),
+	};
+
+	let patterns = patterns
+		.into_iter()
+		.filter_map(|pattern| match pattern.parse() {
+			Ok(pattern) => Some(pattern),
+			Err(err) => {
+				log::warn!("invalid file name pattern {pattern:?}: {err}");
+				None
+			}
+		})
+		.collect();
+	let _ = PATTERNS.set(patterns);
+}
+
+/// fill the tags of an untagged track from its file name `stem`
+///
+/// only used if the track has no title, tags that are set are kept
+pub fn fill(stem: &str, tag: &mut Tag) {
+	if tag.title().is_some() {
+		return;
+	}
+
+	let patterns = PATTERNS.get().map_or(&[][..], Vec::as_slice);
+	for pattern in patterns {
+		if pattern.apply(stem, tag) {
+			return;
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{Pattern, PatternError};
+	use id3::{Tag, TagLike};
+
+	#[test]
+	fn pattern() {
+		let pattern = "{track} - {artist} - {title}".parse::<Pattern>().unwrap();
+
+		let mut tag = Tag::new();
+		assert!(pattern.apply("07 - Some Artist - A Title - Live", &mut tag));
+		assert_eq!(tag.track(), Some(7));
+		assert_eq!(tag.artist(), Some("Some Artist"));
+		assert_eq!(tag.title(), Some("A Title - Live"));
+
+		// tags that are set are kept
+		let mut tag = Tag::new();
+		tag.set_artist("Tagged");
+		assert!(pattern.apply("01 - Artist - Title", &mut tag));
+		assert_eq!(tag.artist(), Some("Tagged"));
+
+		let mut tag = Tag::new();
+		assert!(!pattern.apply("Artist - Title", &mut tag));
+		assert!(!pattern.apply("xx - Artist - Title", &mut tag));
+		assert_eq!(tag.title(), None);
+
+		let pattern = "{track}. {title}".parse::<Pattern>().unwrap();
+		assert!(pattern.apply("12. Title", &mut tag));
+		assert_eq!((tag.track(), tag.title()), (Some(12), Some("Title")));
+
+		assert_eq!(
+			"{track}{title}".parse::<Pattern>(),
+			Err(PatternError::Adjacent)
+		);
+		assert_eq!(
+			"{artist} - {name}".parse::<Pattern>(),
+			Err(PatternError::UnknownField("name".to_owned()))
+		);
+		assert_eq!("{artist}".parse::<Pattern>(), Err(PatternError::NoTitle));
+		assert_eq!("{title".parse::<Pattern>(), Err(PatternError::Unclosed));
+	}
+}
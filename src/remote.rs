@@ -0,0 +1,401 @@
+//! remote libraries, mirrored into the cache directory
+//!
+//! directories of a remote library are mirrored as directories and tracks as placeholder
+//! mp3 files, that only contain an id3 tag. placeholders are downloaded right before they
+//! are played and then stay cached, so everything else only ever sees local files.
+//!
+//! supports a subsonic server and webdav or sftp directories in the lists of the config.
+//! all requests are made with `curl`, single urls can also be resolved with `yt-dlp`
+
+use crate::config::{CONFIG_DIR, Config, List, Subsonic};
+use camino::{Utf8Path, Utf8PathBuf};
+use id3::{Tag, TagLike, Version};
+use std::{
+	collections::{HashMap, HashSet},
+	fs::{self, File},
+	io::{self, Write},
+	path::Path,
+	process::{Command, Stdio},
+	sync::{LazyLock, Mutex, OnceLock, mpsc::Sender},
+	time::Duration,
+};
+use thiserror::Error;
+
+mod files;
+mod subsonic;
+mod ytdlp;
+
+pub(crate) use self::files::{decode, elements, unescape};
+
+/// directory the remote libraries are mirrored into
+///
+/// [`None`] if the cache directory isn't valid utf-8
+static REMOTE_DIR: LazyLock<Option<Utf8PathBuf>> = LazyLock::new(|| {
+	let cache = dirs::cache_dir().map_or_else(|| CONFIG_DIR.join("cache"), |dir| dir.join("maym"));
+	Utf8PathBuf::from_path_buf(cache.join("remote")).ok()
+});
+
+/// configured subsonic server, set in [`load`]
+static SUBSONIC: OnceLock<Subsonic> = OnceLock::new();
+
+/// roots of the mirrors of remote directories from the config, mapped to their url
+static ROOTS: LazyLock<Mutex<HashMap<Utf8PathBuf, String>>> = LazyLock::new(Default::default);
+
+/// directories synced in this session
+static SYNCED: LazyLock<Mutex<HashSet<Utf8PathBuf>>> = LazyLock::new(Default::default);
+
+/// placeholders that are currently downloaded
+static FETCHING: LazyLock<Mutex<HashSet<Utf8PathBuf>>> = LazyLock::new(Default::default);
+
+/// description of the id3 extended text frame that marks a placeholder
+///
+/// its value is the source of the track, like `subsonic:<id>` or `url:<url>`
+const MARKER: &str = "maym remote";
+
+/// remote error
+#[derive(Debug, Error)]
+pub enum RemoteError {
+	/// io error
+	#[error("io error")]
+	IoError(#[from] io::Error),
+	/// curl failed
+	#[error("curl failed: {0}")]
+	Curl(String),
+	/// yt-dlp failed
+	#[error("yt-dlp failed: {0}")]
+	YtDlp(String),
+	/// serde error
+	#[error("serde error")]
+	SerdeJsonError(#[from] serde_json::Error),
+	/// the server returned an error
+	#[error("server error {0}: {1}")]
+	Server(i64, String),
+	/// the response is missing fields
+	#[error("unexpected response")]
+	Response,
+}
+
+/// load the remote libraries from the [`Config`]
+pub fn load(config: &Config) {
+	if let Some(subsonic) = config.subsonic() {
+		let _ = SUBSONIC.set(subsonic.clone());
+	}
+}
+
+/// root directory of the subsonic mirror
+fn subsonic_root() -> Option<Utf8PathBuf> {
+	SUBSONIC.get()?;
+	REMOTE_DIR.as_ref().map(|dir| dir.join("subsonic"))
+}
+
+/// [`List`]s of the mirrors of the configured remote libraries
+pub fn roots() -> Vec<List> {
+	let Some(root) = subsonic_root() else {
+		return Vec::new();
+	};
+
+	if let Err(err) = fs::create_dir_all(&root) {
+		log::error!("couldn't create {root:?}: {err}");
+		return Vec::new();
+	}
+
+	List::new(root).into_iter().collect()
+}
+
+/// mirror of the webdav or sftp directory `url` from the config lists
+///
+/// [`None`] if `url` isn't a remote url or the mirror couldn't be created
+pub fn mirror(url: &str) -> Option<Utf8PathBuf> {
+	let (remote, path) = files::parse(url)?;
+	let path = REMOTE_DIR.as_ref()?.join(path);
+
+	if let Err(err) = files::init(&path, &remote) {
+		log::error!("couldn't create {path:?}: {err}");
+		return None;
+	}
+
+	ROOTS.lock().unwrap().insert(path.clone(), url.to_owned());
+	Some(path)
+}
+
+/// display name if `path` is the root of a mirror
+pub fn name(path: &Utf8Path) -> Option<String> {
+	if subsonic_root().is_some_and(|root| root == path) {
+		Some("subsonic".to_owned())
+	} else {
+		ROOTS.lock().unwrap().get(path).cloned()
+	}
+}
+
+/// returns if `path` is inside a mirror
+fn is_remote(path: &Utf8Path) -> bool {
+	REMOTE_DIR.as_ref().is_some_and(|dir| path.starts_with(dir))
+}
+
+/// mirror the remote directory at `path`, recursing into subdirectories if `deep` is set
+///
+/// directories are only synced once per session, does nothing outside of a mirror
+pub fn sync(path: &Utf8Path, deep: bool) {
+	if is_remote(path)
+		&& let Err(err) = sync_dir(path, deep)
+	{
+		log::error!("couldn't sync {path:?}: {err}");
+	}
+}
+
+fn sync_dir(path: &Utf8Path, deep: bool) -> Result<(), RemoteError> {
+	let synced = SYNCED.lock().unwrap().contains(path);
+	let dirs = if synced {
+		children(path)
+	} else {
+		let dirs = if let Some(server) = SUBSONIC.get()
+			&& let Some(root) = subsonic_root()
+			&& path.starts_with(&root)
+		{
+			subsonic::sync(server, &root, path)?
+		} else {
+			files::sync(path)?
+		};
+
+		SYNCED.lock().unwrap().insert(path.to_owned());
+		dirs
+	};
+
+	if deep {
+		for dir in dirs {
+			sync_dir(&dir, deep)?;
+		}
+	}
+
+	Ok(())
+}
+
+/// subdirectories of `path`
+fn children(path: &Utf8Path) -> Vec<Utf8PathBuf> {
+	let Ok(read) = fs::read_dir(path) else {
+		return Vec::new();
+	};
+
+	read.flatten()
+		.map(|entry| entry.path())
+		.flat_map(Utf8PathBuf::try_from)
+		.filter(|path| path.is_dir())
+		.collect()
+}
+
+/// resolve `url` with yt-dlp in the background
+///
+/// the path of the downloaded track is sent to `tx` once it is done
+pub fn resolve(url: String, tx: Sender<Utf8PathBuf>) {
+	let Some(dir) = REMOTE_DIR.as_ref().map(|dir| dir.join("yt-dlp")) else {
+		log::error!("cache directory isn't valid utf-8");
+		return;
+	};
+
+	log::info!("resolving {url}");
+	let spawned = std::thread::Builder::new()
+		.name("resolve".to_owned())
+		.spawn(move || {
+			let path = fs::create_dir_all(&dir)
+				.map_err(RemoteError::from)
+				.and_then(|()| ytdlp::download(&url, &dir));
+
+			match path {
+				Ok(path) => {
+					let _ = tx.send(path);
+				}
+				Err(err) => log::error!("couldn't resolve {url}: {err}"),
+			}
+		});
+
+	if let Err(err) = spawned {
+		log::error!("couldn't spawn resolve thread: {err}");
+	}
+}
+
+/// download the track at `path` if it is a placeholder
+///
+/// blocks until the download is done, also if it was started by [`prefetch`]
+pub fn fetch(path: &Utf8Path) {
+	if !is_remote(path) {
+		return;
+	}
+
+	loop {
+		let mut fetching = FETCHING.lock().unwrap();
+		if fetching.insert(path.to_owned()) {
+			break;
+		}
+
+		drop(fetching);
+		std::thread::sleep(Duration::from_millis(50));
+	}
+
+	if let Some((tag, source)) = placeholder(path)
+		&& let Err(err) = download(path, tag, &source)
+	{
+		log::error!("couldn't download {path:?}: {err}");
+	}
+
+	FETCHING.lock().unwrap().remove(path);
+}
+
+/// download the track at `path` in the background if it is a placeholder
+///
+/// returns if the track is available locally
+pub fn prefetch(path: &Utf8Path) -> bool {
+	if !is_remote(path) {
+		return true;
+	} else if FETCHING.lock().unwrap().contains(path) {
+		return false;
+	} else if placeholder(path).is_none() {
+		return true;
+	}
+
+	let path = path.to_owned();
+	let spawned = std::thread::Builder::new()
+		.name("prefetch".to_owned())
+		.spawn(move || fetch(&path));
+	if let Err(err) = spawned {
+		log::error!("couldn't spawn prefetch thread: {err}");
+	}
+
+	false
+}
+
+/// returns the tag and source of the placeholder at `path`
+///
+/// [`None`] if the track was already downloaded
+fn placeholder(path: &Utf8Path) -> Option<(Tag, String)> {
+	let tag = Tag::read_from_path(path).ok()?;
+	let source = tag
+		.extended_texts()
+		.find(|text| text.description == MARKER)
+		.map(|text| text.value.clone())?;
+	Some((tag, source))
+}
+
+/// create a placeholder at `path` that contains `tag` and is downloaded from `source`
+fn create_placeholder(path: &Utf8Path, mut tag: Tag, source: &str) -> Result<(), RemoteError> {
+	tag.add_frame(id3::frame::ExtendedText {
+		description: MARKER.to_owned(),
+		value: source.to_owned(),
+	});
+
+	let file = File::create(path)?;
+	tag.write_to(file, Version::Id3v24)
+		.map_err(io::Error::other)?;
+	Ok(())
+}
+
+/// download `source` over the placeholder at `path`
+///
+/// keeps the tag of the placeholder if the downloaded file doesn't have one
+fn download(path: &Utf8Path, mut tag: Tag, source: &str) -> Result<(), RemoteError> {
+	let url = match source.split_once(':') {
+		Some(("subsonic", id)) => {
+			let server = SUBSONIC.get().ok_or(RemoteError::Response)?;
+			subsonic::stream(server, id)
+		}
+		Some(("url", url)) => url.to_owned(),
+		_ => return Err(RemoteError::Response),
+	};
+
+	let file_name = path.file_name().unwrap_or_default();
+	let part = path.with_file_name(format!(".{file_name}.part"));
+	if let Err(err) = curl(&url, &[], Some(part.as_std_path())) {
+		let _ = fs::remove_file(&part);
+		return Err(err);
+	}
+
+	if Tag::read_from_path(&part).is_err() {
+		tag.remove_extended_text(Some(MARKER), None);
+		tag.write_to_path(&part, Version::Id3v24)
+			.map_err(io::Error::other)?;
+	}
+
+	fs::rename(&part, path)?;
+	Ok(())
+}
+
+/// request `url` with curl and return the body, or write it to `output`
+///
+/// `options` are long curl options with their value, like `("request", "PROPFIND")`.
+/// they are passed on stdin with the url, so credentials don't show up in the process list
+fn curl(
+	url: &str,
+	options: &[(&str, &str)],
+	output: Option<&Path>,
+) -> Result<Vec<u8>, RemoteError> {
+	let mut command = Command::new("curl");
+	command.args([
+		"--silent",
+		"--show-error",
+		"--fail",
+		"--location",
+		"--netrc-optional",
+		"--config",
+		"-",
+	]);
+	if let Some(output) = output {
+		command.arg("--output").arg(output);
+	}
+
+	let mut child = command
+		.stdin(Stdio::piped())
+		.stdout(Stdio::piped())
+		.stderr(Stdio::piped())
+		.spawn()?;
+
+	let mut stdin = child.stdin.take().expect("stdin should be piped");
+	let quote = |value: &str| value.replace('\\', "\\\\").replace('"', "\\\"");
+	writeln!(stdin, "url = \"{}\"", quote(url))?;
+	for (option, value) in options {
+		writeln!(stdin, "{option} = \"{}\"", quote(value))?;
+	}
+	drop(stdin);
+
+	let output = child.wait_with_output()?;
+	if output.status.success() {
+		Ok(output.stdout)
+	} else {
+		let stderr = String::from_utf8_lossy(&output.stderr);
+		Err(RemoteError::Curl(stderr.trim().to_owned()))
+	}
+}
+
+/// percent-encode `text` for use in a url
+fn encode(text: &str) -> String {
+	text.bytes()
+		.map(|byte| match byte {
+			b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+				char::from(byte).to_string()
+			}
+			_ => format!("%{byte:02X}"),
+		})
+		.collect()
+}
+
+/// turn a remote name into a file name, that doesn't leave or hide in its directory
+pub(crate) fn file_name(name: &str) -> String {
+	let name = name.trim().replace(['/', '\\', '\0'], "_");
+	let name = name.trim_start_matches('.');
+	if name.is_empty() {
+		"_".to_owned()
+	} else {
+		name.to_owned()
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{encode, file_name};
+
+	#[test]
+	fn names() {
+		assert_eq!(encode("a b/ä~"), "a%20b%2F%C3%A4~");
+		assert_eq!(file_name("AC/DC"), "AC_DC");
+		assert_eq!(file_name("..hidden"), "hidden");
+		assert_eq!(file_name(" .. "), "_");
+	}
+}
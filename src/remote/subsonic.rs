@@ -0,0 +1,238 @@
+//! [subsonic api](https://www.subsonic.org/pages/api.jsp), also served by navidrome and others
+
+use super::{RemoteError, create_placeholder, curl, encode, file_name};
+use crate::config::Subsonic;
+use camino::{Utf8Path, Utf8PathBuf};
+use id3::{Tag, TagLike};
+use serde_json::Value;
+use std::fs;
+
+/// requested api version
+const VERSION: &str = "1.16.1";
+
+/// file in a mirrored directory that holds its remote id
+const ID_FILE: &str = ".id";
+
+/// entry of a remote directory
+#[derive(Debug)]
+enum Entry {
+	Dir {
+		id: String,
+		name: String,
+	},
+	Song {
+		id: String,
+		/// file name of the placeholder
+		name: String,
+		tag: Tag,
+	},
+}
+
+/// url of the api `method` with `params`
+fn url(server: &Subsonic, method: &str, params: &[(&str, &str)]) -> String {
+	let password = server
+		.password
+		.bytes()
+		.map(|byte| format!("{byte:02x}"))
+		.collect::<String>();
+
+	let mut url = format!(
+		"{}/rest/{method}?u={}&p=enc:{password}&v={VERSION}&c=maym&f=json",
+		server.url.trim_end_matches('/'),
+		encode(&server.user),
+	);
+	for (key, value) in params {
+		url.push_str(&format!("&{key}={}", encode(value)));
+	}
+
+	url
+}
+
+/// url to download the track with `id` as mp3
+pub fn stream(server: &Subsonic, id: &str) -> String {
+	url(server, "stream", &[("id", id), ("format", "mp3")])
+}
+
+/// call the api `method` and return the inner response
+fn request(server: &Subsonic, method: &str, params: &[(&str, &str)]) -> Result<Value, RemoteError> {
+	let body = curl(&url(server, method, params), &[], None)?;
+	response(&body)
+}
+
+/// unwrap the `subsonic-response` of `body`, turning failures into [`RemoteError::Server`]
+fn response(body: &[u8]) -> Result<Value, RemoteError> {
+	let mut json = serde_json::from_slice::<Value>(body)?;
+	let response = json["subsonic-response"].take();
+
+	if response["status"] == "ok" {
+		Ok(response)
+	} else {
+		let error = &response["error"];
+		let code = error["code"].as_i64().unwrap_or_default();
+		let message = error["message"].as_str().unwrap_or_default();
+		Err(RemoteError::Server(code, message.to_owned()))
+	}
+}
+
+/// ids are strings, but some servers send numbers
+fn id(value: &Value) -> Option<String> {
+	value
+		.as_str()
+		.map(str::to_owned)
+		.or_else(|| value.as_u64().map(|id| id.to_string()))
+}
+
+/// artists of a `getIndexes` response
+fn indexes(response: &Value) -> Vec<Entry> {
+	let indexes = response["indexes"]["index"].as_array();
+	indexes
+		.into_iter()
+		.flatten()
+		.filter_map(|index| index["artist"].as_array())
+		.flatten()
+		.filter_map(|artist| {
+			let id = id(&artist["id"])?;
+			let name = artist["name"].as_str()?.to_owned();
+			Some(Entry::Dir { id, name })
+		})
+		.collect()
+}
+
+/// children of a `getMusicDirectory` response
+fn directory(response: &Value) -> Vec<Entry> {
+	let children = response["directory"]["child"].as_array();
+	children.into_iter().flatten().filter_map(entry).collect()
+}
+
+fn entry(child: &Value) -> Option<Entry> {
+	let id = id(&child["id"])?;
+	let title = child["title"].as_str()?;
+
+	if child["isDir"].as_bool().unwrap_or(false) {
+		let name = title.to_owned();
+		return Some(Entry::Dir { id, name });
+	}
+
+	let number = |key: &str| child[key].as_u64().and_then(|n| u32::try_from(n).ok());
+
+	let mut tag = Tag::new();
+	tag.set_title(title);
+	if let Some(artist) = child["artist"].as_str() {
+		tag.set_artist(artist);
+	}
+	if let Some(album) = child["album"].as_str() {
+		tag.set_album(album);
+	}
+	if let Some(track) = number("track") {
+		tag.set_track(track);
+	}
+	if let Some(disc) = number("discNumber") {
+		tag.set_disc(disc);
+	}
+	if let Some(year) = number("year").and_then(|year| i32::try_from(year).ok()) {
+		tag.set_year(year);
+	}
+	if let Some(duration) = number("duration") {
+		tag.set_duration(duration.saturating_mul(1000));
+	}
+
+	let name = match (number("discNumber"), number("track")) {
+		(Some(disc @ 2..), Some(track)) => format!("{disc}-{track:02} {title}"),
+		(_, Some(track)) => format!("{track:02} {title}"),
+		(_, None) => title.to_owned(),
+	};
+	let name = format!("{}.mp3", file_name(&name));
+
+	Some(Entry::Song { id, name, tag })
+}
+
+/// mirror the directory at `path` below the mirror `root`
+///
+/// creates subdirectories and placeholders for the tracks and returns the subdirectories
+pub fn sync(
+	server: &Subsonic,
+	root: &Utf8Path,
+	path: &Utf8Path,
+) -> Result<Vec<Utf8PathBuf>, RemoteError> {
+	let entries = if path == root {
+		indexes(&request(server, "getIndexes", &[])?)
+	} else {
+		// not a mirrored directory
+		let Ok(id) = fs::read_to_string(path.join(ID_FILE)) else {
+			return Ok(Vec::new());
+		};
+		directory(&request(server, "getMusicDirectory", &[("id", id.trim())])?)
+	};
+
+	let mut dirs = Vec::new();
+	for entry in entries {
+		match entry {
+			Entry::Dir { id, name } => {
+				let dir = path.join(file_name(&name));
+				fs::create_dir_all(&dir)?;
+				fs::write(dir.join(ID_FILE), id)?;
+				dirs.push(dir);
+			}
+			Entry::Song { id, name, tag } => {
+				let file = path.join(name);
+				if !file.exists() {
+					create_placeholder(&file, tag, &format!("subsonic:{id}"))?;
+				}
+			}
+		}
+	}
+
+	Ok(dirs)
+}
+
+#[cfg(test)]
+mod test {
+	use super::{Entry, directory, indexes, response};
+	use crate::remote::RemoteError;
+	use id3::TagLike;
+
+	#[test]
+	fn parse() {
+		let body = br#"{ "subsonic-response": { "status": "ok", "version": "1.16.1",
+			"indexes": { "index": [
+				{ "name": "A", "artist": [{ "id": "ar-1", "name": "ABBA" }] },
+				{ "name": "B", "artist": [{ "id": 2, "name": "Blur" }, { "name": "no id" }] }
+			] }
+		} }"#;
+		let artists = indexes(&response(body).unwrap());
+		assert!(matches!(
+			artists.as_slice(),
+			[Entry::Dir { id: a, name: abba }, Entry::Dir { id: b, name: blur }]
+				if a == "ar-1" && abba == "ABBA" && b == "2" && blur == "Blur"
+		));
+
+		let body = br#"{ "subsonic-response": { "status": "ok",
+			"directory": { "id": "al-1", "name": "Arrival", "child": [
+				{ "id": "al-2", "isDir": true, "title": "Bonus/Live" },
+				{ "id": "so-1", "isDir": false, "title": "Dancing Queen", "artist": "ABBA",
+					"album": "Arrival", "track": 2, "duration": 231, "suffix": "flac" },
+				{ "id": "so-2", "isDir": false, "title": "Tiger", "track": 3, "discNumber": 2 }
+			] }
+		} }"#;
+		let children = directory(&response(body).unwrap());
+		let [
+			Entry::Dir { name: dir, .. },
+			Entry::Song { id, name, tag },
+			Entry::Song { name: disc, .. },
+		] = children.as_slice()
+		else {
+			panic!("unexpected entries {children:?}");
+		};
+		assert_eq!(dir, "Bonus/Live");
+		assert_eq!(id, "so-1");
+		assert_eq!(name, "02 Dancing Queen.mp3");
+		assert_eq!(disc, "2-03 Tiger.mp3");
+		assert_eq!(tag.artist(), Some("ABBA"));
+		assert_eq!(tag.duration(), Some(231_000));
+
+		let body = br#"{ "subsonic-response": { "status": "failed",
+			"error": { "code": 40, "message": "Wrong username or password" }
+		} }"#;
+		assert!(matches!(response(body), Err(RemoteError::Server(40, _))));
+	}
+}
@@ -0,0 +1,100 @@
+//! urls of youtube, bandcamp, soundcloud and everything else supported by `yt-dlp`
+//!
+//! the audio is extracted to an mp3 file, as that is what the players can decode
+
+use super::RemoteError;
+use camino::{Utf8Path, Utf8PathBuf};
+use id3::{Tag, TagLike, Version};
+use serde::Deserialize;
+use std::{
+	io,
+	process::{Command, Stdio},
+};
+
+/// fields printed by yt-dlp once the file is done
+#[derive(Debug, Deserialize, PartialEq, Eq)]
+struct Info {
+	filepath: Utf8PathBuf,
+	title: Option<String>,
+	uploader: Option<String>,
+}
+
+/// download the audio of `url` into `dir` and return the path of the file
+///
+/// the title and uploader are written into the id3 tag, if yt-dlp didn't already
+pub fn download(url: &str, dir: &Utf8Path) -> Result<Utf8PathBuf, RemoteError> {
+	let output = Command::new("yt-dlp")
+		.args([
+			"--no-playlist",
+			"--extract-audio",
+			"--audio-format",
+			"mp3",
+			"--embed-metadata",
+			"--output",
+			"%(id)s.%(ext)s",
+			"--print",
+			"after_move:%(.{filepath,title,uploader})j",
+			"--paths",
+		])
+		.arg(dir)
+		.arg("--")
+		.arg(url)
+		.stdin(Stdio::null())
+		.stdout(Stdio::piped())
+		.stderr(Stdio::piped())
+		.output()?;
+
+	if !output.status.success() {
+		let stderr = String::from_utf8_lossy(&output.stderr);
+		let error = stderr.lines().last().unwrap_or_default();
+		return Err(RemoteError::YtDlp(error.trim().to_owned()));
+	}
+
+	let info = parse(&output.stdout)?;
+
+	let mut tag = Tag::read_from_path(&info.filepath).unwrap_or_default();
+	if tag.title().is_none()
+		&& let Some(title) = &info.title
+	{
+		tag.set_title(title);
+	}
+	if tag.artist().is_none()
+		&& let Some(uploader) = &info.uploader
+	{
+		tag.set_artist(uploader);
+	}
+	tag.write_to_path(&info.filepath, Version::Id3v24)
+		.map_err(io::Error::other)?;
+
+	Ok(info.filepath)
+}
+
+/// parse the last line printed by yt-dlp
+fn parse(stdout: &[u8]) -> Result<Info, RemoteError> {
+	let stdout = String::from_utf8_lossy(stdout);
+	let line = stdout.lines().last().ok_or(RemoteError::Response)?;
+	let info = serde_json::from_str(line)?;
+	Ok(info)
+}
+
+#[cfg(test)]
+mod test {
+	use super::{Info, parse};
+
+	#[test]
+	fn info() {
+		let stdout = b"{\"filepath\": \"/cache/yt-dlp/dQw4w9WgXcQ.mp3\", \"title\": \"Never Gonna Give You Up\", \"uploader\": \"Rick Astley\"}\n";
+		assert_eq!(
+			parse(stdout).unwrap(),
+			Info {
+				filepath: "/cache/yt-dlp/dQw4w9WgXcQ.mp3".into(),
+				title: Some("Never Gonna Give You Up".to_owned()),
+				uploader: Some("Rick Astley".to_owned()),
+			}
+		);
+
+		let stdout = b"{\"filepath\": \"/cache/yt-dlp/1.mp3\", \"title\": \"untitled\", \"uploader\": null}\n";
+		assert_eq!(parse(stdout).unwrap().uploader, None);
+		assert!(parse(b"").is_err());
+	}
+}
@@ -1,29 +1,32 @@
 //! application [`State`]
 
-#[cfg(feature = "mpris")]
-use crate::mpris::{Mpris, MprisUpdate};
 use crate::{
-	config::CONFIG_DIR,
-	player::Player,
-	queue::{Queue, Track},
-	ui::Ui,
+	bus::Event,
+	config::{self, CONFIG_DIR},
+	history::{self, Play},
+	player::{Dropouts, Output, Playable},
+	queue::{Identity, Queue, Repeat, Track},
 };
-use camino::Utf8PathBuf;
+use camino::{Utf8Path, Utf8PathBuf};
 use serde::{Deserialize, Serialize};
 use std::{
 	fs::{self, File},
 	io::{BufWriter, Write},
-	path::PathBuf,
+	path::{Path, PathBuf},
 	sync::LazyLock,
-	time::Duration,
+	time::{Duration, Instant, SystemTime},
 };
 use thiserror::Error;
 
-#[cfg(not(feature = "mpris"))]
-type Mpris = ();
+/// difference between the expected and the actual elapsed time that counts as a seek
+const SEEK_THRESHOLD: Duration = Duration::from_secs(1);
+
+/// number of recently queued lists that are remembered
+const RECENT: usize = 5;
 
 /// path for state file
-static STATE_PATH: LazyLock<PathBuf> = LazyLock::new(|| CONFIG_DIR.join("status.json"));
+static STATE_PATH: LazyLock<PathBuf> =
+	LazyLock::new(|| CONFIG_DIR.join(config::profile_file("status", "json")));
 
 /// state error
 #[derive(Debug, Error)]
@@ -54,6 +57,12 @@ pub struct State {
 	pub paused: bool,
 	/// is muted
 	pub muted: bool,
+	/// stereo balance from -100 (left) to 100 (right)
+	#[serde(default)]
+	pub balance: i8,
+	/// channels are mixed down to mono
+	#[serde(default)]
+	pub mono: bool,
 	/// track time elapsed
 	#[serde(with = "duration")]
 	elapsed: Option<Duration>,
@@ -62,20 +71,88 @@ pub struct State {
 	duration: Option<Duration>,
 	/// [`Queue`] is shuffle
 	pub shuffle: bool,
+	/// [`Repeat`] mode of the [`Queue`]
+	#[serde(default)]
+	pub repeat: Repeat,
 	/// [`Utf8PathBuf`] to queue
 	pub queue: Option<Utf8PathBuf>,
+	/// recently queued directories and playlists, newest first
+	#[serde(default)]
+	pub recent: Vec<Utf8PathBuf>,
 	/// current [`Track`]
 	#[serde(deserialize_with = "Track::maybe_deserialize")]
 	pub track: Option<Track>,
+	/// track list of the [`Queue`], only if it was edited
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub tracks: Option<Vec<Utf8PathBuf>>,
+	/// [`Identity`] of the current track, to restore it if it was moved
+	#[serde(default, skip_serializing_if = "Option::is_none")]
+	pub identity: Option<Identity>,
+	/// output device or current track is still being opened
+	#[serde(skip)]
+	pub loading: bool,
+	/// state of the output stream
+	#[serde(skip)]
+	pub output: Output,
+	/// output is being recorded
+	#[serde(skip)]
+	pub recording: bool,
+	/// tracks checked by the running library scan
+	#[serde(skip)]
+	pub indexing: Option<usize>,
+	/// audio dropouts
+	#[serde(skip)]
+	pub dropouts: Dropouts,
+	/// last track or state change, in words
+	#[serde(skip)]
+	pub announcement: Option<String>,
+	/// announcement not yet spoken
+	#[serde(skip)]
+	speech: Option<String>,
+	/// if the queue has a next track
+	#[serde(skip)]
+	pub can_next: bool,
+	/// if the queue has a previous track
+	#[serde(skip)]
+	pub can_last: bool,
+	/// track played after the current one, see [`Queue::upcoming`]
+	#[serde(skip)]
+	pub upcoming: Option<Track>,
+	/// start of the play of the current track
+	#[serde(skip)]
+	started: Option<SystemTime>,
+	/// time the current track was listened to
+	#[serde(skip)]
+	listened: Duration,
+	/// time of the last tick
+	#[serde(skip)]
+	ticked: Option<Instant>,
 }
 
 impl State {
 	/// read from file and use [`Default::default`] on error
+	///
+	/// falls back to the backup if the file is missing or corrupt
 	pub fn init() -> Self {
-		fs::read_to_string(&*STATE_PATH)
-			.ok()
-			.and_then(|file| serde_json::from_str(&file).ok())
-			.unwrap_or_default()
+		State::read(&STATE_PATH).unwrap_or_default()
+	}
+
+	/// read the state at `path` or its backup
+	fn read(path: &Path) -> Option<Self> {
+		let parse = |path: &Path| -> Result<State, StateError> {
+			let file = fs::read_to_string(path)?;
+			Ok(serde_json::from_str(&file)?)
+		};
+
+		match parse(path) {
+			Ok(state) => Some(state),
+			Err(err) => {
+				let backup = backup(path);
+				let state = parse(&backup).ok()?;
+				log::warn!("couldn't read {path:?} ({err}), restored {backup:?}");
+				Some(state)
+			}
+		}
 	}
 
 	/// time elapsed and duration
@@ -96,65 +173,257 @@ impl State {
 		self.duration
 	}
 
-	/// update self to reflect current application state
-	pub fn tick(&mut self, player: &mut Player, queue: &Queue, ui: &mut Ui, mpris: &mut Mpris) {
-		#[cfg(not(feature = "mpris"))]
-		let _ = mpris;
+	/// playhead in steps of 250ms, the rate long titles scroll at
+	#[inline]
+	pub fn step(&self) -> Option<u128> {
+		self.elapsed.map(|elapsed| elapsed.as_millis() / 250)
+	}
 
+	/// everything the ui displays from the state, used to skip redraws when nothing changed
+	#[allow(clippy::type_complexity)]
+	pub fn frame(
+		&self,
+	) -> (
+		u8,
+		bool,
+		Output,
+		bool,
+		bool,
+		bool,
+		i8,
+		bool,
+		bool,
+		Repeat,
+		Option<u128>,
+		// titles of the seek bar
+		(Dropouts, Option<usize>),
+	) {
+		(
+			self.volume,
+			self.loading,
+			self.output,
+			self.recording,
+			self.paused,
+			self.muted,
+			self.balance,
+			self.mono,
+			self.shuffle,
+			self.repeat,
+			self.step(),
+			(self.dropouts, self.indexing),
+		)
+	}
+
+	/// take the announcement that wasn't spoken yet
+	pub fn take_speech(&mut self) -> Option<String> {
+		self.speech.take()
+	}
+
+	/// update self to reflect current application state
+	///
+	/// returns the [`Event`]s for the integrations, see [`bus::publish`](crate::bus::publish)
+	pub fn tick<P: Playable>(&mut self, player: &mut P, queue: &mut Queue) -> Vec<Event> {
 		player.update();
 
+		let mut events = Vec::new();
+
 		let volume = player.volume();
 		if self.volume != volume {
 			self.volume = volume;
-			#[cfg(feature = "mpris")]
-			mpris.update(MprisUpdate::Volume);
+			events.push(Event::VolumeChanged(volume));
 		}
 
+		let was_paused = self.paused;
 		let paused = player.paused();
 		if self.paused != paused {
 			self.paused = paused;
-			#[cfg(feature = "mpris")]
-			mpris.update(MprisUpdate::PlayerStatus);
+			events.push(Event::Paused(paused));
 		}
 
 		let muted = player.muted();
 		if self.muted != muted {
 			self.muted = muted;
-			#[cfg(feature = "mpris")]
-			mpris.update(MprisUpdate::Volume);
+			events.push(Event::Muted(muted));
 		}
 
+		self.loading = player.loading();
+		self.output = player.output();
+		self.recording = player.recording();
+		#[cfg(feature = "library")]
+		{
+			self.indexing = crate::library::progress();
+		}
+		self.balance = player.balance();
+		self.mono = player.mono();
+
 		self.duration = player.duration();
-		self.elapsed = player.elapsed();
+
+		// the elapsed time of the same track jumping means it was seeked
+		let elapsed = player.elapsed();
+		let expected = self.elapsed.zip(self.ticked).map(|(last, ticked)| {
+			if was_paused {
+				last
+			} else {
+				last.saturating_add(ticked.elapsed())
+			}
+		});
+		if !was_paused && let Some(ticked) = self.ticked {
+			self.listened += ticked.elapsed();
+		}
+
+		let seeked = self.track.as_ref() == queue.track()
+			&& expected
+				.zip(elapsed)
+				.is_some_and(|(expected, elapsed)| expected.abs_diff(elapsed) > SEEK_THRESHOLD);
+		self.elapsed = elapsed;
+		self.ticked = Some(Instant::now());
+
+		if seeked {
+			events.push(Event::Seeked);
+		}
+
+		let dropouts = player.dropouts();
+		if self.dropouts != dropouts {
+			let Dropouts { underruns, stalls } = dropouts;
+			log::warn!("audio dropout ({underruns} underruns, {stalls} stalls)");
+			self.dropouts = dropouts;
+		}
+
+		if let Some((track, duration)) = queue.track().zip(self.duration) {
+			track.cache_duration(duration);
+		}
 
 		let shuffle = queue.is_shuffle();
 		if self.shuffle != shuffle {
 			self.shuffle = shuffle;
-			#[cfg(feature = "mpris")]
-			mpris.update(MprisUpdate::Shuffle);
+			events.push(Event::ShuffleChanged(shuffle));
+		}
+
+		let repeat = queue.repeat();
+		if self.repeat != repeat {
+			self.repeat = repeat;
+			events.push(Event::RepeatChanged(repeat));
+		}
+
+		let (can_next, can_last) = (queue.has_next(), queue.has_last());
+		if (self.can_next, self.can_last) != (can_next, can_last) {
+			self.can_next = can_next;
+			self.can_last = can_last;
+			events.push(Event::ControlsChanged);
+		}
+
+		// picks the next shuffled track early, so it is known while the current one plays
+		let upcoming = queue.upcoming();
+		if self.upcoming.as_ref() != upcoming {
+			self.upcoming = upcoming.cloned();
 		}
 
 		let q = queue.path();
 		if self.queue.as_deref() != q {
-			ui.change_queue(queue);
+			if let Some(q) = q {
+				remember(&mut self.recent, q);
+			}
+
 			self.queue = q.map(ToOwned::to_owned);
+			events.push(Event::QueueChanged);
+		}
+
+		if !queue.is_edited() {
+			self.tracks = None;
+		} else if (self.tracks.as_deref()).is_none_or(|tracks| {
+			!tracks
+				.iter()
+				.map(Utf8PathBuf::as_path)
+				.eq(queue.tracks().iter().map(Track::path))
+		}) {
+			self.tracks = Some(queue.tracks().iter().map(|t| t.path().to_owned()).collect());
+			events.push(Event::TracksEdited);
 		}
 
 		if self.track.as_ref() != queue.track() {
-			ui.change_track(queue);
+			self.record();
 			self.track = queue.track().cloned();
-			#[cfg(feature = "mpris")]
-			mpris.update(MprisUpdate::Metadata);
+			self.identity = self.track.as_ref().map(Track::identity);
+			events.push(Event::TrackChanged);
+		} else if let Some(track) = queue.track()
+			&& self.track.as_ref().map(Track::modified) != Some(track.modified())
+		{
+			self.track = Some(track.clone());
+			self.identity = Some(track.identity());
+			events.push(Event::TagsChanged);
+		}
+
+		if self.track.is_some() && self.started.is_none() {
+			self.started = Some(SystemTime::now());
+		}
+
+		let words = events.iter().filter_map(|&event| self.describe(event));
+		let announcement = words.collect::<Vec<_>>().join(", ");
+		if !announcement.is_empty() {
+			self.speech = Some(announcement.clone());
+			self.announcement = Some(announcement);
+		}
+
+		events
+	}
+
+	/// `event` in words for screen readers
+	fn describe(&self, event: Event) -> Option<String> {
+		let words = match event {
+			Event::VolumeChanged(volume) => format!("volume {volume}%"),
+			Event::Paused(paused) => String::from(if paused { "paused" } else { "playing" }),
+			Event::Muted(muted) => String::from(if muted { "muted" } else { "unmuted" }),
+			Event::ShuffleChanged(shuffle) => {
+				String::from(if shuffle { "shuffle on" } else { "shuffle off" })
+			}
+			Event::RepeatChanged(repeat) => String::from(repeat.name()),
+			Event::TrackChanged => {
+				let track = self.track.as_ref()?;
+				let title = track.title().unwrap_or("unknown title");
+				let artists = track.artists();
+				let artist = match artists.split_last() {
+					None => String::from("unknown artist"),
+					Some((last, [])) => (*last).to_owned(),
+					Some((last, rest)) => format!("{} and {last}", rest.join(", ")),
+				};
+				format!("now playing {title} by {artist}")
+			}
+			Event::TagsChanged
+			| Event::Seeked
+			| Event::QueueChanged
+			| Event::TracksEdited
+			| Event::ControlsChanged => return None,
+		};
+
+		Some(words)
+	}
+
+	/// add the play of the current track to the history
+	pub fn record(&mut self) {
+		let listened = std::mem::take(&mut self.listened);
+		if let Some((track, started)) = self.track.as_ref().zip(self.started.take())
+			&& !listened.is_zero()
+		{
+			history::record(&Play::new(track, started, listened));
+			#[cfg(feature = "library")]
+			crate::library::played(track);
 		}
 	}
 
 	/// write to file
 	pub fn write(&self) -> Result<(), StateError> {
-		let file = if let Ok(file) = File::create(&*STATE_PATH) {
+		self.write_to(&STATE_PATH)
+	}
+
+	/// write to a temporary file and rename it to `path`, so a crash can't leave it half
+	/// written. the previous file is kept as the backup
+	fn write_to(&self, path: &Path) -> Result<(), StateError> {
+		let tmp = path.with_extension("json.tmp");
+		let file = if let Ok(file) = File::create(&tmp) {
 			file
 		} else {
-			fs::create_dir_all(&*CONFIG_DIR)?;
-			File::create(&*STATE_PATH)?
+			fs::create_dir_all(path.parent().unwrap_or(&CONFIG_DIR))?;
+			File::create(&tmp)?
 		};
 		let mut file = BufWriter::new(file);
 
@@ -165,25 +434,64 @@ impl State {
 		writeln!(file)?;
 
 		file.flush()?;
+		file.get_ref().sync_all()?;
+
+		if path.exists() {
+			fs::rename(path, backup(path))?;
+		}
+		fs::rename(&tmp, path)?;
+
 		Ok(())
 	}
 }
 
+/// move `path` to the front of the `recent` lists
+fn remember(recent: &mut Vec<Utf8PathBuf>, path: &Utf8Path) {
+	recent.retain(|recent| recent != path);
+	recent.insert(0, path.to_owned());
+	recent.truncate(RECENT);
+}
+
+/// path of the backup of the state at `path`
+fn backup(path: &Path) -> PathBuf {
+	path.with_extension("json.bak")
+}
+
 impl Default for State {
 	fn default() -> Self {
 		State {
 			volume: 50,
 			paused: true,
 			muted: false,
+			balance: 0,
+			mono: false,
 			elapsed: None,
 			duration: None,
 			shuffle: true,
+			repeat: Repeat::Playlist,
 			queue: None,
+			recent: Vec::new(),
 			track: None,
+			tracks: None,
+			identity: None,
+			loading: false,
+			output: Output::default(),
+			recording: false,
+			indexing: None,
+			dropouts: Dropouts::default(),
+			announcement: None,
+			speech: None,
+			can_next: false,
+			can_last: false,
+			upcoming: None,
+			started: None,
+			listened: Duration::ZERO,
+			ticked: None,
 		}
 	}
 }
 
+/// durations as seconds with millisecond precision, whole seconds of older states are read as well
 mod duration {
 	use serde::{Deserialize, Serialize};
 	use std::time::Duration;
@@ -192,8 +500,10 @@ mod duration {
 	where
 		D: serde::Deserializer<'de>,
 	{
-		let secs = Option::<u64>::deserialize(deserializer)?;
-		let duration = secs.map(Duration::from_secs);
+		let secs = Option::<f64>::deserialize(deserializer)?;
+		let duration = secs
+			.filter(|secs| secs.is_finite() && *secs >= 0.)
+			.map(|secs| Duration::from_millis((secs * 1000.).round() as u64));
 		Ok(duration)
 	}
 
@@ -201,16 +511,21 @@ mod duration {
 	where
 		S: serde::Serializer,
 	{
-		let secs = value.map(|duration| duration.as_secs());
+		let secs = value.map(|duration| duration.as_millis() as f64 / 1000.);
 		secs.serialize(serializer)
 	}
 }
 
 #[cfg(test)]
 pub mod test {
-	use super::State;
-	use crate::queue::{QueueError, Track};
-	use camino::Utf8PathBuf;
+	use super::{RECENT, State, backup, remember};
+	use crate::{
+		bus::Event,
+		player::{Dropouts, Output},
+		queue::{QueueError, Repeat, Track},
+	};
+	use camino::{Utf8Path, Utf8PathBuf};
+	use std::{fs, time::Duration};
 
 	pub fn mock<P: Into<Utf8PathBuf>>(
 		queue: Option<P>,
@@ -223,12 +538,99 @@ pub mod test {
 			volume: 45,
 			paused: true,
 			muted: false,
+			balance: 0,
+			mono: false,
 			elapsed: None,
 			duration: None,
 			queue,
+			recent: Vec::new(),
 			shuffle: true,
+			repeat: Repeat::Playlist,
 			track,
+			tracks: None,
+			identity: None,
+			loading: false,
+			output: Output::default(),
+			recording: false,
+			indexing: None,
+			dropouts: Dropouts::default(),
+			announcement: None,
+			speech: None,
+			can_next: false,
+			can_last: false,
+			upcoming: None,
+			started: None,
+			listened: Duration::ZERO,
+			ticked: None,
 		};
 		Ok(state)
 	}
+
+	#[test]
+	fn recover() {
+		let dir = std::env::temp_dir().join(format!("maym-state-{}", std::process::id()));
+		let path = dir.join("status.json");
+
+		let mut state = mock(Some("mock/list 01"), None).unwrap();
+		state.write_to(&path).unwrap();
+		state.volume = 80;
+		state.elapsed = Some(Duration::from_millis(61_234));
+		state.write_to(&path).unwrap();
+
+		assert!(!path.with_extension("json.tmp").exists());
+		assert_eq!(State::read(&path).unwrap().volume, 80);
+		assert_eq!(
+			State::read(&path).unwrap().elapsed,
+			Some(Duration::from_millis(61_234))
+		);
+		assert_eq!(State::read(&backup(&path)).unwrap().volume, 45);
+
+		// a truncated file falls back to the backup
+		fs::write(&path, "{\n\t\"volume\": 8").unwrap();
+		assert_eq!(State::read(&path).unwrap().volume, 45);
+
+		fs::remove_dir_all(&dir).unwrap();
+	}
+
+	#[test]
+	fn duration() {
+		// older states store whole seconds
+		let mut whole = serde_json::Deserializer::from_str("61");
+		let elapsed = super::duration::deserialize(&mut whole).unwrap();
+		assert_eq!(elapsed, Some(Duration::from_secs(61)));
+
+		let mut millis = serde_json::Deserializer::from_str("61.234");
+		let elapsed = super::duration::deserialize(&mut millis).unwrap();
+		assert_eq!(elapsed, Some(Duration::from_millis(61_234)));
+	}
+
+	#[test]
+	fn recent() {
+		let mut recent = Vec::new();
+		remember(&mut recent, Utf8Path::new("mock/list 01"));
+		remember(&mut recent, Utf8Path::new("mock/list 02"));
+		remember(&mut recent, Utf8Path::new("mock/list 01"));
+		assert_eq!(recent, ["mock/list 01", "mock/list 02"]);
+
+		for idx in 0..10 {
+			remember(&mut recent, Utf8Path::new(&format!("mock/list {idx}")));
+		}
+		assert_eq!(recent.len(), RECENT);
+		assert_eq!(recent[0], "mock/list 9");
+	}
+
+	#[test]
+	fn describe() {
+		let state = mock(None, Some("mock/list 01/track 01.mp3")).unwrap();
+		let title = state.track.as_ref().and_then(Track::title).unwrap();
+
+		let volume = state.describe(Event::VolumeChanged(50));
+		assert_eq!(volume.as_deref(), Some("volume 50%"));
+		let paused = state.describe(Event::Paused(false));
+		assert_eq!(paused.as_deref(), Some("playing"));
+
+		let playing = state.describe(Event::TrackChanged).unwrap();
+		assert!(playing.starts_with(&format!("now playing {title} by ")));
+		assert_eq!(state.describe(Event::Seeked), None);
+	}
 }
@@ -1,17 +1,38 @@
-use self::popup::{Lists, Tracks};
+use self::{
+	finder::Finder,
+	popup::{Lists, Tracks},
+	start::Start,
+};
 use crate::{
-	config::Config,
-	player::Player,
-	queue::{Queue, QueueError},
+	bus::{Event, Subscribe},
+	config::{Config, Layout, List, TimeDisplay},
+	player::Playable,
+	queue::{Queue, QueueError, Track},
 	state::State,
 };
-use ratatui::{Frame, layout::Rect};
-use std::fmt::Debug;
+use camino::{Utf8Path, Utf8PathBuf};
+use ratatui::{
+	Frame,
+	crossterm::event::{KeyCode, KeyEvent, KeyModifiers},
+	layout::{Position, Rect},
+};
+use std::{collections::HashSet, fmt::Debug};
 
+mod finder;
 mod popup;
+mod start;
+mod stats;
 pub mod utils;
 mod window;
 
+/// terminal height below which [`Ui`] switches to the mini layout
+const MINI_HEIGHT: u16 = 12;
+
+/// smallest terminal width that fits the mini layout
+const MIN_WIDTH: u16 = 20;
+/// smallest terminal height that fits the mini layout
+const MIN_HEIGHT: u16 = 2;
+
 trait Popup {
 	fn draw(&mut self, frame: &mut Frame, area: Rect, queue: &Queue);
 
@@ -21,6 +42,30 @@ trait Popup {
 		let _ = queue;
 	}
 
+	fn change_recent(&mut self, recent: &[Utf8PathBuf]) {
+		let _ = recent;
+	}
+
+	fn change_lists(&mut self, lists: &[List]) {
+		let _ = lists;
+	}
+
+	/// path of the selected root list
+	fn root(&self) -> Option<&Utf8Path> {
+		None
+	}
+
+	/// path of the selected track, to be previewed
+	fn selected_track(&self, queue: &Queue) -> Option<Utf8PathBuf> {
+		let _ = queue;
+		None
+	}
+
+	/// receive results of background work, returns if the popup has to be redrawn
+	fn poll(&mut self) -> bool {
+		false
+	}
+
 	fn up(&mut self);
 
 	fn down(&mut self);
@@ -35,16 +80,40 @@ trait Popup {
 
 	fn pg_down(&mut self) {}
 
+	fn half_up(&mut self) {}
+
+	fn half_down(&mut self) {}
+
+	fn search(&mut self, query: Option<String>) {
+		let _ = query;
+	}
+
+	fn next_match(&mut self) {}
+
+	fn prev_match(&mut self) {}
+
 	fn home(&mut self) {}
 
 	fn end(&mut self) {}
 
-	fn enter(&mut self, player: &mut Player, queue: &mut Queue) -> Result<(), QueueError> {
+	fn delete(&mut self, queue: &mut Queue) {
+		let _ = queue;
+	}
+
+	fn move_up(&mut self, queue: &mut Queue) {
+		let _ = queue;
+	}
+
+	fn move_down(&mut self, queue: &mut Queue) {
+		let _ = queue;
+	}
+
+	fn enter(&mut self, player: &mut dyn Playable, queue: &mut Queue) -> Result<(), QueueError> {
 		let _ = (player, queue);
 		Ok(())
 	}
 
-	fn space(&mut self, player: &mut Player, queue: &mut Queue) -> Result<(), QueueError> {
+	fn space(&mut self, player: &mut dyn Playable, queue: &mut Queue) -> Result<(), QueueError> {
 		let _ = (player, queue);
 		Ok(())
 	}
@@ -56,11 +125,72 @@ enum PopupType {
 	Lyrics = 1,
 	Tracks = 2,
 	Lists = 3,
+	Log = 4,
+	Party = 5,
+}
+
+/// popups cycled through with tab and shift+tab
+const TABS: [PopupType; 4] = [
+	PopupType::Tags,
+	PopupType::Lyrics,
+	PopupType::Tracks,
+	PopupType::Lists,
+];
+
+/// what the text entered into the prompt is used for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Prompt {
+	/// a [`Filter`](crate::queue::Filter) of the queue, opened with `F`
+	Filter,
+	/// a [`Control`](crate::control::Control) of the command palette, opened with `:`
+	Command,
+	/// text to find in the open text popup, opened with `/`
+	Search,
+	/// directory to add to the root lists, opened with `a` in the lists popup
+	List,
+}
+
+/// region of the [`Ui`] under the mouse pointer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hover {
+	/// the open popup
+	Popup,
+	/// the seek bar
+	Seek,
+	/// anything else
+	None,
 }
 
 pub struct Ui {
-	popups: [Box<dyn Popup>; 4],
+	popups: [Box<dyn Popup>; 6],
 	popup: Option<PopupType>,
+	popup_size: (u16, u16),
+	time: TimeDisplay,
+	mini: bool,
+	/// area of the popup when last drawn
+	popup_area: Option<Rect>,
+	/// area of the seek bar when last drawn
+	seek_area: Rect,
+	/// text entered into the prompt, if it's open
+	prompt: Option<(Prompt, String)>,
+	/// show announcements on the last line
+	announce: bool,
+	/// fuzzy finder, if it's open
+	finder: Option<Finder>,
+	/// root lists, searched by the finder in library scope
+	roots: Vec<Utf8PathBuf>,
+	/// every track of the [`Ui::roots`], loaded when the finder first searches the library
+	library: Option<Vec<Track>>,
+	/// start screen, until a track is played
+	start: Option<Start>,
+	/// kiosk mode, the lists, the library and the start screen are hidden
+	kiosk: bool,
+	/// show the next tracks in a panel on the right
+	sidebar: bool,
+	/// show the browser in a permanent pane on the left, see [`Layout::Split`]
+	split: bool,
+	/// browser shown in the pane, the tracks or the lists popup
+	browser: PopupType,
 }
 
 impl Debug for Ui {
@@ -68,50 +198,214 @@ impl Debug for Ui {
 		f.debug_struct("Ui")
 			.field("popups", &[..])
 			.field("popup", &self.popup)
+			.field("time", &self.time)
+			.field("mini", &self.mini)
+			.field("popup_area", &self.popup_area)
+			.field("seek_area", &self.seek_area)
+			.field("prompt", &self.prompt)
+			.field("announce", &self.announce)
+			.field("finder", &self.finder)
+			.field("roots", &self.roots)
+			.field("library", &self.library.as_ref().map(Vec::len))
+			.field("start", &self.start)
+			.field("kiosk", &self.kiosk)
+			.field("sidebar", &self.sidebar)
+			.field("split", &self.split)
+			.field("browser", &self.browser)
 			.finish()
 	}
 }
 
+impl Subscribe for Ui {
+	fn notify(&mut self, event: Event, state: &State, queue: &Queue) {
+		match event {
+			Event::TrackChanged | Event::TagsChanged => self.change_track(queue),
+			Event::QueueChanged => {
+				self.change_queue(queue);
+				self.change_recent(&state.recent);
+			}
+			_ => {}
+		}
+	}
+}
+
 impl Ui {
 	pub fn new(queue: &Queue, config: &Config) -> Self {
 		Ui {
 			popups: [
 				Box::new(self::popup::tags()),
 				Box::new(self::popup::lyrics()),
-				Box::new(Tracks::new(queue)),
+				Box::new(Tracks::new(queue, config.bpm_column())),
 				Box::new(Lists::new(config, queue)),
+				Box::new(self::popup::log()),
+				Box::new(self::popup::party()),
 			],
 			popup: None,
+			popup_size: config.popup_size(),
+			time: config.time(),
+			mini: false,
+			popup_area: None,
+			seek_area: Rect::default(),
+			prompt: None,
+			announce: config.announce(),
+			finder: None,
+			roots: config.roots(),
+			library: None,
+			start: (queue.track().is_none())
+				.then(Start::new)
+				.filter(|start| !start.is_empty()),
+			kiosk: false,
+			sidebar: false,
+			split: config.layout() == Layout::Split,
+			browser: PopupType::Tracks,
 		}
 	}
 
-	#[cfg(feature = "mpris")]
+	/// hide everything that would change the queue, for kiosk mode
+	pub fn lock(&mut self) {
+		self.kiosk = true;
+		self.start = None;
+	}
+
+	#[cfg(mpris)]
 	pub fn draw_lock(&mut self, frame: &mut Frame, state: &std::sync::Mutex<State>, queue: &Queue) {
 		let state = state.lock().unwrap();
 		self.draw(frame, &state, queue);
 	}
 
 	pub fn draw(&mut self, frame: &mut Frame, state: &State, queue: &Queue) {
-		let size = frame.area();
-		let (window, seek) = window::layout(size);
+		let area = frame.area();
+		if area.width < MIN_WIDTH || area.height < MIN_HEIGHT {
+			window::too_small(frame, area, (MIN_WIDTH, MIN_HEIGHT));
+			self.popup_area = None;
+			self.seek_area = Rect::default();
+			return;
+		}
 
-		window::main(frame, window, state);
-		window::seek(frame, seek, state);
+		if state.track.is_some() {
+			self.start = None;
+		}
 
-		if let Some(popup) = self.popup {
-			let area = window::popup(window);
+		let size = if self.announce {
+			let (size, line) = window::announce_layout(frame.area());
+			window::announcement(frame, line, state);
+			size
+		} else {
+			frame.area()
+		};
+
+		let (window, pane) = if self.mini || size.height < MINI_HEIGHT {
+			window::mini(frame, size, state, self.time);
+			self.seek_area = size;
+			(size, None)
+		} else {
+			let (size, pane) = if self.split {
+				window::split_layout(size)
+			} else {
+				(size, None)
+			};
+
+			let (size, sidebar) = if self.sidebar {
+				window::sidebar_layout(size)
+			} else {
+				(size, None)
+			};
+			if let Some(sidebar) = sidebar {
+				window::up_next(frame, sidebar, queue);
+			}
+
+			let (window, seek) = window::layout(size);
+
+			if let Some(start) = &self.start {
+				start.draw(frame, window);
+			} else {
+				window::main(frame, window, state);
+			}
+			window::seek(frame, seek, state, self.time);
+			self.seek_area = seek;
+			(window, pane)
+		};
+
+		// the browser stays in its pane, even while another popup is open
+		if let Some(pane) = pane
+			&& self.popup != Some(self.browser)
+		{
+			self.popups[self.browser as usize].draw(frame, pane, queue);
+		}
+
+		self.popup_area = self.popup.map(|popup| {
+			let area = match pane {
+				Some(pane) if popup == self.browser => pane,
+				_ => window::popup(window, self.popup_size),
+			};
 			self.popups[popup as usize].draw(frame, area, queue);
+			area
+		});
+
+		if let Some((prompt, text)) = &self.prompt {
+			let title = match prompt {
+				Prompt::Filter => " filter ",
+				Prompt::Command => " command ",
+				Prompt::Search => " search ",
+				Prompt::List => " add list ",
+			};
+			window::prompt(frame, window, title, text);
+		}
+
+		if let Some(finder) = &mut self.finder {
+			let area = window::popup(window, self.popup_size);
+			let tracks = match &self.library {
+				Some(library) if finder.library => library,
+				_ => queue.tracks(),
+			};
+			finder.draw(frame, area, tracks, queue);
 		}
 	}
 
+	/// return the region under the mouse pointer at `column` and `row`
+	pub fn hover(&self, column: u16, row: u16) -> Hover {
+		let position = Position::new(column, row);
+		if self.popup_area.is_some_and(|area| area.contains(position)) {
+			Hover::Popup
+		} else if self.seek_area.contains(position) {
+			Hover::Seek
+		} else {
+			Hover::None
+		}
+	}
+
+	pub fn cycle_time(&mut self) {
+		self.time = self.time.next();
+	}
+
+	pub fn toggle_mini(&mut self) {
+		self.mini = !self.mini;
+	}
+
+	pub fn toggle_sidebar(&mut self) {
+		self.sidebar = !self.sidebar;
+	}
+
 	pub fn is_popup(&self) -> bool {
 		self.popup.is_some()
 	}
 
+	pub fn is_lists(&self) -> bool {
+		self.popup == Some(PopupType::Lists)
+	}
+
 	pub fn is_selectable(&self) -> bool {
 		matches!(self.popup, Some(PopupType::Tracks | PopupType::Lists))
 	}
 
+	/// returns if the open popup is text that can be searched
+	pub fn is_searchable(&self) -> bool {
+		matches!(
+			self.popup,
+			Some(PopupType::Tags | PopupType::Lyrics | PopupType::Log | PopupType::Party)
+		)
+	}
+
 	pub fn change_track(&mut self, queue: &Queue) {
 		for (idx, popup) in self.popups.iter_mut().enumerate() {
 			let active = self.popup.is_some_and(|popup| popup as usize == idx);
@@ -125,12 +419,72 @@ impl Ui {
 		}
 	}
 
+	/// returns if the open popup received something from the background and has to be redrawn
+	pub fn poll(&mut self) -> bool {
+		let polled = self.split && self.popups[self.browser as usize].poll();
+		let Some(popup) = self
+			.popup
+			.filter(|&popup| !self.split || popup != self.browser)
+		else {
+			return polled;
+		};
+		self.popups[popup as usize].poll() || polled
+	}
+
+	/// update the root lists after they were changed in the [`Config`]
+	pub fn change_lists(&mut self, lists: &[List]) {
+		self.popups[PopupType::Lists as usize].change_lists(lists);
+		self.roots = lists.iter().map(|list| list.path.clone()).collect();
+		self.library = None;
+	}
+
+	/// path of the root list selected in the lists popup
+	pub fn root(&self) -> Option<Utf8PathBuf> {
+		let popup = self.popup.filter(|&popup| popup == PopupType::Lists)?;
+		self.popups[popup as usize].root().map(ToOwned::to_owned)
+	}
+
+	/// path of the track selected in the tracks or lists popup
+	pub fn selected_track(&self, queue: &Queue) -> Option<Utf8PathBuf> {
+		let popup = self.popup.filter(|_| self.is_selectable())?;
+		self.popups[popup as usize].selected_track(queue)
+	}
+
+	/// update the recently queued lists shown in the lists popup
+	pub fn change_recent(&mut self, recent: &[Utf8PathBuf]) {
+		self.popups[PopupType::Lists as usize].change_recent(recent);
+	}
+
 	fn toggle(&mut self, popup: PopupType) {
 		if self.popup == Some(popup) {
 			self.popup = None;
 		} else {
 			self.popup = Some(popup);
 		}
+
+		if matches!(popup, PopupType::Tracks | PopupType::Lists) {
+			self.browser = popup;
+		}
+	}
+
+	/// switch the open popup to the next one of [`TABS`], or the previous one if not `forward`
+	///
+	/// the popups keep their scroll and selection
+	pub fn cycle(&mut self, forward: bool) {
+		let Some(idx) = self
+			.popup
+			.and_then(|popup| TABS.iter().position(|&tab| tab == popup))
+		else {
+			return;
+		};
+
+		let tabs = if self.kiosk { &TABS[..3] } else { &TABS[..] };
+		let idx = if forward {
+			(idx + 1) % tabs.len()
+		} else {
+			(idx + tabs.len() - 1) % tabs.len()
+		};
+		self.toggle(tabs[idx]);
 	}
 
 	pub fn tags(&mut self) {
@@ -146,17 +500,33 @@ impl Ui {
 	}
 
 	pub fn lists(&mut self) {
-		self.toggle(PopupType::Lists);
+		if !self.kiosk {
+			self.toggle(PopupType::Lists);
+		}
+	}
+
+	pub fn log(&mut self) {
+		self.toggle(PopupType::Log);
+	}
+
+	pub fn party(&mut self) {
+		self.toggle(PopupType::Party);
 	}
 
 	pub fn up(&mut self) {
-		let Some(popup) = self.popup else { return };
-		self.popups[popup as usize].up();
+		if let Some(popup) = self.popup {
+			self.popups[popup as usize].up();
+		} else if let Some(start) = &mut self.start {
+			start.up();
+		}
 	}
 
 	pub fn down(&mut self) {
-		let Some(popup) = self.popup else { return };
-		self.popups[popup as usize].down();
+		if let Some(popup) = self.popup {
+			self.popups[popup as usize].down();
+		} else if let Some(start) = &mut self.start {
+			start.down();
+		}
 	}
 
 	pub fn left(&mut self) {
@@ -169,6 +539,21 @@ impl Ui {
 		self.popups[popup as usize].right(queue);
 	}
 
+	pub fn delete(&mut self, queue: &mut Queue) {
+		let Some(popup) = self.popup else { return };
+		self.popups[popup as usize].delete(queue);
+	}
+
+	pub fn move_up(&mut self, queue: &mut Queue) {
+		let Some(popup) = self.popup else { return };
+		self.popups[popup as usize].move_up(queue);
+	}
+
+	pub fn move_down(&mut self, queue: &mut Queue) {
+		let Some(popup) = self.popup else { return };
+		self.popups[popup as usize].move_down(queue);
+	}
+
 	pub fn pg_up(&mut self) {
 		let Some(popup) = self.popup else { return };
 		self.popups[popup as usize].pg_up();
@@ -179,6 +564,32 @@ impl Ui {
 		self.popups[popup as usize].pg_down();
 	}
 
+	/// search the open popup for `query`, or clear the search if it's empty
+	pub fn search(&mut self, query: String) {
+		let Some(popup) = self.popup else { return };
+		self.popups[popup as usize].search(Some(query));
+	}
+
+	pub fn next_match(&mut self) {
+		let Some(popup) = self.popup else { return };
+		self.popups[popup as usize].next_match();
+	}
+
+	pub fn prev_match(&mut self) {
+		let Some(popup) = self.popup else { return };
+		self.popups[popup as usize].prev_match();
+	}
+
+	pub fn half_up(&mut self) {
+		let Some(popup) = self.popup else { return };
+		self.popups[popup as usize].half_up();
+	}
+
+	pub fn half_down(&mut self) {
+		let Some(popup) = self.popup else { return };
+		self.popups[popup as usize].half_down();
+	}
+
 	pub fn home(&mut self) {
 		let Some(popup) = self.popup else { return };
 		self.popups[popup as usize].home();
@@ -189,15 +600,25 @@ impl Ui {
 		self.popups[popup as usize].end();
 	}
 
-	pub fn enter(&mut self, player: &mut Player, queue: &mut Queue) -> Result<(), QueueError> {
+	pub fn enter(
+		&mut self,
+		player: &mut dyn Playable,
+		queue: &mut Queue,
+	) -> Result<(), QueueError> {
 		if let Some(popup) = self.popup {
 			self.popups[popup as usize].enter(player, queue)
+		} else if let Some(start) = self.start.take() {
+			start.enter(player, queue)
 		} else {
 			Ok(())
 		}
 	}
 
-	pub fn space(&mut self, player: &mut Player, queue: &mut Queue) -> Result<(), QueueError> {
+	pub fn space(
+		&mut self,
+		player: &mut dyn Playable,
+		queue: &mut Queue,
+	) -> Result<(), QueueError> {
 		if let Some(popup) = self.popup {
 			self.popups[popup as usize].space(player, queue)
 		} else {
@@ -208,4 +629,130 @@ impl Ui {
 	pub fn esc(&mut self) {
 		self.popup = None;
 	}
+
+	/// open the `prompt`
+	pub fn prompt(&mut self, prompt: Prompt) {
+		self.prompt = Some((prompt, String::new()));
+	}
+
+	pub fn is_prompt(&self) -> bool {
+		self.prompt.is_some()
+	}
+
+	/// edit the open prompt with `key`
+	///
+	/// returns the entered text once it's submitted with enter
+	pub fn prompt_key(&mut self, key: KeyCode) -> Option<(Prompt, String)> {
+		let (_, text) = self.prompt.as_mut()?;
+		match key {
+			KeyCode::Char(ch) => text.push(ch),
+			KeyCode::Backspace => {
+				text.pop();
+			}
+			KeyCode::Esc => self.prompt = None,
+			KeyCode::Enter => return self.prompt.take(),
+			_ => {}
+		}
+
+		None
+	}
+
+	/// open the fuzzy finder over the tracks of the queue
+	pub fn finder(&mut self, queue: &Queue) {
+		self.finder = Some(Finder::new(queue.tracks()));
+	}
+
+	pub fn is_finder(&self) -> bool {
+		self.finder.is_some()
+	}
+
+	/// every track of the root lists, walked once and cached
+	///
+	/// read from the index instead, if the library is indexed
+	fn library(&mut self) -> &[Track] {
+		self.library.get_or_insert_with(|| {
+			#[cfg(feature = "library")]
+			if let Some(tracks) = crate::library::tracks() {
+				return tracks;
+			}
+
+			let mut seen = HashSet::new();
+			let mut tracks = self
+				.roots
+				.iter()
+				.filter_map(|root| Track::directory(root).ok())
+				.flatten()
+				.filter(|track| seen.insert(track.path().to_owned()))
+				.collect::<Vec<_>>();
+			tracks.sort_by(|a, b| a.path().cmp(b.path()));
+			tracks
+		})
+	}
+
+	/// edit the open finder with `key`
+	///
+	/// tab switches between the queue and the whole library, enter jumps to the selected
+	/// track and queues its directory if it isn't in the queue
+	pub fn finder_key(
+		&mut self,
+		key: KeyEvent,
+		player: &mut dyn Playable,
+		queue: &mut Queue,
+	) -> Result<(), QueueError> {
+		let Some(mut finder) = self.finder.take() else {
+			return Ok(());
+		};
+
+		match (key.code, key.modifiers) {
+			(KeyCode::Esc, _) | (KeyCode::Char('c'), KeyModifiers::CONTROL) => return Ok(()),
+			(KeyCode::Up, _) | (KeyCode::Char('p'), KeyModifiers::CONTROL) => finder.up(),
+			(KeyCode::Down, _) | (KeyCode::Char('n'), KeyModifiers::CONTROL) => finder.down(),
+			(KeyCode::Tab, _) if !self.kiosk => {
+				finder.library = !finder.library;
+				let tracks = if finder.library {
+					self.library()
+				} else {
+					queue.tracks()
+				};
+				finder.search(tracks);
+			}
+			(KeyCode::Backspace, _) => {
+				let tracks = if finder.library {
+					self.library()
+				} else {
+					queue.tracks()
+				};
+				finder.pop(tracks);
+			}
+			(KeyCode::Char(ch), KeyModifiers::NONE | KeyModifiers::SHIFT) => {
+				let tracks = if finder.library {
+					self.library()
+				} else {
+					queue.tracks()
+				};
+				finder.push(ch, tracks);
+			}
+			(KeyCode::Enter, _) => {
+				let Some(idx) = finder.selected() else {
+					return Ok(());
+				};
+
+				if !finder.library {
+					return queue.select_idx(idx, player);
+				}
+
+				let path = self.library()[idx].path().to_owned();
+				if !queue.tracks().iter().any(|track| track.path() == path)
+					&& let Some(dir) = path.parent()
+				{
+					queue.queue(dir)?;
+				}
+				return queue.select_path(&path, player);
+			}
+			_ => {}
+		}
+
+		self.finder = Some(finder);
+		Ok(())
+	}
 }
@@ -0,0 +1,226 @@
+//! fuzzy finder to jump to a track of the queue or the library, opened with ctrl+p
+//!
+//! terms like `genre:rock` only keep the tracks of that genre
+
+use super::utils;
+use crate::queue::{Filter, Queue, Track};
+use ratatui::{
+	Frame,
+	layout::{Alignment, Rect},
+	text::{Line, Span},
+	widgets::{Block, Clear, List as ListWidget, ListItem, ListState, Paragraph},
+};
+use std::cmp::Reverse;
+use unicode_width::UnicodeWidthStr;
+
+#[derive(Debug)]
+pub struct Finder {
+	/// text typed so far
+	query: String,
+	/// search the whole library instead of the queue
+	pub library: bool,
+	/// indices of the matching tracks, best match first
+	matches: Vec<usize>,
+	state: ListState,
+}
+
+impl Finder {
+	pub fn new(tracks: &[Track]) -> Self {
+		let mut finder = Finder {
+			query: String::new(),
+			library: false,
+			matches: Vec::new(),
+			state: ListState::default(),
+		};
+		finder.search(tracks);
+		finder
+	}
+
+	/// index of the selected track
+	pub fn selected(&self) -> Option<usize> {
+		let selected = self.state.selected()?;
+		self.matches.get(selected).copied()
+	}
+
+	pub fn push(&mut self, ch: char, tracks: &[Track]) {
+		self.query.push(ch);
+		self.search(tracks);
+	}
+
+	pub fn pop(&mut self, tracks: &[Track]) {
+		self.query.pop();
+		self.search(tracks);
+	}
+
+	pub fn up(&mut self) {
+		self.state.select_previous();
+	}
+
+	pub fn down(&mut self) {
+		self.state.select_next();
+	}
+
+	/// match `tracks` against the query again and select the best match
+	pub fn search(&mut self, tracks: &[Track]) {
+		let (genres, query) = split_genres(&self.query);
+		let query = query.to_lowercase();
+
+		let mut matches = tracks
+			.iter()
+			.enumerate()
+			.filter(|(_, track)| genres.iter().all(|genre| genre.matches(track)))
+			.filter_map(|(index, track)| {
+				let name = track.path().file_stem().unwrap_or_default();
+				let haystack = format!("{track} {name}").to_lowercase();
+				score(&query, &haystack).map(|score| (index, score))
+			})
+			.collect::<Vec<_>>();
+		matches.sort_by_key(|&(index, score)| (Reverse(score), index));
+
+		self.matches = matches.into_iter().map(|(index, _)| index).collect();
+		self.state = ListState::default().with_selected((!self.matches.is_empty()).then_some(0));
+	}
+
+	pub fn draw(&mut self, frame: &mut Frame, area: Rect, tracks: &[Track], queue: &Queue) {
+		let title = if self.library {
+			" jump to track (library) "
+		} else {
+			" jump to track "
+		};
+		let block = utils::popup::block().title(title);
+		let inner = block.inner(area);
+		let (prompt_area, list_area) = utils::popup::double_layout(inner);
+
+		frame.render_widget(Clear, area);
+		frame.render_widget(block, area);
+
+		let width = usize::from(prompt_area.width.saturating_sub(3));
+		let query = utils::widgets::truncate_start(&self.query, width);
+		let count = format!("{}/{}", self.matches.len(), tracks.len());
+		let line = Line::from(vec![
+			Span::styled("> ", utils::style::accent()),
+			Span::raw(&*query),
+		]);
+		frame.render_widget(Paragraph::new(line), prompt_area);
+		let count = utils::widgets::line(count, utils::style::dim());
+		frame.render_widget(
+			Paragraph::new(count).alignment(Alignment::Right),
+			prompt_area,
+		);
+
+		let cursor = 2 + u16::try_from(query.width()).unwrap_or(u16::MAX);
+		frame.set_cursor_position((prompt_area.x + cursor.min(prompt_area.width), prompt_area.y));
+
+		let width = usize::from(list_area.width);
+		let items = self
+			.matches
+			.iter()
+			.map(|&index| ListItem::new(tracks[index].line(queue, width)))
+			.collect::<Vec<_>>();
+		let list = ListWidget::new(items)
+			.block(Block::default())
+			.style(utils::style::dim())
+			.highlight_style(utils::style::selection());
+		frame.render_stateful_widget(list, list_area, &mut self.state);
+
+		let page = usize::from(list_area.height);
+		let max_offset = self.matches.len().saturating_sub(page);
+		utils::popup::scrollbar(frame, area, max_offset, page, self.state.offset());
+	}
+}
+
+/// split the `genre:` terms off `query`, the rest is matched fuzzily
+///
+/// a term without a genre yet is dropped, so the matches don't vanish while it is typed
+fn split_genres(query: &str) -> (Vec<Filter>, String) {
+	let mut genres = Vec::new();
+	let mut rest = Vec::new();
+
+	for term in query.split_whitespace() {
+		match term.split_once(':') {
+			Some((key, genre)) if key.eq_ignore_ascii_case("genre") => {
+				if !genre.is_empty() {
+					genres.push(Filter::Genre(genre.to_owned()));
+				}
+			}
+			_ => rest.push(term),
+		}
+	}
+
+	(genres, rest.join(" "))
+}
+
+/// score how well the lowercase `query` matches `haystack`, like fzf
+///
+/// every character of the query has to appear in order. consecutive characters and
+/// characters at the start of a word score higher, gaps between them score lower.
+/// returns [`None`] if it doesn't match
+fn score(query: &str, haystack: &str) -> Option<i64> {
+	let query = query
+		.chars()
+		.filter(|ch| !ch.is_whitespace())
+		.collect::<Vec<_>>();
+	let haystack = haystack.chars().collect::<Vec<_>>();
+
+	let Some(&first) = query.first() else {
+		return Some(0);
+	};
+
+	// greedily match from every occurrence of the first character and keep the best
+	(0..haystack.len())
+		.filter(|&start| haystack[start] == first)
+		.filter_map(|start| score_from(&query, &haystack, start))
+		.max()
+}
+
+fn score_from(query: &[char], haystack: &[char], start: usize) -> Option<i64> {
+	let mut query = query.iter().peekable();
+
+	let mut score = 0;
+	let mut last = None::<usize>;
+	for (pos, &ch) in haystack.iter().enumerate().skip(start) {
+		if query.peek() != Some(&&ch) {
+			continue;
+		}
+
+		query.next();
+		score += 16;
+		if pos == 0 || !haystack[pos - 1].is_alphanumeric() {
+			score += 8;
+		}
+		match last {
+			Some(last) if last + 1 == pos => score += 8,
+			Some(last) => score -= (pos - last).min(16) as i64,
+			None => {}
+		}
+		last = Some(pos);
+	}
+
+	query.peek().is_none().then_some(score)
+}
+
+#[cfg(test)]
+mod test {
+	use super::{score, split_genres};
+	use crate::queue::Filter;
+
+	#[test]
+	fn fuzzy() {
+		assert_eq!(score("", "anything"), Some(0));
+		assert_eq!(score("xyz", "dancing queen ~ abba"), None);
+		assert_eq!(score("qa", "abba ~ dancing queen"), None);
+		assert!(score("dq", "dancing queen ~ abba").is_some());
+
+		// word starts and consecutive characters rank higher
+		assert!(score("queen", "dancing queen") > score("queen", "quiet evening"));
+		assert!(score("dq", "dancing queen") > score("dq", "odd quirk"));
+		assert!(score("abba", "dancing queen ~ abba") > score("abba", "a bad bank"));
+	}
+
+	#[test]
+	fn genres() {
+		let (genres, query) = split_genres("queen Genre:rock genre: abba");
+		assert_eq!(genres, [Filter::Genre("rock".to_owned())]);
+		assert_eq!(query, "queen abba");
+	}
+}
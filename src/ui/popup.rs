@@ -1,16 +1,27 @@
-use super::{Popup, utils};
+use super::{
+	Popup,
+	stats::{Stat, Stats},
+	utils,
+};
 use crate::{
 	config::{Child, Config, List},
-	player::Player,
+	favorites::Favorites,
+	logger,
+	player::Playable,
+	playlist,
 	queue::{Queue, QueueError},
+	remote,
 };
+use camino::{Utf8Path, Utf8PathBuf};
+use log::Level;
 use ratatui::{
 	Frame,
-	layout::Rect,
+	layout::{Alignment, Rect},
 	style::{Modifier, Style},
-	text::Line,
+	text::{Line, Span},
 	widgets::{Block, Clear, List as ListWidget, ListItem, ListState, Paragraph},
 };
+use unicode_width::UnicodeWidthStr;
 
 #[derive(Debug)]
 pub struct TextPopup {
@@ -18,6 +29,16 @@ pub struct TextPopup {
 	title: &'static str,
 	scroll: u16,
 	max_scroll: u16,
+	/// height of the text area when last drawn
+	page: u16,
+	/// text searched for with `/`
+	query: Option<String>,
+	/// lines containing the query
+	matches: Vec<u16>,
+	/// index of the selected match in [`TextPopup::matches`]
+	current: Option<usize>,
+	/// jump to the first match once the lines are searched
+	jump: bool,
 }
 
 impl TextPopup {
@@ -27,6 +48,11 @@ impl TextPopup {
 			title,
 			scroll: 0,
 			max_scroll: 0,
+			page: 0,
+			query: None,
+			matches: Vec::new(),
+			current: None,
+			jump: false,
 		}
 	}
 
@@ -36,32 +62,138 @@ impl TextPopup {
 
 		self.max_scroll = lines.saturating_sub(height);
 		self.scroll = self.scroll.clamp(0, self.max_scroll);
+		self.page = height;
+	}
+
+	/// scroll up by `amt` lines
+	fn scroll_up(&mut self, amt: u16) {
+		self.scroll = self.scroll.saturating_sub(amt);
+	}
+
+	/// scroll down by `amt` lines, stopping at [`TextPopup::max_scroll`]
+	fn scroll_down(&mut self, amt: u16) {
+		self.scroll = self.scroll.saturating_add(amt).min(self.max_scroll);
+	}
+
+	/// find the lines containing the query and highlight the matches
+	fn find<'a>(&mut self, lines: Vec<Line<'a>>) -> Vec<Line<'a>> {
+		let Some(query) = self.query.clone() else {
+			return lines;
+		};
+
+		self.matches = (lines.iter().enumerate())
+			.filter(|(_, line)| !utils::widgets::matches(&line.to_string(), &query).is_empty())
+			.map(|(idx, _)| u16::try_from(idx).unwrap_or(u16::MAX))
+			.collect();
+
+		if std::mem::take(&mut self.jump) {
+			let after = self.matches.iter().position(|&line| line >= self.scroll);
+			self.current = after.or((!self.matches.is_empty()).then_some(0));
+			self.scroll_to_match();
+		}
+
+		let current = self.current.and_then(|idx| self.matches.get(idx)).copied();
+		(lines.into_iter().enumerate())
+			.map(|(idx, line)| {
+				let style = if current.is_some_and(|current| usize::from(current) == idx) {
+					utils::style::accent().add_modifier(Modifier::REVERSED)
+				} else {
+					Style::new().add_modifier(Modifier::REVERSED)
+				};
+				utils::widgets::highlight(line, &query, style)
+			})
+			.collect()
+	}
+
+	/// scroll the selected match into view, with a bit of context above it
+	fn scroll_to_match(&mut self) {
+		if let Some(&line) = self.current.and_then(|idx| self.matches.get(idx)) {
+			self.scroll = line.saturating_sub(2);
+		}
 	}
 }
 
 impl Popup for TextPopup {
 	fn draw(&mut self, frame: &mut Frame, area: Rect, queue: &Queue) {
-		let block = utils::popup::block().title(self.title);
+		let mut block = utils::popup::block().title(self.title);
 		let list = (self.inner)(queue);
+		let list = self.find(list);
+
+		if self.query.is_some() {
+			let found = match self.current {
+				Some(current) => format!(" {}/{} ", current + 1, self.matches.len()),
+				None => " no matches ".to_owned(),
+			};
+			block = block.title_bottom(Line::from(found).right_aligned());
+		}
 
 		self.update_scroll(area, &list);
 
+		let page = usize::from(block.inner(area).height);
 		let par = Paragraph::new(list).block(block).scroll((self.scroll, 0));
 
 		frame.render_widget(Clear, area);
 		frame.render_widget(par, area);
+
+		let (max_scroll, scroll) = (usize::from(self.max_scroll), usize::from(self.scroll));
+		utils::popup::scrollbar(frame, area, max_scroll, page, scroll);
 	}
 
 	fn change_track(&mut self, _active: bool, _queue: &Queue) {
 		self.scroll = 0;
+		self.search(None);
+	}
+
+	fn search(&mut self, query: Option<String>) {
+		self.query = query.filter(|query| !query.is_empty());
+		self.matches.clear();
+		self.current = None;
+		self.jump = true;
+	}
+
+	fn next_match(&mut self) {
+		if !self.matches.is_empty() {
+			let next = self
+				.current
+				.map_or(0, |current| (current + 1) % self.matches.len());
+			self.current = Some(next);
+			self.scroll_to_match();
+		}
+	}
+
+	fn prev_match(&mut self) {
+		if !self.matches.is_empty() {
+			let len = self.matches.len();
+			let prev = self
+				.current
+				.map_or(len - 1, |current| (current + len - 1) % len);
+			self.current = Some(prev);
+			self.scroll_to_match();
+		}
 	}
 
 	fn up(&mut self) {
-		self.scroll = self.scroll.saturating_sub(1);
+		self.scroll_up(1);
 	}
 
 	fn down(&mut self) {
-		self.scroll = self.scroll.saturating_add(1).min(self.max_scroll);
+		self.scroll_down(1);
+	}
+
+	fn pg_up(&mut self) {
+		self.scroll_up(self.page.max(1));
+	}
+
+	fn pg_down(&mut self) {
+		self.scroll_down(self.page.max(1));
+	}
+
+	fn half_up(&mut self) {
+		self.scroll_up((self.page / 2).max(1));
+	}
+
+	fn half_down(&mut self) {
+		self.scroll_down((self.page / 2).max(1));
 	}
 
 	fn home(&mut self) {
@@ -75,7 +207,7 @@ impl Popup for TextPopup {
 
 pub fn lyrics() -> TextPopup {
 	TextPopup::new(" lyrics ", |state| {
-		let dimmed = Style::default().dim().italic();
+		let dimmed = utils::style::placeholder();
 
 		if let Some(track) = state.track() {
 			if let Some(lyrics) = track.lyrics() {
@@ -91,16 +223,19 @@ pub fn lyrics() -> TextPopup {
 
 pub fn tags() -> TextPopup {
 	TextPopup::new(" tags ", |state| {
-		let dimmed = Style::default().dim().italic();
+		let dimmed = utils::style::placeholder();
 		if let Some(track) = state.track() {
 			let underline = Style::default().underlined();
 
 			let title = track
 				.title()
 				.map_or_else(|| utils::widgets::line("none", dimmed), Line::from);
-			let artist = track
-				.artist()
-				.map_or_else(|| utils::widgets::line("none", dimmed), Line::from);
+			let artists = track.artists();
+			let artist = if artists.is_empty() {
+				utils::widgets::line("none", dimmed)
+			} else {
+				Line::from(artists.join(", "))
+			};
 			let album = track
 				.album()
 				.map_or_else(|| utils::widgets::line("none", dimmed), Line::from);
@@ -108,9 +243,51 @@ pub fn tags() -> TextPopup {
 				|| utils::widgets::line("none", dimmed),
 				|num| Line::from(num.to_string()),
 			);
+			let genre = track.genre().map_or_else(
+				|| utils::widgets::line("none", dimmed),
+				|genre| Line::from(genre.into_owned()),
+			);
+			let year = track.year().map_or_else(
+				|| utils::widgets::line("none", dimmed),
+				|year| Line::from(year.to_string()),
+			);
+			let bpm = track.bpm().map_or_else(
+				|| utils::widgets::line("none", dimmed),
+				|bpm| Line::from(bpm.to_string()),
+			);
 			let path = Line::from(track.path().as_str());
 
-			vec![
+			let none = || utils::widgets::line("none", dimmed);
+			let info = track.info();
+			let codec = (info.and_then(|info| info.codec)).map_or_else(none, Line::from);
+			let sample_rate = (info.and_then(|info| info.sample_rate))
+				.map_or_else(none, |rate| Line::from(format!("{rate} Hz")));
+			let bit_depth = (info.and_then(|info| info.bits_per_sample))
+				.map_or_else(none, |bits| Line::from(format!("{bits} bit")));
+			let bitrate = (info.and_then(|info| info.bitrate)).map_or_else(none, |bitrate| {
+				Line::from(format!("{} kbps", bitrate / 1000))
+			});
+			let channels = (info.and_then(|info| info.channels))
+				.map_or_else(none, |channels| Line::from(channels.to_string()));
+			let size = info.map_or_else(none, |info| Line::from(utils::fmt_size(info.size)));
+
+			// only shown if they are set, as they are mostly used by classical releases
+			let classical = [
+				("composer", track.composer()),
+				("conductor", track.conductor()),
+				("work", track.work()),
+			]
+			.into_iter()
+			.filter_map(|(name, value)| value.map(|value| (name, value)))
+			.flat_map(|(name, value)| {
+				[
+					utils::widgets::line(name, underline),
+					Line::from(value),
+					Line::default(),
+				]
+			});
+
+			let mut lines = vec![
 				utils::widgets::line("title", underline),
 				title,
 				Line::default(),
@@ -120,27 +297,110 @@ pub fn tags() -> TextPopup {
 				utils::widgets::line("album", underline),
 				album,
 				Line::default(),
+			];
+			lines.extend(classical);
+			lines.extend([
 				utils::widgets::line("track", underline),
 				num,
 				Line::default(),
+				utils::widgets::line("genre", underline),
+				genre,
+				Line::default(),
+				utils::widgets::line("year", underline),
+				year,
+				Line::default(),
+				utils::widgets::line("bpm", underline),
+				bpm,
+				Line::default(),
 				utils::widgets::line("path", underline),
 				path,
-			]
+				Line::default(),
+				utils::widgets::line("codec", underline),
+				codec,
+				Line::default(),
+				utils::widgets::line("sample rate", underline),
+				sample_rate,
+				Line::default(),
+				utils::widgets::line("bit depth", underline),
+				bit_depth,
+				Line::default(),
+				utils::widgets::line("bitrate", underline),
+				bitrate,
+				Line::default(),
+				utils::widgets::line("channels", underline),
+				channels,
+				Line::default(),
+				utils::widgets::line("size", underline),
+				size,
+			]);
+			lines
 		} else {
 			vec![utils::widgets::line("no track playing", dimmed)]
 		}
 	})
 }
 
+pub fn log() -> TextPopup {
+	TextPopup::new(" log ", |_| {
+		let recent = logger::recent();
+		if recent.is_empty() {
+			let dimmed = utils::style::placeholder();
+			return vec![utils::widgets::line("nothing logged", dimmed)];
+		}
+
+		recent
+			.into_iter()
+			.map(|(level, message)| {
+				let style = match level {
+					Level::Error => Style::default().red().bold(),
+					_ => Style::default().yellow(),
+				};
+				let level = Span::styled(format!("{:<5} ", level.as_str().to_lowercase()), style);
+				Line::from(vec![level, Span::raw(message)])
+			})
+			.collect()
+	})
+}
+
+pub fn party() -> TextPopup {
+	TextPopup::new(" party ", |queue| {
+		let dimmed = utils::style::placeholder();
+		let Some(party) = queue.party() else {
+			return vec![utils::widgets::line("party mode is off", dimmed)];
+		};
+
+		let underline = Style::default().underlined();
+		let (votes, needed) = party.votes();
+
+		let mut lines = vec![
+			utils::widgets::line("votes to skip", underline),
+			Line::from(format!("{votes} / {needed}")),
+			Line::default(),
+			utils::widgets::line("requests", underline),
+		];
+
+		if party.requests().is_empty() {
+			lines.push(utils::widgets::line("none", dimmed));
+		} else {
+			let requests = party.requests().iter().enumerate();
+			lines.extend(requests.map(|(idx, track)| Line::from(format!("{}. {track}", idx + 1))));
+		}
+
+		lines
+	})
+}
+
 #[derive(Debug)]
 pub struct Tracks {
 	state: ListState,
 	len: usize,
 	page: Option<usize>,
+	/// show the bpm of every track
+	bpm: bool,
 }
 
 impl Tracks {
-	pub fn new(queue: &Queue) -> Self {
+	pub fn new(queue: &Queue, bpm: bool) -> Self {
 		let idx = queue.index().unwrap_or(0);
 		let state = ListState::default()
 			.with_selected(Some(idx))
@@ -150,16 +410,23 @@ impl Tracks {
 			state,
 			len: queue.tracks().len(),
 			page: None,
+			bpm,
 		}
 	}
 }
 
 impl Tracks {
-	fn items(queue: &Queue) -> Vec<ListItem<'_>> {
+	fn items(queue: &Queue, width: usize, bpm: bool) -> Vec<ListItem<'_>> {
 		queue
 			.tracks()
 			.iter()
-			.map(|track| track.line(queue))
+			.map(|track| {
+				// padded, so the titles are truncated to the same width
+				let bpm = bpm.then(|| {
+					(track.bpm()).map_or_else(|| String::from("   "), |bpm| format!("{bpm:>3}"))
+				});
+				with_column(|width| track.line(queue, width), bpm, width)
+			})
 			.map(ListItem::new)
 			.collect()
 	}
@@ -172,7 +439,16 @@ impl Tracks {
 
 impl Popup for Tracks {
 	fn draw(&mut self, frame: &mut Frame, area: Rect, queue: &Queue) {
-		let block = utils::popup::block().title(" tracks ");
+		let title = queue.order().name().map_or_else(
+			|| String::from(" tracks "),
+			|order| format!(" tracks (by {order}) "),
+		);
+		let title = if let Some(filter) = queue.filter() {
+			format!("{title}[{filter}] ")
+		} else {
+			title
+		};
+		let block = utils::popup::block().title(title);
 		let inner = block.inner(area);
 		let (title_area, list_area) = utils::popup::double_layout(inner);
 
@@ -187,19 +463,45 @@ impl Popup for Tracks {
 
 		let path = queue.path();
 		let line = path.map_or_else(
-			|| utils::widgets::line("nothing playing", Style::default().bold().dim().italic()),
-			|path| utils::widgets::line(format!(">> {path:?}"), Style::default().bold()),
+			|| utils::widgets::line("nothing playing", utils::style::placeholder().bold()),
+			|path| {
+				let path = if Favorites::is_path(path) {
+					String::from(">> ♥ favorites")
+				} else if playlist::is_path(path) {
+					format!(">> ≡ {}", playlist::name(path))
+				} else {
+					format!(">> {path:?}")
+				};
+				utils::widgets::line(path, Style::default().bold())
+			},
 		);
 		let title = Paragraph::new(line).block(Block::default());
 		frame.render_widget(title, title_area);
 
-		let items = Tracks::items(queue);
+		if let Some(index) = queue.index() {
+			let progress = format!("{}/{}", index + 1, self.len);
+			let progress = if let Some(remaining) = queue.remaining() {
+				let remaining = utils::fmt_duration(remaining);
+				format!("{progress} ~ {remaining} left")
+			} else {
+				progress
+			};
+
+			let progress = utils::widgets::line(progress, utils::style::dim());
+			let progress = Paragraph::new(progress).alignment(Alignment::Right);
+			frame.render_widget(progress, title_area);
+		}
+
+		let items = Tracks::items(queue, usize::from(list_area.width), self.bpm);
 		let list = ListWidget::new(items)
 			.block(Block::default())
-			.style(Style::default().dim())
-			.highlight_style(Style::default().remove_modifier(Modifier::DIM));
+			.style(utils::style::dim())
+			.highlight_style(utils::style::selection());
 
 		frame.render_stateful_widget(list, list_area, &mut self.state);
+
+		let max_offset = self.len.saturating_sub(page);
+		utils::popup::scrollbar(frame, area, max_offset, page, self.state.offset());
 	}
 
 	fn change_track(&mut self, active: bool, queue: &Queue) {
@@ -215,7 +517,8 @@ impl Popup for Tracks {
 	}
 
 	fn change_queue(&mut self, queue: &Queue) {
-		self.state.select(Some(0));
+		let idx = queue.index().unwrap_or(0);
+		self.state.select(Some(idx));
 		self.len = queue.tracks().len();
 	}
 
@@ -272,33 +575,78 @@ impl Popup for Tracks {
 		*self.state.offset_mut() = self.offset();
 	}
 
-	fn enter(&mut self, player: &mut Player, queue: &mut Queue) -> Result<(), QueueError> {
+	fn delete(&mut self, queue: &mut Queue) {
+		let idx = self.state.selected().expect("state should always be Some");
+		if queue.remove(idx).is_some() {
+			self.len = queue.tracks().len();
+			self.state.select(Some(idx.min(self.len.saturating_sub(1))));
+		}
+	}
+
+	fn move_up(&mut self, queue: &mut Queue) {
+		let idx = self.state.selected().expect("state should always be Some");
+		if idx > 0 && queue.move_track(idx, idx - 1) {
+			self.state.select(Some(idx - 1));
+		}
+	}
+
+	fn move_down(&mut self, queue: &mut Queue) {
+		let idx = self.state.selected().expect("state should always be Some");
+		if queue.move_track(idx, idx + 1) {
+			self.state.select(Some(idx + 1));
+		}
+	}
+
+	fn selected_track(&self, queue: &Queue) -> Option<Utf8PathBuf> {
+		let idx = self.state.selected().expect("state should always be Some");
+		queue.tracks().get(idx).map(|track| track.path().to_owned())
+	}
+
+	fn enter(&mut self, player: &mut dyn Playable, queue: &mut Queue) -> Result<(), QueueError> {
 		let idx = self.state.selected().expect("state should always be Some");
 		queue.select_idx(idx, player)
 	}
 
-	fn space(&mut self, player: &mut Player, queue: &mut Queue) -> Result<(), QueueError> {
+	fn space(&mut self, player: &mut dyn Playable, queue: &mut Queue) -> Result<(), QueueError> {
 		self.enter(player, queue)
 	}
 }
 
+/// popup width from which the children of the selected list are previewed
+const PREVIEW_WIDTH: u16 = 60;
+
 #[derive(Debug)]
 enum ListType<'a> {
 	Child(Child, &'a List),
 	List(&'a List),
+	/// the virtual favorites list, shown after the root lists
+	Favorites,
+	/// an imported playlist, shown after the favorites
+	Playlist(&'a Utf8Path),
+	/// a recently queued list, shown before the root lists
+	Recent(&'a Utf8Path),
 }
 
 #[derive(Debug)]
 pub struct Lists {
 	state: ListState,
 	lists: Vec<List>,
+	/// imported playlists
+	playlists: Vec<Utf8PathBuf>,
+	/// recently queued lists that still exist, newest first
+	recent: Vec<Utf8PathBuf>,
+	/// track counts and durations of the children
+	stats: Stats,
 	list: Option<List>,
 	page: Option<usize>,
+	/// last error of queueing the selection, shown until the selection changes
+	error: Option<String>,
 }
 
 impl Lists {
 	pub fn new(config: &Config, queue: &Queue) -> Self {
-		let lists = config.lists().to_owned();
+		let mut lists = config.lists().to_owned();
+		lists.extend(remote::roots());
 
 		let list = queue
 			.path()
@@ -308,6 +656,7 @@ impl Lists {
 			&& let Some(list) = &list
 		{
 			list.children()
+				.unwrap_or_default()
 				.iter()
 				.enumerate()
 				.find_map(|(i, child)| (child == track).then_some(i))
@@ -320,16 +669,20 @@ impl Lists {
 		Lists {
 			state,
 			lists,
+			playlists: playlist::all(),
+			recent: Vec::new(),
+			stats: Stats::new(),
 			list,
 			page: None,
+			error: None,
 		}
 	}
 
 	fn len(&self) -> usize {
 		if let Some(list) = &self.list {
-			list.children().len()
+			list.children().map_or(0, |children| children.len())
 		} else {
-			self.lists.len()
+			self.recent.len() + self.lists.len() + 1 + self.playlists.len()
 		}
 	}
 
@@ -340,18 +693,173 @@ impl Lists {
 
 	fn curr(&self) -> ListType<'_> {
 		if let Some(list) = &self.list {
-			let children = list.children();
 			let idx = self.state.selected().expect("state should always be Some");
 
-			let child = children[idx].clone();
+			// the list could have become unreadable since it was opened
+			let child = list
+				.children()
+				.map_err(|err| err.to_string())
+				.and_then(|children| children.get(idx).cloned().ok_or_else(|| "empty".to_owned()))
+				.unwrap_or_else(|reason| Child::Unreadable(list.path.clone(), reason));
 			ListType::Child(child, list)
 		} else {
 			let idx = self.state.selected().expect("state should always be Some");
-			let list = &self.lists[idx];
-			ListType::List(list)
+			if let Some(recent) = self.recent.get(idx) {
+				return ListType::Recent(recent);
+			}
+
+			let idx = idx - self.recent.len();
+			if let Some(list) = self.lists.get(idx) {
+				ListType::List(list)
+			} else if let Some(playlist) =
+				(idx.checked_sub(self.lists.len() + 1)).and_then(|idx| self.playlists.get(idx))
+			{
+				ListType::Playlist(playlist)
+			} else {
+				ListType::Favorites
+			}
 		}
 	}
 
+	/// import the playlists in the file at `path` and queue the first one
+	fn import(
+		&mut self,
+		path: &Utf8Path,
+		player: &mut dyn Playable,
+		queue: &mut Queue,
+	) -> Result<(), QueueError> {
+		match playlist::import(path) {
+			Ok(imported) => {
+				self.playlists = playlist::all();
+				queue.queue(&imported[0])?;
+				queue.next(player);
+			}
+			Err(err) => log::error!("couldn't import {path:?}: {err}"),
+		}
+
+		Ok(())
+	}
+
+	/// draw the children of the selected list next to it
+	fn preview(&mut self, frame: &mut Frame, area: Rect, queue: &Queue) {
+		let list = match self.curr() {
+			ListType::List(list) => list.clone(),
+			ListType::Child(Child::List(list), _) => list,
+			_ => return,
+		};
+
+		let children = list.children().unwrap_or_default();
+		let items = lists_list(&children, &mut self.stats, queue, usize::from(area.width));
+		let preview = ListWidget::new(items).style(utils::style::dim());
+		frame.render_widget(preview, area);
+	}
+
+	/// open the selected list or queue the selection
+	fn open(&mut self, player: &mut dyn Playable, queue: &mut Queue) -> Result<(), QueueError> {
+		let curr = self.curr();
+
+		match curr {
+			ListType::List(list) => {
+				let list = list.clone();
+				remote::sync(&list.path, false);
+				self.set(Some(list), 0);
+			}
+			ListType::Favorites | ListType::Playlist(_) | ListType::Recent(_) => {
+				return self.play(player, queue);
+			}
+			ListType::Child(child, parent) => match child {
+				Child::List(list) => {
+					remote::sync(&list.path, false);
+					self.set(Some(list), 0);
+				}
+				Child::Mp3(path) => {
+					queue.queue(&parent.path)?;
+					queue.select_path(&path, player)?;
+				}
+				Child::Playlist(path) => self.import(&path, player, queue)?,
+				Child::Unreadable(..) => {}
+			},
+		}
+
+		Ok(())
+	}
+
+	/// queue the selection
+	fn play(&mut self, player: &mut dyn Playable, queue: &mut Queue) -> Result<(), QueueError> {
+		let curr = self.curr();
+
+		match curr {
+			ListType::List(list) => {
+				remote::sync(&list.path, true);
+				queue.queue(&list.path)?;
+				queue.next(player);
+			}
+			ListType::Favorites => {
+				if let Some(path) = Favorites::path() {
+					queue.queue(path)?;
+					queue.next(player);
+				}
+			}
+			ListType::Playlist(path) | ListType::Recent(path) => {
+				queue.queue(path)?;
+				queue.next(player);
+			}
+			ListType::Child(child, parent) => match child {
+				Child::List(list) => {
+					remote::sync(&list.path, true);
+					queue.queue(&list.path)?;
+					queue.next(player);
+				}
+				Child::Mp3(track) => {
+					queue.queue(&parent.path)?;
+					queue.select_path(&track, player)?;
+				}
+				Child::Playlist(path) => self.import(&path, player, queue)?,
+				Child::Unreadable(..) => {}
+			},
+		}
+
+		Ok(())
+	}
+
+	/// show the error of `result` in the popup, instead of returning it and closing the popup
+	fn catch(&mut self, result: Result<(), QueueError>) {
+		self.error = result.err().map(|err| {
+			log::warn!("couldn't queue the selection: {err}");
+			err.to_string()
+		});
+	}
+
+	/// select the next or previous entry, wrapping around and skipping [`Child::Unreadable`] ones
+	fn step(&mut self, forward: bool) {
+		let len = self.len();
+		let unreadable = (self.list.as_ref())
+			.and_then(|list| list.children().ok())
+			.map(|children| {
+				children
+					.iter()
+					.map(Child::is_unreadable)
+					.collect::<Vec<_>>()
+			})
+			.unwrap_or_default();
+
+		let Some(mut idx) = self.state.selected() else {
+			return;
+		};
+		for _ in 0..len {
+			idx = if forward {
+				(idx + 1) % len
+			} else {
+				(idx + len - 1) % len
+			};
+			if !unreadable.get(idx).copied().unwrap_or(false) {
+				break;
+			}
+		}
+
+		self.state.select(Some(idx));
+	}
+
 	/// overwrites `self.list` and sets the index for `self.state`
 	fn set(&mut self, list: Option<List>, idx: usize) {
 		self.list = list;
@@ -362,16 +870,27 @@ impl Lists {
 
 impl Popup for Lists {
 	fn draw(&mut self, frame: &mut Frame, area: Rect, queue: &Queue) {
-		let children = self.list.as_ref().map(|list| list.children());
-		let items = if let Some(children) = &children {
-			lists_list(children, queue)
-		} else {
-			root_list(&self.lists, queue)
-		};
-
 		let block = utils::popup::block().title(" lists ");
 		let inner = block.inner(area);
 		let (title_area, list_area) = utils::popup::double_layout(inner);
+		let (mut list_area, preview_area) = utils::popup::columns(list_area, PREVIEW_WIDTH);
+
+		let error_area = self
+			.error
+			.as_ref()
+			.filter(|_| list_area.height > 1)
+			.map(|_| {
+				list_area.height -= 1;
+				Rect::new(list_area.x, list_area.bottom(), list_area.width, 1)
+			});
+
+		let width = usize::from(list_area.width);
+		let children = (self.list.as_ref()).map(|list| list.children().unwrap_or_default());
+		let items = if let Some(children) = &children {
+			lists_list(children, &mut self.stats, queue, width)
+		} else {
+			root_list(&self.recent, &self.lists, &self.playlists, queue, width)
+		};
 
 		frame.render_widget(Clear, area);
 		frame.render_widget(block, area);
@@ -391,10 +910,66 @@ impl Popup for Lists {
 
 		let list = ListWidget::new(items)
 			.block(Block::default())
-			.style(Style::default().dim())
-			.highlight_style(Style::default().remove_modifier(Modifier::DIM));
+			.style(utils::style::dim())
+			.highlight_style(utils::style::selection());
 
 		frame.render_stateful_widget(list, list_area, &mut self.state);
+
+		if let Some(preview_area) = preview_area {
+			self.preview(frame, preview_area, queue);
+		}
+
+		if let Some((error, error_area)) = self.error.as_ref().zip(error_area) {
+			let error = format!("couldn't queue: {error}");
+			let error = utils::widgets::truncate(error, usize::from(error_area.width));
+			let line = utils::widgets::line(error, Style::default().red());
+			frame.render_widget(Paragraph::new(line), error_area);
+		}
+
+		let max_offset = self.len().saturating_sub(page);
+		utils::popup::scrollbar(frame, area, max_offset, page, self.state.offset());
+	}
+
+	fn change_lists(&mut self, lists: &[List]) {
+		self.lists = lists.to_owned();
+		self.lists.extend(remote::roots());
+
+		if self.list.is_none() {
+			let idx = self.state.selected().unwrap_or(0);
+			self.state.select(Some(idx.min(self.len() - 1)));
+		}
+	}
+
+	fn root(&self) -> Option<&Utf8Path> {
+		match self.curr() {
+			ListType::List(list) => Some(&list.path),
+			_ => None,
+		}
+	}
+
+	fn selected_track(&self, queue: &Queue) -> Option<Utf8PathBuf> {
+		let _ = queue;
+		match self.curr() {
+			ListType::Child(Child::Mp3(path), _) => Some(path),
+			_ => None,
+		}
+	}
+
+	fn poll(&mut self) -> bool {
+		self.stats.poll()
+	}
+
+	fn change_recent(&mut self, recent: &[Utf8PathBuf]) {
+		self.recent = recent
+			.iter()
+			.filter(|path| path.exists())
+			.cloned()
+			.collect();
+
+		if self.list.is_none() {
+			let idx = self.state.selected().unwrap_or(0);
+			self.state.select(Some(idx.min(self.len() - 1)));
+		}
 	}
 
 	fn change_track(&mut self, active: bool, queue: &Queue) {
@@ -404,7 +979,7 @@ impl Popup for Lists {
 
 		let Some(track) = queue.track() else { return };
 		if let Some(list) = &self.list {
-			let children = list.children();
+			let children = list.children().unwrap_or_default();
 			let idx = children.iter().position(|child| child == track);
 			let idx = idx.unwrap_or(0);
 
@@ -414,28 +989,17 @@ impl Popup for Lists {
 	}
 
 	fn down(&mut self) {
-		let max = self.len().saturating_sub(1);
-		let idx = self
-			.state
-			.selected()
-			.map(|i| if i == max { 0 } else { i.saturating_add(1) });
-
-		self.state.select(idx);
+		self.error = None;
+		self.step(true);
 	}
 
 	fn up(&mut self) {
-		let idx = self.state.selected().map(|i| {
-			if i == 0 {
-				self.len().saturating_sub(1)
-			} else {
-				i.saturating_sub(1)
-			}
-		});
-
-		self.state.select(idx);
+		self.error = None;
+		self.step(false);
 	}
 
 	fn pg_down(&mut self) {
+		self.error = None;
 		if let Some(page) = self.page {
 			let idx = self
 				.state
@@ -450,6 +1014,7 @@ impl Popup for Lists {
 	}
 
 	fn pg_up(&mut self) {
+		self.error = None;
 		if let Some(page) = self.page {
 			let idx = self.state.selected().map(|i| i.saturating_sub(page));
 			self.state.select(idx);
@@ -458,104 +1023,174 @@ impl Popup for Lists {
 	}
 
 	fn home(&mut self) {
+		self.error = None;
 		self.state.select(Some(0));
 		*self.state.offset_mut() = 0;
 	}
 
 	fn end(&mut self) {
+		self.error = None;
 		let len = self.len().saturating_sub(1);
 		self.state.select(Some(len));
 		*self.state.offset_mut() = self.offset();
 	}
 
 	fn right(&mut self, queue: &Queue) {
+		self.error = None;
 		let curr = self.curr();
 		match curr {
 			ListType::Child(child, _) => {
 				if let Some(list) = child.list() {
 					let list = list.clone();
+					remote::sync(&list.path, false);
 					let idx = list.position(queue).unwrap_or(0);
 					self.set(Some(list), idx);
 				}
 			}
 			ListType::List(list) => {
 				let list = list.clone();
+				remote::sync(&list.path, false);
 				let idx = list.position(queue).unwrap_or(0);
 				self.set(Some(list), idx);
 			}
+			ListType::Favorites | ListType::Playlist(_) | ListType::Recent(_) => {}
 		}
 	}
 
 	fn left(&mut self) {
+		self.error = None;
 		if let Some(list) = self.list.take() {
 			if list.has_parent() {
 				let (idx, parent) = list.into_parent().unwrap();
 				self.set(Some(parent), idx.unwrap_or(0));
 			} else {
 				let idx = self.lists.iter().position(|root| root == &list);
-				self.set(None, idx.unwrap_or(0));
+				let idx = idx.map_or(0, |idx| idx + self.recent.len());
+				self.set(None, idx);
 			}
 		}
 	}
 
-	fn enter(&mut self, player: &mut Player, queue: &mut Queue) -> Result<(), QueueError> {
-		let curr = self.curr();
-
-		match curr {
-			ListType::List(list) => {
-				let list = list.clone();
-				self.set(Some(list), 0);
-			}
-			ListType::Child(child, parent) => match child {
-				Child::List(list) => {
-					self.set(Some(list), 0);
-				}
-				Child::Mp3(path) => {
-					queue.queue(&parent.path)?;
-					queue.select_path(&path, player)?;
-				}
-			},
-		}
-
+	fn enter(&mut self, player: &mut dyn Playable, queue: &mut Queue) -> Result<(), QueueError> {
+		let result = self.open(player, queue);
+		self.catch(result);
 		Ok(())
 	}
 
-	fn space(&mut self, player: &mut Player, queue: &mut Queue) -> Result<(), QueueError> {
-		let curr = self.curr();
+	fn space(&mut self, player: &mut dyn Playable, queue: &mut Queue) -> Result<(), QueueError> {
+		let result = self.play(player, queue);
+		self.catch(result);
+		Ok(())
+	}
+}
 
-		match curr {
-			ListType::List(list) => {
-				queue.queue(&list.path)?;
-				queue.next(player);
-			}
-			ListType::Child(child, parent) => match child {
-				Child::List(list) => {
-					queue.queue(&list.path)?;
-					queue.next(player);
-				}
-				Child::Mp3(track) => {
-					queue.queue(&parent.path)?;
-					queue.select_path(&track, player)?;
-				}
-			},
+/// right align `column` after the line, if there's enough room left for the line
+///
+/// `line` is called with the width that's left for it
+fn with_column<'a, F: FnOnce(usize) -> Line<'a>>(
+	line: F,
+	column: Option<String>,
+	width: usize,
+) -> Line<'a> {
+	match column.filter(|column| column.width() + 12 <= width) {
+		Some(column) => {
+			let mut line = line(width - column.width() - 1);
+			let pad = width.saturating_sub(line.width() + column.width());
+			line.push_span(Span::raw(" ".repeat(pad)));
+			line.push_span(Span::styled(column, utils::style::dim()));
+			line
 		}
-
-		Ok(())
+		None => line(width),
 	}
 }
 
-fn lists_list<'a>(children: &'a [Child], queue: &Queue) -> Vec<ListItem<'a>> {
+fn lists_list<'a>(
+	children: &'a [Child],
+	stats: &mut Stats,
+	queue: &Queue,
+	width: usize,
+) -> Vec<ListItem<'a>> {
 	children
 		.iter()
-		.map(|child| child.line(queue))
+		.map(|child| {
+			let info = match child {
+				Child::List(list) => stats.get(&list.path).map(|stat| {
+					let Stat { tracks, duration } = stat;
+					format!("{tracks} · {}", utils::fmt_duration(duration))
+				}),
+				Child::Mp3(path) => stats
+					.get(path)
+					.map(|stat| utils::fmt_duration(stat.duration)),
+				Child::Playlist(_) | Child::Unreadable(..) => None,
+			};
+
+			with_column(|width| child.line(queue, width), info, width)
+		})
 		.map(ListItem::new)
 		.collect()
 }
 
-fn root_list<'a>(lists: &'a [List], queue: &Queue) -> Vec<ListItem<'a>> {
-	lists
+fn root_list<'a>(
+	recent: &'a [Utf8PathBuf],
+	lists: &'a [List],
+	playlists: &'a [Utf8PathBuf],
+	queue: &Queue,
+	width: usize,
+) -> Vec<ListItem<'a>> {
+	let favorites = if queue.path().is_some_and(Favorites::is_path) {
+		utils::widgets::line("♥ favorites", utils::style::accent().bold())
+	} else {
+		utils::widgets::line("♥ favorites", utils::style::italic())
+	};
+
+	let line = |root: &'a List| {
+		let Some(name) = remote::name(&root.path) else {
+			return root.line(queue, width);
+		};
+
+		let name = format!("☁ {name}");
+		if queue
+			.path()
+			.is_some_and(|path| path.starts_with(&root.path))
+		{
+			utils::widgets::line(name, utils::style::accent().bold())
+		} else {
+			utils::widgets::line(name, utils::style::italic())
+		}
+	};
+
+	let playlist = |path: &'a Utf8PathBuf| {
+		let name = format!("≡ {}", playlist::name(path));
+		if queue.path() == Some(path) {
+			utils::widgets::line(name, utils::style::accent().bold())
+		} else {
+			utils::widgets::line(name, utils::style::italic())
+		}
+	};
+
+	let queued = |path: &'a Utf8PathBuf| {
+		let name = if Favorites::is_path(path) {
+			"favorites"
+		} else if playlist::is_path(path) {
+			playlist::name(path)
+		} else {
+			path.file_name().unwrap_or(path.as_str())
+		};
+
+		let name = format!("↺ {name}");
+		if queue.path() == Some(path) {
+			utils::widgets::line(name, utils::style::accent().bold())
+		} else {
+			utils::widgets::line(name, utils::style::italic())
+		}
+	};
+
+	recent
 		.iter()
-		.map(|root| root.line(queue))
+		.map(queued)
+		.chain(lists.iter().map(line))
+		.chain([favorites])
+		.chain(playlists.iter().map(playlist))
 		.map(ListItem::new)
 		.collect()
 }
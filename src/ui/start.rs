@@ -0,0 +1,148 @@
+//! start screen in the main window, shown when no queue was restored
+
+use super::utils;
+use crate::{
+	favorites::Favorites,
+	history,
+	player::Playable,
+	playlist,
+	queue::{Queue, QueueError},
+};
+use camino::Utf8PathBuf;
+use ratatui::{
+	Frame,
+	layout::Rect,
+	text::Line,
+	widgets::{Block, Borders, List as ListWidget, ListItem, ListState, Padding},
+};
+
+/// number of recent and most played directories that are listed
+const ENTRIES: usize = 5;
+
+/// list that can be queued from the start screen
+#[derive(Debug)]
+struct Entry {
+	/// title of the section the entry is listed under
+	section: &'static str,
+	name: String,
+	path: Utf8PathBuf,
+}
+
+#[derive(Debug)]
+pub struct Start {
+	entries: Vec<Entry>,
+	selected: usize,
+}
+
+impl Start {
+	/// collect recent and most played directories from the history and the favorite lists
+	pub fn new() -> Self {
+		let dir = |section| {
+			move |path: Utf8PathBuf| {
+				let name = path.file_name().unwrap_or(path.as_str()).to_owned();
+				Entry {
+					section,
+					name,
+					path,
+				}
+			}
+		};
+
+		let recent = history::recent(ENTRIES)
+			.into_iter()
+			.map(dir("jump back in"));
+
+		let favorites = Favorites::path()
+			.filter(|path| path.exists())
+			.map(|path| Entry {
+				section: "favorites",
+				name: "♥ favorites".to_owned(),
+				path,
+			});
+		let playlists = playlist::all().into_iter().map(|path| Entry {
+			section: "favorites",
+			name: format!("≡ {}", playlist::name(&path)),
+			path,
+		});
+
+		let albums = history::albums(ENTRIES)
+			.into_iter()
+			.map(|(name, path)| Entry {
+				section: "most played",
+				name,
+				path,
+			});
+
+		let entries = recent
+			.chain(favorites)
+			.chain(playlists)
+			.chain(albums)
+			.filter(|entry| entry.path.exists())
+			.collect();
+
+		Start {
+			entries,
+			selected: 0,
+		}
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.entries.is_empty()
+	}
+
+	pub fn up(&mut self) {
+		self.selected = self.selected.saturating_sub(1);
+	}
+
+	pub fn down(&mut self) {
+		self.selected = (self.selected + 1).min(self.entries.len().saturating_sub(1));
+	}
+
+	/// queue the selected entry and play its first track
+	pub fn enter(&self, player: &mut dyn Playable, queue: &mut Queue) -> Result<(), QueueError> {
+		let Some(entry) = self.entries.get(self.selected) else {
+			return Ok(());
+		};
+
+		queue.queue(&entry.path)?;
+		queue.next(player);
+		Ok(())
+	}
+
+	pub fn draw(&self, frame: &mut Frame, area: Rect) {
+		let block = Block::default()
+			.title(" main ")
+			.borders(Borders::ALL)
+			.border_style(utils::style::border())
+			.padding(Padding::new(4, 4, 1, 1));
+		let width = usize::from(block.inner(area).width);
+
+		let mut items = Vec::with_capacity(self.entries.len() + 3);
+		let mut selected = 0;
+		for (idx, entry) in self.entries.iter().enumerate() {
+			let first = idx.checked_sub(1).map(|prev| self.entries[prev].section);
+			if first != Some(entry.section) {
+				if first.is_some() {
+					items.push(ListItem::new(Line::default()));
+				}
+
+				let section = utils::widgets::line(entry.section, utils::style::accent().bold());
+				items.push(ListItem::new(section));
+			}
+
+			if idx == self.selected {
+				selected = items.len();
+			}
+
+			let name = utils::widgets::truncate(format!("  {}", entry.name), width);
+			items.push(ListItem::new(Line::from(name)));
+		}
+
+		let list = ListWidget::new(items)
+			.block(block)
+			.style(utils::style::dim())
+			.highlight_style(utils::style::selection());
+		let mut state = ListState::default().with_selected(Some(selected));
+		frame.render_stateful_widget(list, area, &mut state);
+	}
+}
@@ -0,0 +1,130 @@
+//! track counts and durations for the lists popup, computed on a background thread
+
+use crate::queue::Track;
+use camino::{Utf8Path, Utf8PathBuf};
+use std::{
+	collections::HashMap,
+	sync::mpsc::{self, Receiver, Sender},
+	thread,
+	time::Duration,
+};
+use walkdir::WalkDir;
+
+/// number of tracks and their total duration
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Stat {
+	pub tracks: usize,
+	pub duration: Duration,
+}
+
+impl Stat {
+	/// read the duration of the track at `path`, from the tags or the stream header
+	fn track(path: &Utf8Path) -> Self {
+		let duration = Track::new(path.to_owned()).ok().and_then(|track| {
+			track
+				.duration()
+				.or_else(|| track.info().and_then(|info| info.duration))
+		});
+
+		Stat {
+			tracks: 1,
+			duration: duration.unwrap_or_default(),
+		}
+	}
+
+	/// walk the directory at `path` and add up the stats of every track
+	fn directory(path: &Utf8Path) -> Self {
+		WalkDir::new(path)
+			.into_iter()
+			.filter_map(Result::ok)
+			.filter(|entry| entry.file_type().is_file())
+			.filter_map(|entry| Utf8PathBuf::try_from(entry.into_path()).ok())
+			.filter(|path| path.extension() == Some("mp3"))
+			.map(|path| Stat::track(&path))
+			.fold(Stat::default(), |total, stat| Stat {
+				tracks: total.tracks + stat.tracks,
+				duration: total.duration + stat.duration,
+			})
+	}
+}
+
+/// cache of [`Stat`]s, missing ones are requested from the worker thread
+#[derive(Debug)]
+pub struct Stats {
+	/// computed stats, [`None`] while they are pending
+	cache: HashMap<Utf8PathBuf, Option<Stat>>,
+	request_tx: Sender<Utf8PathBuf>,
+	stat_rx: Receiver<(Utf8PathBuf, Stat)>,
+}
+
+impl Stats {
+	pub fn new() -> Self {
+		let (request_tx, request_rx) = mpsc::channel::<Utf8PathBuf>();
+		let (stat_tx, stat_rx) = mpsc::channel();
+
+		// the thread exits once the [`Stats`] are dropped
+		thread::spawn(move || {
+			for path in request_rx {
+				let stat = if path.is_dir() {
+					Stat::directory(&path)
+				} else {
+					Stat::track(&path)
+				};
+
+				if stat_tx.send((path, stat)).is_err() {
+					break;
+				}
+			}
+		});
+
+		Stats {
+			cache: HashMap::new(),
+			request_tx,
+			stat_rx,
+		}
+	}
+
+	/// cached stats of `path`, requested from the worker if they are missing
+	pub fn get(&mut self, path: &Utf8Path) -> Option<Stat> {
+		if let Some(stat) = self.cache.get(path) {
+			return *stat;
+		}
+
+		self.cache.insert(path.to_owned(), None);
+		let _ = self.request_tx.send(path.to_owned());
+		None
+	}
+
+	/// store the stats the worker computed, returns if there were any
+	pub fn poll(&mut self) -> bool {
+		let mut received = false;
+		for (path, stat) in self.stat_rx.try_iter() {
+			self.cache.insert(path, Some(stat));
+			received = true;
+		}
+
+		received
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::Stats;
+	use camino::Utf8Path;
+	use std::time::{Duration, Instant};
+
+	#[test]
+	fn stats() {
+		let mut stats = Stats::new();
+		let path = Utf8Path::new("mock/list 01");
+		assert_eq!(stats.get(path), None);
+
+		let start = Instant::now();
+		while !stats.poll() {
+			assert!(start.elapsed() < Duration::from_secs(5));
+			std::thread::sleep(Duration::from_millis(10));
+		}
+
+		assert_eq!(stats.get(path).map(|stat| stat.tracks), Some(6));
+	}
+}
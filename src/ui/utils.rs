@@ -1,10 +1,34 @@
 use std::time::Duration;
 
+/// format duration as `mm:ss`, or `hh:mm:ss` if it is longer than an hour
 pub fn fmt_duration(duration: Duration) -> String {
+	let hrs = duration.as_secs() / 3600;
 	let min = (duration.as_secs() / 60) % 60;
 	let sec = duration.as_secs() % 60;
 
-	format!("{min:0>2}:{sec:0>2}")
+	if hrs > 0 {
+		format!("{hrs:0>2}:{min:0>2}:{sec:0>2}")
+	} else {
+		format!("{min:0>2}:{sec:0>2}")
+	}
+}
+
+/// format a size in bytes with a binary unit, e.g. `4.2 MiB`
+pub fn fmt_size(bytes: u64) -> String {
+	const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+
+	if bytes < 1024 {
+		return format!("{bytes} B");
+	}
+
+	let mut size = bytes as f64 / 1024.;
+	let mut unit = 0;
+	while size >= 1024. && unit < UNITS.len() - 1 {
+		size /= 1024.;
+		unit += 1;
+	}
+
+	format!("{size:.1} {}", UNITS[unit])
 }
 
 pub mod widgets {
@@ -12,25 +36,194 @@ pub mod widgets {
 		style::Style,
 		text::{Line, Span},
 	};
-	use std::borrow::Cow;
+	use std::{borrow::Cow, ops::Range};
+	use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 	pub fn line<'a, I: Into<Cow<'a, str>>>(txt: I, style: Style) -> Line<'a> {
 		let spans = vec![Span::styled(txt, style)];
 		Line::from(spans)
 	}
+
+	/// byte ranges of the occurrences of `query` in `text`, matched case insensitively
+	///
+	/// the chars are compared lowercased one by one, so the ranges stay valid in `text`
+	/// even where lowercasing changes the length
+	pub fn matches(text: &str, query: &str) -> Vec<Range<usize>> {
+		let query = query
+			.chars()
+			.flat_map(char::to_lowercase)
+			.collect::<Vec<_>>();
+		let mut ranges = Vec::new();
+		if query.is_empty() {
+			return ranges;
+		}
+
+		let mut last = 0;
+		for (start, _) in text.char_indices() {
+			if start < last {
+				continue;
+			}
+
+			if let Some(len) = match_at(&text[start..], &query) {
+				last = start + len;
+				ranges.push(start..last);
+			}
+		}
+
+		ranges
+	}
+
+	/// length in bytes of the match of the lowercased `query` at the start of `text`
+	fn match_at(text: &str, query: &[char]) -> Option<usize> {
+		let mut matched = 0;
+		for (idx, ch) in text.char_indices() {
+			for lower in ch.to_lowercase() {
+				if query.get(matched) != Some(&lower) {
+					return None;
+				}
+				matched += 1;
+			}
+
+			if matched == query.len() {
+				return Some(idx + ch.len_utf8());
+			}
+		}
+
+		None
+	}
+
+	/// patch `style` onto every occurrence of `query` in `line`
+	///
+	/// matched case insensitively, matches across spans aren't found
+	pub fn highlight<'a>(line: Line<'a>, query: &str, style: Style) -> Line<'a> {
+		if query.is_empty() {
+			return line;
+		}
+
+		let mut spans = Vec::with_capacity(line.spans.len());
+		for span in line.spans {
+			let mut last = 0;
+			for Range { start, end } in matches(&span.content, query) {
+				if start > last {
+					spans.push(Span::styled(
+						span.content[last..start].to_owned(),
+						span.style,
+					));
+				}
+				let matched = span.content[start..end].to_owned();
+				spans.push(Span::styled(matched, span.style.patch(style)));
+				last = end;
+			}
+
+			if last == 0 {
+				spans.push(span);
+			} else if last < span.content.len() {
+				spans.push(Span::styled(span.content[last..].to_owned(), span.style));
+			}
+		}
+
+		Line { spans, ..line }
+	}
+
+	/// gap between the end and the start of a scrolling [`marquee`]
+	const MARQUEE_GAP: &str = "   ~   ";
+
+	/// collect characters until they fill `width` terminal columns
+	fn take_width<I: Iterator<Item = char>>(chars: I, width: usize) -> String {
+		let mut used = 0;
+		chars
+			.take_while(|ch| {
+				used += ch.width().unwrap_or(0);
+				used <= width
+			})
+			.collect()
+	}
+
+	/// truncate text to `width` terminal columns, ending in an ellipsis
+	///
+	/// text that fits is returned as is
+	pub fn truncate<'a, I: Into<Cow<'a, str>>>(txt: I, width: usize) -> Cow<'a, str> {
+		let txt = txt.into();
+		if txt.width() <= width {
+			return txt;
+		}
+
+		let mut truncated = take_width(txt.chars(), width.saturating_sub(1));
+		if width > 0 {
+			truncated.push('…');
+		}
+		Cow::Owned(truncated)
+	}
+
+	/// truncate text to its last `width` terminal columns, starting with an ellipsis
+	///
+	/// text that fits is returned as is
+	pub fn truncate_start(txt: &str, width: usize) -> Cow<'_, str> {
+		if txt.width() <= width {
+			return Cow::Borrowed(txt);
+		}
+
+		let tail = take_width(txt.chars().rev(), width.saturating_sub(1));
+		let tail = tail.chars().rev().collect::<String>();
+		if width > 0 {
+			Cow::Owned(format!("…{tail}"))
+		} else {
+			Cow::Owned(tail)
+		}
+	}
+
+	/// scroll text that doesn't fit into `width` terminal columns by `step` characters
+	///
+	/// text that fits is returned as is
+	pub fn marquee(txt: &str, width: usize, step: usize) -> Cow<'_, str> {
+		if txt.width() <= width {
+			return Cow::Borrowed(txt);
+		}
+
+		let cycle = txt.chars().count() + MARQUEE_GAP.chars().count();
+		let chars = txt.chars().chain(MARQUEE_GAP.chars()).cycle();
+		let scrolled = take_width(chars.skip(step % cycle), width);
+		Cow::Owned(scrolled)
+	}
 }
 
 pub mod style {
-	use crate::config::Config;
-	use ratatui::style::{Color, Style};
+	use crate::config::{Config, Styles};
+	use ratatui::style::{Color, Modifier, Style};
 	use std::sync::OnceLock;
 
 	static ACCENT: OnceLock<Color> = OnceLock::new();
 
+	static STYLES: OnceLock<Styles> = OnceLock::new();
+
+	static GRADIENT: OnceLock<Vec<Color>> = OnceLock::new();
+
+	static HIGH_CONTRAST: OnceLock<bool> = OnceLock::new();
+
 	pub fn load(config: &Config) {
 		if let Some(color) = config.accent() {
 			ACCENT.set(color).expect("load should only be called once");
 		}
+
+		STYLES
+			.set(config.styles())
+			.expect("load should only be called once");
+
+		GRADIENT
+			.set(config.gauge_gradient())
+			.expect("load should only be called once");
+
+		HIGH_CONTRAST
+			.set(config.high_contrast())
+			.expect("load should only be called once");
+	}
+
+	fn styles() -> Styles {
+		STYLES.get().copied().unwrap_or_default()
+	}
+
+	fn high_contrast() -> bool {
+		HIGH_CONTRAST.get().copied().unwrap_or(false)
 	}
 
 	pub fn accent() -> Style {
@@ -38,26 +231,106 @@ pub mod style {
 		Style::new().fg(*color)
 	}
 
+	/// style for secondary text
+	///
+	/// dimmed, or gray in high-contrast mode
+	pub fn dim() -> Style {
+		if high_contrast() {
+			Style::new().fg(Color::Gray)
+		} else {
+			Style::new().dim()
+		}
+	}
+
+	/// style for placeholders like "no track playing"
+	///
+	/// dimmed and italic, or yellow in high-contrast mode
+	pub fn placeholder() -> Style {
+		if high_contrast() {
+			Style::new().fg(Color::Yellow)
+		} else {
+			Style::new().dim().italic()
+		}
+	}
+
+	/// style for emphasized text
+	///
+	/// italic, or bold in high-contrast mode
+	pub fn italic() -> Style {
+		if high_contrast() {
+			Style::new().bold()
+		} else {
+			Style::new().italic()
+		}
+	}
+
+	/// highlight style for lists using [`dim`] as their base style
+	pub fn undim() -> Style {
+		if high_contrast() {
+			Style::new().fg(Color::White).add_modifier(Modifier::BOLD)
+		} else {
+			Style::new().remove_modifier(Modifier::DIM)
+		}
+	}
+
+	/// selection style for lists using [`dim`] as their base style
+	///
+	/// [`undim`] in the selection color, if one is configured
+	pub fn selection() -> Style {
+		styles()
+			.selection()
+			.map_or_else(undim, |color| undim().fg(color))
+	}
+
+	/// style for the currently playing track
+	pub fn playing() -> Style {
+		styles()
+			.playing()
+			.map_or_else(accent, |color| Style::new().fg(color))
+			.bold()
+	}
+
+	/// style for the borders of windows and popups
+	pub fn border() -> Style {
+		styles()
+			.border()
+			.map_or_else(Style::new, |color| Style::new().fg(color))
+	}
+
+	/// colors of the seek bar gradient, if one is configured
+	///
+	/// [`None`] while paused in high-contrast mode, to keep the gray [`gauge_style`]
+	pub fn gauge_gradient(paused: bool) -> Option<&'static [Color]> {
+		let gradient = GRADIENT.get().filter(|gradient| !gradient.is_empty())?;
+		(!paused || !high_contrast()).then_some(gradient)
+	}
+
 	pub fn gauge_style(paused: bool) -> (Style, Style) {
-		if paused {
-			(accent().dim(), Style::new().dim())
+		let filled = styles()
+			.gauge()
+			.map_or_else(accent, |color| Style::new().fg(color));
+
+		if paused && high_contrast() {
+			(Style::new().fg(Color::Gray), Style::new())
+		} else if paused {
+			(filled.dim(), Style::new().dim())
 		} else {
-			(accent(), Style::new())
+			(filled, Style::new())
 		}
 	}
 }
 
 pub mod popup {
 	use ratatui::{
-		layout::{Constraint, Direction, Layout, Rect},
-		style::Style,
-		widgets::{Block, Borders, Padding},
+		Frame,
+		layout::{Constraint, Direction, Layout, Margin, Rect},
+		widgets::{Block, Borders, Padding, Scrollbar, ScrollbarOrientation, ScrollbarState},
 	};
 
 	pub fn block() -> Block<'static> {
 		Block::default()
 			.borders(Borders::ALL)
-			.border_style(Style::default().dim())
+			.border_style(super::style::dim().patch(super::style::border()))
 			.padding(Padding::new(2, 2, 1, 1))
 	}
 
@@ -71,4 +344,145 @@ pub mod popup {
 		let list = layout[1];
 		(title, list)
 	}
+
+	/// split `area` into two columns with a gap, if it's at least `min_width` wide
+	pub fn columns(area: Rect, min_width: u16) -> (Rect, Option<Rect>) {
+		if area.width < min_width {
+			return (area, None);
+		}
+
+		let layout = Layout::default()
+			.direction(Direction::Horizontal)
+			.constraints([
+				Constraint::Fill(1),
+				Constraint::Length(2),
+				Constraint::Fill(1),
+			])
+			.split(area);
+
+		(layout[0], Some(layout[2]))
+	}
+
+	/// render a vertical scrollbar on the right border of the popup
+	///
+	/// only renders if the content doesn't fit into one `page`,
+	/// i.e. the `max_offset` is greater than zero
+	pub fn scrollbar(frame: &mut Frame, area: Rect, max_offset: usize, page: usize, offset: usize) {
+		if max_offset == 0 {
+			return;
+		}
+
+		let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+			.begin_symbol(None)
+			.end_symbol(None)
+			.track_style(super::style::dim());
+		let mut state = ScrollbarState::new(max_offset + 1)
+			.viewport_content_length(page)
+			.position(offset);
+
+		let area = area.inner(Margin::new(0, 1));
+		frame.render_stateful_widget(scrollbar, area, &mut state);
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::{
+		fmt_duration, fmt_size,
+		widgets::{highlight, marquee, matches, truncate, truncate_start},
+	};
+	use ratatui::{
+		style::{Modifier, Style},
+		text::{Line, Span},
+	};
+	use std::time::Duration;
+
+	#[test]
+	fn duration() {
+		assert_eq!(fmt_duration(Duration::ZERO), "00:00");
+		assert_eq!(fmt_duration(Duration::from_secs(59)), "00:59");
+		assert_eq!(fmt_duration(Duration::from_secs(754)), "12:34");
+		assert_eq!(fmt_duration(Duration::from_secs(3599)), "59:59");
+		assert_eq!(fmt_duration(Duration::from_secs(3600)), "01:00:00");
+		assert_eq!(fmt_duration(Duration::from_secs(37230)), "10:20:30");
+	}
+
+	#[test]
+	fn marquee_scroll() {
+		assert_eq!(marquee("short", 5, 3), "short");
+		assert_eq!(marquee("a long title", 6, 0), "a long");
+		assert_eq!(marquee("a long title", 6, 2), "long t");
+		assert_eq!(marquee("a long title", 6, 9), "tle   ");
+		assert_eq!(marquee("a long title", 6, 19), "a long");
+		assert_eq!(marquee("äöü ß", 3, 1), "öü ");
+		assert_eq!(marquee("日本語の曲", 5, 1), "本語");
+	}
+
+	#[test]
+	fn truncate_width() {
+		assert_eq!(truncate("short", 5), "short");
+		assert_eq!(truncate("a long title", 6), "a lon…");
+		assert_eq!(truncate("日本語の曲", 10), "日本語の曲");
+		assert_eq!(truncate("日本語の曲", 6), "日本…");
+		assert_eq!(truncate("日本語の曲", 5), "日本…");
+		assert_eq!(truncate("🎵🎵🎵", 4), "🎵…");
+		assert_eq!(truncate("text", 0), "");
+	}
+
+	#[test]
+	fn highlight_matches() {
+		let hit = Style::new().add_modifier(Modifier::REVERSED);
+		let line = Line::from(vec![Span::raw("Dancing "), Span::raw("Queen, dancing")]);
+
+		let highlighted = highlight(line.clone(), "DANCING", hit);
+		let spans = highlighted
+			.spans
+			.iter()
+			.map(|span| (span.content.as_ref(), span.style == hit))
+			.collect::<Vec<_>>();
+		assert_eq!(
+			spans,
+			[
+				("Dancing", true),
+				(" ", false),
+				("Queen, ", false),
+				("dancing", true)
+			]
+		);
+
+		assert_eq!(highlight(line.clone(), "", hit), line);
+		assert_eq!(highlight(line.clone(), "abba", hit), line);
+	}
+
+	#[test]
+	fn unicode_matches() {
+		let found = |text, query| {
+			(matches(text, query).into_iter())
+				.map(|range| (range.start, range.end))
+				.collect::<Vec<_>>()
+		};
+
+		assert_eq!(found("Ärger ÜBER alles, über", "über"), [(7, 12), (20, 25)]);
+		assert_eq!(found("Москва", "МОСКВА"), [(0, 12)]);
+		// lowercasing `İ` takes a byte more than the original
+		assert_eq!(found("İstanbul, istanbul", "i\u{307}stanbul"), [(0, 9)]);
+		assert_eq!(found("İstanbul", "stan"), [(2, 6)]);
+		assert!(found("straße", "").is_empty());
+	}
+
+	#[test]
+	fn size() {
+		assert_eq!(fmt_size(512), "512 B");
+		assert_eq!(fmt_size(2048), "2.0 KiB");
+		assert_eq!(fmt_size(4_404_019), "4.2 MiB");
+		assert_eq!(fmt_size(3 * 1024 * 1024 * 1024), "3.0 GiB");
+	}
+
+	#[test]
+	fn truncate_start_width() {
+		assert_eq!(truncate_start("short", 5), "short");
+		assert_eq!(truncate_start("artist:foo", 6), "…t:foo");
+		assert_eq!(truncate_start("日本語の曲", 6), "…の曲");
+		assert_eq!(truncate_start("text", 0), "");
+	}
 }
@@ -1,35 +1,46 @@
 use super::utils;
-use crate::state::State;
+use crate::{config::TimeDisplay, player::Output, queue::Queue, state::State};
 use ratatui::{
 	Frame,
-	layout::{Constraint, Direction, Layout, Rect},
+	layout::{Alignment, Constraint, Direction, Layout, Rect},
 	style::Style,
-	text::Line,
-	widgets::{Block, Borders, Padding, Paragraph},
+	text::{Line, Span},
+	widgets::{Block, Borders, Clear, Padding, Paragraph},
 };
+use unicode_width::UnicodeWidthStr;
 
 pub fn main(frame: &mut Frame, area: Rect, state: &State) {
 	let bold = Style::default().bold();
-	let dim = Style::default().dim();
-	let dim_italic = dim.italic();
+	let dim = utils::style::dim();
+	let dim_italic = utils::style::placeholder();
 
 	let block = Block::default()
 		.title(" main ")
 		.borders(Borders::ALL)
+		.border_style(utils::style::border())
 		.padding(Padding::new(4, 4, 2, 2));
 
 	if let Some(track) = state.track.as_ref() {
-		let title = track.title().map_or_else(
+		// scroll long titles with the playhead, 4 characters per second
+		let width = usize::from(block.inner(area).width);
+		let step = state.step().unwrap_or(0) as usize;
+
+		let title = track.heading().map_or_else(
 			|| utils::widgets::line("unknown title", dim_italic),
-			|title| utils::widgets::line(title, bold),
+			|title| {
+				utils::widgets::line(
+					utils::widgets::marquee(&title, width, step).into_owned(),
+					bold,
+				)
+			},
 		);
-		let artist = track.artist().map_or_else(
+		let artist = track.credit().map_or_else(
 			|| utils::widgets::line("unknown artist", dim_italic),
-			Line::from,
+			|artist| Line::from(utils::widgets::truncate(artist.into_owned(), width)),
 		);
 
 		let text = if let Some(album) = track.album() {
-			let album = utils::widgets::line(album, dim);
+			let album = utils::widgets::line(utils::widgets::truncate(album, width), dim);
 			vec![title, artist, album]
 		} else {
 			vec![title, artist]
@@ -39,13 +50,38 @@ pub fn main(frame: &mut Frame, area: Rect, state: &State) {
 		frame.render_widget(para, area);
 	} else {
 		let line = utils::widgets::line("no track playing", dim_italic);
-		let para = Paragraph::new(line).block(block.border_style(dim));
+		let para =
+			Paragraph::new(line).block(block.border_style(dim.patch(utils::style::border())));
 		frame.render_widget(para, area);
 	}
 }
 
-pub fn seek(frame: &mut Frame, area: Rect, state: &State) {
-	let block = Block::default().title(" seek ").borders(Borders::ALL);
+pub fn seek(frame: &mut Frame, area: Rect, state: &State, time: TimeDisplay) {
+	let mut block = Block::default()
+		.title(" seek ")
+		.borders(Borders::ALL)
+		.border_style(utils::style::border());
+
+	let dropouts = state.dropouts.total();
+	if dropouts > 0 {
+		let line = Line::styled(format!(" {dropouts} dropouts "), Style::default().yellow());
+		block = block.title(line.right_aligned());
+	}
+
+	if let Some(checked) = state.indexing {
+		let line = Line::styled(format!(" indexing… {checked} tracks "), utils::style::dim());
+		block = block.title_bottom(line.left_aligned());
+	}
+
+	if let Some(upcoming) = state.upcoming.as_ref() {
+		let title = upcoming.heading();
+		let title = title.as_deref().unwrap_or("unknown title");
+		let artist = upcoming.credit();
+		let artist = artist.as_deref().unwrap_or("unknown artist");
+
+		let line = Line::styled(format!(" next: {title} ~ {artist} "), utils::style::dim());
+		block = block.title_bottom(line.right_aligned());
+	}
 
 	if let Some((elapsed, duration)) = state.elapsed_duration() {
 		frame.render_widget(block, area);
@@ -59,43 +95,167 @@ pub fn seek(frame: &mut Frame, area: Rect, state: &State) {
 			unreachable!();
 		};
 
-		self::seek::progress(frame, (elapsed, duration), state, seek);
+		self::seek::progress(frame, (elapsed, duration), state, time, seek);
 		self::seek::info(frame, state, info);
 	} else {
-		let dimmed = Style::default().dim();
-		let dim = dimmed.italic();
+		let dimmed = utils::style::dim();
+		let dim = utils::style::placeholder();
 
 		let padding = Padding::new(2, 0, 1, 0);
-		let line = utils::widgets::line("no track playing", dim);
-		let para = Paragraph::new(line).block(block.padding(padding).border_style(dimmed));
+		let text = match state.output {
+			Output::NoDevice => "no audio device",
+			Output::Failed => "couldn't open audio output",
+			_ if state.loading => "loading…",
+			_ => "no track playing",
+		};
+		let line = utils::widgets::line(text, dim);
+		let para = Paragraph::new(line).block(
+			block
+				.padding(padding)
+				.border_style(dimmed.patch(utils::style::border())),
+		);
 		frame.render_widget(para, area);
 	}
 }
 
+/// compact two line layout combining [`main`] and [`seek`]
+pub fn mini(frame: &mut Frame, area: Rect, state: &State, time: TimeDisplay) {
+	let dim_italic = utils::style::placeholder();
+
+	let [track, seek] = *Layout::default()
+		.direction(Direction::Vertical)
+		.constraints([Constraint::Max(1), Constraint::Max(1)])
+		.split(area)
+	else {
+		unreachable!();
+	};
+
+	let line = if let Some(track) = state.track.as_ref() {
+		let title = track.title().map_or_else(
+			|| Span::styled("unknown title", dim_italic),
+			|title| Span::styled(title, Style::default().bold()),
+		);
+		let artist = track
+			.credit()
+			.map_or_else(|| Span::styled("unknown artist", dim_italic), Span::raw);
+
+		Line::from(vec![title, Span::raw(" ~ "), artist])
+	} else {
+		utils::widgets::line("no track playing", dim_italic)
+	};
+	let block = Block::default().padding(Padding::new(2, 2, 0, 0));
+	frame.render_widget(Paragraph::new(line).block(block), track);
+
+	if let Some((elapsed, duration)) = state.elapsed_duration() {
+		let fmt_vol = format!("{: >3}% ", state.volume);
+		let vol = if state.muted {
+			Span::styled(fmt_vol, utils::style::dim())
+		} else {
+			Span::raw(fmt_vol)
+		};
+
+		let [progress, info] = *Layout::default()
+			.direction(Direction::Horizontal)
+			.constraints([Constraint::Min(0), Constraint::Max(6)])
+			.split(seek)
+		else {
+			unreachable!();
+		};
+
+		self::seek::progress(frame, (elapsed, duration), state, time, progress);
+		frame.render_widget(Paragraph::new(vol).alignment(Alignment::Right), info);
+	}
+}
+
 mod seek {
 	use super::utils;
-	use crate::state::State;
+	use crate::{config::TimeDisplay, player::Output, queue::Repeat, state::State};
 	use ratatui::{
 		Frame,
+		buffer::Buffer,
 		layout::{Alignment, Constraint, Direction, Layout, Rect},
-		style::Style,
+		style::{Color, Style},
 		symbols,
 		text::{Line, Span},
-		widgets::{Block, LineGauge, Padding, Paragraph},
+		widgets::{Block, LineGauge, Padding, Paragraph, Widget},
 	};
 	use std::time::Duration;
 
+	/// [`LineGauge`] whose filled part fades through `colors` from the start to the end of the track
+	struct GradientGauge<'a> {
+		ratio: f64,
+		colors: &'a [Color],
+		paused: bool,
+	}
+
+	impl Widget for GradientGauge<'_> {
+		fn render(self, area: Rect, buf: &mut Buffer) {
+			// same geometry as a LineGauge with an empty label
+			let start = area.left() + 1;
+			if area.is_empty() || start >= area.right() {
+				return;
+			}
+
+			let width = area.right() - start;
+			let end = start + (f64::from(width) * self.ratio).floor() as u16;
+			for col in start..area.right() {
+				let style = if col < end {
+					let position = f64::from(col - start) / f64::from(width);
+					Style::new().fg(gradient(self.colors, position))
+				} else {
+					Style::new()
+				};
+				let style = if self.paused { style.dim() } else { style };
+
+				buf[(col, area.top())]
+					.set_symbol(symbols::line::THICK.horizontal)
+					.set_style(style);
+			}
+		}
+	}
+
+	/// color at `position`, between 0 and 1, of the gradient through `colors`
+	///
+	/// only rgb colors are blended, other colors switch halfway to the next one
+	pub(super) fn gradient(colors: &[Color], position: f64) -> Color {
+		let last = colors.len() - 1;
+		let scaled = position.clamp(0.0, 1.0) * last as f64;
+		let idx = (scaled.floor() as usize).min(last);
+		let t = scaled - idx as f64;
+
+		match (colors[idx], colors[(idx + 1).min(last)]) {
+			(Color::Rgb(r1, g1, b1), Color::Rgb(r2, g2, b2)) => {
+				let blend = |from: u8, to: u8| {
+					(f64::from(from) + (f64::from(to) - f64::from(from)) * t).round() as u8
+				};
+				Color::Rgb(blend(r1, r2), blend(g1, g2), blend(b1, b2))
+			}
+			(from, _) if t < 0.5 => from,
+			(_, to) => to,
+		}
+	}
+
 	pub fn progress(
 		frame: &mut Frame,
 		(elapsed, duration): (Duration, Duration),
 		state: &State,
+		time: TimeDisplay,
 		area: Rect,
 	) {
-		let fmt_elapsed = utils::fmt_duration(elapsed);
+		let fmt_elapsed = match time {
+			TimeDisplay::Elapsed => utils::fmt_duration(elapsed),
+			TimeDisplay::Remaining => {
+				format!("-{}", utils::fmt_duration(duration.saturating_sub(elapsed)))
+			}
+			TimeDisplay::Both => {
+				let remaining = utils::fmt_duration(duration.saturating_sub(elapsed));
+				format!("{} -{remaining}", utils::fmt_duration(elapsed))
+			}
+		};
 		let fmt_duration = utils::fmt_duration(duration);
 		let text = Line::from(vec![
 			if state.paused {
-				Span::styled(&fmt_elapsed, Style::default().dim())
+				Span::styled(&fmt_elapsed, utils::style::dim())
 			} else {
 				Span::raw(&fmt_elapsed)
 			},
@@ -123,6 +283,16 @@ mod seek {
 		let gauge_area = chunks[1];
 		let block = Block::default().padding(Padding::new(0, 2, 0, 0));
 
+		if let Some(colors) = utils::style::gauge_gradient(state.paused) {
+			let gauge = GradientGauge {
+				ratio: progress,
+				colors,
+				paused: state.paused,
+			};
+			frame.render_widget(gauge, block.inner(gauge_area));
+			return;
+		}
+
 		let (filled, unfilled) = utils::style::gauge_style(state.paused);
 		let gauge = LineGauge::default()
 			.block(block)
@@ -140,14 +310,20 @@ mod seek {
 		let (vol_str, vol) = if state.muted {
 			(
 				Span::styled("[mute]", utils::style::accent()),
-				Span::styled(fmt_vol, Style::default().dim()),
+				Span::styled(fmt_vol, utils::style::dim()),
 			)
 		} else {
 			(Span::raw("[vol]:"), Span::raw(fmt_vol))
 		};
 
-		let paused = if state.paused {
-			Span::styled("[stop]", Style::default().dim())
+		let paused = if state.output == Output::NoDevice {
+			Span::styled("[no device]", Style::default().yellow())
+		} else if state.output == Output::Failed {
+			Span::styled("[no audio]", Style::default().red())
+		} else if state.loading {
+			Span::styled("[loading]", utils::style::dim())
+		} else if state.paused {
+			Span::styled("[stop]", utils::style::dim())
 		} else {
 			Span::styled("[play]", utils::style::accent())
 		};
@@ -155,18 +331,45 @@ mod seek {
 		let shuffle = if state.shuffle {
 			Span::styled("[shuffle]", utils::style::accent())
 		} else {
-			Span::styled("[no shuffle]", Style::default().dim())
+			Span::styled("[no shuffle]", utils::style::dim())
 		};
 
+		let mut spans = vec![shuffle, Span::raw(" ~ ")];
+
+		if state.repeat != Repeat::Playlist {
+			let repeat = format!("[{}]", state.repeat.name());
+			spans.extend([
+				Span::styled(repeat, utils::style::accent()),
+				Span::raw(" ~ "),
+			]);
+		}
+
+		spans.extend([paused, Span::raw(" ~ ")]);
+
+		if state.recording {
+			spans.extend([
+				Span::styled("[rec]", Style::default().red().bold()),
+				Span::raw(" ~ "),
+			]);
+		}
+
+		if state.mono {
+			spans.extend([
+				Span::styled("[mono]", utils::style::accent()),
+				Span::raw(" ~ "),
+			]);
+		}
+
+		if state.balance != 0 {
+			let side = if state.balance < 0 { 'l' } else { 'r' };
+			let balance = format!("[bal]: {side}{}", state.balance.unsigned_abs());
+			spans.extend([Span::raw(balance), Span::raw(" ~ ")]);
+		}
+
+		spans.extend([vol_str, vol]);
+
 		let block = Block::default().padding(Padding::new(2, 2, 0, 0));
-		let line = Line::from(vec![
-			shuffle,
-			Span::raw(" ~ "),
-			paused,
-			Span::raw(" ~ "),
-			vol_str,
-			vol,
-		]);
+		let line = Line::from(spans);
 		let par = Paragraph::new(line)
 			.block(block)
 			.alignment(Alignment::Right);
@@ -174,6 +377,33 @@ mod seek {
 	}
 }
 
+/// split off the last line for announcements
+pub fn announce_layout(size: Rect) -> (Rect, Rect) {
+	let chunks = Layout::default()
+		.direction(Direction::Vertical)
+		.constraints([Constraint::Min(0), Constraint::Length(1)])
+		.split(size);
+	(chunks[0], chunks[1])
+}
+
+/// last track or state change in words, for screen readers
+pub fn announcement(frame: &mut Frame, area: Rect, state: &State) {
+	let text = state.announcement.as_deref().unwrap_or_default();
+	let text = utils::widgets::truncate(text, usize::from(area.width));
+	frame.render_widget(Paragraph::new(text), area);
+}
+
+/// shown instead of the ui if the terminal is smaller than `min`
+pub fn too_small(frame: &mut Frame, area: Rect, (width, height): (u16, u16)) {
+	let text = format!("terminal too small (need {width}x{height})");
+	let text = utils::widgets::truncate(text, usize::from(area.width));
+	let line = utils::widgets::line(text, utils::style::placeholder());
+
+	let y = area.y + area.height.saturating_sub(1) / 2;
+	let row = Rect::new(area.x, y, area.width, area.height.min(1));
+	frame.render_widget(Paragraph::new(line).alignment(Alignment::Center), row);
+}
+
 pub fn layout(size: Rect) -> (Rect, Rect) {
 	let chunks = Layout::default()
 		.direction(Direction::Vertical)
@@ -182,22 +412,146 @@ pub fn layout(size: Rect) -> (Rect, Rect) {
 	(chunks[0], chunks[1])
 }
 
-pub fn popup(main: Rect) -> Rect {
-	let vert = Layout::default()
-		.direction(Direction::Vertical)
-		.constraints([
-			Constraint::Percentage(15),
-			Constraint::Percentage(80),
-			Constraint::Percentage(5),
-		])
-		.split(main);
-
-	Layout::default()
+/// width of the browser pane of [`Layout::Split`](crate::config::Layout::Split) in percent of the window
+const PANE_WIDTH: u16 = 40;
+
+/// split off the left-hand side for the browser pane, if the window is wide enough
+pub fn split_layout(size: Rect) -> (Rect, Option<Rect>) {
+	if size.width < SIDEBAR_MIN_WIDTH {
+		return (size, None);
+	}
+
+	let chunks = Layout::default()
+		.direction(Direction::Horizontal)
+		.constraints([Constraint::Percentage(PANE_WIDTH), Constraint::Min(0)])
+		.split(size);
+	(chunks[1], Some(chunks[0]))
+}
+
+/// window width below which the [`up_next`] panel is hidden
+const SIDEBAR_MIN_WIDTH: u16 = 80;
+/// width of the [`up_next`] panel in percent of the window
+const SIDEBAR_WIDTH: u16 = 30;
+/// tracks shown in the [`up_next`] panel
+const UP_NEXT: usize = 10;
+
+/// split off the right-hand side for the [`up_next`] panel, if the window is wide enough
+pub fn sidebar_layout(size: Rect) -> (Rect, Option<Rect>) {
+	if size.width < SIDEBAR_MIN_WIDTH {
+		return (size, None);
+	}
+
+	let chunks = Layout::default()
 		.direction(Direction::Horizontal)
-		.constraints([
-			Constraint::Percentage(15),
-			Constraint::Percentage(70),
-			Constraint::Percentage(15),
-		])
-		.split(vert[1])[1]
+		.constraints([Constraint::Min(0), Constraint::Percentage(SIDEBAR_WIDTH)])
+		.split(size);
+	(chunks[0], Some(chunks[1]))
+}
+
+/// the next tracks of the [`Queue`], see [`Queue::up_next`]
+pub fn up_next(frame: &mut Frame, area: Rect, queue: &Queue) {
+	let block = Block::default()
+		.title(" up next ")
+		.borders(Borders::ALL)
+		.border_style(utils::style::border())
+		.padding(Padding::new(2, 2, 1, 1));
+	let width = usize::from(block.inner(area).width);
+
+	let up_next = queue.up_next(UP_NEXT);
+	let lines = if up_next.is_empty() {
+		vec![utils::widgets::line(
+			"nothing up next",
+			utils::style::placeholder(),
+		)]
+	} else {
+		up_next
+			.into_iter()
+			.map(|track| {
+				let title = track.heading();
+				let title = title.as_deref().unwrap_or("unknown title");
+				let artist = track.credit();
+				let artist = artist.as_deref().unwrap_or("unknown artist");
+
+				let text = format!("{title} ~ {artist}");
+				Line::raw(utils::widgets::truncate(text, width))
+			})
+			.collect()
+	};
+
+	frame.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+/// single line text input at the bottom of the `main` window
+pub fn prompt(frame: &mut Frame, main: Rect, title: &str, text: &str) {
+	if main.height < 3 {
+		return;
+	}
+
+	let area = Rect::new(main.x, main.y + main.height - 3, main.width, 3);
+	let block = Block::default()
+		.title(title)
+		.borders(Borders::ALL)
+		.border_style(utils::style::border())
+		.padding(Padding::horizontal(1));
+	let inner = block.inner(area);
+
+	let width = usize::from(inner.width.saturating_sub(3));
+	let text = utils::widgets::truncate_start(text, width);
+	let line = Line::from(vec![
+		Span::styled("> ", utils::style::accent()),
+		Span::raw(&*text),
+	]);
+
+	frame.render_widget(Clear, area);
+	frame.render_widget(Paragraph::new(line).block(block), area);
+
+	let cursor = 2 + u16::try_from(text.width()).unwrap_or(u16::MAX);
+	frame.set_cursor_position((inner.x + cursor.min(inner.width), inner.y));
+}
+
+/// minimum width of a popup, below which it covers the entire window
+const POPUP_MIN_WIDTH: u16 = 60;
+/// minimum height of a popup, below which it covers the entire window
+const POPUP_MIN_HEIGHT: u16 = 16;
+
+/// popup area sized in percent of the main window
+///
+/// grows to the minimum size if possible and covers the
+/// entire window if it is smaller than that
+pub fn popup(main: Rect, (width, height): (u16, u16)) -> Rect {
+	if main.width <= POPUP_MIN_WIDTH || main.height <= POPUP_MIN_HEIGHT {
+		return main;
+	}
+
+	let width = (u32::from(main.width) * u32::from(width) / 100) as u16;
+	let width = width.clamp(POPUP_MIN_WIDTH, main.width);
+	let height = (u32::from(main.height) * u32::from(height) / 100) as u16;
+	let height = height.clamp(POPUP_MIN_HEIGHT, main.height);
+
+	// center horizontally, but sit a bit lower than the vertical center
+	let x = main.x + (main.width - width) / 2;
+	let y = main.y + (main.height - height) * 3 / 4;
+	Rect::new(x, y, width, height)
+}
+
+#[cfg(test)]
+mod test {
+	use super::seek::gradient;
+	use ratatui::style::Color;
+
+	#[test]
+	fn gauge_gradient() {
+		let rgb = [Color::Rgb(0, 0, 0), Color::Rgb(200, 100, 0)];
+		assert_eq!(gradient(&rgb, 0.0), Color::Rgb(0, 0, 0));
+		assert_eq!(gradient(&rgb, 0.5), Color::Rgb(100, 50, 0));
+		assert_eq!(gradient(&rgb, 1.0), Color::Rgb(200, 100, 0));
+		assert_eq!(gradient(&rgb, 2.0), Color::Rgb(200, 100, 0));
+
+		let named = [Color::Cyan, Color::Yellow, Color::Red];
+		assert_eq!(gradient(&named, 0.2), Color::Cyan);
+		assert_eq!(gradient(&named, 0.4), Color::Yellow);
+		assert_eq!(gradient(&named, 0.9), Color::Red);
+
+		assert_eq!(gradient(&[Color::Red], 0.5), Color::Red);
+	}
 }